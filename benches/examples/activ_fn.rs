@@ -0,0 +1,41 @@
+use test::{Bencher, black_box};
+
+use rusty_machine::learning::toolkit::activ_fn::{ActivationFunc, ReLU, Sigmoid};
+use rusty_machine::linalg::{Matrix, BaseMatrixMut};
+
+/// A matrix with one million elements, half of them negative.
+fn generate_data() -> Matrix<f64> {
+    Matrix::from_fn(1000, 1000, |c, r| (r as f64 - c as f64) / 100.0)
+}
+
+#[bench]
+fn relu_elementwise(b: &mut Bencher) {
+    let inputs = generate_data();
+    b.iter(|| black_box(inputs.clone().apply(&ReLU::func)));
+}
+
+#[bench]
+fn relu_apply_to_slice(b: &mut Bencher) {
+    let inputs = generate_data();
+    b.iter(|| {
+        let mut outputs = inputs.clone();
+        ReLU::apply_to_slice(outputs.mut_data());
+        black_box(outputs)
+    });
+}
+
+#[bench]
+fn sigmoid_elementwise(b: &mut Bencher) {
+    let inputs = generate_data();
+    b.iter(|| black_box(inputs.clone().apply(&Sigmoid::func)));
+}
+
+#[bench]
+fn sigmoid_apply_to_slice(b: &mut Bencher) {
+    let inputs = generate_data();
+    b.iter(|| {
+        let mut outputs = inputs.clone();
+        Sigmoid::apply_to_slice(outputs.mut_data());
+        black_box(outputs)
+    });
+}
@@ -70,3 +70,17 @@ fn nnet_and_gate_predict(b: &mut Bencher) {
         let _ = black_box(model.predict(&test_inputs));
     })
 }
+
+#[bench]
+fn nnet_large_input_predict(b: &mut Bencher) {
+    const SAMPLES: usize = 50_000;
+    let inputs = Matrix::new(SAMPLES, 2, (0..SAMPLES * 2).map(|_| random::<f64>()).collect::<Vec<_>>());
+    let layers = &[2, 4, 1];
+    let criterion = BCECriterion::new(Regularization::L2(0.));
+
+    let model = NeuralNet::mlp(layers, criterion, StochasticGD::default(), Sigmoid);
+
+    b.iter(|| {
+        let _ = black_box(model.predict(&inputs));
+    })
+}
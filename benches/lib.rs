@@ -5,6 +5,7 @@ extern crate test;
 extern crate rand;
 
 mod examples {
+    mod activ_fn;
     mod cross_validation;
     mod k_means;
     mod nnet;
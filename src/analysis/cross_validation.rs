@@ -5,7 +5,9 @@ use std::iter::Chain;
 use std::slice::Iter;
 use linalg::{BaseMatrix, Matrix};
 use learning::{LearningResult, SupModel};
+use learning::error::{Error, ErrorKind};
 use learning::toolkit::rand_utils::in_place_fisher_yates;
+use rand::{Rng, SeedableRng, StdRng};
 
 /// Randomly splits the inputs into k 'folds'. For each fold a model
 /// is trained using all inputs except for that fold, and tested on the
@@ -75,6 +77,259 @@ pub fn k_fold_validate<M, S>(model: &mut M,
     Ok(costs)
 }
 
+/// Performs k-fold cross validation, training a fresh model for each fold.
+///
+/// This behaves like `k_fold_validate` except that `factory` is called to
+/// construct a new, untrained model for every fold. This ensures that no
+/// fold is influenced by training on another - which matters for models
+/// like `NeuralNet` whose training continues from their current weights.
+///
+/// If the number of samples is not divisible by `k` the remainder is spread
+/// over the first folds, so that fold sizes differ by at most one.
+///
+/// # Arguments
+/// * `factory` - Constructs the model to train and predict with for each fold.
+/// * `inputs` - All input samples.
+/// * `targets` - All targets.
+/// * `k` - Number of folds to use.
+/// * `score` - Used to compare the outputs for each fold to the targets. Higher scores are better. See the `analysis::score` module for examples.
+///
+/// # Examples
+/// ```
+/// use rusty_machine::analysis::cross_validation::k_fold;
+/// use rusty_machine::analysis::score::neg_mean_squared_error;
+/// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+/// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+/// use rusty_machine::learning::toolkit::activ_fn::Linear;
+/// use rusty_machine::linalg::Matrix;
+///
+/// let inputs = Matrix::new(5, 1, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+/// let targets = Matrix::new(5, 1, vec![2.0, 4.0, 6.0, 8.0, 10.0]);
+///
+/// let scores = k_fold(|| NeuralNet::mlp(&[1, 1],
+///                                       MSECriterion::default(),
+///                                       StochasticGD::default(),
+///                                       Linear),
+///                     &inputs,
+///                     &targets,
+///                     2,
+///                     neg_mean_squared_error).unwrap();
+///
+/// assert_eq!(scores.len(), 2);
+/// ```
+pub fn k_fold<M, F, S>(mut factory: F,
+                       inputs: &Matrix<f64>,
+                       targets: &Matrix<f64>,
+                       k: usize,
+                       score: S) -> LearningResult<Vec<f64>>
+    where F: FnMut() -> M,
+          S: Fn(&Matrix<f64>, &Matrix<f64>) -> f64,
+          M: SupModel<Matrix<f64>, Matrix<f64>>,
+{
+    assert_eq!(inputs.rows(), targets.rows());
+    let num_samples = inputs.rows();
+    let shuffled_indices = create_shuffled_indices(num_samples);
+    let folds = Folds::new(&shuffled_indices, k);
+
+    let mut scores: Vec<f64> = Vec::with_capacity(k);
+
+    for p in folds {
+        let train_inputs = inputs.select_rows(p.train_indices_iter.clone());
+        let train_targets = targets.select_rows(p.train_indices_iter.clone());
+        let test_inputs = inputs.select_rows(p.test_indices_iter.clone());
+        let test_targets = targets.select_rows(p.test_indices_iter.clone());
+
+        let mut model = factory();
+        try!(model.train(&train_inputs, &train_targets));
+        let outputs = try!(model.predict(&test_inputs));
+        scores.push(score(&outputs, &test_targets));
+    }
+
+    Ok(scores)
+}
+
+/// Returns the out-of-fold prediction of every sample.
+///
+/// The samples are split into k folds using a permutation determined by
+/// `seed`. For each fold a fresh model is trained on the other folds and
+/// used to predict the samples in the fold. Every sample is therefore
+/// predicted exactly once, by a model which did not see it in training.
+///
+/// Row `i` of the returned matrix is the prediction for row `i` of the
+/// inputs. These predictions can be used to evaluate a model or as the
+/// inputs of a model stacked on top of it.
+///
+/// # Arguments
+/// * `factory` - Constructs the model to train and predict with for each fold.
+/// * `inputs` - All input samples.
+/// * `targets` - All targets.
+/// * `k` - Number of folds to use.
+/// * `seed` - Seeds the random assignment of samples to folds.
+///
+/// # Examples
+/// ```
+/// use rusty_machine::analysis::cross_validation::oof_predict;
+/// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+/// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+/// use rusty_machine::learning::toolkit::activ_fn::Linear;
+/// use rusty_machine::linalg::{BaseMatrix, Matrix};
+///
+/// let inputs = Matrix::new(6, 1, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+/// let targets = Matrix::new(6, 1, vec![2.0, 4.0, 6.0, 8.0, 10.0, 12.0]);
+///
+/// let predictions = oof_predict(|| NeuralNet::mlp(&[1, 1],
+///                                                 MSECriterion::default(),
+///                                                 StochasticGD::default(),
+///                                                 Linear),
+///                               &inputs,
+///                               &targets,
+///                               3,
+///                               42).unwrap();
+///
+/// assert_eq!(predictions.rows(), 6);
+/// ```
+pub fn oof_predict<M, F>(mut factory: F,
+                         inputs: &Matrix<f64>,
+                         targets: &Matrix<f64>,
+                         k: usize,
+                         seed: usize) -> LearningResult<Matrix<f64>>
+    where F: FnMut() -> M,
+          M: SupModel<Matrix<f64>, Matrix<f64>>,
+{
+    assert_eq!(inputs.rows(), targets.rows());
+    let num_samples = inputs.rows();
+
+    let mut indices: Vec<usize> = (0..num_samples).collect();
+    let mut rng = StdRng::from_seed(&[seed]);
+    rng.shuffle(&mut indices);
+    let shuffled_indices = ShuffledIndices(indices);
+    let folds = Folds::new(&shuffled_indices, k);
+
+    let mut predictions: Vec<Vec<f64>> = vec![Vec::new(); num_samples];
+
+    for p in folds {
+        let train_inputs = inputs.select_rows(p.train_indices_iter.clone());
+        let train_targets = targets.select_rows(p.train_indices_iter.clone());
+        let test_inputs = inputs.select_rows(p.test_indices_iter.clone());
+
+        let mut model = factory();
+        try!(model.train(&train_inputs, &train_targets));
+        let outputs = try!(model.predict(&test_inputs));
+
+        for (&idx, row) in p.test_indices_iter.zip(outputs.row_iter()) {
+            predictions[idx] = row.raw_slice().to_vec();
+        }
+    }
+
+    let cols = predictions[0].len();
+    let data = predictions.into_iter().flat_map(|row| row).collect::<Vec<_>>();
+    Ok(Matrix::new(num_samples, cols, data))
+}
+
+/// The weight of the out-of-bag score in the .632 bootstrap estimate.
+const BOOTSTRAP_632_WEIGHT: f64 = 0.632;
+
+/// Estimates the score of a model using the .632 bootstrap.
+///
+/// The score of a model on its own training data is optimistic, while
+/// the score on the samples left out of a bootstrap resample is
+/// pessimistic, as the model is trained on only about 63.2% of the
+/// distinct samples. The .632 estimate combines them as
+/// `0.368 * training score + 0.632 * out-of-bag score`. This is useful
+/// for small datasets where holding out a test set is costly.
+///
+/// Each of the `n_boot` resamples draws as many samples as the inputs,
+/// with replacement. The model is trained on the resample and scored on
+/// the samples which were not drawn, and the out-of-bag score is the
+/// mean over the resamples. Resamples which leave no sample out, or on
+/// which the model cannot be trained (for example because a class is
+/// absent from the resample), are skipped.
+///
+/// # Arguments
+/// * `factory` - Constructs the model to train and score for each resample.
+/// * `inputs` - All input samples.
+/// * `targets` - All targets.
+/// * `score` - Used to compare the outputs to the targets. Higher scores are better.
+/// * `n_boot` - The number of bootstrap resamples.
+/// * `seed` - Seeds the resampling, so that the estimate is reproducible.
+///
+/// # Examples
+/// ```
+/// use rusty_machine::analysis::cross_validation::bootstrap_632;
+/// use rusty_machine::analysis::score::neg_mean_squared_error;
+/// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+/// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+/// use rusty_machine::learning::toolkit::activ_fn::Linear;
+/// use rusty_machine::linalg::Matrix;
+///
+/// let inputs = Matrix::new(6, 1, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+/// let targets = Matrix::new(6, 1, vec![2.0, 4.0, 6.0, 8.0, 10.0, 12.0]);
+///
+/// let estimate = bootstrap_632(|| NeuralNet::mlp(&[1, 1],
+///                                                MSECriterion::default(),
+///                                                StochasticGD::default(),
+///                                                Linear),
+///                              &inputs,
+///                              &targets,
+///                              neg_mean_squared_error,
+///                              20,
+///                              42).unwrap();
+/// ```
+pub fn bootstrap_632<M, F, S>(mut factory: F,
+                              inputs: &Matrix<f64>,
+                              targets: &Matrix<f64>,
+                              score: S,
+                              n_boot: usize,
+                              seed: usize) -> LearningResult<f64>
+    where F: FnMut() -> M,
+          M: SupModel<Matrix<f64>, Matrix<f64>>,
+          S: Fn(&Matrix<f64>, &Matrix<f64>) -> f64
+{
+    assert_eq!(inputs.rows(), targets.rows());
+    let num_samples = inputs.rows();
+
+    let mut model = factory();
+    try!(model.train(inputs, targets));
+    let train_score = score(&try!(model.predict(inputs)), targets);
+
+    let mut rng = StdRng::from_seed(&[seed]);
+    let mut oob_total = 0f64;
+    let mut used = 0;
+
+    for _ in 0..n_boot {
+        let mut drawn = vec![false; num_samples];
+        let sample = (0..num_samples)
+            .map(|_| {
+                let idx = rng.gen_range(0, num_samples);
+                drawn[idx] = true;
+                idx
+            })
+            .collect::<Vec<_>>();
+
+        let out_of_bag = (0..num_samples).filter(|&i| !drawn[i]).collect::<Vec<_>>();
+        if out_of_bag.is_empty() {
+            continue;
+        }
+
+        let mut model = factory();
+        if model.train(&inputs.select_rows(&sample), &targets.select_rows(&sample)).is_err() {
+            continue;
+        }
+
+        let outputs = try!(model.predict(&inputs.select_rows(&out_of_bag)));
+        oob_total += score(&outputs, &targets.select_rows(&out_of_bag));
+        used += 1;
+    }
+
+    if used == 0 {
+        return Err(Error::new(ErrorKind::InvalidState,
+                              "None of the bootstrap resamples could be scored."));
+    }
+
+    let oob_score = oob_total / used as f64;
+    Ok((1f64 - BOOTSTRAP_632_WEIGHT) * train_score + BOOTSTRAP_632_WEIGHT * oob_score)
+}
+
 /// A permutation of 0..n.
 struct ShuffledIndices(Vec<usize>);
 
@@ -199,7 +454,40 @@ impl<'a> Iterator for Folds<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{ShuffledIndices, Folds};
+    use super::{ShuffledIndices, Folds, k_fold, oof_predict, bootstrap_632};
+    use analysis::score::neg_mean_squared_error;
+    use learning::{LearningResult, SupModel};
+    use learning::error::{Error, ErrorKind};
+    use learning::nnet::{NeuralNet, MSECriterion};
+    use learning::optim::grad_desc::StochasticGD;
+    use learning::toolkit::activ_fn::Linear;
+    use linalg::{BaseMatrix, Matrix};
+    use rand::{Rng, SeedableRng, StdRng};
+
+    /// Predicts the target of the closest training input.
+    struct Memorizer {
+        inputs: Matrix<f64>,
+        targets: Matrix<f64>,
+    }
+
+    impl SupModel<Matrix<f64>, Matrix<f64>> for Memorizer {
+        fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+            let closest = inputs.row_iter()
+                .map(|x| {
+                    (0..self.inputs.rows())
+                        .min_by_key(|&i| ((self.inputs[[i, 0]] - x[0]).abs() * 1e6) as u64)
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            Ok(self.targets.select_rows(&closest))
+        }
+
+        fn train(&mut self, inputs: &Matrix<f64>, targets: &Matrix<f64>) -> LearningResult<()> {
+            self.inputs = inputs.clone();
+            self.targets = targets.clone();
+            Ok(())
+        }
+    }
 
     // k % n == 0
     #[test]
@@ -275,6 +563,125 @@ mod tests {
             ]);
     }
 
+    #[test]
+    fn test_k_fold_fresh_models() {
+        let inputs = Matrix::new(7, 1, (0..7).map(|x| x as f64).collect::<Vec<_>>());
+        let targets = Matrix::new(7, 1, (0..7).map(|x| 3. * x as f64 + 1.).collect::<Vec<_>>());
+
+        let mut built = 0;
+        let scores = k_fold(|| {
+                                built += 1;
+                                NeuralNet::mlp(&[1, 1],
+                                               MSECriterion::default(),
+                                               StochasticGD::default(),
+                                               Linear)
+                            },
+                            &inputs,
+                            &targets,
+                            3,
+                            neg_mean_squared_error).unwrap();
+
+        assert_eq!(scores.len(), 3);
+        assert_eq!(built, 3);
+        assert!(scores.iter().all(|s| s.is_finite()));
+    }
+
+    #[test]
+    fn test_oof_predict() {
+        let inputs = Matrix::new(7, 1, (0..7).map(|x| x as f64).collect::<Vec<_>>());
+        let targets = Matrix::new(7, 1, vec![3.0, -1.0, 4.0, 1.0, -5.0, 9.0, 2.0]);
+        let factory = || Memorizer { inputs: Matrix::zeros(0, 1), targets: Matrix::zeros(0, 1) };
+
+        let predictions = oof_predict(factory, &inputs, &targets, 3, 7).unwrap();
+        assert_eq!(predictions.rows(), 7);
+        assert_eq!(predictions.cols(), 1);
+
+        // In sample the model predicts every target exactly
+        let mut model = factory();
+        model.train(&inputs, &targets).unwrap();
+        assert_eq!(model.predict(&inputs).unwrap(), targets);
+
+        // Out of fold no sample can be predicted from itself
+        for (p, t) in predictions.iter().zip(targets.iter()) {
+            assert!(p != t);
+        }
+
+        // The same seed gives the same folds
+        assert_eq!(oof_predict(factory, &inputs, &targets, 3, 7).unwrap(), predictions);
+    }
+
+    #[test]
+    fn test_bootstrap_632() {
+        let inputs = Matrix::new(12, 1, (0..12).map(|x| x as f64).collect::<Vec<_>>());
+        let targets = Matrix::new(12, 1, (0..12).map(|x| ((x * 7) % 5) as f64).collect::<Vec<_>>());
+        let factory = || Memorizer { inputs: Matrix::zeros(0, 1), targets: Matrix::zeros(0, 1) };
+
+        let estimate = bootstrap_632(factory, &inputs, &targets, neg_mean_squared_error, 50, 3)
+            .unwrap();
+
+        // The training score is perfect
+        let mut model = factory();
+        model.train(&inputs, &targets).unwrap();
+        let train_score = neg_mean_squared_error(&model.predict(&inputs).unwrap(), &targets);
+        assert_eq!(train_score, 0.0);
+
+        // Score the same resamples out of bag
+        let mut rng = StdRng::from_seed(&[3]);
+        let mut oob_scores = Vec::new();
+        for _ in 0..50 {
+            let sample = (0..12).map(|_| rng.gen_range(0, 12)).collect::<Vec<usize>>();
+            let out_of_bag = (0..12).filter(|i| !sample.contains(i)).collect::<Vec<_>>();
+            if out_of_bag.is_empty() {
+                continue;
+            }
+
+            let mut model = factory();
+            model.train(&inputs.select_rows(&sample), &targets.select_rows(&sample)).unwrap();
+            let outputs = model.predict(&inputs.select_rows(&out_of_bag)).unwrap();
+            oob_scores.push(neg_mean_squared_error(&outputs, &targets.select_rows(&out_of_bag)));
+        }
+        let oob_score = oob_scores.iter().fold(0.0, |acc, s| acc + s) / oob_scores.len() as f64;
+
+        // The estimate lies between the optimistic and pessimistic scores
+        assert!(oob_score < estimate && estimate < train_score);
+        assert!((estimate - (0.368 * train_score + 0.632 * oob_score)).abs() < 1e-12);
+
+        // The same seed gives the same estimate
+        assert_eq!(bootstrap_632(factory, &inputs, &targets, neg_mean_squared_error, 50, 3)
+                       .unwrap(),
+                   estimate);
+    }
+
+    /// A model which cannot be trained without the first sample.
+    struct NeedsFirst(Memorizer);
+
+    impl SupModel<Matrix<f64>, Matrix<f64>> for NeedsFirst {
+        fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+            self.0.predict(inputs)
+        }
+
+        fn train(&mut self, inputs: &Matrix<f64>, targets: &Matrix<f64>) -> LearningResult<()> {
+            if inputs.iter().any(|&x| x == 0.0) {
+                self.0.train(inputs, targets)
+            } else {
+                Err(Error::new(ErrorKind::InvalidData, "The first sample is missing."))
+            }
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_632_skips_failed_resamples() {
+        let inputs = Matrix::new(6, 1, (0..6).map(|x| x as f64).collect::<Vec<_>>());
+        let targets = Matrix::new(6, 1, vec![1.0, 2.0, 1.0, 2.0, 1.0, 2.0]);
+        let factory = || NeedsFirst(Memorizer { inputs: Matrix::zeros(0, 1), targets: Matrix::zeros(0, 1) });
+
+        let estimate = bootstrap_632(factory, &inputs, &targets, neg_mean_squared_error, 30, 1);
+        assert!(estimate.unwrap().is_finite());
+
+        // Fails when no resample can be scored
+        assert!(bootstrap_632(factory, &inputs, &targets, neg_mean_squared_error, 0, 1).is_err());
+    }
+
     fn collect_folds<'a>(folds: Folds<'a>) -> Vec<(Vec<usize>, Vec<usize>)> {
         folds
             .map(|p|
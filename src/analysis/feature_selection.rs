@@ -0,0 +1,132 @@
+//! Module for scoring features to select those related to a target.
+
+use linalg::{BaseMatrix, Matrix, Vector};
+
+/// Returns the chi-squared statistic between each feature and the labels.
+///
+/// Each feature column is split into `bins` bins of equal width between
+/// its smallest and largest values, and the statistic is computed from
+/// the table of counts of each bin and label. Higher scores indicate a
+/// stronger association between the feature and the labels, and an
+/// independent feature scores close to zero.
+///
+/// Cells whose expected count is zero, which happens for bins that
+/// contain no rows, are skipped. A constant feature scores zero.
+///
+/// # Arguments
+///
+/// * `features` - Matrix of features, one row per sample.
+/// * `labels` - The class label of each sample.
+/// * `bins` - The number of bins each feature is split into.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::analysis::feature_selection::chi2_scores;
+/// use rusty_machine::linalg::{Matrix, Vector};
+///
+/// // The first feature matches the labels, the second does not
+/// let features = Matrix::new(4, 2, vec![0.0, 0.0,
+///                                       0.0, 1.0,
+///                                       1.0, 0.0,
+///                                       1.0, 1.0]);
+/// let labels = Vector::new(vec![0.0, 0.0, 1.0, 1.0]);
+///
+/// let scores = chi2_scores(&features, &labels, 2);
+/// assert_eq!(scores[0], 4.0);
+/// assert_eq!(scores[1], 0.0);
+/// ```
+///
+/// # Panics
+///
+/// - features and labels have a different number of rows
+/// - bins is zero
+/// - the labels contain NaN
+pub fn chi2_scores(features: &Matrix<f64>, labels: &Vector<f64>, bins: usize) -> Vector<f64> {
+    assert!(features.rows() == labels.size(),
+            "features and labels must have the same number of rows");
+    assert!(bins > 0, "there must be at least one bin");
+
+    let mut classes = labels.data().clone();
+    classes.sort_by(|a, b| a.partial_cmp(b).expect("labels cannot be NaN"));
+    classes.dedup();
+
+    let label_idx = labels.iter()
+        .map(|l| classes.binary_search_by(|c| c.partial_cmp(l).unwrap()).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut class_totals = vec![0f64; classes.len()];
+    for &c in &label_idx {
+        class_totals[c] += 1f64;
+    }
+
+    let n = features.rows() as f64;
+    let scores: Vec<f64> = (0..features.cols())
+        .map(|j| {
+            let column = features.col(j);
+            let (min, max) = column.iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY),
+                      |(lo, hi), &x| (lo.min(x), hi.max(x)));
+
+            let mut counts = vec![0f64; bins * classes.len()];
+            for (&x, &c) in column.iter().zip(&label_idx) {
+                let bin = if max > min {
+                    (((x - min) / (max - min) * bins as f64) as usize).min(bins - 1)
+                } else {
+                    0
+                };
+                counts[bin * classes.len() + c] += 1f64;
+            }
+
+            let mut chi2 = 0f64;
+            for bin_counts in counts.chunks(classes.len()) {
+                let bin_total = bin_counts.iter().sum::<f64>();
+                for (&observed, &class_total) in bin_counts.iter().zip(&class_totals) {
+                    let expected = bin_total * class_total / n;
+                    if expected > 0f64 {
+                        chi2 += (observed - expected) * (observed - expected) / expected;
+                    }
+                }
+            }
+            chi2
+        })
+        .collect();
+
+    Vector::new(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chi2_scores;
+    use linalg::{Matrix, Vector};
+
+    #[test]
+    fn associated_feature_scores_higher() {
+        let labels = (0..60).map(|i| (i % 3) as f64).collect::<Vec<_>>();
+
+        let mut data = Vec::with_capacity(180);
+        for i in 0..60 {
+            // Determined by the label, up to a little noise
+            data.push((i % 3) as f64 + 0.1 * (i % 2) as f64);
+            // Every combination of value and label occurs equally often
+            data.push(((i / 3) % 5) as f64);
+            // Constant
+            data.push(1.0);
+        }
+        let features = Matrix::new(60, 3, data);
+
+        let scores = chi2_scores(&features, &Vector::new(labels), 5);
+
+        // Perfect association gives n * (classes - 1)
+        assert!((scores[0] - 120.0).abs() < 1e-10);
+        assert!(scores[1].abs() < 1e-10);
+        assert_eq!(scores[2], 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_rows() {
+        let features = Matrix::new(3, 1, vec![0.0, 1.0, 2.0]);
+        chi2_scores(&features, &Vector::new(vec![0.0, 1.0]), 2);
+    }
+}
@@ -5,6 +5,7 @@
 use libnum::{Zero, One};
 
 use linalg::{BaseMatrix, Matrix};
+use analysis::confusion_matrix::confusion_matrix;
 use learning::toolkit::cost_fn::{CostFunc, MeanSqError};
 
 // ************************************
@@ -197,6 +198,125 @@ pub fn f1<'a, I, T>(outputs: I, targets: I) -> f64
     2.0f64 * tpos / (2.0f64 * tpos + fneg + fpos)
 }
 
+/// Returns the area under the ROC curve for 2 class classification.
+///
+/// The area is computed from the ranks of the scores as the Mann-Whitney U
+/// statistic divided by the number of positive-negative pairs, which is the
+/// probability that a random positive is scored above a random negative.
+/// Tied scores are given the average of their ranks and so count as half.
+///
+/// If all labels are the same class the area is undefined and `NaN` is returned.
+///
+/// # Arguments
+///
+/// * `scores` - Predicted scores or probabilities of the positive class.
+/// * `labels` - Expected (actual) labels which only contain 0 or 1.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::analysis::score::roc_auc;
+/// let scores = [0.1, 0.4, 0.35, 0.8];
+/// let labels = [0.0, 0.0, 1.0, 1.0];
+///
+/// assert_eq!(roc_auc(&scores, &labels), 0.75);
+/// ```
+///
+/// # Panics
+///
+/// - scores and labels have different length
+/// - labels contains a value which is not 0 or 1
+/// - scores contains `NaN`
+pub fn roc_auc(scores: &[f64], labels: &[f64]) -> f64 {
+    assert!(scores.len() == labels.len(), "scores and labels must have the same length");
+
+    let mut order = (0..scores.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| scores[a].partial_cmp(&scores[b]).expect("scores must not be NaN"));
+
+    let mut pos_rank_sum = 0f64;
+    let mut start = 0;
+    while start < order.len() {
+        // Find the run of tied scores and give each the average rank
+        let mut end = start + 1;
+        while end < order.len() && scores[order[end]] == scores[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2f64;
+
+        for &idx in &order[start..end] {
+            if labels[idx] == 1f64 {
+                pos_rank_sum += rank;
+            } else if labels[idx] != 0f64 {
+                panic!("roc_auc must be used for 2 class classification")
+            }
+        }
+        start = end;
+    }
+
+    let pos = labels.iter().filter(|&&l| l == 1f64).count() as f64;
+    let neg = labels.len() as f64 - pos;
+
+    let u = pos_rank_sum - pos * (pos + 1f64) / 2f64;
+    u / (pos * neg)
+}
+
+/// Returns Cohen's kappa of the predictions.
+///
+/// This is the agreement between predictions and targets corrected for the
+/// agreement expected by chance, computed from the confusion matrix as
+/// `(p_o - p_e) / (1 - p_e)`. Here `p_o` is the observed agreement (the
+/// accuracy) and `p_e` is the chance agreement given the frequency of each
+/// class in the predictions and targets.
+///
+/// Perfect agreement gives 1, chance-level agreement gives roughly 0 and
+/// systematic disagreement gives a negative score. If the chance agreement
+/// is 1, for example when every prediction and target is the same class,
+/// the score is undefined and `NaN` is returned.
+///
+/// # Arguments
+///
+/// * `outputs` - Predicted class labels in `0..num_classes`.
+/// * `targets` - Expected (actual) class labels in `0..num_classes`.
+/// * `num_classes` - The number of classes.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::analysis::score::cohens_kappa;
+/// let outputs = [0, 1, 1, 2, 2, 2];
+/// let targets = [0, 1, 2, 2, 2, 2];
+///
+/// let kappa = cohens_kappa(&outputs, &targets, 3);
+/// assert!(kappa > 0.7 && kappa < 1.0);
+/// ```
+///
+/// # Panics
+///
+/// - outputs and targets have different length
+/// - outputs or targets contain a label which is not less than `num_classes`
+pub fn cohens_kappa(outputs: &[usize], targets: &[usize], num_classes: usize) -> f64 {
+    assert!(outputs.len() == targets.len(), "outputs and targets must have the same length");
+    assert!(outputs.iter().chain(targets).all(|&l| l < num_classes),
+            "labels must be less than the number of classes");
+
+    let confusion = confusion_matrix(outputs, targets, Some((0..num_classes).collect()));
+    let n = outputs.len() as f64;
+
+    let mut observed = 0f64;
+    let mut chance = 0f64;
+    for i in 0..num_classes {
+        observed += confusion[[i, i]] as f64;
+
+        let actual = confusion.row(i).sum() as f64;
+        let predicted = confusion.col(i).sum() as f64;
+        chance += actual * predicted;
+    }
+    observed /= n;
+    chance /= n * n;
+
+    (observed - chance) / (1f64 - chance)
+}
+
 // ************************************
 // Regression Scores
 // ************************************
@@ -214,7 +334,7 @@ pub fn neg_mean_squared_error(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f
 #[cfg(test)]
 mod tests {
     use linalg::Matrix;
-    use super::{accuracy, precision, recall, f1, neg_mean_squared_error};
+    use super::{accuracy, precision, recall, f1, roc_auc, cohens_kappa, neg_mean_squared_error};
 
     #[test]
     fn test_accuracy() {
@@ -333,6 +453,63 @@ mod tests {
         f1(outputs.iter(), targets.iter());
     }
 
+    #[test]
+    fn test_roc_auc() {
+        // Of the 9 positive-negative pairs 6 are ordered correctly
+        // and one is tied.
+        let scores = [0.9, 0.8, 0.7, 0.6, 0.6, 0.2];
+        let labels = [1.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        assert_eq!(roc_auc(&scores, &labels), 6.5 / 9.0);
+
+        // Perfectly separated
+        let scores = [0.3, 0.1, 0.9, 0.7];
+        let labels = [0.0, 0.0, 1.0, 1.0];
+        assert_eq!(roc_auc(&scores, &labels), 1.0);
+
+        // Perfectly reversed
+        let labels = [1.0, 1.0, 0.0, 0.0];
+        assert_eq!(roc_auc(&scores, &labels), 0.0);
+
+        // All scores tied
+        assert_eq!(roc_auc(&[0.5; 4], &labels), 0.5);
+    }
+
+    #[test]
+    fn test_roc_auc_single_class() {
+        assert!(roc_auc(&[0.1, 0.5, 0.9], &[1.0, 1.0, 1.0]).is_nan());
+        assert!(roc_auc(&[0.1, 0.5, 0.9], &[0.0, 0.0, 0.0]).is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_roc_auc_labels_not_2class() {
+        roc_auc(&[0.1, 0.5, 0.9], &[1.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn test_cohens_kappa() {
+        let targets = [0, 0, 1, 1, 2, 2, 2, 0];
+        assert_eq!(cohens_kappa(&targets, &targets, 3), 1.0);
+
+        // Predictions independent of the targets agree only by chance
+        let targets = [0, 0, 1, 1, 0, 0, 1, 1];
+        let outputs = [0, 1, 0, 1, 0, 1, 0, 1];
+        assert!(cohens_kappa(&outputs, &targets, 2).abs() < 1e-12);
+
+        // Systematic disagreement
+        let outputs = [1, 1, 0, 0, 1, 1, 0, 0];
+        assert_eq!(cohens_kappa(&outputs, &targets, 2), -1.0);
+
+        // Unused classes do not change the score
+        assert_eq!(cohens_kappa(&outputs, &targets, 4), -1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cohens_kappa_label_out_of_range() {
+        cohens_kappa(&[0, 1, 2], &[0, 1, 1], 2);
+    }
+
     #[test]
     fn test_neg_mean_squared_error_1d() {
         let outputs = Matrix::new(3, 1, vec![1f64, 2f64, 3f64]);
@@ -0,0 +1,155 @@
+//! CSV reading and writing
+//!
+//! This module contains functions to read numeric CSV data into a
+//! `Matrix` and to write a `Matrix` back out as CSV.
+//!
+//! Only plain comma separated numbers are supported - quoted fields
+//! and other separators are not.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::data::csv;
+//! use rusty_machine::linalg::{Matrix, BaseMatrix};
+//!
+//! let data = "x,y\n1.0,2.0\n3.5,-4.0\n";
+//! let mat = csv::from_csv(data.as_bytes(), true).unwrap();
+//! assert_eq!(mat.rows(), 2);
+//! assert_eq!(mat.cols(), 2);
+//!
+//! // Write the matrix back out
+//! let mut buffer = Vec::new();
+//! csv::to_csv(&mat, &mut buffer).unwrap();
+//! assert_eq!(String::from_utf8(buffer).unwrap(), "1,2\n3.5,-4\n");
+//! ```
+
+use learning::LearningResult;
+use learning::error::{Error, ErrorKind};
+use linalg::{Matrix, BaseMatrix};
+
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Reads comma separated numeric data into a matrix.
+///
+/// If `has_header` is true the first line is skipped. The number of
+/// columns is taken from the first data row and every other row must
+/// match it. Blank lines, such as a trailing newline, are ignored.
+///
+/// An input with no data rows produces a matrix with zero rows and columns.
+pub fn from_csv<R: Read>(reader: R, has_header: bool) -> LearningResult<Matrix<f64>> {
+    let mut lines = BufReader::new(reader).lines();
+    if has_header {
+        if let Some(header) = lines.next() {
+            try!(header);
+        }
+    }
+
+    let mut data = Vec::new();
+    let mut cols = None;
+    let mut rows = 0;
+
+    for (line_idx, line) in lines.enumerate() {
+        let line = try!(line);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let start = data.len();
+        for cell in line.split(',') {
+            let value = try!(cell.trim().parse::<f64>().map_err(|_| {
+                Error::new(ErrorKind::InvalidData,
+                           format!("Could not parse '{}' on data line {} as a number",
+                                   cell.trim(),
+                                   line_idx + 1))
+            }));
+            data.push(value);
+        }
+
+        let row_len = data.len() - start;
+        match cols {
+            None => cols = Some(row_len),
+            Some(c) if c != row_len => {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      format!("Data line {} has {} columns, expected {}",
+                                              line_idx + 1,
+                                              row_len,
+                                              c)));
+            }
+            _ => {}
+        }
+        rows += 1;
+    }
+
+    Ok(Matrix::new(rows, cols.unwrap_or(0), data))
+}
+
+/// Writes a matrix as comma separated values, one row per line.
+///
+/// Values are written in their shortest form which parses back exactly,
+/// so `from_csv` recovers an identical matrix.
+pub fn to_csv<W: Write>(mat: &Matrix<f64>, mut writer: W) -> LearningResult<()> {
+    for row in mat.row_iter() {
+        let mut first = true;
+        for x in row.raw_slice() {
+            if first {
+                first = false;
+            } else {
+                try!(write!(writer, ","));
+            }
+            try!(write!(writer, "{}", x));
+        }
+        try!(writeln!(writer));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_csv, to_csv};
+    use linalg::{Matrix, BaseMatrix};
+
+    #[test]
+    fn round_trip_test() {
+        let mat = Matrix::new(3, 2, vec![1.0, -2.5,
+                                         0.1, 1e-20,
+                                         3.0 / 7.0, 12345.678]);
+
+        let mut buffer = Vec::new();
+        to_csv(&mat, &mut buffer).unwrap();
+        let read = from_csv(&buffer[..], false).unwrap();
+
+        assert_eq!(read, mat);
+    }
+
+    #[test]
+    fn header_and_trailing_newline_test() {
+        let data = "a,b,c\n1,2,3\n4,5,6\n\n";
+        let mat = from_csv(data.as_bytes(), true).unwrap();
+
+        assert_eq!(mat, Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+    }
+
+    #[test]
+    fn empty_test() {
+        let mat = from_csv("".as_bytes(), false).unwrap();
+        assert_eq!(mat.rows(), 0);
+        assert_eq!(mat.cols(), 0);
+
+        let mat = from_csv("a,b\n".as_bytes(), true).unwrap();
+        assert_eq!(mat.rows(), 0);
+    }
+
+    #[test]
+    fn ragged_rows_test() {
+        assert!(from_csv("1,2\n3\n".as_bytes(), false).is_err());
+        assert!(from_csv("1,2\n3,4,5\n".as_bytes(), false).is_err());
+    }
+
+    #[test]
+    fn non_numeric_test() {
+        assert!(from_csv("1,2\n3,x\n".as_bytes(), false).is_err());
+        // Header is not skipped so fails to parse
+        assert!(from_csv("a,b\n1,2\n".as_bytes(), false).is_err());
+    }
+}
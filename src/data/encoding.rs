@@ -0,0 +1,294 @@
+//! Categorical encodings
+//!
+//! This module contains the `TargetEncoder` which replaces each category
+//! of a feature with the mean target value of that category.
+//!
+//! Categories are the distinct values of each input column. Rare
+//! categories have unreliable means, so each mean is smoothed toward the
+//! global target mean. To avoid leaking each row's own target into its
+//! encoding, `fit_transform` encodes the training data using statistics
+//! from the other folds only.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::data::encoding::TargetEncoderFitter;
+//! use rusty_machine::data::transforms::Transformer;
+//! use rusty_machine::linalg::{Matrix, Vector};
+//!
+//! // A single categorical feature with categories 0, 1 and 2
+//! let inputs = Matrix::new(6, 1, vec![0.0, 0.0, 1.0, 1.0, 1.0, 2.0]);
+//! let targets = Vector::new(vec![1.0, 3.0, 10.0, 12.0, 11.0, 5.0]);
+//!
+//! let (mut encoder, encoded_train) = TargetEncoderFitter::new(1.0, 3)
+//!     .fit_transform(&inputs, &targets)
+//!     .unwrap();
+//!
+//! // Encode new data, unseen categories map to the global mean
+//! let encoded = encoder.transform(Matrix::new(2, 1, vec![1.0, 7.0])).unwrap();
+//! ```
+
+use learning::LearningResult;
+use learning::error::{Error, ErrorKind};
+use linalg::{Matrix, BaseMatrix, BaseMatrixMut, Vector};
+use data::transforms::Transformer;
+
+use std::collections::HashMap;
+
+/// A builder used to construct a `TargetEncoder`
+#[derive(Clone, Copy, Debug)]
+pub struct TargetEncoderFitter {
+    /// The weight of the global mean in each category's encoding
+    smoothing: f64,
+    /// The number of folds used to encode the training data
+    folds: usize,
+}
+
+/// Create a `TargetEncoderFitter` with a smoothing of 1 and 5 folds.
+impl Default for TargetEncoderFitter {
+    fn default() -> Self {
+        TargetEncoderFitter {
+            smoothing: 1f64,
+            folds: 5,
+        }
+    }
+}
+
+impl TargetEncoderFitter {
+    /// Construct a new `TargetEncoderFitter`.
+    ///
+    /// A category seen `n` times with mean target `m` is encoded as
+    /// `(n * m + smoothing * global_mean) / (n + smoothing)`.
+    ///
+    /// With a single fold `fit_transform` encodes the training data using
+    /// statistics computed from all of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::data::encoding::TargetEncoderFitter;
+    ///
+    /// let fitter = TargetEncoderFitter::new(10.0, 5);
+    /// ```
+    pub fn new(smoothing: f64, folds: usize) -> TargetEncoderFitter {
+        assert!(smoothing >= 0f64, "The smoothing cannot be negative.");
+        assert!(folds > 0, "There must be at least one fold.");
+
+        TargetEncoderFitter {
+            smoothing: smoothing,
+            folds: folds,
+        }
+    }
+
+    /// Fit the encoder to the inputs and targets.
+    pub fn fit(self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<TargetEncoder> {
+        try!(check_data(inputs, targets));
+        Ok(self.encoder(inputs, targets, |_| true))
+    }
+
+    /// Fit the encoder and encode the training data without leakage.
+    ///
+    /// Row `i` belongs to fold `i % folds` and is encoded by an encoder
+    /// fitted on the other folds. The returned encoder is fitted on all
+    /// of the data and should be used for new data.
+    pub fn fit_transform(self,
+                         inputs: &Matrix<f64>,
+                         targets: &Vector<f64>)
+                         -> LearningResult<(TargetEncoder, Matrix<f64>)> {
+        try!(check_data(inputs, targets));
+
+        let encoder = self.encoder(inputs, targets, |_| true);
+        if self.folds == 1 {
+            let encoded = encoder.encode(inputs.clone());
+            return Ok((encoder, encoded));
+        }
+
+        if inputs.rows() < self.folds {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "There must be at least as many rows as folds."));
+        }
+
+        let mut encoded = inputs.clone();
+        for fold in 0..self.folds {
+            let fold_encoder = self.encoder(inputs, targets, |i| i % self.folds != fold);
+
+            for (i, mut row) in encoded.row_iter_mut().enumerate() {
+                if i % self.folds == fold {
+                    fold_encoder.encode_row(row.raw_slice_mut());
+                }
+            }
+        }
+
+        Ok((encoder, encoded))
+    }
+
+    /// Fits an encoder to the rows selected by the filter.
+    fn encoder<F: Fn(usize) -> bool>(&self,
+                                     inputs: &Matrix<f64>,
+                                     targets: &Vector<f64>,
+                                     use_row: F)
+                                     -> TargetEncoder {
+        let mut stats = vec![HashMap::new(); inputs.cols()];
+        let mut total = 0f64;
+        let mut count = 0f64;
+
+        for (i, (row, &t)) in inputs.row_iter().zip(targets.data()).enumerate() {
+            if !use_row(i) {
+                continue;
+            }
+
+            total += t;
+            count += 1f64;
+            for (column, &x) in stats.iter_mut().zip(row.raw_slice()) {
+                let entry = column.entry(category_key(x)).or_insert((0f64, 0f64));
+                entry.0 += t;
+                entry.1 += 1f64;
+            }
+        }
+
+        let global_mean = total / count;
+        let encodings = stats.into_iter()
+            .map(|column| {
+                column.into_iter()
+                    .map(|(key, (sum, n))| {
+                        (key, (sum + self.smoothing * global_mean) / (n + self.smoothing))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        TargetEncoder {
+            global_mean: global_mean,
+            encodings: encodings,
+        }
+    }
+}
+
+/// The Target Encoder
+///
+/// Replaces each category with its smoothed mean target value. Created
+/// using a `TargetEncoderFitter`.
+#[derive(Debug)]
+pub struct TargetEncoder {
+    /// The mean of all the fitted targets
+    global_mean: f64,
+    /// The encoding of each category, per column
+    encodings: Vec<HashMap<u64, f64>>,
+}
+
+impl TargetEncoder {
+    /// The mean of all the fitted targets.
+    ///
+    /// Categories not seen while fitting are encoded as this value.
+    pub fn global_mean(&self) -> f64 {
+        self.global_mean
+    }
+
+    /// The encoding of a category in the given column, if it was seen while fitting.
+    pub fn encoding(&self, column: usize, category: f64) -> Option<f64> {
+        self.encodings[column].get(&category_key(category)).cloned()
+    }
+
+    fn encode_row(&self, row: &mut [f64]) {
+        for (x, column) in row.iter_mut().zip(self.encodings.iter()) {
+            *x = column.get(&category_key(*x)).cloned().unwrap_or(self.global_mean);
+        }
+    }
+
+    fn encode(&self, mut inputs: Matrix<f64>) -> Matrix<f64> {
+        for mut row in inputs.row_iter_mut() {
+            self.encode_row(row.raw_slice_mut());
+        }
+        inputs
+    }
+}
+
+impl Transformer<Matrix<f64>> for TargetEncoder {
+    fn transform(&mut self, inputs: Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        if inputs.cols() != self.encodings.len() {
+            Err(Error::new(ErrorKind::InvalidData,
+                           "Input data has different number of columns from fitted data."))
+        } else {
+            Ok(self.encode(inputs))
+        }
+    }
+}
+
+/// Checks the inputs and targets can be used for fitting.
+fn check_data(inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
+    if inputs.rows() != targets.size() {
+        Err(Error::new(ErrorKind::InvalidData,
+                       "The inputs and targets must have the same number of rows."))
+    } else if inputs.rows() == 0 {
+        Err(Error::new(ErrorKind::InvalidData, "Cannot fit an encoder to empty data."))
+    } else {
+        Ok(())
+    }
+}
+
+/// The key identifying a category.
+///
+/// Adding zero maps -0.0 to 0.0 so they are the same category.
+fn category_key(x: f64) -> u64 {
+    (x + 0f64).to_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TargetEncoderFitter;
+    use data::transforms::Transformer;
+    use linalg::{Matrix, Vector};
+
+    fn data() -> (Matrix<f64>, Vector<f64>) {
+        // Category 0 has mean 2, category 1 has mean 11 and category 2 is rare
+        let inputs = Matrix::new(9, 1, vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 2.0]);
+        let targets = Vector::new(vec![1.0, 3.0, 1.0, 3.0, 10.0, 12.0, 10.0, 12.0, 20.0]);
+        (inputs, targets)
+    }
+
+    #[test]
+    fn encodes_category_means() {
+        let (inputs, targets) = data();
+        let mut encoder = TargetEncoderFitter::new(0.0, 1).fit(&inputs, &targets).unwrap();
+
+        assert_eq!(encoder.global_mean(), 8.0);
+        assert_eq!(encoder.encoding(0, 0.0), Some(2.0));
+        assert_eq!(encoder.encoding(0, 1.0), Some(11.0));
+        assert_eq!(encoder.encoding(0, 2.0), Some(20.0));
+
+        // Unseen categories map to the global mean
+        let encoded = encoder.transform(Matrix::new(2, 1, vec![1.0, 5.0])).unwrap();
+        assert_eq!(encoded.into_vec(), vec![11.0, 8.0]);
+
+        assert!(encoder.transform(Matrix::new(1, 2, vec![1.0, 0.0])).is_err());
+    }
+
+    #[test]
+    fn smoothing_pulls_rare_categories() {
+        let (inputs, targets) = data();
+        let encoder = TargetEncoderFitter::new(1.0, 1).fit(&inputs, &targets).unwrap();
+
+        // The rare category moves halfway to the global mean
+        assert_eq!(encoder.encoding(0, 2.0), Some(14.0));
+        // Frequent categories move much less
+        assert_eq!(encoder.encoding(0, 0.0), Some(3.2));
+        assert_eq!(encoder.encoding(0, 1.0), Some(10.4));
+    }
+
+    #[test]
+    fn fit_transform_uses_other_folds() {
+        let (inputs, targets) = data();
+        let (encoder, encoded) = TargetEncoderFitter::new(0.0, 2)
+            .fit_transform(&inputs, &targets)
+            .unwrap();
+
+        // Even rows are encoded from odd rows and vice versa
+        let expected = vec![3.0, 1.0, 3.0, 1.0, 12.0, 10.0, 12.0, 10.0, 7.5];
+        assert_eq!(encoded.into_vec(), expected);
+
+        // The returned encoder uses all of the data
+        assert_eq!(encoder.encoding(0, 0.0), Some(2.0));
+
+        assert!(TargetEncoderFitter::new(1.0, 10).fit_transform(&inputs, &targets).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Feature type inference
+//!
+//! This module contains `infer_feature_types` which guesses the kind of
+//! each column of a dataset from its values. The result can be used to
+//! choose how each feature is encoded or scaled, for example scaling
+//! continuous features and target encoding categorical ones.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::data::feature_types::{infer_feature_types, FeatureType};
+//! use rusty_machine::linalg::Matrix;
+//!
+//! let data = Matrix::new(4, 3, vec![0.0, 3.0, 1.5,
+//!                                   1.0, 1.0, -0.2,
+//!                                   1.0, 2.0, 7.1,
+//!                                   0.0, 3.0, 2.4]);
+//!
+//! assert_eq!(infer_feature_types(&data),
+//!            vec![FeatureType::Binary, FeatureType::Categorical, FeatureType::Continuous]);
+//! ```
+
+use linalg::{Matrix, BaseMatrix};
+
+/// The largest number of distinct integer values of a categorical feature.
+const MAX_CATEGORIES: usize = 20;
+
+/// The kind of values taken by a feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureType {
+    /// Every value is the same, so the feature carries no information.
+    Constant,
+    /// The feature takes exactly two distinct values.
+    Binary,
+    /// The feature takes a few distinct integer values.
+    Categorical,
+    /// The feature takes fractional values or many distinct integers.
+    Continuous,
+}
+
+/// Infers the type of each column of the data.
+///
+/// A column is classified as:
+///
+/// - `Constant` if all of its values are the same.
+/// - `Binary` if it takes exactly two distinct values.
+/// - `Categorical` if all of its values are integers, with at most 20
+/// distinct values.
+/// - `Continuous` otherwise.
+///
+/// `NaN` values are treated as missing and ignored, so a column with
+/// no other values is `Constant`.
+pub fn infer_feature_types(data: &Matrix<f64>) -> Vec<FeatureType> {
+    (0..data.cols())
+        .map(|j| {
+            let mut values = data.col(j)
+                .iter()
+                .cloned()
+                .filter(|x| !x.is_nan())
+                .collect::<Vec<_>>();
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            values.dedup();
+
+            let integers = values.iter().all(|x| x.is_finite() && x.fract() == 0f64);
+            match values.len() {
+                0 | 1 => FeatureType::Constant,
+                2 => FeatureType::Binary,
+                n if integers && n <= MAX_CATEGORIES => FeatureType::Categorical,
+                _ => FeatureType::Continuous,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer_feature_types, FeatureType};
+    use linalg::Matrix;
+    use std::f64::NAN;
+
+    #[test]
+    fn classifies_columns() {
+        let mut data = Vec::new();
+        for i in 0..30 {
+            data.push((i % 2) as f64);
+            data.push((i % 5) as f64);
+            data.push(i as f64 * 0.37);
+            data.push(i as f64);
+            data.push(4.0);
+        }
+        let types = infer_feature_types(&Matrix::new(30, 5, data));
+
+        assert_eq!(types,
+                   vec![FeatureType::Binary,
+                        FeatureType::Categorical,
+                        FeatureType::Continuous,
+                        // Too many distinct integers to be categories
+                        FeatureType::Continuous,
+                        FeatureType::Constant]);
+    }
+
+    #[test]
+    fn ignores_missing_values() {
+        let data = Matrix::new(4, 3, vec![NAN, 1.0, NAN,
+                                          2.0, 2.0, NAN,
+                                          NAN, 3.0, NAN,
+                                          2.0, NAN, NAN]);
+
+        assert_eq!(infer_feature_types(&data),
+                   vec![FeatureType::Constant, FeatureType::Categorical, FeatureType::Constant]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Random Fourier Features
+//!
+//! This module contains the `RandomFourierFeatures` transformer which maps
+//! inputs into a randomized feature space. The inner products of the
+//! transformed inputs approximate the squared exponential (RBF) kernel, so
+//! a linear model trained on the transformed data approximates a kernel
+//! model, such as kernel ridge regression or an SVM, at a fraction of the
+//! cost on large datasets.
+//!
+//! Each feature is `sqrt(2 * ampl / D) * cos(w · x + b)` where `D` is the
+//! number of features, `w` is drawn from a gaussian with standard deviation
+//! `1 / ls` and `b` uniformly from `[0, 2π)`. The error of the approximation
+//! shrinks like `1 / sqrt(D)`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::data::transforms::{Transformer, TransformFitter};
+//! use rusty_machine::data::transforms::fourier::RandomFourierFeaturesFitter;
+//! use rusty_machine::learning::toolkit::kernel::SquaredExp;
+//! use rusty_machine::linalg::{Matrix, BaseMatrix};
+//!
+//! let inputs = Matrix::new(3, 2, vec![0.0, 1.0, 0.5, 0.2, -1.0, 0.3]);
+//!
+//! // Draw the random frequencies for inputs with two columns
+//! let fitter = RandomFourierFeaturesFitter::new(SquaredExp::new(1.0, 1.0), 500, 42);
+//! let mut rff = fitter.fit(&inputs).unwrap();
+//!
+//! let features = rff.transform(inputs).unwrap();
+//! assert_eq!(features.cols(), 500);
+//! ```
+
+use learning::LearningResult;
+use learning::error::{Error, ErrorKind};
+use learning::toolkit::kernel::SquaredExp;
+use linalg::{Matrix, BaseMatrix};
+use super::{Transformer, TransformFitter};
+
+use rand::{Rng, SeedableRng, StdRng};
+use rand::distributions::{IndependentSample, Normal};
+
+use std::f64::consts::PI;
+
+/// A builder used to construct `RandomFourierFeatures`
+#[derive(Clone, Copy, Debug)]
+pub struct RandomFourierFeaturesFitter {
+    /// The kernel being approximated
+    kernel: SquaredExp,
+    /// The number of random features
+    n_features: usize,
+    /// The seed of the random frequencies and offsets
+    seed: usize,
+}
+
+impl RandomFourierFeaturesFitter {
+    /// Constructs a fitter for `n_features` random features.
+    ///
+    /// The same seed gives the same features for inputs with the same
+    /// number of columns.
+    ///
+    /// # Panics
+    ///
+    /// - The number of features is zero.
+    /// - The length scale of the kernel is not positive.
+    pub fn new(kernel: SquaredExp, n_features: usize, seed: usize) -> RandomFourierFeaturesFitter {
+        assert!(n_features > 0, "There must be at least one random feature.");
+        assert!(kernel.ls > 0f64, "The kernel length scale must be positive.");
+
+        RandomFourierFeaturesFitter {
+            kernel: kernel,
+            n_features: n_features,
+            seed: seed,
+        }
+    }
+}
+
+impl TransformFitter<Matrix<f64>, RandomFourierFeatures> for RandomFourierFeaturesFitter {
+    /// Draws the frequencies and offsets for inputs with the columns of `inputs`.
+    fn fit(self, inputs: &Matrix<f64>) -> LearningResult<RandomFourierFeatures> {
+        if inputs.cols() == 0 {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Cannot fit random features to data without columns."));
+        }
+
+        let mut rng = StdRng::from_seed(&[self.seed]);
+        let normal = Normal::new(0f64, 1f64 / self.kernel.ls);
+
+        let freqs = (0..inputs.cols() * self.n_features)
+            .map(|_| normal.ind_sample(&mut rng))
+            .collect::<Vec<_>>();
+        let offsets = (0..self.n_features)
+            .map(|_| rng.gen_range(0f64, 2f64 * PI))
+            .collect::<Vec<_>>();
+
+        Ok(RandomFourierFeatures {
+            freqs: Matrix::new(inputs.cols(), self.n_features, freqs),
+            offsets: offsets,
+            scale: (2f64 * self.kernel.ampl / self.n_features as f64).sqrt(),
+        })
+    }
+}
+
+/// Random Fourier Features
+///
+/// Approximates the squared exponential kernel with an explicit feature map.
+///
+/// The random frequencies are drawn by `RandomFourierFeaturesFitter`, and
+/// the transformed data must have the same number of columns as the data
+/// it was fitted to.
+#[derive(Debug)]
+pub struct RandomFourierFeatures {
+    /// The frequencies, one column per feature
+    freqs: Matrix<f64>,
+    /// The offset of each feature
+    offsets: Vec<f64>,
+    /// The scale of the features, `sqrt(2 * ampl / D)`
+    scale: f64,
+}
+
+impl RandomFourierFeatures {
+    /// The number of random features.
+    pub fn n_features(&self) -> usize {
+        self.offsets.len()
+    }
+}
+
+impl Transformer<Matrix<f64>> for RandomFourierFeatures {
+    fn transform(&mut self, inputs: Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        if self.freqs.rows() != inputs.cols() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Input data has different number of columns than fitted data."));
+        }
+
+        let mut features = inputs * &self.freqs;
+        for row in features.mut_data().chunks_mut(self.offsets.len()) {
+            for (z, b) in row.iter_mut().zip(&self.offsets) {
+                *z = self.scale * (*z + b).cos();
+            }
+        }
+
+        Ok(features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RandomFourierFeaturesFitter;
+    use data::transforms::{Transformer, TransformFitter};
+    use learning::toolkit::kernel::{Kernel, SquaredExp};
+    use linalg::{Matrix, BaseMatrix};
+
+    /// The largest error of the approximated kernel between the rows.
+    fn max_error(n_features: usize, seed: usize) -> f64 {
+        let kernel = SquaredExp::new(1.5, 2.0);
+        let inputs = Matrix::new(4, 3, vec![0.0, 0.0, 0.0,
+                                            0.5, -0.2, 1.0,
+                                            1.0, 1.0, 0.3,
+                                            -0.7, 0.4, 0.1]);
+
+        let mut rff = RandomFourierFeaturesFitter::new(kernel, n_features, seed).fit(&inputs).unwrap();
+        let features = rff.transform(inputs.clone()).unwrap();
+
+        let mut error = 0f64;
+        for i in 0..4 {
+            for j in 0..4 {
+                let approx = features.row(i)
+                    .iter()
+                    .zip(features.row(j).iter())
+                    .fold(0f64, |acc, (a, b)| acc + a * b);
+                let exact = kernel.kernel(inputs.row(i).raw_slice(), inputs.row(j).raw_slice());
+                error = error.max((approx - exact).abs());
+            }
+        }
+        error
+    }
+
+    #[test]
+    fn approximates_kernel() {
+        assert!(max_error(20000, 1) < 0.1);
+
+        // More features give a better approximation on average
+        let few = (0..5).map(|s| max_error(10, s)).sum::<f64>();
+        let many = (0..5).map(|s| max_error(5000, s)).sum::<f64>();
+        assert!(many < few);
+    }
+
+    #[test]
+    fn same_seed_same_features() {
+        let inputs = Matrix::new(2, 2, vec![0.3, -0.1, 1.0, 2.0]);
+
+        let fitter = RandomFourierFeaturesFitter::new(SquaredExp::default(), 8, 7);
+        let mut a = fitter.fit(&inputs).unwrap();
+        let mut b = fitter.fit(&inputs).unwrap();
+        assert_eq!(a.n_features(), 8);
+        assert_eq!(a.transform(inputs.clone()).unwrap(), b.transform(inputs.clone()).unwrap());
+
+        // Transforming leaves the features unchanged
+        assert_eq!(a.transform(inputs.clone()).unwrap(), b.transform(inputs.clone()).unwrap());
+
+        // The fitted frequencies require the same number of columns
+        assert!(a.transform(Matrix::new(1, 3, vec![0.0; 3])).is_err());
+    }
+}
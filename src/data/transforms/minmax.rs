@@ -28,7 +28,7 @@
 use learning::error::{Error, ErrorKind};
 use learning::LearningResult;
 use linalg::{Matrix, BaseMatrix, BaseMatrixMut, Vector};
-use super::{Invertible, Transformer, TransformFitter};
+use super::{Invertible, Transformer, TransformFitter, RangeCheck};
 
 use rulinalg::utils;
 
@@ -38,14 +38,16 @@ use libnum::Float;
 #[derive(Debug)]
 pub struct MinMaxFitter<T: Float> {
     scaled_min: T,
-    scaled_max: T
+    scaled_max: T,
+    range_tolerance: Option<T>,
 }
 
 impl<T: Float> Default for MinMaxFitter<T> {
     fn default() -> Self {
         MinMaxFitter {
             scaled_min: T::zero(),
-            scaled_max: T::one()
+            scaled_max: T::one(),
+            range_tolerance: None,
         }
     }
 }
@@ -74,9 +76,34 @@ impl<T: Float> MinMaxFitter<T> {
     pub fn new(min: T, max: T) -> Self {
         MinMaxFitter {
             scaled_min: min,
-            scaled_max: max
+            scaled_max: max,
+            range_tolerance: None,
         }
     }
+
+    /// Reject data far outside the fitted range at transform time.
+    ///
+    /// Values may exceed the fitted minimum or maximum of each column
+    /// by up to `tolerance` times that column's range. Transforming data
+    /// beyond this returns an `InvalidData` error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::data::transforms::{Transformer, TransformFitter, MinMaxFitter};
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let mat = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 5.0]);
+    /// let mut transformer = MinMaxFitter::new(0.0, 1.0).with_range_check(0.5).fit(&mat).unwrap();
+    ///
+    /// assert!(transformer.transform(Matrix::new(1, 2, vec![3.5, 1.0])).is_ok());
+    /// assert!(transformer.transform(Matrix::new(1, 2, vec![100.0, 1.0])).is_err());
+    /// ```
+    pub fn with_range_check(mut self, tolerance: T) -> Self {
+        assert!(tolerance >= T::zero(), "The range tolerance cannot be negative.");
+        self.range_tolerance = Some(tolerance);
+        self
+    }
 }
 
 impl<T: Float> TransformFitter<Matrix<T>, MinMaxScaler<T>> for MinMaxFitter<T> {
@@ -128,7 +155,8 @@ impl<T: Float> TransformFitter<Matrix<T>, MinMaxScaler<T>> for MinMaxFitter<T> {
         
         Ok(MinMaxScaler {
             scale_factors: Vector::new(scales),
-            const_factors: Vector::new(consts)
+            const_factors: Vector::new(consts),
+            range_check: self.range_tolerance.map(|tol| RangeCheck::new(inputs, tol)),
         })
     }
 }
@@ -146,6 +174,8 @@ pub struct MinMaxScaler<T: Float> {
     scale_factors: Vector<T>,
     /// Values to add to each column after scaling
     const_factors: Vector<T>,
+    /// Bounds new data is checked against, if enabled
+    range_check: Option<RangeCheck<T>>,
 }
 
 
@@ -155,6 +185,10 @@ impl<T: Float> Transformer<Matrix<T>> for MinMaxScaler<T> {
             Err(Error::new(ErrorKind::InvalidData,
                             "Input data has different number of columns than fitted data."))
         } else {
+            if let Some(ref range_check) = self.range_check {
+                try!(range_check.check(&inputs));
+            }
+
             for mut row in inputs.row_iter_mut() {
                 utils::in_place_vec_bin_op(row.raw_slice_mut(), self.scale_factors.data(), |x, &y| {
                     *x = *x * y;
@@ -264,4 +298,28 @@ mod tests {
 
         assert!((inputs - original).data().iter().all(|x| x.abs() < 1e-5));
     }
+
+    #[test]
+    fn range_check_test() {
+        let inputs = Matrix::new(3, 2, vec![0.0, 10.0, 1.0, 20.0, 2.0, 30.0]);
+
+        let mut scaler = MinMaxFitter::new(0.0, 1.0).with_range_check(0.1).fit(&inputs).unwrap();
+
+        // Within range, and new extrema inside the tolerance
+        assert!(scaler.transform(inputs.clone()).is_ok());
+        assert!(scaler.transform(Matrix::new(1, 2, vec![2.15, 8.5])).is_ok());
+
+        // Beyond the tolerance in either direction
+        assert!(scaler.transform(Matrix::new(1, 2, vec![2.3, 20.0])).is_err());
+        assert!(scaler.transform(Matrix::new(1, 2, vec![1.0, -100.0])).is_err());
+        assert!(scaler.transform(Matrix::new(1, 2, vec![f64::NAN, 20.0])).is_err());
+    }
+
+    #[test]
+    fn no_range_check_test() {
+        let inputs = Matrix::new(2, 2, vec![0.0, 10.0, 1.0, 20.0]);
+
+        let mut scaler = MinMaxFitter::new(0.0, 1.0).fit(&inputs).unwrap();
+        assert!(scaler.transform(Matrix::new(1, 2, vec![1000.0, -1000.0])).is_ok());
+    }
 }
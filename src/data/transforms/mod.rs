@@ -25,13 +25,19 @@
 //! let transformed = scaler.transform(data).expect("Failed to transformer data");
 //! ```
 
+pub mod fourier;
 pub mod minmax;
 pub mod normalize;
 pub mod standardize;
 pub mod shuffle;
 
 use learning::LearningResult;
+use learning::error::{Error, ErrorKind};
+use linalg::{Matrix, BaseMatrix};
 
+use libnum::Float;
+
+pub use self::fourier::RandomFourierFeaturesFitter;
 pub use self::minmax::MinMaxFitter;
 pub use self::normalize::Normalizer;
 pub use self::shuffle::Shuffler;
@@ -54,3 +60,55 @@ pub trait Invertible<T> : Transformer<T> {
     /// Maps the inputs using the inverse of the fitted transform.
     fn inv_transform(&self, inputs: T) -> LearningResult<T>;
 }
+
+/// Per-column bounds recorded while fitting a scaler.
+///
+/// Used to reject data at transform time which lies far outside
+/// the range seen during fitting. Each column accepts values up to
+/// `tolerance` times the fitted range beyond either extreme.
+#[derive(Debug)]
+struct RangeCheck<T: Float> {
+    lower: Vec<T>,
+    upper: Vec<T>,
+}
+
+impl<T: Float> RangeCheck<T> {
+    /// Record the column ranges of the inputs, widened by the tolerance.
+    fn new(inputs: &Matrix<T>, tolerance: T) -> RangeCheck<T> {
+        let mut lower = vec![T::infinity(); inputs.cols()];
+        let mut upper = vec![T::neg_infinity(); inputs.cols()];
+
+        for row in inputs.row_iter() {
+            for ((x, min), max) in row.raw_slice().iter().zip(lower.iter_mut()).zip(upper.iter_mut()) {
+                *min = min.min(*x);
+                *max = max.max(*x);
+            }
+        }
+
+        for (min, max) in lower.iter_mut().zip(upper.iter_mut()) {
+            let margin = (*max - *min) * tolerance;
+            *min = *min - margin;
+            *max = *max + margin;
+        }
+
+        RangeCheck {
+            lower: lower,
+            upper: upper,
+        }
+    }
+
+    /// Check that every value lies within the recorded bounds.
+    fn check(&self, inputs: &Matrix<T>) -> LearningResult<()> {
+        for row in inputs.row_iter() {
+            for (idx, x) in row.raw_slice().iter().enumerate() {
+                if !(*x >= self.lower[idx] && *x <= self.upper[idx]) {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          format!("Data point in column {} is outside of the \
+                                                   range seen during fitting",
+                                                  idx)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
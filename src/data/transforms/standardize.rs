@@ -27,7 +27,7 @@
 use learning::LearningResult;
 use learning::error::{Error, ErrorKind};
 use linalg::{Matrix, Vector, Axes, BaseMatrix, BaseMatrixMut};
-use super::{Invertible, Transformer, TransformFitter};
+use super::{Invertible, Transformer, TransformFitter, RangeCheck};
 
 use rulinalg::utils;
 
@@ -37,14 +37,16 @@ use libnum::{Float, FromPrimitive};
 #[derive(Debug)]
 pub struct StandardizerFitter<T: Float> {
     scaled_mean: T,
-    scaled_stdev: T
+    scaled_stdev: T,
+    range_tolerance: Option<T>,
 }
 
 impl<T: Float> Default for StandardizerFitter<T> {
     fn default() -> Self {
         StandardizerFitter {
             scaled_mean: T::zero(),
-            scaled_stdev: T::one()
+            scaled_stdev: T::one(),
+            range_tolerance: None,
         }
     }
 }
@@ -73,9 +75,21 @@ impl<T: Float> StandardizerFitter<T> {
     pub fn new(mean: T, stdev: T) -> StandardizerFitter<T> {
         StandardizerFitter {
             scaled_mean: mean,
-            scaled_stdev: stdev
+            scaled_stdev: stdev,
+            range_tolerance: None,
         }
     }
+
+    /// Reject data far outside the fitted range at transform time.
+    ///
+    /// Values may exceed the fitted minimum or maximum of each column
+    /// by up to `tolerance` times that column's range. Transforming data
+    /// beyond this returns an `InvalidData` error.
+    pub fn with_range_check(mut self, tolerance: T) -> Self {
+        assert!(tolerance >= T::zero(), "The range tolerance cannot be negative.");
+        self.range_tolerance = Some(tolerance);
+        self
+    }
 }
 
 impl<T: Float + FromPrimitive> TransformFitter<Matrix<T>, Standardizer<T>> for StandardizerFitter<T> {
@@ -97,7 +111,8 @@ impl<T: Float + FromPrimitive> TransformFitter<Matrix<T>, Standardizer<T>> for S
                 means: mean,
                 variances: variance,
                 scaled_mean: self.scaled_mean,
-                scaled_stdev: self.scaled_stdev
+                scaled_stdev: self.scaled_stdev,
+                range_check: self.range_tolerance.map(|tol| RangeCheck::new(inputs, tol)),
             })
         }
     }
@@ -120,6 +135,8 @@ pub struct Standardizer<T: Float> {
     scaled_mean: T,
     /// The standard deviation of the new data (default 1)
     scaled_stdev: T,
+    /// Bounds new data is checked against, if enabled
+    range_check: Option<RangeCheck<T>>,
 }
 
 impl<T: Float + FromPrimitive> Transformer<Matrix<T>> for Standardizer<T> {
@@ -128,6 +145,10 @@ impl<T: Float + FromPrimitive> Transformer<Matrix<T>> for Standardizer<T> {
             Err(Error::new(ErrorKind::InvalidData,
                             "Input data has different number of columns from fitted data."))
         } else {
+            if let Some(ref range_check) = self.range_check {
+                try!(range_check.check(&inputs));
+            }
+
             for mut row in inputs.row_iter_mut() {
                 // Subtract the mean
                 utils::in_place_vec_bin_op(row.raw_slice_mut(), self.means.data(), |x, &y| *x = *x - y);
@@ -244,4 +265,14 @@ mod tests {
 
         assert!((inputs - original).data().iter().all(|x| x.abs() < 1e-5));
     }
+
+    #[test]
+    fn range_check_test() {
+        let inputs = Matrix::new(3, 2, vec![0.0, 10.0, 1.0, 20.0, 2.0, 30.0]);
+
+        let mut standardizer = StandardizerFitter::default().with_range_check(0.5).fit(&inputs).unwrap();
+
+        assert!(standardizer.transform(Matrix::new(1, 2, vec![2.9, 0.5])).is_ok());
+        assert!(standardizer.transform(Matrix::new(1, 2, vec![50.0, 20.0])).is_err());
+    }
 }
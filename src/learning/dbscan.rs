@@ -16,6 +16,20 @@
 //! If a point is not within distance `eps` of a cluster it will be classified
 //! as noise. This means that it will be set to `None` in the clusters `Vector`.
 //!
+//! A suitable `eps` can be chosen using `k_distance_graph`, which gives the
+//! sorted distances from each point to its `k`-th nearest neighbour. The
+//! "knee" of this curve, found with `k_distance_knee`, separates points in
+//! dense regions from noise. Use `min_points = k + 1` alongside it, as each
+//! point counts itself as a neighbour.
+//!
+//! The `OPTICS` model orders the points by their reachability distance. This
+//! ordering captures the clusterings for every `eps` up to a maximum at once,
+//! and DBSCAN-like clusters can be extracted from it for any such `eps`.
+//!
+//! Data with many exactly repeated points can be clustered faster by using
+//! `self.set_deduplicate(true)`. Each distinct point is then only visited
+//! once, while still counting towards densities once per repeat.
+//!
 //! # Examples
 //!
 //! ```
@@ -43,6 +57,9 @@ use linalg::{Matrix, Vector, BaseMatrix};
 use rulinalg::utils;
 use rulinalg::matrix::Row;
 
+use std::collections::HashMap;
+use std::f64;
+
 /// DBSCAN Model
 ///
 /// Implements clustering using the DBSCAN algorithm
@@ -53,6 +70,7 @@ pub struct DBSCAN {
     min_points: usize,
     clusters: Option<Vector<Option<usize>>>,
     predictive: bool,
+    deduplicate: bool,
     _visited: Vec<bool>,
     _cluster_data: Option<Matrix<f64>>,
 }
@@ -69,6 +87,7 @@ impl Default for DBSCAN {
             min_points: 5,
             clusters: None,
             predictive: false,
+            deduplicate: false,
             _visited: Vec::new(),
             _cluster_data: None,
         }
@@ -78,22 +97,16 @@ impl Default for DBSCAN {
 impl UnSupModel<Matrix<f64>, Vector<Option<usize>>> for DBSCAN {
     /// Train the classifier using input data.
     fn train(&mut self, inputs: &Matrix<f64>) -> LearningResult<()> {
-        self.init_params(inputs.rows());
-        let mut cluster = 0;
-
-        for (idx, point) in inputs.row_iter().enumerate() {
-            let visited = self._visited[idx];
-
-            if !visited {
-                self._visited[idx] = true;
-
-                let neighbours = self.region_query(point, inputs);
-
-                if neighbours.len() >= self.min_points {
-                    self.expand_cluster(inputs, idx, neighbours, cluster);
-                    cluster += 1;
-                }
-            }
+        if self.deduplicate {
+            let (unique, counts, unique_idx) = unique_rows(inputs);
+            self.cluster(&unique, &counts);
+
+            let unique_clusters = self.clusters.take().expect("Clusters are set by training");
+            self.clusters = Some(Vector::new(unique_idx.iter()
+                                                       .map(|&i| unique_clusters[i])
+                                                       .collect::<Vec<_>>()));
+        } else {
+            self.cluster(inputs, &vec![1; inputs.rows()]);
         }
 
         if self.predictive {
@@ -148,6 +161,7 @@ impl DBSCAN {
             min_points: min_points,
             clusters: None,
             predictive: false,
+            deduplicate: false,
             _visited: Vec::new(),
             _cluster_data: None,
         }
@@ -162,13 +176,45 @@ impl DBSCAN {
         self.predictive = predictive;
     }
 
+    /// Set deduplicate to true to merge identical points before clustering.
+    ///
+    /// Each distinct point is visited once and counts towards the density
+    /// of its neighbours once for every time it is repeated, so the
+    /// clustering matches that of the original data. The labels are
+    /// expanded back to every original row.
+    pub fn set_deduplicate(&mut self, deduplicate: bool) {
+        self.deduplicate = deduplicate;
+    }
+
     /// Return an Option pointing to the model clusters.
     pub fn clusters(&self) -> Option<&Vector<Option<usize>>> {
         self.clusters.as_ref()
     }
 
+    /// Cluster the inputs where each row stands for `counts[i]` points.
+    fn cluster(&mut self, inputs: &Matrix<f64>, counts: &[usize]) {
+        self.init_params(inputs.rows());
+        let mut cluster = 0;
+
+        for (idx, point) in inputs.row_iter().enumerate() {
+            let visited = self._visited[idx];
+
+            if !visited {
+                self._visited[idx] = true;
+
+                let neighbours = self.region_query(point, inputs);
+
+                if total_count(&neighbours, counts) >= self.min_points {
+                    self.expand_cluster(inputs, counts, idx, neighbours, cluster);
+                    cluster += 1;
+                }
+            }
+        }
+    }
+
     fn expand_cluster(&mut self,
                       inputs: &Matrix<f64>,
+                      counts: &[usize],
                       point_idx: usize,
                       neighbour_pts: Vec<usize>,
                       cluster: usize) {
@@ -186,8 +232,8 @@ impl DBSCAN {
                 let data_point_row = unsafe { inputs.row_unchecked(*data_point_idx) };
                 let sub_neighbours = self.region_query(data_point_row, inputs);
 
-                if sub_neighbours.len() >= self.min_points {
-                    self.expand_cluster(inputs, *data_point_idx, sub_neighbours, cluster);
+                if total_count(&sub_neighbours, counts) >= self.min_points {
+                    self.expand_cluster(inputs, counts, *data_point_idx, sub_neighbours, cluster);
                 }
             }
         }
@@ -226,10 +272,326 @@ impl DBSCAN {
     }
 }
 
+/// OPTICS Model
+///
+/// Orders points by reachability distance (Ankerst et al. 1999), from which
+/// density based clusterings can be extracted for any `eps` up to `max_eps`.
+///
+/// Points which are not density reachable from any core point within
+/// `max_eps` have an undefined reachability, stored as infinity.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::dbscan::OPTICS;
+/// use rusty_machine::learning::UnSupModel;
+/// use rusty_machine::linalg::Matrix;
+///
+/// let inputs = Matrix::new(6, 2, vec![1.0, 2.0,
+///                                     1.1, 2.2,
+///                                     0.9, 1.9,
+///                                     1.0, 2.1,
+///                                     -2.0, 3.0,
+///                                     -2.2, 3.1]);
+///
+/// let mut model = OPTICS::new(2, 1.0);
+/// model.train(&inputs).unwrap();
+///
+/// let clustering = model.extract_dbscan(0.5).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct OPTICS {
+    min_points: usize,
+    max_eps: f64,
+    ordering: Option<Vec<usize>>,
+    reachability: Option<Vec<f64>>,
+    core_distances: Option<Vec<f64>>,
+}
+
+impl OPTICS {
+    /// Create a new OPTICS model with a minimum number of points
+    /// per cluster and the largest distance considered.
+    pub fn new(min_points: usize, max_eps: f64) -> OPTICS {
+        assert!(max_eps > 0f64, "The maximum epsilon must be positive.");
+
+        OPTICS {
+            min_points: min_points,
+            max_eps: max_eps,
+            ordering: None,
+            reachability: None,
+            core_distances: None,
+        }
+    }
+
+    /// The order in which the points were processed.
+    ///
+    /// Plotting `reachability()[i]` for each `i` in this order gives the
+    /// reachability plot, where valleys correspond to clusters.
+    pub fn ordering(&self) -> Option<&[usize]> {
+        self.ordering.as_ref().map(|x| &x[..])
+    }
+
+    /// The reachability distance of each point, indexed by the input rows.
+    pub fn reachability(&self) -> Option<&[f64]> {
+        self.reachability.as_ref().map(|x| &x[..])
+    }
+
+    /// The core distance of each point, indexed by the input rows.
+    ///
+    /// This is the smallest `eps` for which the point is a core point,
+    /// or infinity if it is not a core point for any `eps` up to `max_eps`.
+    pub fn core_distances(&self) -> Option<&[f64]> {
+        self.core_distances.as_ref().map(|x| &x[..])
+    }
+
+    /// Extract a DBSCAN clustering at the given `eps` from the ordering.
+    ///
+    /// The `eps` must not exceed `max_eps`. Core points are clustered as
+    /// `DBSCAN::new(eps, min_points)` would cluster them. Border points,
+    /// which are reachable from a core point without being core points
+    /// themselves, join the cluster they were reached from.
+    pub fn extract_dbscan(&self, eps: f64) -> LearningResult<Vector<Option<usize>>> {
+        if eps > self.max_eps {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "Cannot extract clusters for eps larger than max_eps."));
+        }
+
+        if let (&Some(ref ordering), &Some(ref reachability), &Some(ref core_distances)) =
+               (&self.ordering, &self.reachability, &self.core_distances) {
+            let mut clusters = vec![None; ordering.len()];
+            let mut current = None;
+            let mut next_cluster = 0;
+
+            for &idx in ordering {
+                if reachability[idx] < eps {
+                    clusters[idx] = current;
+                } else if core_distances[idx] < eps {
+                    current = Some(next_cluster);
+                    next_cluster += 1;
+                    clusters[idx] = current;
+                }
+            }
+
+            Ok(Vector::new(clusters))
+        } else {
+            Err(Error::new_untrained())
+        }
+    }
+
+    /// Finds the points within `max_eps` of the point and their distances.
+    fn neighbour_distances(&self, point: Row<f64>, inputs: &Matrix<f64>) -> Vec<(usize, f64)> {
+        let mut neighbours = Vec::new();
+        for (idx, data_point) in inputs.row_iter().enumerate() {
+            let point_distance = utils::vec_bin_op(data_point.raw_slice(), point.raw_slice(), |x, y| x - y);
+            let dist = utils::dot(&point_distance, &point_distance).sqrt();
+
+            if dist < self.max_eps {
+                neighbours.push((idx, dist));
+            }
+        }
+        neighbours
+    }
+
+    /// The distance to the `min_points`-th closest neighbour, counting the point itself.
+    fn core_distance(&self, neighbours: &[(usize, f64)]) -> f64 {
+        if self.min_points == 0 {
+            return 0f64;
+        }
+        if neighbours.len() < self.min_points {
+            return f64::INFINITY;
+        }
+
+        let mut distances = neighbours.iter().map(|&(_, d)| d).collect::<Vec<_>>();
+        distances.sort_by(|a, b| a.partial_cmp(b).expect("Distances must not be NaN"));
+        distances[self.min_points - 1]
+    }
+}
+
+impl UnSupModel<Matrix<f64>, Vector<Option<usize>>> for OPTICS {
+    /// Compute the reachability ordering of the inputs.
+    fn train(&mut self, inputs: &Matrix<f64>) -> LearningResult<()> {
+        let n = inputs.rows();
+        let mut processed = vec![false; n];
+        let mut in_seeds = vec![false; n];
+        let mut reachability = vec![f64::INFINITY; n];
+        let mut core_distances = vec![f64::INFINITY; n];
+        let mut ordering = Vec::with_capacity(n);
+
+        for start in 0..n {
+            if processed[start] {
+                continue;
+            }
+
+            let mut seeds = vec![start];
+            in_seeds[start] = true;
+
+            while !seeds.is_empty() {
+                // Process the seed with the smallest reachability next
+                let (pos, _) = seeds.iter()
+                    .enumerate()
+                    .fold((0, f64::INFINITY), |(best, best_r), (i, &idx)| {
+                        if reachability[idx] < best_r {
+                            (i, reachability[idx])
+                        } else {
+                            (best, best_r)
+                        }
+                    });
+                let idx = seeds.swap_remove(pos);
+                in_seeds[idx] = false;
+                processed[idx] = true;
+                ordering.push(idx);
+
+                let point = unsafe { inputs.row_unchecked(idx) };
+                let neighbours = self.neighbour_distances(point, inputs);
+                let core_dist = self.core_distance(&neighbours);
+                core_distances[idx] = core_dist;
+
+                if core_dist.is_finite() {
+                    for (other, dist) in neighbours {
+                        if processed[other] {
+                            continue;
+                        }
+                        let reach = core_dist.max(dist);
+                        if reach < reachability[other] {
+                            reachability[other] = reach;
+                        }
+                        if !in_seeds[other] {
+                            in_seeds[other] = true;
+                            seeds.push(other);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.ordering = Some(ordering);
+        self.reachability = Some(reachability);
+        self.core_distances = Some(core_distances);
+        Ok(())
+    }
+
+    /// OPTICS does not classify new points.
+    ///
+    /// Use `extract_dbscan` to obtain a clustering of the training data.
+    fn predict(&self, _: &Matrix<f64>) -> LearningResult<Vector<Option<usize>>> {
+        Err(Error::new(ErrorKind::InvalidState,
+                       "OPTICS cannot predict new points. Use `self.extract_dbscan(eps)`."))
+    }
+}
+
+/// Computes the k-distance graph of the inputs.
+///
+/// Returns the distance from each point to its `k`-th nearest neighbour
+/// (not counting the point itself), sorted in ascending order. The knee
+/// of this curve suggests a value for `eps`.
+///
+/// `k` must be at least 1 and less than the number of points.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::dbscan::{k_distance_graph, k_distance_knee};
+/// use rusty_machine::linalg::Matrix;
+///
+/// let inputs = Matrix::new(5, 1, vec![0.0, 0.1, 0.2, 0.3, 5.0]);
+/// let distances = k_distance_graph(&inputs, 2).unwrap();
+///
+/// let eps = k_distance_knee(&distances);
+/// ```
+pub fn k_distance_graph(inputs: &Matrix<f64>, k: usize) -> LearningResult<Vec<f64>> {
+    if k == 0 || k >= inputs.rows() {
+        return Err(Error::new(ErrorKind::InvalidParameters,
+                              "k must be positive and less than the number of points."));
+    }
+
+    let mut k_distances = Vec::with_capacity(inputs.rows());
+    let mut distances = Vec::with_capacity(inputs.rows() - 1);
+    for (i, point) in inputs.row_iter().enumerate() {
+        distances.clear();
+        for (j, other) in inputs.row_iter().enumerate() {
+            if i != j {
+                let diff = utils::vec_bin_op(point.raw_slice(), other.raw_slice(), |x, y| x - y);
+                distances.push(utils::dot(&diff, &diff).sqrt());
+            }
+        }
+        distances.sort_by(|a, b| a.partial_cmp(b).expect("Distances must not be NaN"));
+        k_distances.push(distances[k - 1]);
+    }
+
+    k_distances.sort_by(|a, b| a.partial_cmp(b).expect("Distances must not be NaN"));
+    Ok(k_distances)
+}
+
+/// Finds the knee of a sorted k-distance graph.
+///
+/// The knee is taken as the point lying furthest below the straight line
+/// joining the first and last points, after scaling both axes to `[0, 1]`.
+///
+/// Returns `None` if there are fewer than three distances or the curve is flat.
+pub fn k_distance_knee(distances: &[f64]) -> Option<f64> {
+    let n = distances.len();
+    if n < 3 {
+        return None;
+    }
+
+    let (first, last) = (distances[0], distances[n - 1]);
+    let range = last - first;
+    if !(range > 0f64) {
+        return None;
+    }
+
+    let mut knee = None;
+    let mut max_gap = 0f64;
+    for (i, d) in distances.iter().enumerate() {
+        let x = i as f64 / (n - 1) as f64;
+        let y = (d - first) / range;
+        if x - y > max_gap {
+            max_gap = x - y;
+            knee = Some(*d);
+        }
+    }
+
+    knee
+}
+
+/// The number of points represented by the given rows.
+fn total_count(indices: &[usize], counts: &[usize]) -> usize {
+    indices.iter().map(|&i| counts[i]).sum()
+}
+
+/// Finds the distinct rows of the inputs.
+///
+/// Returns the distinct rows, how many times each occurs, and the
+/// index of the distinct row matching each input row.
+fn unique_rows(inputs: &Matrix<f64>) -> (Matrix<f64>, Vec<usize>, Vec<usize>) {
+    let mut seen = HashMap::new();
+    let mut unique_data = Vec::new();
+    let mut counts = Vec::new();
+    let mut unique_idx = Vec::with_capacity(inputs.rows());
+
+    for row in inputs.row_iter() {
+        // Adding zero maps -0.0 to 0.0 so they compare equal
+        let key = row.raw_slice().iter().map(|x| (x + 0f64).to_bits()).collect::<Vec<_>>();
+        let next_idx = counts.len();
+        let idx = *seen.entry(key).or_insert(next_idx);
+
+        if idx == next_idx {
+            unique_data.extend_from_slice(row.raw_slice());
+            counts.push(0);
+        }
+        counts[idx] += 1;
+        unique_idx.push(idx);
+    }
+
+    (Matrix::new(counts.len(), inputs.cols(), unique_data), counts, unique_idx)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::DBSCAN;
+    use super::{DBSCAN, OPTICS, unique_rows, k_distance_graph, k_distance_knee};
+    use learning::UnSupModel;
     use linalg::{Matrix, BaseMatrix};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_region_query() {
@@ -256,4 +618,162 @@ mod tests {
 
         assert!(neighbours.len() == 1);
     }
+
+    #[test]
+    fn test_unique_rows() {
+        let inputs = Matrix::new(4, 2, vec![1.0, 2.0, 0.0, 1.0, 1.0, 2.0, -0.0, 1.0]);
+        let (unique, counts, idx) = unique_rows(&inputs);
+
+        assert_eq!(unique, Matrix::new(2, 2, vec![1.0, 2.0, 0.0, 1.0]));
+        assert_eq!(counts, vec![2, 2]);
+        assert_eq!(idx, vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_deduplicate_matches() {
+        let base = vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 5.0, 5.0, 5.1, 5.2, 9.0, 9.0];
+        let mut data = Vec::new();
+        for i in 0..6 {
+            // Repeat each point a different number of times
+            for _ in 0..(i % 3 + 1) * 4 {
+                data.extend_from_slice(&base[2 * i..2 * i + 2]);
+            }
+        }
+        let inputs = Matrix::new(data.len() / 2, 2, data);
+
+        let mut model = DBSCAN::new(0.5, 6);
+        model.train(&inputs).unwrap();
+
+        let mut dedup_model = DBSCAN::new(0.5, 6);
+        dedup_model.set_deduplicate(true);
+        dedup_model.train(&inputs).unwrap();
+
+        assert_eq!(model.clusters().unwrap().data(), dedup_model.clusters().unwrap().data());
+        assert_eq!(dedup_model.clusters().unwrap().size(), inputs.rows());
+    }
+
+    #[test]
+    fn test_deduplicate_all_identical() {
+        let inputs = Matrix::new(10, 2, vec![1.0; 20]);
+
+        let mut model = DBSCAN::new(0.5, 5);
+        model.set_deduplicate(true);
+        model.train(&inputs).unwrap();
+        assert!(model.clusters().unwrap().iter().all(|c| *c == Some(0)));
+
+        let mut model = DBSCAN::new(0.5, 11);
+        model.set_deduplicate(true);
+        model.train(&inputs).unwrap();
+        assert!(model.clusters().unwrap().iter().all(|c| c.is_none()));
+    }
+
+    #[test]
+    fn test_k_distance_graph() {
+        let inputs = Matrix::new(4, 1, vec![0.0, 1.0, 3.0, 7.0]);
+
+        let distances = k_distance_graph(&inputs, 1).unwrap();
+        assert_eq!(distances, vec![1.0, 1.0, 2.0, 4.0]);
+
+        let distances = k_distance_graph(&inputs, 3).unwrap();
+        assert_eq!(distances, vec![4.0, 6.0, 7.0, 7.0]);
+
+        assert!(k_distance_graph(&inputs, 0).is_err());
+        assert!(k_distance_graph(&inputs, 4).is_err());
+    }
+
+    #[test]
+    fn test_k_distance_knee_two_blobs() {
+        let mut data = Vec::new();
+        for &(cx, cy) in &[(0.0, 0.0), (5.0, 5.0)] {
+            for i in 0..5 {
+                for j in 0..5 {
+                    data.push(cx + 0.1 * i as f64);
+                    data.push(cy + 0.1 * j as f64 + 0.013 * i as f64);
+                }
+            }
+        }
+        // A few isolated noise points
+        data.extend_from_slice(&[10.0, 0.0, 0.0, 10.0, -5.0, -5.0]);
+        let inputs = Matrix::new(53, 2, data);
+
+        let k = 3;
+        let distances = k_distance_graph(&inputs, k).unwrap();
+        assert!(distances.windows(2).all(|w| w[0] <= w[1]));
+
+        let eps = k_distance_knee(&distances).unwrap();
+        assert!(eps < 1.0);
+
+        // Region queries use a strict comparison, so nudge eps above the knee
+        let mut model = DBSCAN::new(eps * 1.01, k + 1);
+        model.train(&inputs).unwrap();
+        let clusters = model.clusters().unwrap();
+
+        assert!(clusters.iter().take(25).all(|c| *c == Some(0)));
+        assert!(clusters.iter().skip(25).take(25).all(|c| *c == Some(1)));
+        assert!(clusters.iter().skip(50).all(|c| c.is_none()));
+    }
+
+    #[test]
+    fn test_k_distance_knee_flat() {
+        assert_eq!(k_distance_knee(&[1.0, 2.0]), None);
+        assert_eq!(k_distance_knee(&[1.0, 1.0, 1.0]), None);
+    }
+
+    #[test]
+    fn test_optics_matches_dbscan() {
+        let mut data = Vec::new();
+        for &(cx, cy) in &[(0.0, 0.0), (3.0, 1.0), (0.5, 4.0)] {
+            for i in 0..4 {
+                for j in 0..4 {
+                    data.push(cx + 0.1 * i as f64);
+                    data.push(cy + 0.1 * j as f64);
+                }
+            }
+        }
+        // Isolated noise points
+        data.extend_from_slice(&[10.0, 0.0, -4.0, 2.0]);
+        let inputs = Matrix::new(50, 2, data);
+
+        let mut optics = OPTICS::new(4, 2.0);
+        optics.train(&inputs).unwrap();
+
+        let ordering = optics.ordering().unwrap();
+        let mut sorted = ordering.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+
+        let reachability = optics.reachability().unwrap();
+        assert!(reachability[48].is_infinite());
+        assert!(reachability[49].is_infinite());
+
+        // The blobs are more than 2 apart, so they stay separate at every eps
+        for &eps in &[0.15, 0.25, 1.5] {
+            let mut dbscan = DBSCAN::new(eps, 4);
+            dbscan.train(&inputs).unwrap();
+
+            let extracted = optics.extract_dbscan(eps).unwrap();
+            assert!(same_clusters(extracted.data(), dbscan.clusters().unwrap().data()));
+        }
+
+        let extracted = optics.extract_dbscan(1.5).unwrap();
+        assert!(extracted.iter().take(48).all(|c| c.is_some()));
+        assert_eq!(extracted[48], None);
+        assert_eq!(extracted[49], None);
+
+        assert!(optics.extract_dbscan(2.5).is_err());
+    }
+
+    /// Whether two clusterings are equal up to relabelling the clusters.
+    fn same_clusters(a: &[Option<usize>], b: &[Option<usize>]) -> bool {
+        let mut a_to_b = BTreeMap::new();
+        let mut b_to_a = BTreeMap::new();
+        a.len() == b.len() &&
+        a.iter().zip(b).all(|(x, y)| match (*x, *y) {
+            (None, None) => true,
+            (Some(x), Some(y)) => {
+                *a_to_b.entry(x).or_insert(y) == y && *b_to_a.entry(y).or_insert(x) == x
+            }
+            _ => false,
+        })
+    }
 }
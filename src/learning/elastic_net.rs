@@ -0,0 +1,295 @@
+//! Elastic Net Regression module
+//!
+//! Contains an implementation of linear regression with combined
+//! L1 and L2 penalties, fitted by coordinate descent.
+//!
+//! The model minimizes
+//!
+//! `1/(2n) ||y - b - Xw||^2 + lambda * (l1_ratio * ||w||_1 + (1 - l1_ratio)/2 * ||w||^2)`
+//!
+//! The L1 penalty sets some coefficients exactly to zero, so larger values of
+//! `lambda` give sparser models. The intercept `b` is not penalized, and as
+//! with `LinRegressor` it is added automatically.
+//!
+//! # Usage
+//!
+//! ```
+//! use rusty_machine::learning::elastic_net::ElasticNet;
+//! use rusty_machine::learning::SupModel;
+//! use rusty_machine::linalg::{Matrix, Vector};
+//!
+//! let inputs = Matrix::new(4, 2, vec![1.0, 0.5,
+//!                                     2.0, 0.1,
+//!                                     3.0, 0.9,
+//!                                     4.0, 0.3]);
+//! let targets = Vector::new(vec![3.0, 5.0, 7.0, 9.0]);
+//!
+//! let mut model = ElasticNet::new(0.1, 0.5);
+//! model.train(&inputs, &targets).unwrap();
+//!
+//! let output = model.predict(&Matrix::new(1, 2, vec![5.0, 0.5])).unwrap();
+//!
+//! // The coefficients along a decreasing sequence of penalties
+//! let path = model.regularization_path(&inputs, &targets, &[10.0, 1.0, 0.1]).unwrap();
+//! ```
+
+use linalg::{Matrix, BaseMatrix, Vector};
+use learning::{LearningResult, SupModel};
+use learning::error::{Error, ErrorKind};
+
+/// The default largest number of coordinate descent passes.
+const MAX_ITERS: usize = 1000;
+/// The default tolerance on the largest coefficient change in a pass.
+const TOLERANCE: f64 = 1e-10;
+
+/// Elastic Net Regression Model.
+///
+/// The parameters contain the intercept followed by a coefficient for
+/// each input column.
+#[derive(Debug)]
+pub struct ElasticNet {
+    /// The strength of the penalty
+    lambda: f64,
+    /// The fraction of the penalty which is L1
+    l1_ratio: f64,
+    /// The largest number of coordinate descent passes
+    max_iters: usize,
+    /// The parameters of the model
+    parameters: Option<Vector<f64>>,
+}
+
+/// The default Elastic Net model.
+///
+/// The defaults are:
+///
+/// - lambda = 1
+/// - l1_ratio = 0.5
+impl Default for ElasticNet {
+    fn default() -> ElasticNet {
+        ElasticNet::new(1f64, 0.5)
+    }
+}
+
+/// Inputs and targets centered by their means.
+struct CenteredData {
+    inputs: Matrix<f64>,
+    targets: Vec<f64>,
+    input_means: Vec<f64>,
+    target_mean: f64,
+    /// The mean of the squares of each centered input column
+    col_sq: Vec<f64>,
+}
+
+impl ElasticNet {
+    /// Constructs an untrained Elastic Net model.
+    ///
+    /// An `l1_ratio` of 1 gives the Lasso and an `l1_ratio` of 0 gives
+    /// ridge regression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::elastic_net::ElasticNet;
+    ///
+    /// let model = ElasticNet::new(0.5, 0.9);
+    /// ```
+    pub fn new(lambda: f64, l1_ratio: f64) -> ElasticNet {
+        assert!(lambda >= 0f64, "The penalty (lambda) cannot be negative.");
+        assert!(0f64 <= l1_ratio && l1_ratio <= 1f64,
+                "The L1 ratio must be between 0 and 1.");
+
+        ElasticNet {
+            lambda: lambda,
+            l1_ratio: l1_ratio,
+            max_iters: MAX_ITERS,
+            parameters: None,
+        }
+    }
+
+    /// Get the parameters from the model.
+    ///
+    /// The first parameter is the intercept. Returns `None` if the model
+    /// has not been trained.
+    pub fn parameters(&self) -> Option<&Vector<f64>> {
+        self.parameters.as_ref()
+    }
+
+    /// Computes the parameters for each penalty in a sequence.
+    ///
+    /// Column `i` of the returned matrix holds the intercept followed by the
+    /// coefficients of the model fitted with penalty `lambdas[i]`, and the
+    /// `l1_ratio` of this model. Each fit starts from the coefficients of the
+    /// previous one, which is much faster than fitting each penalty from
+    /// scratch when the penalties are in decreasing order. The fitted model
+    /// is not changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::elastic_net::ElasticNet;
+    /// use rusty_machine::linalg::{BaseMatrix, Matrix, Vector};
+    ///
+    /// let inputs = Matrix::new(3, 2, vec![1.0, 2.0, 2.0, 1.0, 3.0, 3.5]);
+    /// let targets = Vector::new(vec![1.0, 2.0, 3.0]);
+    ///
+    /// let model = ElasticNet::new(1.0, 1.0);
+    /// let path = model.regularization_path(&inputs, &targets, &[10.0, 0.1]).unwrap();
+    ///
+    /// // The large penalty sets every coefficient to zero
+    /// assert_eq!(path[[1, 0]], 0.0);
+    /// assert_eq!(path[[2, 0]], 0.0);
+    /// assert_eq!(path.cols(), 2);
+    /// ```
+    pub fn regularization_path(&self,
+                               inputs: &Matrix<f64>,
+                               targets: &Vector<f64>,
+                               lambdas: &[f64])
+                               -> LearningResult<Matrix<f64>> {
+        let data = try!(ElasticNet::center(inputs, targets));
+        if lambdas.iter().any(|&l| !(l >= 0f64)) {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The penalties (lambdas) cannot be negative."));
+        }
+
+        let rows = inputs.cols() + 1;
+        let mut path = vec![0f64; rows * lambdas.len()];
+        let mut coefs = vec![0f64; inputs.cols()];
+
+        for (i, &lambda) in lambdas.iter().enumerate() {
+            coefs = self.coordinate_descent(&data, lambda, coefs);
+            let params = ElasticNet::with_intercept(&data, &coefs);
+            for (j, p) in params.into_iter().enumerate() {
+                path[j * lambdas.len() + i] = p;
+            }
+        }
+
+        Ok(Matrix::new(rows, lambdas.len(), path))
+    }
+
+    /// Centers the data, checking it can be used for fitting.
+    fn center(inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<CenteredData> {
+        if inputs.rows() != targets.size() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The inputs and targets must have the same number of rows."));
+        }
+        if inputs.rows() == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "Cannot fit a model to empty data."));
+        }
+
+        let n = inputs.rows() as f64;
+        let input_means = (0..inputs.cols())
+            .map(|j| inputs.col(j).iter().sum::<f64>() / n)
+            .collect::<Vec<_>>();
+        let target_mean = targets.sum() / n;
+
+        let mut centered = inputs.clone();
+        let cols = inputs.cols();
+        for (idx, x) in centered.mut_data().iter_mut().enumerate() {
+            *x -= input_means[idx % cols];
+        }
+
+        let col_sq = (0..cols)
+            .map(|j| centered.col(j).iter().map(|x| x * x).sum::<f64>() / n)
+            .collect();
+
+        Ok(CenteredData {
+            inputs: centered,
+            targets: targets.iter().map(|t| t - target_mean).collect(),
+            input_means: input_means,
+            target_mean: target_mean,
+            col_sq: col_sq,
+        })
+    }
+
+    /// Minimizes the objective for the penalty starting from the given coefficients.
+    fn coordinate_descent(&self, data: &CenteredData, lambda: f64, mut coefs: Vec<f64>) -> Vec<f64> {
+        let n = data.inputs.rows() as f64;
+        let l1 = lambda * self.l1_ratio;
+        let l2 = lambda * (1f64 - self.l1_ratio);
+
+        // The residuals of the current coefficients
+        let mut residuals = data.targets.clone();
+        for (r, row) in residuals.iter_mut().zip(data.inputs.row_iter()) {
+            *r -= row.raw_slice().iter().zip(&coefs).map(|(x, w)| x * w).sum::<f64>();
+        }
+
+        for _ in 0..self.max_iters {
+            let mut max_change = 0f64;
+
+            for j in 0..coefs.len() {
+                if data.col_sq[j] == 0f64 {
+                    continue;
+                }
+
+                let col = data.inputs.col(j);
+                // The correlation of the column with the residuals excluding it
+                let rho = col.iter()
+                    .zip(&residuals)
+                    .map(|(x, r)| x * r)
+                    .sum::<f64>() / n + data.col_sq[j] * coefs[j];
+                let new_coef = soft_threshold(rho, l1) / (data.col_sq[j] + l2);
+
+                let change = new_coef - coefs[j];
+                if change != 0f64 {
+                    for (r, x) in residuals.iter_mut().zip(col.iter()) {
+                        *r -= change * x;
+                    }
+                    coefs[j] = new_coef;
+                    max_change = max_change.max(change.abs());
+                }
+            }
+
+            if max_change < TOLERANCE {
+                break;
+            }
+        }
+
+        coefs
+    }
+
+    /// Prepends the intercept for the uncentered data to the coefficients.
+    fn with_intercept(data: &CenteredData, coefs: &[f64]) -> Vec<f64> {
+        let shift = data.input_means.iter().zip(coefs).map(|(m, w)| m * w).sum::<f64>();
+
+        let mut params = Vec::with_capacity(coefs.len() + 1);
+        params.push(data.target_mean - shift);
+        params.extend_from_slice(coefs);
+        params
+    }
+}
+
+/// The soft thresholding operator, `sign(x) * max(|x| - t, 0)`.
+fn soft_threshold(x: f64, t: f64) -> f64 {
+    if x > t {
+        x - t
+    } else if x < -t {
+        x + t
+    } else {
+        0f64
+    }
+}
+
+impl SupModel<Matrix<f64>, Vector<f64>> for ElasticNet {
+    /// Train the model using coordinate descent.
+    fn train(&mut self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
+        let data = try!(ElasticNet::center(inputs, targets));
+        let coefs = self.coordinate_descent(&data, self.lambda, vec![0f64; inputs.cols()]);
+        self.parameters = Some(Vector::new(ElasticNet::with_intercept(&data, &coefs)));
+        Ok(())
+    }
+
+    /// Predict output values from input data.
+    fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
+        if let Some(ref v) = self.parameters {
+            if inputs.cols() + 1 != v.size() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Input data must have the same number of columns as training data"));
+            }
+            let ones = Matrix::<f64>::ones(inputs.rows(), 1);
+            let full_inputs = ones.hcat(inputs);
+            Ok(full_inputs * v)
+        } else {
+            Err(Error::new_untrained())
+        }
+    }
+}
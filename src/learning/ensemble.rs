@@ -0,0 +1,270 @@
+//! Weighted Ensembles
+//!
+//! Provides an ensemble which combines the predictions of several
+//! regression models using a learned convex combination.
+//!
+//! The weights are chosen to minimize the mean squared error of the
+//! combined predictions, subject to being non-negative and summing to one.
+//!
+//! # Usage
+//!
+//! ```
+//! use rusty_machine::learning::ensemble::WeightedEnsemble;
+//! use rusty_machine::learning::lin_reg::LinRegressor;
+//! use rusty_machine::learning::SupModel;
+//! use rusty_machine::linalg::{Matrix, Vector};
+//!
+//! let inputs = Matrix::new(4, 1, vec![1.0, 3.0, 5.0, 7.0]);
+//! let targets = Vector::new(vec![1., 5., 9., 13.]);
+//!
+//! let models = vec![LinRegressor::default(), LinRegressor::default()];
+//! let mut ensemble = WeightedEnsemble::new(models);
+//!
+//! // Train the base models and learn the weights
+//! ensemble.train(&inputs, &targets).unwrap();
+//!
+//! let weights = ensemble.weights().unwrap();
+//! assert!((weights[0] + weights[1] - 1.0).abs() < 1e-10);
+//!
+//! let outputs = ensemble.predict(&Matrix::new(1, 1, vec![10.])).unwrap();
+//! ```
+
+use linalg::{Matrix, BaseMatrix, Vector};
+use learning::{LearningResult, SupModel};
+use learning::error::{Error, ErrorKind};
+
+/// The maximum number of projected gradient steps used to fit the weights.
+const WEIGHT_ITERS: usize = 10000;
+/// Stop fitting the weights once no weight changes by more than this.
+const WEIGHT_TOL: f64 = 1e-12;
+
+/// Weighted Ensemble
+///
+/// Combines the predictions of several models using a convex
+/// combination of weights learned from data.
+#[derive(Debug)]
+pub struct WeightedEnsemble<M> {
+    models: Vec<M>,
+    weights: Option<Vec<f64>>,
+}
+
+impl<M> WeightedEnsemble<M>
+    where M: SupModel<Matrix<f64>, Vector<f64>>
+{
+    /// Constructs a new ensemble from the base models.
+    ///
+    /// The weights are learned when the ensemble is trained,
+    /// or by calling `fit_weights` with already trained models.
+    pub fn new(models: Vec<M>) -> WeightedEnsemble<M> {
+        WeightedEnsemble {
+            models: models,
+            weights: None,
+        }
+    }
+
+    /// The base models of the ensemble.
+    pub fn models(&self) -> &[M] {
+        &self.models
+    }
+
+    /// The learned weight for each base model.
+    ///
+    /// Returns None if the weights have not been fitted.
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.weights.as_ref().map(|w| &w[..])
+    }
+
+    /// Learn the ensemble weights from the predictions of the base models.
+    ///
+    /// The base models must already be trained. Ideally the inputs and
+    /// targets are held-out validation data, as weights fitted to the
+    /// training data favour models which overfit.
+    pub fn fit_weights(&mut self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
+        if self.models.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidState,
+                                  "The ensemble has no base models."));
+        }
+
+        let predictions = try!(self.base_predictions(inputs));
+        if predictions.rows() != targets.size() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Number of predictions does not match the number of targets."));
+        }
+        if predictions.data().iter().any(|x| !x.is_finite()) {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Base model predictions must be finite."));
+        }
+
+        self.weights = Some(simplex_least_squares(&predictions, targets));
+        Ok(())
+    }
+
+    /// Computes a matrix whose columns are the predictions of each base model.
+    fn base_predictions(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        let mut predictions = Vec::with_capacity(self.models.len());
+        for model in &self.models {
+            let output = try!(model.predict(inputs));
+            if output.size() != inputs.rows() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Base model returned the wrong number of predictions."));
+            }
+            predictions.push(output);
+        }
+
+        Ok(Matrix::from_fn(inputs.rows(), predictions.len(), |c, r| predictions[c][r]))
+    }
+}
+
+impl<M> SupModel<Matrix<f64>, Vector<f64>> for WeightedEnsemble<M>
+    where M: SupModel<Matrix<f64>, Vector<f64>>
+{
+    /// Predict using the weighted combination of the base models.
+    fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
+        if let Some(ref weights) = self.weights {
+            let predictions = try!(self.base_predictions(inputs));
+            Ok(predictions * Vector::new(weights.clone()))
+        } else {
+            Err(Error::new_untrained())
+        }
+    }
+
+    /// Train each base model and then learn the weights on the same data.
+    ///
+    /// Use `fit_weights` directly to learn the weights on validation data.
+    fn train(&mut self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
+        for model in &mut self.models {
+            try!(model.train(inputs, targets));
+        }
+        self.fit_weights(inputs, targets)
+    }
+}
+
+/// Minimize the mean squared error `|Pw - y|^2 / n` over the probability simplex.
+///
+/// Uses projected gradient descent with a step size bounded by the
+/// trace of the Gram matrix, which guarantees a decreasing cost.
+fn simplex_least_squares(predictions: &Matrix<f64>, targets: &Vector<f64>) -> Vec<f64> {
+    let n = predictions.rows() as f64;
+    let m = predictions.cols();
+
+    let pt = predictions.transpose();
+    let gram = (&pt * predictions) / n;
+    let proj_targets = (&pt * targets) / n;
+
+    let mut weights = Vector::new(vec![1f64 / m as f64; m]);
+
+    // The gradient 2(Gw - b) is Lipschitz with constant 2 * max eigenvalue <= 2 * trace.
+    let lipschitz = 2f64 * gram.diag().fold(0f64, |acc, x| acc + x);
+    if lipschitz <= 0f64 {
+        return weights.into_vec();
+    }
+
+    for _ in 0..WEIGHT_ITERS {
+        let grad: Vector<f64> = (&gram * &weights - &proj_targets) * 2f64;
+        let step: Vector<f64> = &weights - grad / lipschitz;
+        let next = Vector::new(project_simplex(step.data()));
+
+        let change = (&next - &weights).data().iter().fold(0f64, |acc, x| acc.max(x.abs()));
+        weights = next;
+        if change < WEIGHT_TOL {
+            break;
+        }
+    }
+
+    weights.into_vec()
+}
+
+/// Euclidean projection onto the probability simplex (Duchi et al. 2008).
+fn project_simplex(v: &[f64]) -> Vec<f64> {
+    let mut sorted = v.to_vec();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+    let mut cumsum = 0f64;
+    let mut theta = 0f64;
+    for (i, x) in sorted.iter().enumerate() {
+        cumsum += *x;
+        let t = (cumsum - 1f64) / (i + 1) as f64;
+        if *x > t {
+            theta = t;
+        }
+    }
+
+    v.iter().map(|x| (x - theta).max(0f64)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WeightedEnsemble, project_simplex};
+    use learning::{LearningResult, SupModel};
+    use linalg::{Matrix, BaseMatrix, Vector};
+
+    /// A fixed linear model of the first input column.
+    struct FixedModel {
+        scale: f64,
+        offset: f64,
+    }
+
+    impl SupModel<Matrix<f64>, Vector<f64>> for FixedModel {
+        fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
+            Ok(Vector::new(inputs.row_iter()
+                                 .map(|row| self.scale * row[0] + self.offset)
+                                 .collect::<Vec<_>>()))
+        }
+
+        fn train(&mut self, _: &Matrix<f64>, _: &Vector<f64>) -> LearningResult<()> {
+            Ok(())
+        }
+    }
+
+    fn mse(outputs: &Vector<f64>, targets: &Vector<f64>) -> f64 {
+        (outputs - targets).data().iter().fold(0f64, |acc, x| acc + x * x) / targets.size() as f64
+    }
+
+    #[test]
+    fn test_project_simplex() {
+        let p = project_simplex(&[0.5, 0.5]);
+        assert_eq!(p, vec![0.5, 0.5]);
+
+        let p = project_simplex(&[2.0, -1.0, 0.0]);
+        assert_eq!(p, vec![1.0, 0.0, 0.0]);
+
+        let p = project_simplex(&[0.4, 0.4, 0.4]);
+        assert!(p.iter().all(|x| (x - 1.0 / 3.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_best_model_gets_largest_weight() {
+        let inputs = Matrix::new(8, 1, vec![0., 1., 2., 3., 4., 5., 6., 7.]);
+        let targets = Vector::new(vec![0., 2., 4., 6., 8., 10., 12., 14.]);
+
+        let models = vec![FixedModel { scale: 0.0, offset: 3.0 },
+                          FixedModel { scale: 2.0, offset: 0.1 },
+                          FixedModel { scale: 1.5, offset: 0.0 }];
+        let mut ensemble = WeightedEnsemble::new(models);
+        ensemble.fit_weights(&inputs, &targets).unwrap();
+
+        let weights = ensemble.weights().unwrap().to_vec();
+        assert!(weights.iter().all(|&w| w >= 0.0));
+        assert!((weights.iter().fold(0.0, |acc, w| acc + w) - 1.0).abs() < 1e-10);
+        assert!(weights[1] > weights[0] && weights[1] > weights[2]);
+
+        let ensemble_err = mse(&ensemble.predict(&inputs).unwrap(), &targets);
+        let best_err = ensemble.models()
+                               .iter()
+                               .map(|m| mse(&m.predict(&inputs).unwrap(), &targets))
+                               .fold(f64::INFINITY, f64::min);
+        assert!(ensemble_err <= best_err + 1e-10);
+    }
+
+    #[test]
+    fn test_untrained_and_empty() {
+        let inputs = Matrix::new(2, 1, vec![0., 1.]);
+        let targets = Vector::new(vec![0., 1.]);
+
+        let ensemble = WeightedEnsemble::new(vec![FixedModel { scale: 1.0, offset: 0.0 }]);
+        assert!(ensemble.predict(&inputs).is_err());
+
+        let mut empty: WeightedEnsemble<FixedModel> = WeightedEnsemble::new(vec![]);
+        assert!(empty.fit_weights(&inputs, &targets).is_err());
+    }
+}
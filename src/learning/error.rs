@@ -4,6 +4,7 @@ use std::boxed::Box;
 use std::convert::Into;
 use std::error;
 use std::fmt;
+use std::io;
 use std::marker::{Send, Sync};
 
 use rulinalg;
@@ -30,7 +31,9 @@ pub enum ErrorKind {
     /// The model has not been trained
     UntrainedModel,
     /// Linear algebra related error
-    LinearAlgebra
+    LinearAlgebra,
+    /// Reading or writing data failed
+    Io,
 }
 
 impl Error {
@@ -63,6 +66,12 @@ impl From<rulinalg::error::Error> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::new(ErrorKind::Io, e)
+    }
+}
+
 impl error::Error for Error {
     fn description(&self) -> &str {
         self.error.description()
@@ -40,6 +40,13 @@ use linalg::{Matrix, BaseMatrix};
 
 use learning::{LearningResult, SupModel};
 use learning::error::{Error, ErrorKind};
+use learning::toolkit::plateau::PlateauDetector;
+
+/// The greatest number of IRLS iterations.
+const MAX_ITERS: usize = 8;
+
+/// The relative deviance improvement below which training stops.
+const DEVIANCE_TOLERANCE: f64 = 1e-12;
 
 /// The Generalized Linear Model
 ///
@@ -81,6 +88,10 @@ impl<C: Criterion> SupModel<Matrix<f64>, Vector<f64>> for GenLinearModel<C> {
     /// Predict output from inputs.
     fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
         if let Some(ref v) = self.parameters {
+            if inputs.cols() + 1 != v.size() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Input data must have the same number of columns as training data"));
+            }
             let ones = Matrix::<f64>::ones(inputs.rows(), 1);
             let full_inputs = ones.hcat(inputs);
             Ok(self.criterion.apply_link_inv(full_inputs * v))
@@ -107,8 +118,9 @@ impl<C: Criterion> SupModel<Matrix<f64>, Vector<f64>> for GenLinearModel<C> {
         let full_inputs = ones.hcat(inputs);
         let x_t = full_inputs.transpose();
 
-        // Iterate to convergence
-        for _ in 0..8 {
+        // Iterate until the deviance stops improving
+        let mut plateau = PlateauDetector::new(DEVIANCE_TOLERANCE, 1);
+        for _ in 0..MAX_ITERS {
             let w_diag = self.criterion.compute_working_weight(mu.data());
             let y_bar_data = self.criterion.compute_y_bar(targets.data(), mu.data());
 
@@ -121,17 +133,21 @@ impl<C: Criterion> SupModel<Matrix<f64>, Vector<f64>> for GenLinearModel<C> {
                 .inverse()
                 .expect("Could not compute input data inverse.") *
                            x_t_w * z;
-            let diff = (beta - &new_beta).apply(&|x| x.abs()).sum();
-            beta = new_beta;
-
-            if diff < 1e-10 {
-                break;
-            }
 
             // Update z and mu
-            let fitted = &full_inputs * &beta;
+            let fitted = &full_inputs * &new_beta;
             z = y_bar + &fitted;
             mu = self.criterion.apply_link_inv(fitted);
+
+            // The deviance need not fall every iteration, so keep the best estimate
+            let deviance = self.criterion.deviance(targets.data(), mu.data());
+            if plateau.best().map_or(true, |best| deviance < best) {
+                beta = new_beta;
+            }
+
+            if plateau.update(deviance) {
+                break;
+            }
         }
 
         self.parameters = Some(beta);
@@ -159,6 +175,16 @@ pub trait Criterion {
         y.to_vec()
     }
 
+    /// The deviance of the fitted means from the targets.
+    ///
+    /// Training stops once an iteration no longer improves the deviance.
+    /// By default this is the Pearson statistic `sum((y - u)^2 / Var(u))`.
+    fn deviance(&self, y: &[f64], mu: &[f64]) -> f64 {
+        y.iter()
+            .zip(mu)
+            .fold(0f64, |acc, (y, m)| acc + (y - m) * (y - m) / self.model_variance(*m))
+    }
+
     /// Computes the working weights that make up the diagonal
     /// of the `W` matrix used in the iterative reweighted least squares
     /// algorithm.
@@ -302,6 +328,12 @@ pub struct Bernoulli;
 impl Criterion for Bernoulli {
     type Link = Logit;
 
+    fn deviance(&self, y: &[f64], mu: &[f64]) -> f64 {
+        -2f64 * y.iter()
+            .zip(mu)
+            .fold(0f64, |acc, (y, m)| acc + x_ln_y(*y, *m) + x_ln_y(1f64 - y, 1f64 - m))
+    }
+
     fn model_variance(&self, mu: f64) -> f64 {
         let var = mu * (1f64 - mu);
 
@@ -370,6 +402,15 @@ pub struct Binomial {
 impl Criterion for Binomial {
     type Link = Logit;
 
+    fn deviance(&self, y: &[f64], mu: &[f64]) -> f64 {
+        2f64 * y.iter()
+            .zip(mu)
+            .zip(&self.weights)
+            .fold(0f64, |acc, ((y, m), n)| {
+                acc + n * (x_ln_y(*y, y / m) + x_ln_y(1f64 - y, (1f64 - y) / (1f64 - m)))
+            })
+    }
+
     fn model_variance(&self, mu: f64) -> f64 {
         let var = mu * (1f64 - mu);
 
@@ -439,6 +480,10 @@ pub struct Normal;
 impl Criterion for Normal {
     type Link = Identity;
 
+    fn deviance(&self, y: &[f64], mu: &[f64]) -> f64 {
+        y.iter().zip(mu).fold(0f64, |acc, (y, m)| acc + (y - m) * (y - m))
+    }
+
     fn model_variance(&self, _: f64) -> f64 {
         1f64
     }
@@ -451,6 +496,12 @@ pub struct Poisson;
 impl Criterion for Poisson {
     type Link = Log;
 
+    fn deviance(&self, y: &[f64], mu: &[f64]) -> f64 {
+        2f64 * y.iter()
+            .zip(mu)
+            .fold(0f64, |acc, (y, m)| acc + x_ln_y(*y, y / m) - (y - m))
+    }
+
     fn model_variance(&self, mu: f64) -> f64 {
         mu
     }
@@ -473,3 +524,68 @@ impl Criterion for Poisson {
         mu.to_vec()
     }
 }
+
+/// Computes `x * ln(y)`, taken to be zero when `x` is zero.
+fn x_ln_y(x: f64, y: f64) -> f64 {
+    if x == 0f64 {
+        0f64
+    } else {
+        x * y.ln()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GenLinearModel, Criterion, Bernoulli, Binomial, Normal, Poisson};
+    use learning::SupModel;
+    use linalg::{Matrix, Vector};
+
+    #[test]
+    fn test_deviance_at_targets() {
+        let y = vec![1.0, 0.0, 3.0];
+        assert_eq!(Normal.deviance(&y, &y), 0.0);
+        assert_eq!(Poisson.deviance(&y, &y), 0.0);
+        assert_eq!(Bernoulli.deviance(&[1.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(Binomial { weights: vec![2.0, 3.0] }.deviance(&[0.5, 0.0], &[0.5, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_deviance() {
+        assert_eq!(Normal.deviance(&[1.0, 2.0], &[0.0, 4.0]), 5.0);
+
+        let dev = Bernoulli.deviance(&[1.0, 0.0], &[0.5, 0.25]);
+        assert!((dev + 2.0 * (0.5f64.ln() + 0.75f64.ln())).abs() < 1e-12);
+
+        let dev = Poisson.deviance(&[2.0, 0.0], &[1.0, 0.5]);
+        assert!((dev - 2.0 * (2.0 * 2f64.ln() - 1.0 + 0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_normal_matches_least_squares() {
+        let inputs = Matrix::new(4, 1, vec![1.0, 2.0, 3.0, 4.0]);
+        let targets = Vector::new(vec![1.0, 3.0, 2.0, 5.0]);
+
+        let mut model = GenLinearModel::new(Normal);
+        model.train(&inputs, &targets).unwrap();
+
+        // The least squares line is y = 1.1x
+        let outputs = model.predict(&inputs).unwrap();
+        for (x, y) in inputs.data().iter().zip(outputs.data()) {
+            assert!((y - 1.1 * x).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_bernoulli_reduces_deviance() {
+        let inputs = Matrix::new(8, 1, vec![1.0, 3.0, 5.0, 7.0, 2.0, 4.0, 6.0, 8.0]);
+        let targets = Vector::new(vec![0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+
+        let mut model = GenLinearModel::new(Bernoulli);
+        model.train(&inputs, &targets).unwrap();
+        let outputs = model.predict(&inputs).unwrap();
+
+        // The maximum likelihood fit has a deviance of about 7.047
+        let deviance = Bernoulli.deviance(targets.data(), outputs.data());
+        assert!(deviance < 7.1);
+    }
+}
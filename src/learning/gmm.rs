@@ -36,6 +36,7 @@ use rulinalg::matrix::decomposition::{PartialPivLu};
 
 use learning::{LearningResult, UnSupModel};
 use learning::toolkit::rand_utils;
+use learning::toolkit::plateau::PlateauDetector;
 use learning::error::{Error, ErrorKind};
 
 /// Covariance options for GMMs.
@@ -88,16 +89,17 @@ impl UnSupModel<Matrix<f64>, Matrix<f64>> for GaussianMixtureModel {
             rand_utils::reservoir_sample(&(0..inputs.rows()).collect::<Vec<usize>>(), k);
         self.model_means = Some(inputs.select_rows(&random_rows));
 
-        for _ in 0..self.max_iters {
-            let log_lik_0 = self.log_lik;
+        // The log-likelihood is maximized
+        let mut plateau = convergence_check();
 
-            let (weights, log_lik_1) = try!(self.membership_weights(inputs));
+        for _ in 0..self.max_iters {
+            let (weights, log_lik) = try!(self.membership_weights(inputs));
 
-            if (log_lik_1 - log_lik_0).abs() < 1e-15 {
+            if plateau.update(-log_lik) {
                 break;
             }
 
-            self.log_lik = log_lik_1;
+            self.log_lik = log_lik;
 
             self.update_params(inputs, weights);
         }
@@ -347,9 +349,14 @@ impl GaussianMixtureModel {
     }
 }
 
+/// The convergence check on the negative log-likelihood.
+fn convergence_check() -> PlateauDetector {
+    PlateauDetector::new(1e-15, 1)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::GaussianMixtureModel;
+    use super::{GaussianMixtureModel, convergence_check};
     use linalg::Vector;
 
     #[test]
@@ -379,4 +386,25 @@ mod tests {
         let gmm_res = GaussianMixtureModel::with_weights(3, mix_weights);
         assert!(gmm_res.is_err());
     }
+
+    #[test]
+    fn test_convergence_is_relative() {
+        // A tiny improvement on a large negative log-likelihood has converged
+        let mut plateau = convergence_check();
+        assert!(!plateau.update(1e9));
+        assert!(plateau.update(1e9 - 1e-7));
+
+        // Negative values are compared on their magnitude
+        let mut plateau = convergence_check();
+        assert!(!plateau.update(-1e-3));
+        assert!(!plateau.update(-2e-3));
+        assert!(plateau.update(-2e-3));
+    }
+
+    #[test]
+    fn test_convergence_on_decreasing_likelihood() {
+        let mut plateau = convergence_check();
+        assert!(!plateau.update(-10.0));
+        assert!(plateau.update(-9.0));
+    }
 }
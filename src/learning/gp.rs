@@ -25,6 +25,27 @@
 //! Alternatively one could use `gaussp.get_posterior()` which would return both
 //! the predictive mean and covariance. However, this is likely to change in
 //! a future release.
+//!
+//! Several GPs with different kernels can be combined with a
+//! `BayesianModelAverage`, which weights each GP by its marginal likelihood.
+//!
+//! ```
+//! use rusty_machine::learning::gp::{BayesianModelAverage, ConstMean, GaussianProcess};
+//! use rusty_machine::learning::toolkit::kernel::SquaredExp;
+//! use rusty_machine::learning::SupModel;
+//! use rusty_machine::linalg::{Matrix, Vector};
+//!
+//! let smooth = GaussianProcess::new(SquaredExp::new(2., 1.), ConstMean::default(), 0.1);
+//! let rough = GaussianProcess::new(SquaredExp::new(0.2, 1.), ConstMean::default(), 0.1);
+//! let mut average = BayesianModelAverage::new(vec![Box::new(smooth), Box::new(rough)]);
+//!
+//! let train_data = Matrix::new(5,1,vec![0.,1.,2.,3.,4.]);
+//! let target = Vector::new(vec![0.,1.,2.,1.,0.]);
+//! average.train(&train_data, &target).unwrap();
+//!
+//! let weights = average.weights().unwrap();
+//! let outputs = average.predict(&Matrix::new(2,1,vec![1.5,2.5])).unwrap();
+//! ```
 
 use learning::toolkit::kernel::{Kernel, SquaredExp};
 use linalg::{Matrix, BaseMatrix};
@@ -32,6 +53,11 @@ use linalg::Vector;
 use learning::{LearningResult, SupModel};
 use learning::error::{Error, ErrorKind};
 
+use std::f64::consts::{E, PI};
+use std::fmt::Debug;
+
+/// The smallest predictive variance used for the entropy.
+const MIN_VARIANCE: f64 = 1e-12;
 
 /// Trait for GP mean functions.
 pub trait MeanFunc {
@@ -72,6 +98,7 @@ pub struct GaussianProcess<T: Kernel, U: MeanFunc> {
     alpha: Option<Vector<f64>>,
     train_mat: Option<Matrix<f64>>,
     train_data: Option<Matrix<f64>>,
+    log_marginal: Option<f64>,
 }
 
 /// Construct a default Gaussian Process
@@ -93,6 +120,7 @@ impl Default for GaussianProcess<SquaredExp, ConstMean> {
             train_mat: None,
             train_data: None,
             alpha: None,
+            log_marginal: None,
         }
     }
 }
@@ -118,9 +146,21 @@ impl<T: Kernel, U: MeanFunc> GaussianProcess<T, U> {
             train_mat: None,
             train_data: None,
             alpha: None,
+            log_marginal: None,
         }
     }
 
+    /// The log marginal likelihood of the training targets.
+    ///
+    /// This is the log probability of the targets given the training
+    /// inputs, the kernel, the mean function and the noise. It can be
+    /// used to compare models with different hyperparameters.
+    ///
+    /// Returns `None` if the model has not been trained.
+    pub fn log_marginal_likelihood(&self) -> Option<f64> {
+        self.log_marginal
+    }
+
     /// Construct a kernel matrix
     fn ker_mat(&self, m1: &Matrix<f64>, m2: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
         if m1.cols() != m2.cols() {
@@ -166,9 +206,15 @@ impl<T: Kernel, U: MeanFunc> SupModel<Matrix<f64>, Vector<f64>> for GaussianProc
                        "Could not compute Cholesky decomposition.")
         }));
 
-        let x = train_mat.solve_l_triangular(targets - self.mean.func(inputs.clone())).unwrap();
+        let residuals = targets - self.mean.func(inputs.clone());
+        let x = train_mat.solve_l_triangular(residuals.clone()).unwrap();
         let alpha = train_mat.transpose().solve_u_triangular(x).unwrap();
 
+        // log p(y|X) = -y'K^-1y/2 - log|K|/2 - n log(2pi)/2, with log|K|/2 = sum(log L_ii)
+        let log_det = train_mat.diag().fold(0f64, |acc, x| acc + x.ln());
+        self.log_marginal = Some(-0.5 * residuals.dot(&alpha) - log_det -
+                                 0.5 * inputs.rows() as f64 * (2f64 * PI).ln());
+
         self.train_mat = Some(train_mat);
         self.train_data = Some(inputs.clone());
         self.alpha = Some(alpha);
@@ -209,4 +255,173 @@ impl<T: Kernel, U: MeanFunc> GaussianProcess<T, U> {
             Err(Error::new_untrained())
         }
     }
+
+    /// The differential entropy of the predictive distribution at each input.
+    ///
+    /// The prediction for a new observation is gaussian with the posterior
+    /// variance of the function plus the noise, `σ²`, and has entropy
+    /// `0.5 * ln(2πe σ²)`. The entropy is highest where the model is most
+    /// uncertain, such as far from the training data, which makes it useful
+    /// for choosing which points to label in active learning.
+    ///
+    /// The entropy of a gaussian tends to minus infinity as its variance
+    /// goes to zero. To keep the result finite the variance is clamped to at
+    /// least `1e-12`, which can only be reached with little or no noise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::gp::GaussianProcess;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::{Matrix, Vector};
+    ///
+    /// let mut gaussp = GaussianProcess::default();
+    /// gaussp.noise = 0.1;
+    /// gaussp.train(&Matrix::new(3, 1, vec![0., 1., 2.]), &Vector::new(vec![0., 1., 0.])).unwrap();
+    ///
+    /// let entropy = gaussp.predictive_entropy(&Matrix::new(2, 1, vec![1., 10.])).unwrap();
+    /// assert!(entropy[1] > entropy[0]);
+    /// ```
+    pub fn predictive_entropy(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
+        if let (&Some(ref t_mat), &Some(ref t_data)) = (&self.train_mat, &self.train_data) {
+            let test_mat = try!(self.ker_mat(inputs, t_data));
+
+            let entropy = test_mat.row_iter()
+                .zip(inputs.row_iter())
+                .map(|(k_row, x)| {
+                    // The variance explained by the training data is |L^-1 k|^2
+                    let v = t_mat.solve_l_triangular(Vector::new(k_row.raw_slice())).unwrap();
+                    let prior = self.ker.kernel(x.raw_slice(), x.raw_slice());
+                    let variance = (prior - v.dot(&v) + self.noise).max(MIN_VARIANCE);
+                    0.5 * (2f64 * PI * E * variance).ln()
+                })
+                .collect::<Vec<_>>();
+
+            Ok(Vector::new(entropy))
+        } else {
+            Err(Error::new_untrained())
+        }
+    }
+}
+
+/// Trait for regression models with a marginal likelihood.
+///
+/// This allows models with different types, such as GPs with different
+/// kernels, to be combined in a `BayesianModelAverage`.
+pub trait MarginalLikelihood: SupModel<Matrix<f64>, Vector<f64>> + Debug {
+    /// The log marginal likelihood of the training targets.
+    ///
+    /// Returns `None` if the model has not been trained.
+    fn log_marginal_likelihood(&self) -> Option<f64>;
+}
+
+impl<T: Kernel + Debug, U: MeanFunc + Debug> MarginalLikelihood for GaussianProcess<T, U> {
+    fn log_marginal_likelihood(&self) -> Option<f64> {
+        self.log_marginal
+    }
+}
+
+/// Bayesian Model Average
+///
+/// Combines the predictive means of several models, weighting each
+/// model by its marginal likelihood on the training data. This gives
+/// the posterior probability of each model under a uniform prior.
+#[derive(Debug)]
+pub struct BayesianModelAverage {
+    models: Vec<Box<MarginalLikelihood>>,
+    weights: Option<Vec<f64>>,
+}
+
+impl BayesianModelAverage {
+    /// Constructs a new average from the models.
+    ///
+    /// The weights are computed when the average is trained.
+    pub fn new(models: Vec<Box<MarginalLikelihood>>) -> BayesianModelAverage {
+        BayesianModelAverage {
+            models: models,
+            weights: None,
+        }
+    }
+
+    /// The models being averaged.
+    pub fn models(&self) -> &[Box<MarginalLikelihood>] {
+        &self.models
+    }
+
+    /// The posterior weight of each model.
+    ///
+    /// Returns None if the average has not been trained.
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.weights.as_ref().map(|w| &w[..])
+    }
+}
+
+impl SupModel<Matrix<f64>, Vector<f64>> for BayesianModelAverage {
+    /// Predict the weighted average of the models' predictive means.
+    fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
+        if let Some(ref weights) = self.weights {
+            let mut output = Vector::zeros(inputs.rows());
+            for (model, &w) in self.models.iter().zip(weights) {
+                if w > 0f64 {
+                    output = output + try!(model.predict(inputs)) * w;
+                }
+            }
+            Ok(output)
+        } else {
+            Err(Error::new_untrained())
+        }
+    }
+
+    /// Train each model and weight it by its marginal likelihood.
+    fn train(&mut self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
+        if self.models.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidState, "There are no models to average."));
+        }
+
+        let mut log_liks = Vec::with_capacity(self.models.len());
+        for model in &mut self.models {
+            try!(model.train(inputs, targets));
+            let log_lik = try!(model.log_marginal_likelihood().ok_or_else(Error::new_untrained));
+            if log_lik.is_nan() || log_lik == f64::INFINITY {
+                return Err(Error::new(ErrorKind::InvalidState,
+                                      "A model has an invalid marginal likelihood."));
+            }
+            log_liks.push(log_lik);
+        }
+
+        self.weights = Some(try!(normalize_log_weights(&log_liks)));
+        Ok(())
+    }
+}
+
+/// Exponentiates and normalizes log weights using the log-sum-exp trick.
+///
+/// Subtracting the largest log weight first avoids underflow when every
+/// likelihood is tiny.
+fn normalize_log_weights(log_weights: &[f64]) -> LearningResult<Vec<f64>> {
+    let max = log_weights.iter().fold(f64::NEG_INFINITY, |acc, &x| acc.max(x));
+    if max == f64::NEG_INFINITY {
+        return Err(Error::new(ErrorKind::InvalidState,
+                              "Every model has zero marginal likelihood."));
+    }
+
+    let log_sum = max + log_weights.iter().map(|x| (x - max).exp()).sum::<f64>().ln();
+    Ok(log_weights.iter().map(|x| (x - log_sum).exp()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_log_weights;
+
+    #[test]
+    fn normalize_log_weights_avoids_underflow() {
+        let weights = normalize_log_weights(&[-1000.0, -1001.0, -2000.0]).unwrap();
+
+        let e = 1f64.exp();
+        assert!((weights[0] - e / (e + 1.0)).abs() < 1e-12);
+        assert!((weights[1] - 1.0 / (e + 1.0)).abs() < 1e-12);
+        assert_eq!(weights[2], 0.0);
+
+        assert!(normalize_log_weights(&[f64::NEG_INFINITY]).is_err());
+    }
 }
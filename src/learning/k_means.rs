@@ -45,9 +45,9 @@
 use linalg::{Matrix, MatrixSlice, Axes, Vector, BaseMatrix};
 use learning::{LearningResult, UnSupModel};
 use learning::error::{Error, ErrorKind};
+use learning::toolkit::plateau::PlateauDetector;
 
 use rand::{Rng, thread_rng};
-use libnum::abs;
 
 use std::fmt::Debug;
 
@@ -82,6 +82,10 @@ impl<InitAlg: Initializer> UnSupModel<Matrix<f64>, Vector<usize>> for KMeansClas
     /// Model must be trained.
     fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<usize>> {
         if let Some(ref centroids) = self.centroids {
+            if inputs.cols() != centroids.cols() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Input data must have the same number of columns as training data"));
+            }
             Ok(KMeansClassifier::<InitAlg>::find_closest_centroids(centroids.as_slice(), inputs).0)
         } else {
             Err(Error::new_untrained())
@@ -91,19 +95,15 @@ impl<InitAlg: Initializer> UnSupModel<Matrix<f64>, Vector<usize>> for KMeansClas
     /// Train the classifier using input data.
     fn train(&mut self, inputs: &Matrix<f64>) -> LearningResult<()> {
         try!(self.init_centroids(inputs));
-        let mut cost = 0.0;
-        let eps = 1e-14;
+        let mut plateau = convergence_check();
 
         for _i in 0..self.iters {
             let (idx, distances) = try!(self.get_closest_centroids(inputs));
             self.update_centroids(inputs, idx);
 
-            let cost_i = distances.sum();
-            if abs(cost - cost_i) < eps {
+            if plateau.update(distances.sum()) {
                 break;
             }
-
-            cost = cost_i;
         }
 
         Ok(())
@@ -358,6 +358,11 @@ impl Initializer for KPlusPlus {
     }
 }
 
+/// The convergence check on the sum of distances to the closest centroids.
+fn convergence_check() -> PlateauDetector {
+    PlateauDetector::new(1e-14, 1)
+}
+
 /// Sample from an unnormalized distribution.
 ///
 /// The input to this function is assumed to have all positive entries.
@@ -379,3 +384,29 @@ fn sample_discretely(unnorm_dist: &Vector<f64>) -> usize {
 
     panic!("No random value was sampled! There may be more clusters than unique data points.");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::convergence_check;
+
+    #[test]
+    fn test_convergence_is_relative() {
+        // A tiny improvement on a large cost has converged
+        let mut plateau = convergence_check();
+        assert!(!plateau.update(1e8));
+        assert!(plateau.update(1e8 - 1e-7));
+
+        // A large relative improvement on a tiny cost has not
+        let mut plateau = convergence_check();
+        assert!(!plateau.update(1e-10));
+        assert!(!plateau.update(1e-11));
+    }
+
+    #[test]
+    fn test_convergence_on_increase() {
+        let mut plateau = convergence_check();
+        assert!(!plateau.update(10.0));
+        assert!(!plateau.update(5.0));
+        assert!(plateau.update(6.0));
+    }
+}
@@ -29,6 +29,22 @@
 //! // Hopefully we classified our new point correctly!
 //! assert!(output[0] > 17f64, "Our regressor isn't very good!");
 //! ```
+//!
+//! The regressor is generic over the floating point type of the data.
+//! Using `f32` halves the memory used by the model:
+//!
+//! ```
+//! use rusty_machine::learning::lin_reg::LinRegressor;
+//! use rusty_machine::learning::SupModel;
+//! use rusty_machine::linalg::Matrix;
+//! use rusty_machine::linalg::Vector;
+//!
+//! let inputs = Matrix::new(4,1,vec![1.0f32,3.0,5.0,7.0]);
+//! let targets = Vector::new(vec![1.0f32,5.,9.,13.]);
+//!
+//! let mut lin_mod = LinRegressor::<f32>::new(None);
+//! lin_mod.train(&inputs, &targets).unwrap();
+//! ```
 
 use linalg::{Matrix, BaseMatrix};
 use linalg::Vector;
@@ -37,38 +53,47 @@ use learning::toolkit::cost_fn::CostFunc;
 use learning::toolkit::cost_fn::MeanSqError;
 use learning::optim::grad_desc::GradientDesc;
 use learning::optim::{OptimAlgorithm, Optimizable};
-use learning::error::Error;
+use learning::error::{Error, ErrorKind};
+
+use libnum::Float;
+
+use std::any::Any;
 
 /// Linear Regression Model.
 ///
 /// Contains option for optimized parameter.
+///
+/// The model is generic over the floating point type `T` which
+/// defaults to `f64`. Note that with `f32` the normal equations
+/// are accumulated in single precision, so poorly conditioned
+/// or very large datasets will lose accuracy.
 #[derive(Debug)]
-pub struct LinRegressor {
+pub struct LinRegressor<T = f64> {
     /// The parameters for the regression model.
-    parameters: Option<Vector<f64>>,
+    parameters: Option<Vector<T>>,
 }
 
-impl Default for LinRegressor {
-    fn default() -> LinRegressor {
+impl Default for LinRegressor<f64> {
+    fn default() -> LinRegressor<f64> {
         LinRegressor { parameters: None }
     }
 }
 
-impl LinRegressor {
+impl<T> LinRegressor<T> {
     /// Create a new linear regression model from parameters
-    pub fn new(parameters: Option<Vector<f64>>) -> LinRegressor {
+    pub fn new(parameters: Option<Vector<T>>) -> LinRegressor<T> {
         LinRegressor { parameters }
     }
 
     /// Get the parameters from the model.
     ///
     /// Returns an option that is None if the model has not been trained.
-    pub fn parameters(&self) -> Option<&Vector<f64>> {
+    pub fn parameters(&self) -> Option<&Vector<T>> {
         self.parameters.as_ref()
     }
 }
 
-impl SupModel<Matrix<f64>, Vector<f64>> for LinRegressor {
+impl<T: Float + Any> SupModel<Matrix<T>, Vector<T>> for LinRegressor<T> {
     /// Train the linear regression model.
     ///
     /// Takes training data and output values as input.
@@ -87,8 +112,8 @@ impl SupModel<Matrix<f64>, Vector<f64>> for LinRegressor {
     ///
     /// lin_mod.train(&inputs, &targets).unwrap();
     /// ```
-    fn train(&mut self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
-        let ones = Matrix::<f64>::ones(inputs.rows(), 1);
+    fn train(&mut self, inputs: &Matrix<T>, targets: &Vector<T>) -> LearningResult<()> {
+        let ones = Matrix::<T>::ones(inputs.rows(), 1);
         let full_inputs = ones.hcat(inputs);
 
         let xt = full_inputs.transpose();
@@ -98,10 +123,15 @@ impl SupModel<Matrix<f64>, Vector<f64>> for LinRegressor {
 
     /// Predict output value from input data.
     ///
-    /// Model must be trained before prediction can be made.
-    fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
+    /// Model must be trained before prediction can be made, otherwise
+    /// an `UntrainedModel` error is returned.
+    fn predict(&self, inputs: &Matrix<T>) -> LearningResult<Vector<T>> {
         if let Some(ref v) = self.parameters {
-            let ones = Matrix::<f64>::ones(inputs.rows(), 1);
+            if inputs.cols() + 1 != v.size() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Input data must have the same number of columns as training data"));
+            }
+            let ones = Matrix::<T>::ones(inputs.rows(), 1);
             let full_inputs = ones.hcat(inputs);
             Ok(full_inputs * v)
         } else {
@@ -110,7 +140,7 @@ impl SupModel<Matrix<f64>, Vector<f64>> for LinRegressor {
     }
 }
 
-impl Optimizable for LinRegressor {
+impl Optimizable for LinRegressor<f64> {
     type Inputs = Matrix<f64>;
     type Targets = Vector<f64>;
 
@@ -128,9 +158,16 @@ impl Optimizable for LinRegressor {
 
         (cost, grad.into_vec())
     }
+
+    /// The intercept is the only bias term.
+    fn bias_mask(&self, params: &[f64]) -> Vec<bool> {
+        let mut mask = vec![false; params.len()];
+        mask[0] = true;
+        mask
+    }
 }
 
-impl LinRegressor {
+impl LinRegressor<f64> {
     /// Train the linear regressor using Gradient Descent.
     ///
     /// # Examples
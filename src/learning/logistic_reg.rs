@@ -40,8 +40,8 @@ use learning::{LearningResult, SupModel};
 use learning::toolkit::activ_fn::{ActivationFunc, Sigmoid};
 use learning::toolkit::cost_fn::{CostFunc, CrossEntropyError};
 use learning::optim::grad_desc::GradientDesc;
-use learning::optim::{OptimAlgorithm, Optimizable};
-use learning::error::Error;
+use learning::optim::{OptimAlgorithm, Optimizable, TrainingHistory};
+use learning::error::{Error, ErrorKind};
 
 /// Logistic Regression Model.
 ///
@@ -90,6 +90,40 @@ impl<A: OptimAlgorithm<BaseLogisticRegressor>> LogisticRegressor<A> {
     pub fn parameters(&self) -> Option<&Vector<f64>> {
         self.base.parameters()
     }
+
+    /// Train the model and return a history of the optimization.
+    ///
+    /// The history holds the cost of each iteration, and whether the
+    /// optimization converged before running out of iterations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::logistic_reg::LogisticRegressor;
+    /// use rusty_machine::linalg::{Matrix, Vector};
+    ///
+    /// let inputs = Matrix::new(4, 1, vec![1.0, 3.0, 5.0, 7.0]);
+    /// let targets = Vector::new(vec![0., 0., 1., 1.]);
+    ///
+    /// let mut log_mod = LogisticRegressor::default();
+    /// let history = log_mod.train_with_history(&inputs, &targets).unwrap();
+    ///
+    /// println!("Final cost {:?}, converged: {}", history.final_cost(), history.converged());
+    /// ```
+    pub fn train_with_history(&mut self,
+                              inputs: &Matrix<f64>,
+                              targets: &Vector<f64>)
+                              -> LearningResult<TrainingHistory> {
+        let ones = Matrix::<f64>::ones(inputs.rows(), 1);
+        let full_inputs = ones.hcat(inputs);
+
+        let initial_params = vec![0.5; full_inputs.cols()];
+
+        let (optimal_w, history) =
+            self.alg.optimize_with_history(&self.base, &initial_params[..], &full_inputs, targets);
+        self.base.set_parameters(Vector::new(optimal_w));
+        Ok(history)
+    }
 }
 
 impl<A> SupModel<Matrix<f64>, Vector<f64>> for LogisticRegressor<A>
@@ -114,14 +148,7 @@ impl<A> SupModel<Matrix<f64>, Vector<f64>> for LogisticRegressor<A>
     /// logistic_mod.train(&inputs, &targets).unwrap();
     /// ```
     fn train(&mut self, inputs: &Matrix<f64>, targets: &Vector<f64>) -> LearningResult<()> {
-        let ones = Matrix::<f64>::ones(inputs.rows(), 1);
-        let full_inputs = ones.hcat(inputs);
-
-        let initial_params = vec![0.5; full_inputs.cols()];
-
-        let optimal_w = self.alg.optimize(&self.base, &initial_params[..], &full_inputs, targets);
-        self.base.set_parameters(Vector::new(optimal_w));
-        Ok(())
+        self.train_with_history(inputs, targets).map(|_| ())
     }
 
     /// Predict output value from input data.
@@ -129,6 +156,10 @@ impl<A> SupModel<Matrix<f64>, Vector<f64>> for LogisticRegressor<A>
     /// Model must be trained before prediction can be made.
     fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<f64>> {
         if let Some(v) = self.base.parameters() {
+            if inputs.cols() + 1 != v.size() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "Input data must have the same number of columns as training data"));
+            }
             let ones = Matrix::<f64>::ones(inputs.rows(), 1);
             let full_inputs = ones.hcat(inputs);
             Ok((full_inputs * v).apply(&Sigmoid::func))
@@ -192,4 +223,11 @@ impl Optimizable for BaseLogisticRegressor {
 
         (cost, grad.into_vec())
     }
+
+    /// The intercept is the only bias term.
+    fn bias_mask(&self, params: &[f64]) -> Vec<bool> {
+        let mut mask = vec![false; params.len()];
+        mask[0] = true;
+        mask
+    }
 }
@@ -1,6 +1,6 @@
 //! Naive Bayes Classifiers
 //!
-//! The classifier supports Gaussian, Bernoulli and Multinomial distributions.
+//! The classifier supports Gaussian, Bernoulli, Multinomial and Categorical distributions.
 //!
 //! A naive Bayes classifier works by treating the features of each input as independent
 //! observations. Under this assumption we utilize Bayes' rule to compute the
@@ -77,6 +77,28 @@ impl<T: Distribution> NaiveBayes<T> {
         }
     }
 
+    /// Create a new NaiveBayes model from a configured distribution.
+    ///
+    /// The distribution is reset with `Distribution::reset` when the
+    /// model is trained, which keeps any configuration it supports.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::naive_bayes::{NaiveBayes, Categorical};
+    ///
+    /// // Two features with three and two categories.
+    /// let _ = NaiveBayes::with_distr(Categorical::with_cardinalities(vec![3, 2]));
+    /// ```
+    pub fn with_distr(distr: T) -> NaiveBayes<T> {
+        NaiveBayes {
+            distr: Some(distr),
+            cluster_count: None,
+            class_prior: None,
+            class_counts: Vec::new(),
+        }
+    }
+
     /// Get the cluster count for this model.
     ///
     /// Returns an option which is `None` until the model has been trained.
@@ -107,7 +129,10 @@ impl<T: Distribution> NaiveBayes<T> {
 impl<T: Distribution> SupModel<Matrix<f64>, Matrix<f64>> for NaiveBayes<T> {
     /// Train the model using inputs and targets.
     fn train(&mut self, inputs: &Matrix<f64>, targets: &Matrix<f64>) -> LearningResult<()> {
-        self.distr = Some(T::from_model_params(targets.cols(), inputs.cols()));
+        match self.distr {
+            Some(ref mut distr) => distr.reset(targets.cols(), inputs.cols()),
+            None => self.distr = Some(T::from_model_params(targets.cols(), inputs.cols())),
+        }
         self.update_params(inputs, targets)
     }
 
@@ -214,6 +239,15 @@ pub trait Distribution {
     /// Initialize the distribution parameters.
     fn from_model_params(class_count: usize, features: usize) -> Self;
 
+    /// Reset the distribution parameters before training.
+    ///
+    /// By default this replaces the distribution with `from_model_params`.
+    fn reset(&mut self, class_count: usize, features: usize)
+        where Self: Sized
+    {
+        *self = Self::from_model_params(class_count, features);
+    }
+
     /// Updates the distribution parameters.
     fn update_params(&mut self, data: &Matrix<f64>, class: usize) -> LearningResult<()>;
 
@@ -443,12 +477,163 @@ impl Distribution for Multinomial {
     }
 }
 
+/// The Categorical Naive Bayes model distribution.
+///
+/// Each feature holds a category index `0, 1, ..., K - 1` stored as a float.
+/// The per-class probability of each category is estimated from the
+/// category counts with Laplace smoothing.
+///
+/// Defines:
+///
+///    p(x|C<sub>k</sub>) = ∏<sub>i</sub> p<sub>k,i</sub>(x<sub>i</sub>)
+#[derive(Debug)]
+pub struct Categorical {
+    cardinalities: Option<Vec<usize>>,
+    counts: Vec<Vec<Vec<f64>>>,
+    class_totals: Vec<f64>,
+    pseudo_count: f64,
+}
+
+impl Categorical {
+    /// Constructs an untrained distribution with a fixed number of
+    /// categories for each feature.
+    ///
+    /// Without fixed cardinalities, the number of categories of each
+    /// feature is one more than the largest index seen in training.
+    pub fn with_cardinalities(cardinalities: Vec<usize>) -> Categorical {
+        Categorical {
+            cardinalities: Some(cardinalities),
+            counts: Vec::new(),
+            class_totals: Vec::new(),
+            pseudo_count: 1f64,
+        }
+    }
+
+    /// The number of categories of each feature.
+    pub fn cardinalities(&self) -> Vec<usize> {
+        let features = self.counts.first().map_or(0, |c| c.len());
+
+        (0..features)
+            .map(|f| {
+                let observed = self.counts.iter().map(|c| c[f].len()).max().unwrap_or(0);
+                match self.cardinalities {
+                    Some(ref card) => card[f].max(observed),
+                    None => observed,
+                }
+            })
+            .collect()
+    }
+
+    /// The category counts of a feature within a class.
+    ///
+    /// Categories beyond the end of the slice have not been seen.
+    pub fn counts(&self, class: usize, feature: usize) -> &[f64] {
+        &self.counts[class][feature]
+    }
+
+    /// Converts a feature value to a category index.
+    fn category(x: f64) -> LearningResult<usize> {
+        if x >= 0f64 && x.fract() == 0f64 {
+            Ok(x as usize)
+        } else {
+            Err(Error::new(ErrorKind::InvalidData,
+                           "Categorical features must be non-negative integers."))
+        }
+    }
+}
+
+impl Distribution for Categorical {
+    fn from_model_params(class_count: usize, features: usize) -> Categorical {
+        Categorical {
+            cardinalities: None,
+            counts: vec![vec![Vec::new(); features]; class_count],
+            class_totals: vec![0f64; class_count],
+            pseudo_count: 1f64,
+        }
+    }
+
+    fn reset(&mut self, class_count: usize, features: usize) {
+        self.counts = vec![vec![Vec::new(); features]; class_count];
+        self.class_totals = vec![0f64; class_count];
+    }
+
+    fn update_params(&mut self, data: &Matrix<f64>, class: usize) -> LearningResult<()> {
+        if let Some(ref card) = self.cardinalities {
+            if card.len() != data.cols() {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      "The number of cardinalities must match the features."));
+            }
+        }
+
+        for row in data.row_iter() {
+            for (feature, x) in row.raw_slice().iter().enumerate() {
+                let category = try!(Categorical::category(*x));
+
+                if let Some(ref card) = self.cardinalities {
+                    if category >= card[feature] {
+                        return Err(Error::new(ErrorKind::InvalidData,
+                                              format!("Category {} exceeds the cardinality \
+                                                       of feature {}.",
+                                                      category,
+                                                      feature)));
+                    }
+                }
+
+                let counts = &mut self.counts[class][feature];
+                if counts.len() <= category {
+                    counts.resize(category + 1, 0f64);
+                }
+                counts[category] += 1f64;
+            }
+        }
+        self.class_totals[class] = data.rows() as f64;
+
+        Ok(())
+    }
+
+    fn joint_log_lik(&self,
+                     data: &Matrix<f64>,
+                     class_prior: &[f64])
+                     -> LearningResult<Matrix<f64>> {
+        let class_count = class_prior.len();
+        let cardinalities = self.cardinalities();
+
+        if data.cols() != cardinalities.len() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The number of features does not match the training data."));
+        }
+
+        let mut log_lik = Vec::with_capacity(data.rows() * class_count);
+
+        for row in data.row_iter() {
+            for (class, prior) in class_prior.iter().enumerate() {
+                let mut joint = prior.ln();
+
+                for (feature, x) in row.raw_slice().iter().enumerate() {
+                    let category = try!(Categorical::category(*x));
+                    // Unseen categories have a count of zero and only the pseudo count
+                    let count = self.counts[class][feature].get(category).cloned().unwrap_or(0f64);
+                    let total = self.class_totals[class] +
+                                self.pseudo_count * cardinalities[feature] as f64;
+
+                    joint += (count + self.pseudo_count).ln() - total.ln();
+                }
+
+                log_lik.push(joint);
+            }
+        }
+
+        Ok(Matrix::new(data.rows(), class_count, log_lik))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::NaiveBayes;
     use super::Gaussian;
     use super::Bernoulli;
     use super::Multinomial;
+    use super::Categorical;
 
     use learning::SupModel;
 
@@ -502,4 +687,54 @@ mod tests {
         let outputs = model.predict(&inputs).unwrap();
         assert_eq!(outputs.into_vec(), targets.into_vec());
     }
+
+    #[test]
+    fn test_categorical() {
+        // The first feature separates the classes, the second is noise
+        let inputs = Matrix::new(6, 2, vec![0.0, 1.0,
+                                            0.0, 2.0,
+                                            0.0, 0.0,
+                                            2.0, 1.0,
+                                            2.0, 0.0,
+                                            1.0, 2.0]);
+
+        let targets = Matrix::new(6, 2, vec![1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0,
+                                             0.0, 1.0]);
+
+        let mut model = NaiveBayes::<Categorical>::new();
+        model.train(&inputs, &targets).unwrap();
+
+        let outputs = model.predict(&inputs).unwrap();
+        assert_eq!(outputs.into_vec(), targets.into_vec());
+
+        let distr = model.distr().unwrap();
+        assert_eq!(distr.cardinalities(), vec![3, 3]);
+        assert_eq!(distr.counts(0, 0), &[3.0]);
+        assert_eq!(distr.counts(1, 0), &[0.0, 1.0, 2.0]);
+
+        // Unseen categories fall back to the smoothed probability
+        let log_probs = model.get_log_probs(&Matrix::new(1, 2, vec![0.0, 5.0])).unwrap();
+        let expected = 0.5f64.ln() + (4.0f64 / 6.0).ln() + (1.0f64 / 6.0).ln();
+        assert!((log_probs[[0, 0]] - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_categorical_cardinalities() {
+        let inputs = Matrix::new(2, 1, vec![0.0, 1.0]);
+        let targets = Matrix::new(2, 2, vec![1.0, 0.0, 0.0, 1.0]);
+
+        let mut model = NaiveBayes::with_distr(Categorical::with_cardinalities(vec![4]));
+        model.train(&inputs, &targets).unwrap();
+        assert_eq!(model.distr().unwrap().cardinalities(), vec![4]);
+
+        // Retraining keeps the configured cardinalities
+        model.train(&inputs, &targets).unwrap();
+        assert_eq!(model.distr().unwrap().cardinalities(), vec![4]);
+
+        let inputs = Matrix::new(2, 1, vec![0.0, 4.0]);
+        assert!(model.train(&inputs, &targets).is_err());
+
+        let inputs = Matrix::new(2, 1, vec![0.0, 1.5]);
+        assert!(NaiveBayes::<Categorical>::new().train(&inputs, &targets).is_err());
+    }
 }
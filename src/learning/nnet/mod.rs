@@ -41,54 +41,637 @@
 //!
 //! You can define your own criterion by implementing the `Criterion`
 //! trait with a concrete `CostFunc`.
+//!
+//! Networks work in `f64` by default. A network built with
+//! `NeuralNet::with_precision` can instead store its weights and compute
+//! its activations in `f32`, while the cost and regularization are still
+//! evaluated in `f64`. Support for `f32` is limited:
+//!
+//! - Only the `Linear`, `Softmax` and activation layers work in `f32`.
+//! - The optimizers keep their parameters in `f64`, so training converts
+//! the weights to `f32` and the gradient back on every gradient evaluation.
+//! Memory is only saved when predicting; training an `f32` network uses
+//! more memory than an `f64` one.
+//! - Saving, loading, quantization and `NeuralNetBuilder` are `f64` only.
 
 
 pub mod net_layer;
 
-use linalg::{Matrix, MatrixSlice};
+use linalg::{Matrix, MatrixSlice, BaseMatrix, BaseMatrixMut, Vector};
 use rulinalg::utils;
 
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::ops::Range;
+use std::str::FromStr;
+
+#[cfg(feature = "parallel")]
+use rayon;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 use learning::{LearningResult, SupModel};
 use learning::error::{Error, ErrorKind};
 use learning::toolkit::activ_fn;
 use learning::toolkit::activ_fn::ActivationFunc;
 use learning::toolkit::cost_fn;
-use learning::toolkit::cost_fn::CostFunc;
+use learning::toolkit::cost_fn::{CostFunc, Reduction};
+use learning::toolkit::rand_utils;
 use learning::toolkit::regularization::Regularization;
-use learning::optim::{Optimizable, OptimAlgorithm};
+use learning::optim::{Optimizable, OptimAlgorithm, ResumableOptimAlgorithm};
+use learning::optim::{OptimState, TrainingHistory};
+use learning::optim::grad_check;
 use learning::optim::grad_desc::StochasticGD;
 
-use self::net_layer::NetLayer;
+use self::net_layer::{NetLayer, NetFloat, ActivationLayer, WeightInit, layer_from_spec};
 
 /// Neural Network Model
 ///
 /// The Neural Network struct specifies a `Criterion` and
 /// a gradient descent algorithm.
 #[derive(Debug)]
-pub struct NeuralNet<T, A>
+pub struct NeuralNet<T, A, F = f64>
     where T: Criterion,
-          A: OptimAlgorithm<BaseNeuralNet<T>>
+          A: OptimAlgorithm<BaseNeuralNet<T, F>>,
+          F: NetFloat
 {
-    base: BaseNeuralNet<T>,
+    base: BaseNeuralNet<T, F>,
+    alg: A,
+    optim_state: OptimState,
+}
+
+/// A builder used to construct a `NeuralNet`
+///
+/// Layers are added in order from the input, and `build` checks that the
+/// size of each layer's input matches the output of the layer before it.
+/// The common layers have their own methods, and any other layer can be
+/// added with `layer`. A layer can be given a name with `named`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::{NeuralNetBuilder, MSECriterion};
+/// use rusty_machine::learning::nnet::net_layer::Linear;
+/// use rusty_machine::learning::optim::grad_desc::GradientDesc;
+/// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+///
+/// let net = NeuralNetBuilder::new(4)
+///     .layer(Box::new(Linear::new(4, 8)))
+///     .layer(Box::new(Sigmoid))
+///     .layer(Box::new(Linear::new(8, 2)))
+///     .criterion(MSECriterion::default())
+///     .optimizer(GradientDesc::default())
+///     .build()
+///     .unwrap();
+///
+/// // The same network using the layer methods
+/// let builder = NeuralNetBuilder::new(4)
+///     .linear(4, 8).named("hidden")
+///     .sigmoid()
+///     .linear(8, 2).named("output");
+///
+/// let summary = builder.summary().unwrap();
+/// assert_eq!(summary.num_params(), 58);
+/// println!("{}", summary);
+/// let net = builder.build().unwrap();
+/// assert_eq!(net.layer_index("output"), Some(2));
+///
+/// // The second linear layer does not match the output of the first
+/// let result = NeuralNetBuilder::new(4)
+///     .layer(Box::new(Linear::new(4, 8)))
+///     .layer(Box::new(Linear::new(6, 2)))
+///     .build();
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug)]
+pub struct NeuralNetBuilder<T: Criterion, A> {
+    /// The number of columns of the network inputs
+    input_size: usize,
+    layers: Vec<Box<NetLayer>>,
+    /// The name of each layer
+    labels: Vec<Option<String>>,
+    criterion: T,
     alg: A,
+    weight_init: WeightInit,
+    grad_clip: GradClip,
+}
+
+impl NeuralNetBuilder<BCECriterion, StochasticGD> {
+    /// Constructs a builder for a network with inputs of the given size.
+    ///
+    /// Uses the BCE criterion and stochastic gradient descent unless
+    /// others are chosen.
+    pub fn new(input_size: usize) -> NeuralNetBuilder<BCECriterion, StochasticGD> {
+        NeuralNetBuilder {
+            input_size: input_size,
+            layers: Vec::new(),
+            labels: Vec::new(),
+            criterion: BCECriterion::default(),
+            alg: StochasticGD::default(),
+            weight_init: WeightInit::Default,
+            grad_clip: GradClip::None,
+        }
+    }
+}
+
+impl<T: Criterion, A> NeuralNetBuilder<T, A> {
+    /// Adds a layer to the end of the network.
+    pub fn layer(mut self, layer: Box<NetLayer>) -> NeuralNetBuilder<T, A> {
+        self.layers.push(layer);
+        self.labels.push(None);
+        self
+    }
+
+    /// Adds a `Linear` layer with a bias.
+    pub fn linear(self, input_size: usize, output_size: usize) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(net_layer::Linear::new(input_size, output_size)))
+    }
+
+    /// Adds a `ReLU` activation.
+    pub fn relu(self) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(activ_fn::ReLU))
+    }
+
+    /// Adds a `Sigmoid` activation.
+    pub fn sigmoid(self) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(activ_fn::Sigmoid))
+    }
+
+    /// Adds a `Tanh` activation.
+    pub fn tanh(self) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(activ_fn::Tanh))
+    }
+
+    /// Adds a `Softmax` layer.
+    pub fn softmax(self) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(net_layer::Softmax))
+    }
+
+    /// Adds a `Dropout` layer keeping inputs with probability `keep_prob`.
+    ///
+    /// # Panics
+    ///
+    /// - The keep probability is not in `(0, 1]`.
+    pub fn dropout(self, keep_prob: f64) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(net_layer::Dropout::new(keep_prob)))
+    }
+
+    /// Adds a `BatchNorm` layer for inputs of the given size.
+    pub fn batch_norm(self, size: usize) -> NeuralNetBuilder<T, A> {
+        self.layer(Box::new(net_layer::BatchNorm::new(size)))
+    }
+
+    /// Names the last layer added.
+    ///
+    /// The name is shown by `summary` and can be used to find the layer
+    /// with `NeuralNet::layer_index`.
+    ///
+    /// # Panics
+    ///
+    /// - No layers have been added.
+    /// - The name is empty, starts or ends with whitespace, or contains
+    /// control characters such as newlines.
+    pub fn named<S: Into<String>>(mut self, name: S) -> NeuralNetBuilder<T, A> {
+        let name = name.into();
+        assert!(is_valid_layer_name(&name),
+                "Layer names must not be empty, padded by whitespace or contain control characters.");
+        match self.labels.last_mut() {
+            Some(label) => *label = Some(name),
+            None => panic!("A layer must be added before it is named."),
+        }
+        self
+    }
+
+    /// Sets the criterion of the network.
+    pub fn criterion<U: Criterion>(self, criterion: U) -> NeuralNetBuilder<U, A> {
+        NeuralNetBuilder {
+            input_size: self.input_size,
+            layers: self.layers,
+            labels: self.labels,
+            criterion: criterion,
+            alg: self.alg,
+            weight_init: self.weight_init,
+            grad_clip: self.grad_clip,
+        }
+    }
+
+    /// Sets the optimization algorithm used to train the network.
+    pub fn optimizer<B>(self, alg: B) -> NeuralNetBuilder<T, B> {
+        NeuralNetBuilder {
+            input_size: self.input_size,
+            layers: self.layers,
+            labels: self.labels,
+            criterion: self.criterion,
+            alg: alg,
+            weight_init: self.weight_init,
+            grad_clip: self.grad_clip,
+        }
+    }
+
+    /// Sets the initialization of the network weights.
+    ///
+    /// See `NeuralNet::weight_init`.
+    ///
+    /// # Panics
+    ///
+    /// - The standard deviation of a `Normal` initialization is not positive.
+    pub fn weight_init(mut self, init: WeightInit) -> NeuralNetBuilder<T, A> {
+        check_weight_init(&init);
+        self.weight_init = init;
+        self
+    }
+
+    /// Sets the clipping of the gradients used in training.
+    ///
+    /// See `NeuralNet::grad_clip`.
+    pub fn grad_clip(mut self, clip: GradClip) -> NeuralNetBuilder<T, A> {
+        check_grad_clip(clip);
+        self.grad_clip = clip;
+        self
+    }
+
+    /// Describes each layer added so far, see `NeuralNet::summary`.
+    ///
+    /// Returns an `InvalidParameters` error naming the first layer which
+    /// cannot take the output of the layer before it.
+    pub fn summary(&self) -> LearningResult<NetSummary> {
+        let num_params = self.layers.iter().map(|layer| layer.num_params()).collect::<Vec<_>>();
+        let summary = NetSummary::new(&self.layers,
+                                      Some(self.input_size),
+                                      &num_params,
+                                      &vec![false; self.layers.len()],
+                                      &self.labels);
+        try!(summary.check());
+        Ok(summary)
+    }
+
+    /// Checks the layers fit together and constructs the network.
+    ///
+    /// Returns an `InvalidParameters` error naming the first layer which
+    /// cannot take the output of the layer before it.
+    pub fn build(self) -> LearningResult<NeuralNet<T, A>>
+        where A: OptimAlgorithm<BaseNeuralNet<T>>
+    {
+        try!(self.summary());
+
+        let mut net = NeuralNet::new(self.criterion, self.alg);
+        net.base.weight_init = self.weight_init;
+        net.base.grad_clip = self.grad_clip;
+        net.add_layers(self.layers);
+        net.base.labels = self.labels;
+        Ok(net)
+    }
+}
+
+/// Settings for training a neural network with early stopping.
+///
+/// Used by `NeuralNet::train_early_stopping`.
+#[derive(Clone, Copy, Debug)]
+pub struct EarlyStopping {
+    /// The number of training iterations between validation checks
+    eval_every: usize,
+    /// The number of checks without improvement before stopping
+    patience: usize,
+    /// The largest number of training iterations
+    max_iters: usize,
+}
+
+impl EarlyStopping {
+    /// Constructs new early stopping settings.
+    ///
+    /// The validation cost is checked every `eval_every` iterations and
+    /// training stops once it has not improved for `patience` checks, or
+    /// after `max_iters` iterations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::EarlyStopping;
+    ///
+    /// let stopping = EarlyStopping::new(10, 5, 1000);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - `eval_every` is zero.
+    /// - `patience` is zero.
+    pub fn new(eval_every: usize, patience: usize, max_iters: usize) -> EarlyStopping {
+        assert!(eval_every > 0, "Must train for at least one iteration between checks.");
+        assert!(patience > 0, "The patience must be at least 1.");
+
+        EarlyStopping {
+            eval_every: eval_every,
+            patience: patience,
+            max_iters: max_iters,
+        }
+    }
+}
+
+/// The clipping of the gradients used to train a neural network.
+///
+/// Clipping limits the size of each update, which keeps deep or
+/// recurrent networks from diverging when their gradients explode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradClip {
+    /// The gradients are not clipped.
+    None,
+    /// Each entry of the gradient is clipped to `[-max, max]`.
+    Value(f64),
+    /// The whole gradient is scaled down so its L2 norm is at most `max`.
+    ///
+    /// Unlike clipping by value this keeps the direction of the gradient.
+    Norm(f64),
+}
+
+/// The gradients are not clipped.
+impl Default for GradClip {
+    fn default() -> GradClip {
+        GradClip::None
+    }
+}
+
+impl GradClip {
+    /// Clips the gradient in place.
+    fn apply(&self, grad: &mut [f64]) {
+        match *self {
+            GradClip::None => {}
+            GradClip::Value(max) => {
+                for g in grad {
+                    *g = g.max(-max).min(max);
+                }
+            }
+            GradClip::Norm(max) => {
+                let norm = grad.iter().fold(0f64, |acc, g| acc + g * g).sqrt();
+                if norm > max {
+                    for g in grad {
+                        *g *= max / norm;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The smallest number of rows given to each thread when computing gradients.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK_ROWS: usize = 64;
+
+/// Parses the whitespace separated values of a line of a saved network.
+fn parse_values<F: FromStr>(values: &str, line_idx: usize) -> LearningResult<Vec<F>> {
+    values.split_whitespace()
+        .map(|x| {
+            x.parse().map_err(|_| {
+                Error::new(ErrorKind::InvalidData,
+                           format!("Could not parse '{}' on line {}.", x, line_idx + 1))
+            })
+        })
+        .collect()
+}
+
+/// Checks that a clipping threshold is positive.
+fn check_grad_clip(clip: GradClip) {
+    match clip {
+        GradClip::None => {}
+        GradClip::Value(max) | GradClip::Norm(max) => {
+            assert!(max > 0f64, "The gradient clipping threshold must be positive.");
+        }
+    }
+}
+
+/// Checks that a gaussian initialization has a positive standard deviation.
+fn check_weight_init(init: &WeightInit) {
+    if let WeightInit::Normal { std } = *init {
+        assert!(std > 0f64, "The standard deviation must be positive.");
+    }
+}
+
+/// Whether a layer name can be saved and loaded unchanged.
+///
+/// Names must not be empty, start or end with whitespace, or contain
+/// control characters such as newlines.
+fn is_valid_layer_name(name: &str) -> bool {
+    !name.is_empty() && name.trim() == name && !name.chars().any(char::is_control)
+}
+
+/// A description of the layers of a neural network.
+///
+/// Returned by `NeuralNet::summary`, and shown by the `Display`
+/// implementation of `NeuralNet`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetSummary {
+    /// The description of each layer
+    layers: Vec<LayerInfo>,
+}
+
+impl NetSummary {
+    /// Describes each layer, starting from inputs with `input_size` columns.
+    ///
+    /// Until the size of the input is known it is taken from the first
+    /// layer which requires a fixed input size. Sizes after a layer which
+    /// cannot take its input are unknown.
+    fn new<F: NetFloat>(layers: &[Box<NetLayer<F>>],
+                        input_size: Option<usize>,
+                        num_params: &[usize],
+                        frozen: &[bool],
+                        labels: &[Option<String>])
+                        -> NetSummary {
+        let mut size = input_size;
+        let mut infos = Vec::with_capacity(layers.len());
+        for (idx, layer) in layers.iter().enumerate() {
+            if size.is_none() {
+                size = layer.input_size();
+            }
+            let output_size = size.and_then(|s| layer.output_size(s));
+            infos.push(LayerInfo {
+                name: layer.name(),
+                input_size: size,
+                output_size: output_size,
+                param_shape: layer.param_shape(),
+                num_params: num_params[idx],
+                frozen: frozen[idx],
+                label: labels[idx].clone(),
+            });
+            size = output_size;
+        }
+
+        NetSummary { layers: infos }
+    }
+
+    /// Checks that each layer with a known input size can take it.
+    fn check(&self) -> LearningResult<()> {
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if let (Some(input_size), None) = (layer.input_size, layer.output_size) {
+                return Err(Error::new(ErrorKind::InvalidParameters,
+                                      format!("Layer {} cannot take inputs with {} columns.",
+                                              idx,
+                                              input_size)));
+            }
+        }
+        Ok(())
+    }
+
+    /// The description of each layer.
+    pub fn layers(&self) -> &[LayerInfo] {
+        &self.layers
+    }
+
+    /// The total number of parameters owned by the layers.
+    pub fn num_params(&self) -> usize {
+        self.layers.iter().map(|layer| layer.num_params).sum()
+    }
+}
+
+/// Shows each layer on its own line with its type name, input and output
+/// sizes, the number of parameters it owns and its name if it has one,
+/// followed by the total number of parameters. Sizes which are not known
+/// are shown as `?`.
+impl fmt::Display for NetSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |size: Option<usize>| size.map_or("?".to_owned(), |s| s.to_string());
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            try!(write!(f,
+                        "  {:>3}: {:<12} {:>6} -> {:<6} {:>8} params",
+                        idx,
+                        layer.name,
+                        show(layer.input_size),
+                        show(layer.output_size),
+                        layer.num_params));
+            match layer.label {
+                Some(ref label) => try!(writeln!(f, "  {}", label)),
+                None => try!(writeln!(f)),
+            }
+        }
+        write!(f, "Total parameters: {}", self.num_params())
+    }
+}
+
+/// A description of one layer of a neural network.
+///
+/// Part of the `NetSummary` returned by `NeuralNet::summary`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerInfo {
+    /// The type name of the layer
+    name: String,
+    /// The number of input columns, if known
+    input_size: Option<usize>,
+    /// The number of output columns, if known
+    output_size: Option<usize>,
+    /// The shape of the parameters used by the layer
+    param_shape: (usize, usize),
+    /// The number of parameters owned by the layer
+    num_params: usize,
+    /// Whether the layer is frozen
+    frozen: bool,
+    /// The name given to the layer
+    label: Option<String>,
+}
+
+impl LayerInfo {
+    /// The type name of the layer, such as `Linear`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of input columns of the layer, if known.
+    pub fn input_size(&self) -> Option<usize> {
+        self.input_size
+    }
+
+    /// The number of output columns of the layer, if known.
+    pub fn output_size(&self) -> Option<usize> {
+        self.output_size
+    }
+
+    /// The shape of the parameters used by the layer.
+    pub fn param_shape(&self) -> (usize, usize) {
+        self.param_shape
+    }
+
+    /// The number of parameters owned by the layer.
+    ///
+    /// This is zero for layers without parameters, such as activation
+    /// functions, and for layers tied to another layer.
+    pub fn num_params(&self) -> usize {
+        self.num_params
+    }
+
+    /// Whether the layer is excluded from training by `freeze_layer`.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// The name given to the layer, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_ref().map(|l| &l[..])
+    }
+}
+
+/// Shows the architecture of the network.
+///
+/// This is the `NetSummary` of the network, which is shown even if
+/// some layer cannot take the output of the layer before it.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::NeuralNet;
+///
+/// let net = NeuralNet::default(&[3, 4, 2]);
+/// println!("{}", net);
+/// ```
+impl<T, A, F> fmt::Display for NeuralNet<T, A, F>
+    where T: Criterion,
+          A: OptimAlgorithm<BaseNeuralNet<T, F>>,
+          F: NetFloat
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(writeln!(f, "NeuralNet"));
+        write!(f, "{}", self.base.summary())
+    }
 }
 
 /// Supervised learning for the Neural Network.
 ///
 /// The model is trained using back propagation.
-impl<T, A> SupModel<Matrix<f64>, Matrix<f64>> for NeuralNet<T, A>
+impl<T, A, F> SupModel<Matrix<F>, Matrix<F>> for NeuralNet<T, A, F>
     where T: Criterion,
-          A: OptimAlgorithm<BaseNeuralNet<T>>
+          A: OptimAlgorithm<BaseNeuralNet<T, F>>,
+          F: NetFloat
 {
     /// Predict neural network output using forward propagation.
-    fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+    fn predict(&self, inputs: &Matrix<F>) -> LearningResult<Matrix<F>> {
         self.base.forward_prop(inputs)
     }
 
     /// Train the model using gradient optimization and back propagation.
-    fn train(&mut self, inputs: &Matrix<f64>, targets: &Matrix<f64>) -> LearningResult<()> {
-        let optimal_w = self.alg.optimize(&self.base, &self.base.weights, inputs, targets);
-        self.base.weights = optimal_w;
+    ///
+    /// Training starts from the current weights but with a fresh optimizer,
+    /// so momentum and moment estimates from earlier calls are lost. Use
+    /// `partial_train` to keep them.
+    ///
+    /// Returns an `InvalidData` error naming the first `NaN` or infinite
+    /// entry of the inputs or targets. Use `train_unchecked` to skip this check.
+    fn train(&mut self, inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<()> {
+        try!(self.base.check_finite(inputs, targets));
+        self.train_unchecked(inputs, targets)
+    }
+}
+
+impl<T, A, F> NeuralNet<T, A, F>
+    where T: Criterion,
+          A: OptimAlgorithm<BaseNeuralNet<T, F>>,
+          F: NetFloat
+{
+    /// Train the model without checking the data for non-finite values.
+    ///
+    /// This avoids a pass over the data for performance critical code. Any
+    /// `NaN` or infinite entries lead to `NaN` weights.
+    pub fn train_unchecked(&mut self, inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<()> {
+        try!(self.base.fit_input_stats(inputs));
+        let optimal_w = self.alg.optimize(&self.base, &F::as_f64_slice(&self.base.weights), inputs, targets);
+        self.base.weights = F::from_f64_vec(optimal_w);
         Ok(())
     }
 }
@@ -114,6 +697,7 @@ impl NeuralNet<BCECriterion, StochasticGD> {
         NeuralNet {
             base: BaseNeuralNet::default(layer_sizes, activ_fn::Sigmoid),
             alg: StochasticGD::default(),
+            optim_state: OptimState::new(),
         }
     }
 }
@@ -138,13 +722,19 @@ impl<T, A> NeuralNet<T, A>
         NeuralNet {
             base: BaseNeuralNet::new(criterion),
             alg: alg,
+            optim_state: OptimState::new(),
         }
     }
 
     /// Create a multilayer perceptron with the specified layer sizes.
     ///
     /// The layer sizes slice should include the input, hidden layers, and output layer sizes.
-    /// The type of activation function must be specified.
+    /// The activation function must be specified. Any layer without parameters can be
+    /// used, including activations with a parameter such as `LeakyReLU` and `ELU`.
+    ///
+    /// The weights use the default initialization of `Linear`. Another
+    /// initialization can be selected with `weight_init`, for example
+    /// `WeightInit::HeNormal` for a network using `ReLU`.
     ///
     /// Currently defaults to simple batch Gradient Descent for optimization.
     ///
@@ -161,428 +751,4255 @@ impl<T, A> NeuralNet<T, A>
     /// let mut net = NeuralNet::mlp(layers, BCECriterion::default(), StochasticGD::default(), Sigmoid);
     /// ```
     pub fn mlp<U>(layer_sizes: &[usize], criterion: T, alg: A, activ_fn: U) -> NeuralNet<T, A> 
-        where U: ActivationFunc + 'static {
+        where U: ActivationLayer + 'static {
         NeuralNet {
             base: BaseNeuralNet::mlp(layer_sizes, criterion, activ_fn),
             alg: alg,
+            optim_state: OptimState::new(),
         }
     }
 
-    /// Adds the specified layer to the end of the network
+    /// Create a multilayer perceptron with a separate activation function
+    /// after each `Linear` layer.
+    ///
+    /// The layer sizes slice should include the input, hidden layers, and output layer sizes.
+    /// There must be one activation for each pair of consecutive layer sizes.
     ///
     /// # Examples
     ///
     /// ```
-    /// use rusty_machine::linalg::BaseMatrix;
-    /// use rusty_machine::learning::nnet::BCECriterion;
-    /// use rusty_machine::learning::nnet::NeuralNet;
-    /// use rusty_machine::learning::nnet::net_layer::Linear;
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::nnet::net_layer::NetLayer;
+    /// use rusty_machine::learning::toolkit::activ_fn::{ReLU, Linear};
     /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
     ///
-    /// // Create a new neural net 
-    /// let mut net = NeuralNet::new(BCECriterion::default(), StochasticGD::default());
-    ///
-    /// // Give net an input layer of size 3, hidden layer of size 4, and output layer of size 5
-    /// // This net will not apply any activation function to the Linear layer outputs
-    /// net.add(Box::new(Linear::new(3, 4)))
-    ///    .add(Box::new(Linear::new(4, 5)));
+    /// // ReLU hidden layers with a linear output.
+    /// let activations: Vec<Box<NetLayer>> = vec![Box::new(ReLU), Box::new(ReLU), Box::new(Linear)];
+    /// let net = NeuralNet::mlp_with_activations(&[4, 8, 8, 2],
+    ///                                           activations,
+    ///                                           MSECriterion::default(),
+    ///                                           StochasticGD::default())
+    ///                     .unwrap();
     /// ```
-    pub fn add<'a>(&'a mut self, layer: Box<NetLayer>) -> &'a mut NeuralNet<T, A> {
-        self.base.add(layer);
-        self
+    pub fn mlp_with_activations(layer_sizes: &[usize],
+                                activations: Vec<Box<NetLayer>>,
+                                criterion: T,
+                                alg: A)
+                                -> LearningResult<NeuralNet<T, A>> {
+        if layer_sizes.len() < 2 || activations.len() != layer_sizes.len() - 1 {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "There must be one activation between each pair of layer sizes."));
+        }
+
+        let mut net = NeuralNet::new(criterion, alg);
+        for (shape, activation) in layer_sizes.windows(2).zip(activations) {
+            net.add(Box::new(net_layer::Linear::new(shape[0], shape[1])));
+            net.add(activation);
+        }
+        Ok(net)
     }
 
-    /// Adds multiple layers to the end of the network
+    /// Create an autoencoder with the specified encoder layer sizes.
+    ///
+    /// The layer sizes run from the input to the bottleneck, and the decoder
+    /// mirrors them back to the input size. So `&[8, 4, 2]` gives a network
+    /// with layer sizes 8, 4, 2, 4, 8. The activation function follows every
+    /// `Linear` layer, as in `mlp`. The network is trained with the inputs
+    /// as the targets, and `encode` gives the bottleneck activations.
+    ///
+    /// With `tied` the decoder uses the transposed weights of the encoder,
+    /// see `tie_weights`, which halves the number of parameters. Tied layers
+    /// cannot have bias terms, so then every `Linear` layer is without bias.
+    ///
+    /// # Panics
+    ///
+    /// - Fewer than two layer sizes are given.
     ///
     /// # Examples
     ///
     /// ```
-    /// use rusty_machine::linalg::BaseMatrix;
-    /// use rusty_machine::learning::nnet::BCECriterion;
-    /// use rusty_machine::learning::nnet::NeuralNet;
-    /// use rusty_machine::learning::nnet::net_layer::{NetLayer, Linear};
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
     /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
     /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::{Matrix, BaseMatrix};
     ///
-    /// // Create a new neural net 
-    /// let mut net = NeuralNet::new(BCECriterion::default(), StochasticGD::default());
+    /// let mut net = NeuralNet::autoencoder(&[4, 2],
+    ///                                      MSECriterion::default(),
+    ///                                      StochasticGD::default(),
+    ///                                      Sigmoid,
+    ///                                      true);
+    /// assert_eq!(net.num_params(), 8);
     ///
-    /// let linear_sig: Vec<Box<NetLayer>> = vec![Box::new(Linear::new(5, 5)), Box::new(Sigmoid)];
+    /// let inputs = Matrix::new(2, 4, vec![1., 0., 0., 1., 0., 1., 1., 0.]);
+    /// net.train(&inputs, &inputs).unwrap();
     ///
-    /// // Give net a layer of size 5, followed by a Sigmoid activation function
-    /// net.add_layers(linear_sig);
+    /// let codes = net.encode(&inputs).unwrap();
+    /// assert_eq!(codes.cols(), 2);
     /// ```
-    pub fn add_layers<'a, U>(&'a mut self, layers: U) -> &'a mut NeuralNet<T, A>
-        where U: IntoIterator<Item = Box<NetLayer>> {
-            self.base.add_layers(layers);
-            self
+    pub fn autoencoder<U>(layer_sizes: &[usize],
+                          criterion: T,
+                          alg: A,
+                          activ_fn: U,
+                          tied: bool)
+                          -> NeuralNet<T, A>
+        where U: ActivationLayer + 'static
+    {
+        assert!(layer_sizes.len() >= 2,
+                "An autoencoder needs at least an input and a bottleneck size.");
+
+        let mut net = NeuralNet::new(criterion, alg);
+        let decoder_sizes = layer_sizes.iter().rev().cloned().collect::<Vec<_>>();
+        for shape in layer_sizes.windows(2).chain(decoder_sizes.windows(2)) {
+            if tied {
+                net.add(Box::new(net_layer::Linear::without_bias(shape[0], shape[1])));
+            } else {
+                net.add(Box::new(net_layer::Linear::new(shape[0], shape[1])));
+            }
+            net.add(Box::new(activ_fn.clone()));
+        }
+        net.base.bottleneck = Some(2 * (layer_sizes.len() - 1) - 1);
+
+        if tied {
+            // Each decoder layer uses the weights of its mirror in the encoder
+            let depth = layer_sizes.len() - 1;
+            for j in 0..depth {
+                net.base
+                    .tie_weights(2 * (depth - 1 - j), 2 * (depth + j))
+                    .expect("Mirrored layers have transposed shapes.");
+            }
+        }
+        net
     }
 
-    /// Gets matrix of weights between specified layer and forward layer.
+    /// Quantizes the network weights to integers of the given number of bits.
+    ///
+    /// Each layer's weights are scaled so that the largest in magnitude maps
+    /// to the largest integer, then rounded. `bits` must be between 2 and 16.
+    /// With 8 bits predictions are typically close to those of the original
+    /// network.
+    ///
+    /// The quantized network takes the layers of this network, along with
+    /// its input standardization.
+    ///
+    /// # Panics
+    ///
+    /// - `bits` is not between 2 and 16.
     ///
     /// # Examples
     ///
     /// ```
-    /// use rusty_machine::linalg::BaseMatrix;
     /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::Matrix;
     ///
-    /// // Create a neural net with 4 layers, 3 neurons in each.
-    /// let layers = &[3; 4];
-    /// let mut net = NeuralNet::default(layers);
-    ///
-    /// let w = &net.get_net_weights(2);
+    /// let net = NeuralNet::default(&[2, 3, 1]);
+    /// let quantized = net.quantize(8);
     ///
-    /// // We add a bias term to the weight matrix
-    /// assert_eq!(w.rows(), 4);
-    /// assert_eq!(w.cols(), 3);
+    /// let outputs = quantized.predict(&Matrix::new(1, 2, vec![0.5, -0.5])).unwrap();
     /// ```
-    pub fn get_net_weights(&self, idx: usize) -> MatrixSlice<f64> {
-        self.base.get_layer_weights(&self.base.weights[..], idx)
-    }
-}
-
-/// Base Neural Network struct
-///
-/// This struct cannot be instantiated and is used internally only.
-#[derive(Debug)]
-pub struct BaseNeuralNet<T: Criterion> {
-    layers: Vec<Box<NetLayer>>,
-    weights: Vec<f64>,
-    criterion: T,
-}
-
-
-impl BaseNeuralNet<BCECriterion> {
-    /// Creates a base neural network with the specified layer sizes.
-    fn default<U>(layer_sizes: &[usize], activ_fn: U) -> BaseNeuralNet<BCECriterion>
-        where U: ActivationFunc + 'static {
-        BaseNeuralNet::mlp(layer_sizes, BCECriterion::default(), activ_fn)
+    pub fn quantize(self, bits: u8) -> QuantizedNet<T> {
+        QuantizedNet::new(self.base, bits)
     }
-}
-
 
-impl<T: Criterion> BaseNeuralNet<T> {
+    /// Saves the network as text.
+    ///
+    /// The layers, weight tying, layer names, the bottleneck of an
+    /// autoencoder, input standardization and weights are written, one item
+    /// per line. The weights are written exactly, so
+    /// a network read back with `load` makes identical predictions.
+    ///
+    /// The criterion and optimization algorithm are not saved, and are
+    /// given again to `load`. Neither is the optimizer state, so training
+    /// a loaded network starts the optimizer afresh.
+    ///
+    /// Returns an `InvalidParameters` error if a layer cannot be saved,
+    /// see `NetLayer::spec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, BCECriterion};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let net = NeuralNet::default(&[2, 3, 1]);
+    ///
+    /// let mut buffer = Vec::new();
+    /// net.save(&mut buffer).unwrap();
+    ///
+    /// let loaded = NeuralNet::load(&buffer[..], BCECriterion::default(), StochasticGD::default())
+    ///     .unwrap();
+    ///
+    /// let inputs = Matrix::new(1, 2, vec![0.5, -1.0]);
+    /// assert_eq!(loaded.predict(&inputs).unwrap(), net.predict(&inputs).unwrap());
+    /// ```
+    pub fn save<W: Write>(&self, mut writer: W) -> LearningResult<()> {
+        for (idx, layer) in self.base.layers.iter().enumerate() {
+            match layer.spec() {
+                Some(spec) => try!(writeln!(writer, "layer {}", spec)),
+                None => {
+                    return Err(Error::new(ErrorKind::InvalidParameters,
+                                          format!("Layer {} cannot be saved.", idx)))
+                }
+            }
+        }
+        for (b, tied) in self.base.tied.iter().enumerate() {
+            if let Some(a) = *tied {
+                try!(writeln!(writer, "tied {} {}", a, b));
+            }
+        }
+        for (idx, label) in self.base.labels.iter().enumerate() {
+            if let Some(ref label) = *label {
+                try!(writeln!(writer, "name {} {}", idx, label));
+            }
+        }
+        if let Some(idx) = self.base.bottleneck {
+            try!(writeln!(writer, "bottleneck {}", idx));
+        }
+        if self.base.standardize {
+            try!(writeln!(writer, "standardize"));
+        }
+        if let Some((ref mean, ref std)) = self.base.input_stats {
+            try!(write!(writer, "input_stats"));
+            for x in mean.iter().chain(std.iter()) {
+                try!(write!(writer, " {}", x));
+            }
+            try!(writeln!(writer));
+        }
+        try!(write!(writer, "weights"));
+        for w in &self.base.weights {
+            try!(write!(writer, " {}", w));
+        }
+        try!(writeln!(writer));
+        Ok(())
+    }
+
+    /// Loads a network saved by `save`.
+    ///
+    /// The network uses the given criterion and optimization algorithm.
+    ///
+    /// Returns an `InvalidData` error if the saved network is malformed.
+    pub fn load<R: Read>(reader: R, criterion: T, alg: A) -> LearningResult<NeuralNet<T, A>> {
+        let mut net = NeuralNet::new(criterion, alg);
+        let mut weights = None;
+
+        for (line_idx, line) in BufReader::new(reader).lines().enumerate() {
+            let line = try!(line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, rest) = match line.find(char::is_whitespace) {
+                Some(idx) => (&line[..idx], &line[idx..]),
+                None => (line, ""),
+            };
+            match key {
+                "layer" => {
+                    net.base.add(try!(layer_from_spec(rest)));
+                }
+                "tied" => {
+                    let idx = try!(parse_values::<usize>(rest, line_idx));
+                    if idx.len() != 2 {
+                        return Err(Error::new(ErrorKind::InvalidData,
+                                              format!("Line {} must name two tied layers.",
+                                                      line_idx + 1)));
+                    }
+                    try!(net.base.tie_weights(idx[0], idx[1]).map_err(|_| {
+                        Error::new(ErrorKind::InvalidData,
+                                   format!("Line {} does not tie two compatible layers.",
+                                           line_idx + 1))
+                    }));
+                }
+                "name" => {
+                    let rest = rest.trim();
+                    let (idx, name) = match rest.find(char::is_whitespace) {
+                        Some(pos) => (rest[..pos].parse::<usize>().ok(), rest[pos..].trim()),
+                        None => (None, ""),
+                    };
+                    match idx {
+                        Some(idx) if idx < net.base.layers.len() && is_valid_layer_name(name) => {
+                            net.base.labels[idx] = Some(name.to_owned())
+                        }
+                        _ => {
+                            return Err(Error::new(ErrorKind::InvalidData,
+                                                  format!("Line {} does not name a layer.",
+                                                          line_idx + 1)))
+                        }
+                    }
+                }
+                "bottleneck" => {
+                    let idx = try!(parse_values::<usize>(rest, line_idx));
+                    if idx.len() != 1 || idx[0] >= net.base.layers.len() {
+                        return Err(Error::new(ErrorKind::InvalidData,
+                                              format!("Line {} does not name the bottleneck layer.",
+                                                      line_idx + 1)));
+                    }
+                    net.base.bottleneck = Some(idx[0]);
+                }
+                "standardize" => net.base.standardize = true,
+                "input_stats" => {
+                    let mut stats = try!(parse_values::<f64>(rest, line_idx));
+                    if stats.is_empty() || stats.len() % 2 != 0 {
+                        return Err(Error::new(ErrorKind::InvalidData,
+                                              format!("Line {} must hold a mean and standard \
+                                                       deviation for each input.",
+                                                      line_idx + 1)));
+                    }
+                    let std = stats.split_off(stats.len() / 2);
+                    net.base.input_stats = Some((Vector::new(stats), Vector::new(std)));
+                }
+                "weights" => weights = Some(try!(parse_values::<f64>(rest, line_idx))),
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          format!("Unknown entry '{}' on line {}.",
+                                                  key,
+                                                  line_idx + 1)))
+                }
+            }
+        }
+
+        match weights {
+            Some(weights) => {
+                if weights.len() != net.base.weights.len() {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          format!("Expected {} weights, found {}.",
+                                                  net.base.weights.len(),
+                                                  weights.len())));
+                }
+                net.base.weights = weights;
+                Ok(net)
+            }
+            None => Err(Error::new(ErrorKind::InvalidData, "The saved network has no weights.")),
+        }
+    }
+}
+
+impl<T, A, F> NeuralNet<T, A, F>
+    where T: Criterion,
+          A: OptimAlgorithm<BaseNeuralNet<T, F>>,
+          F: NetFloat
+{
+    /// Create a new neural network with no layers, working in the float type `F`
+    ///
+    /// `new` always creates an `f64` network. An `f32` network halves the
+    /// memory of its stored weights and of the activations when predicting,
+    /// and can only hold the `Linear`, `Softmax` and activation layers.
+    /// Training does not save memory: the optimizer keeps an `f64` copy of
+    /// the weights, which are converted to `f32` and back at each gradient
+    /// evaluation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::nnet::net_layer::Linear;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let mut net = NeuralNet::<_, _, f32>::with_precision(MSECriterion::default(),
+    ///                                                      StochasticGD::default());
+    /// net.add(Box::new(Linear::new(2, 3)))
+    ///    .add(Box::new(Sigmoid))
+    ///    .add(Box::new(Linear::new(3, 1)));
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0f32, 1.0, 1.0, 0.0]);
+    /// let targets = Matrix::new(2, 1, vec![1f32, 0.0]);
+    /// net.train(&inputs, &targets).unwrap();
+    ///
+    /// let outputs: Matrix<f32> = net.predict(&inputs).unwrap();
+    /// ```
+    pub fn with_precision(criterion: T, alg: A) -> NeuralNet<T, A, F> {
+        NeuralNet {
+            base: BaseNeuralNet::new(criterion),
+            alg: alg,
+            optim_state: OptimState::new(),
+        }
+    }
+
+    /// Adds the specified layer to the end of the network
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::linalg::BaseMatrix;
+    /// use rusty_machine::learning::nnet::BCECriterion;
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::learning::nnet::net_layer::Linear;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Create a new neural net 
+    /// let mut net = NeuralNet::new(BCECriterion::default(), StochasticGD::default());
+    ///
+    /// // Give net an input layer of size 3, hidden layer of size 4, and output layer of size 5
+    /// // This net will not apply any activation function to the Linear layer outputs
+    /// net.add(Box::new(Linear::new(3, 4)))
+    ///    .add(Box::new(Linear::new(4, 5)));
+    /// ```
+    pub fn add<'a>(&'a mut self, layer: Box<NetLayer<F>>) -> &'a mut NeuralNet<T, A, F> {
+        self.base.add(layer);
+        self
+    }
+
+    /// Adds a layer to the end of the network with its own weight initialization
+    ///
+    /// The layer keeps this initialization when the weights are reset,
+    /// even if the initialization of the network is changed.
+    ///
+    /// # Panics
+    ///
+    /// - The standard deviation of a `Normal` initialization is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, BCECriterion};
+    /// use rusty_machine::learning::nnet::net_layer::{Linear, WeightInit};
+    /// use rusty_machine::learning::toolkit::activ_fn::{ReLU, Sigmoid};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// let mut net = NeuralNet::new(BCECriterion::default(), StochasticGD::default());
+    ///
+    /// // He initialization for the ReLU layer, Glorot for the sigmoid output
+    /// net.add_with_init(Box::new(Linear::new(3, 8)), WeightInit::HeNormal)
+    ///    .add(Box::new(ReLU))
+    ///    .add_with_init(Box::new(Linear::new(8, 1)), WeightInit::GlorotUniform)
+    ///    .add(Box::new(Sigmoid));
+    /// ```
+    pub fn add_with_init(&mut self, layer: Box<NetLayer<F>>, init: WeightInit) -> &mut NeuralNet<T, A, F> {
+        check_weight_init(&init);
+        self.base.add_with_init(layer, Some(init));
+        self
+    }
+
+    /// Adds multiple layers to the end of the network
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::linalg::BaseMatrix;
+    /// use rusty_machine::learning::nnet::BCECriterion;
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::learning::nnet::net_layer::{NetLayer, Linear};
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Create a new neural net 
+    /// let mut net = NeuralNet::new(BCECriterion::default(), StochasticGD::default());
+    ///
+    /// let linear_sig: Vec<Box<NetLayer>> = vec![Box::new(Linear::new(5, 5)), Box::new(Sigmoid)];
+    ///
+    /// // Give net a layer of size 5, followed by a Sigmoid activation function
+    /// net.add_layers(linear_sig);
+    /// ```
+    pub fn add_layers<'a, U>(&'a mut self, layers: U) -> &'a mut NeuralNet<T, A, F>
+        where U: IntoIterator<Item = Box<NetLayer<F>>> {
+            self.base.add_layers(layers);
+            self
+    }
+
+    /// Gets matrix of weights between specified layer and forward layer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::linalg::BaseMatrix;
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    ///
+    /// // Create a neural net with 4 layers, 3 neurons in each.
+    /// let layers = &[3; 4];
+    /// let mut net = NeuralNet::default(layers);
+    ///
+    /// let w = &net.get_net_weights(2);
+    ///
+    /// // We add a bias term to the weight matrix
+    /// assert_eq!(w.rows(), 4);
+    /// assert_eq!(w.cols(), 3);
+    /// ```
+    pub fn get_net_weights(&self, idx: usize) -> MatrixSlice<F> {
+        self.base.get_layer_weights(&self.base.weights[..], idx)
+    }
+
+    /// Computes the cost and its gradient for the current weights.
+    ///
+    /// This is what the optimization algorithm uses at each step, including
+    /// any regularization, but no step is taken. The gradient is laid out in
+    /// the same order as the weights, so the gradient of a layer can be
+    /// found at the same offset as its weights.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let net = NeuralNet::default(&[2, 3, 1]);
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///
+    /// let (cost, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+    /// assert_eq!(grad.len(), net.num_params());
+    /// ```
+    ///
+    /// Returns an `InvalidData` error if the inputs do not match the network,
+    /// or the targets do not match the shape of the network outputs.
+    pub fn cost_and_grad(&self, inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<(f64, Vec<f64>)> {
+        self.base.cost_grad(&F::as_f64_slice(&self.base.weights), inputs, targets)
+    }
+
+    /// Checks the gradient of each layer against finite differences.
+    ///
+    /// Returns the relative error of the gradient of each layer at the
+    /// current weights, see `optim::grad_check`. Layers without weights,
+    /// and tied layers, have no error. This is useful to test a custom
+    /// `NetLayer` or `Criterion` on a small network.
+    ///
+    /// Frozen layers have no gradient, so they show an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let net = NeuralNet::default(&[2, 3, 1]);
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.5]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///
+    /// let errors = net.grad_check(&inputs, &targets);
+    /// assert!(errors.iter().all(|&e| e < 1e-6));
+    /// ```
+    pub fn grad_check(&self, inputs: &Matrix<F>, targets: &Matrix<F>) -> Vec<f64> {
+        grad_check::grad_check(&self.base, &F::as_f64_slice(&self.base.weights), inputs, targets, 1e-5)
+    }
+
+    /// Predicts the outputs for borrowed inputs, such as a range of rows of a larger matrix.
+    ///
+    /// Unless the network standardizes its inputs, the rows are passed to
+    /// the first layer without being copied. The result is the same as that
+    /// of `predict` on a copy of the rows.
+    ///
+    /// There is no borrowed counterpart to `train`, as the optimizers work
+    /// on owned matrices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::{Matrix, BaseMatrix};
+    ///
+    /// let data = Matrix::new(4, 2, vec![0., 1., 1., 0., 1., 1., 0., 0.]);
+    /// let net = NeuralNet::default(&[2, 1]);
+    ///
+    /// // Predict on the last two rows
+    /// let outputs = net.predict_slice(data.sub_slice([2, 0], 2, 2)).unwrap();
+    /// assert_eq!(outputs.rows(), 2);
+    /// ```
+    pub fn predict_slice(&self, inputs: MatrixSlice<F>) -> LearningResult<Matrix<F>> {
+        self.base.forward_prop_with(&self.base.weights, inputs)
+    }
+
+    /// Predicts the outputs of the network a chunk of rows at a time.
+    ///
+    /// The intermediate activations are only computed for `chunk_rows` rows
+    /// at once, so memory use is bounded for very large inputs. The result
+    /// is the same as that of `predict`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let net = NeuralNet::default(&[2, 3, 1]);
+    /// let inputs = Matrix::new(5, 2, vec![0.5; 10]);
+    ///
+    /// let outputs = net.predict_chunked(&inputs, 2).unwrap();
+    /// assert_eq!(outputs, net.predict(&inputs).unwrap());
+    /// ```
+    pub fn predict_chunked(&self, inputs: &Matrix<F>, chunk_rows: usize) -> LearningResult<Matrix<F>> {
+        if chunk_rows == 0 {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The chunks must contain at least one row."));
+        }
+
+        if inputs.rows() <= chunk_rows {
+            return self.base.forward_prop(inputs);
+        }
+
+        let mut data = Vec::new();
+        let mut cols = 0;
+        let mut start = 0;
+        while start < inputs.rows() {
+            let rows = chunk_rows.min(inputs.rows() - start);
+            let chunk = inputs.sub_slice([start, 0], rows, inputs.cols());
+            let outputs = try!(self.base.forward_prop_with(&self.base.weights, chunk));
+
+            cols = outputs.cols();
+            data.extend(outputs.into_vec());
+            start += rows;
+        }
+
+        Ok(Matrix::new(inputs.rows(), cols, data))
+    }
+
+    /// Computes the saliency of each input feature for a class.
+    ///
+    /// The saliency is the absolute gradient of the output `class_idx`
+    /// with respect to each feature of a single input row. Features with a
+    /// large saliency have the most influence on that output. For a network
+    /// with a single output `class_idx` must be 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let net = NeuralNet::default(&[3, 4, 2]);
+    /// let saliency = net.saliency(&Matrix::new(1, 3, vec![0.5, -1.0, 2.0]), 1).unwrap();
+    ///
+    /// assert_eq!(saliency.size(), 3);
+    /// ```
+    pub fn saliency(&self, input: &Matrix<F>, class_idx: usize) -> LearningResult<Vector<F>> {
+        if input.rows() != 1 {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Saliency is computed for a single input row."));
+        }
+
+        let output = try!(self.base.forward_prop(input));
+        if class_idx >= output.cols() {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The class index is out of range of the network outputs."));
+        }
+
+        let mut out_grad = Matrix::zeros(1, output.cols());
+        out_grad[[0, class_idx]] = F::one();
+
+        let in_grad = try!(self.base.input_grad(input, out_grad));
+        Ok(Vector::new(in_grad.into_vec()).apply(&F::abs))
+    }
+
+    /// Computes the output of the given layer of the network.
+    ///
+    /// The inputs are propagated forward through the layers up to and
+    /// including `layer_idx`, whose output is returned. This allows
+    /// a trained network to be used as a feature extractor. Layer indices
+    /// start at zero and count every layer, including activations.
+    ///
+    /// Returns an `InvalidParameters` error if there is no layer with this index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::{BaseMatrix, Matrix};
+    ///
+    /// // Linear and Sigmoid layers of sizes 3 -> 4 -> 2
+    /// let net = NeuralNet::default(&[3, 4, 2]);
+    /// let inputs = Matrix::new(2, 3, vec![1., 0., 1., 0., 1., 1.]);
+    ///
+    /// // The activations of the hidden layer
+    /// let features = net.forward_to_layer(&inputs, 1).unwrap();
+    /// assert_eq!(features.cols(), 4);
+    /// ```
+    pub fn forward_to_layer(&self, inputs: &Matrix<F>, layer_idx: usize) -> LearningResult<Matrix<F>> {
+        self.base.forward_prop_to(&self.base.weights, inputs.as_slice(), layer_idx)
+    }
+
+    /// Encodes the inputs with the encoder of an autoencoder.
+    ///
+    /// These are the activations of the bottleneck of a network created by
+    /// `autoencoder`. Returns an `InvalidParameters` error for any other
+    /// network.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::linalg::{Matrix, BaseMatrix};
+    ///
+    /// let net = NeuralNet::autoencoder(&[3, 2, 1],
+    ///                                  MSECriterion::default(),
+    ///                                  StochasticGD::default(),
+    ///                                  Sigmoid,
+    ///                                  false);
+    ///
+    /// let inputs = Matrix::new(1, 3, vec![0.5, 0.2, 0.9]);
+    /// assert_eq!(net.encode(&inputs).unwrap().cols(), 1);
+    /// ```
+    pub fn encode(&self, inputs: &Matrix<F>) -> LearningResult<Matrix<F>> {
+        match self.base.bottleneck {
+            Some(idx) => self.forward_to_layer(inputs, idx),
+            None => {
+                Err(Error::new(ErrorKind::InvalidParameters,
+                               "Only networks created by autoencoder have an encoder."))
+            }
+        }
+    }
+
+    /// The number of independent parameters in the network.
+    ///
+    /// Layers tied with `tie_weights` share their parameters.
+    pub fn num_params(&self) -> usize {
+        self.base.weights.len()
+    }
+
+    /// Describes each layer of the network.
+    ///
+    /// The input size of the network is taken from the first layer which
+    /// requires a fixed input size, and sizes which cannot be inferred are
+    /// `None`. The parameter counts of the layers sum to `num_params`.
+    ///
+    /// Returns an `InvalidParameters` error naming the first layer which
+    /// cannot take the output of the layer before it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    ///
+    /// let net = NeuralNet::default(&[3, 4, 2]);
+    /// let summary = net.summary().unwrap();
+    ///
+    /// for layer in summary.layers() {
+    ///     println!("{:<10} {:?} -> {:?} {:>6}",
+    ///              layer.name(),
+    ///              layer.input_size(),
+    ///              layer.output_size(),
+    ///              layer.num_params());
+    /// }
+    /// assert_eq!(summary.num_params(), net.num_params());
+    /// ```
+    pub fn summary(&self) -> LearningResult<NetSummary> {
+        let summary = self.base.summary();
+        try!(summary.check());
+        Ok(summary)
+    }
+
+    /// Whether each of the network parameters is a bias term.
+    ///
+    /// The mask is aligned with the parameters used by the optimizer,
+    /// so for `Linear` layers with a bias the first row of their weights
+    /// is marked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    ///
+    /// let net = NeuralNet::default(&[2, 1]);
+    /// assert_eq!(net.bias_mask(), vec![true, false, false]);
+    /// ```
+    pub fn bias_mask(&self) -> Vec<bool> {
+        self.base.layer_bias_mask()
+    }
+
+    /// Ties the weights of layer `layer_b` to the transpose of those of `layer_a`.
+    ///
+    /// Layer `layer_b` then has no weights of its own, and its gradients are
+    /// added to the shared weights of `layer_a`. This is commonly used for
+    /// the decoder of an autoencoder. The parameter shape of `layer_b` must be
+    /// the transpose of that of `layer_a`, so `Linear` layers with bias terms
+    /// cannot be tied.
+    ///
+    /// The weights of `layer_b` are discarded and any optimizer state kept by
+    /// `partial_train` is cleared. For a tied layer `get_net_weights` returns
+    /// the weights of the layer it is tied to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::nnet::net_layer::Linear;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    ///
+    /// let mut net = NeuralNet::new(MSECriterion::default(), StochasticGD::default());
+    /// net.add(Box::new(Linear::without_bias(4, 2)))
+    ///    .add(Box::new(Sigmoid))
+    ///    .add(Box::new(Linear::without_bias(2, 4)));
+    ///
+    /// net.tie_weights(0, 2).unwrap();
+    /// assert_eq!(net.num_params(), 8);
+    /// ```
+    pub fn tie_weights(&mut self, layer_a: usize, layer_b: usize) -> LearningResult<()> {
+        try!(self.base.tie_weights(layer_a, layer_b));
+        self.optim_state.reset();
+        Ok(())
+    }
+
+    /// Freezes the weights of a layer, so that training leaves them unchanged.
+    ///
+    /// The gradient of a frozen layer's weights is zero, and they are
+    /// excluded from weight decay. This allows the lower layers of a
+    /// trained network to be reused while only the last layers are
+    /// fine-tuned. A tied layer shares the weights of the layer it is
+    /// tied to, so freezing either of them freezes both.
+    ///
+    /// Any optimizer state kept by `partial_train` is cleared, so that
+    /// momentum from earlier training does not move the frozen weights.
+    /// Returns an `InvalidParameters` error if the layer index is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::{Matrix, BaseMatrix};
+    ///
+    /// let mut net = NeuralNet::default(&[2, 3, 1]);
+    /// let first_layer = net.get_net_weights(0).iter().cloned().collect::<Vec<_>>();
+    ///
+    /// net.freeze_layer(0).unwrap();
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    /// net.train(&inputs, &targets).unwrap();
+    ///
+    /// assert_eq!(net.get_net_weights(0).iter().cloned().collect::<Vec<_>>(), first_layer);
+    /// ```
+    pub fn freeze_layer(&mut self, idx: usize) -> LearningResult<()> {
+        try!(self.base.set_frozen(idx, true));
+        self.optim_state.reset();
+        Ok(())
+    }
+
+    /// Unfreezes the weights of a layer frozen by `freeze_layer`.
+    ///
+    /// Any optimizer state kept by `partial_train` is cleared.
+    /// Returns an `InvalidParameters` error if the layer index is out of range.
+    pub fn unfreeze_layer(&mut self, idx: usize) -> LearningResult<()> {
+        try!(self.base.set_frozen(idx, false));
+        self.optim_state.reset();
+        Ok(())
+    }
+
+    /// Names a layer of the network.
+    ///
+    /// The name is shown by `summary` and when the network is displayed,
+    /// and the layer can be found by its name with `layer_index`.
+    /// Returns an `InvalidParameters` error if the layer index is out of range,
+    /// or if the name is empty, starts or ends with whitespace, or contains
+    /// control characters such as newlines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    ///
+    /// let mut net = NeuralNet::default(&[3, 4, 2]);
+    /// net.set_layer_name(0, "hidden").unwrap();
+    ///
+    /// let hidden = net.layer_index("hidden").unwrap();
+    /// net.freeze_layer(hidden).unwrap();
+    /// ```
+    pub fn set_layer_name<S: Into<String>>(&mut self, idx: usize, name: S) -> LearningResult<()> {
+        if idx >= self.base.layers.len() {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The layer index is out of range of the network layers."));
+        }
+        let name = name.into();
+        if !is_valid_layer_name(&name) {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "Layer names must not be empty, padded by whitespace or contain \
+                                   control characters."));
+        }
+        self.base.labels[idx] = Some(name);
+        Ok(())
+    }
+
+    /// The index of the first layer with the given name.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.base.labels.iter().position(|label| label.as_ref().map_or(false, |l| l == name))
+    }
+
+    /// Re-initializes the weights of every layer.
+    ///
+    /// Each layer's weights are drawn again from its default initialization,
+    /// leaving the layers themselves unchanged. Any optimizer state kept by
+    /// `partial_train` is also cleared so training starts afresh.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    ///
+    /// let mut net = NeuralNet::default(&[3, 4, 2]);
+    /// net.reset_weights();
+    /// ```
+    pub fn reset_weights(&mut self) {
+        self.base.reset_weights();
+        self.optim_state.reset();
+    }
+
+    /// Sets the initialization of the network weights.
+    ///
+    /// The weights of every layer are redrawn using the new initialization,
+    /// as are the weights of layers added later. Layers without connection
+    /// weights, such as activations and `BatchNorm`, keep their own
+    /// initialization, as do layers added with `add_with_init`.
+    ///
+    /// This also selects the initialization of a network created by `mlp`,
+    /// for example `WeightInit::HeNormal` for a network using `ReLU`.
+    ///
+    /// # Panics
+    ///
+    /// - The standard deviation of a `Normal` initialization is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::learning::nnet::net_layer::WeightInit;
+    ///
+    /// let mut net = NeuralNet::default(&[3, 4, 2]);
+    /// net.weight_init(WeightInit::Normal { std: 0.01 });
+    /// ```
+    pub fn weight_init(&mut self, init: WeightInit) -> &mut NeuralNet<T, A, F> {
+        check_weight_init(&init);
+
+        self.base.weight_init = init;
+        self.reset_weights();
+        self
+    }
+
+    /// Clips the gradients used to train the network.
+    ///
+    /// The clipping is applied to the full gradient, including any
+    /// regularization, before it is passed to the optimization algorithm.
+    /// It therefore works with every algorithm, and also applies to the
+    /// gradient returned by `cost_and_grad`.
+    ///
+    /// # Panics
+    ///
+    /// - The clipping threshold is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, GradClip};
+    ///
+    /// let mut net = NeuralNet::default(&[3, 4, 2]);
+    /// net.grad_clip(GradClip::Norm(5.0));
+    /// ```
+    pub fn grad_clip(&mut self, clip: GradClip) -> &mut NeuralNet<T, A, F> {
+        check_grad_clip(clip);
+        self.base.grad_clip = clip;
+        self
+    }
+
+    /// Sets whether the network predicts in training mode.
+    ///
+    /// Layers such as `Dropout` and `BatchNorm` behave differently while
+    /// the network is trained. By default predictions, `evaluate` and
+    /// `saliency` use the prediction behavior of every layer. In training
+    /// mode they use the training behavior instead, for example to
+    /// sample dropout masks at prediction time (Monte Carlo dropout).
+    ///
+    /// Training always uses the training behavior, whatever the mode.
+    /// Note that a `BatchNorm` layer updates its running statistics on
+    /// every forward pass in training mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::nnet::net_layer::{Linear, Dropout};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let mut net = NeuralNet::new(MSECriterion::default(), StochasticGD::default());
+    /// net.add(Box::new(Linear::new(2, 3)))
+    ///    .add(Box::new(Dropout::new(0.5)))
+    ///    .add(Box::new(Linear::new(3, 1)));
+    ///
+    /// // Each prediction samples a new dropout mask
+    /// net.set_training(true);
+    /// let inputs = Matrix::new(1, 2, vec![1.0, 2.0]);
+    /// let sample = net.predict(&inputs).unwrap();
+    ///
+    /// net.set_training(false);
+    /// assert!(!net.is_training());
+    /// ```
+    pub fn set_training(&mut self, training: bool) -> &mut NeuralNet<T, A, F> {
+        self.base.training = training;
+        self
+    }
+
+    /// Whether the network predicts in training mode.
+    ///
+    /// See `set_training`.
+    pub fn is_training(&self) -> bool {
+        self.base.training
+    }
+
+    /// Creates an untrained copy of the network.
+    ///
+    /// The copy has the same layers, weight tying, criterion and
+    /// optimization algorithm, but freshly initialized weights and no
+    /// optimizer state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    ///
+    /// let net = NeuralNet::default(&[3, 4, 2]);
+    /// let copy = net.fresh_copy();
+    ///
+    /// assert_eq!(copy.num_params(), net.num_params());
+    /// ```
+    pub fn fresh_copy(&self) -> NeuralNet<T, A, F>
+        where T: Clone,
+              A: Clone
+    {
+        NeuralNet {
+            base: self.base.fresh_copy(),
+            alg: self.alg.clone(),
+            optim_state: OptimState::new(),
+        }
+    }
+
+    /// Standardizes the inputs of the network.
+    ///
+    /// The mean and standard deviation of each input column are stored
+    /// when the network is first trained. From then on inputs are shifted
+    /// and scaled by these statistics before entering the first layer,
+    /// both in training and prediction. Columns with no variance are only
+    /// shifted. Inputs with a different number of columns than the
+    /// statistics give an `InvalidData` error.
+    ///
+    /// This helps gradient descent to converge on poorly scaled data
+    /// without the need for a separate scaler. Calling this again clears
+    /// the stored statistics, so that they are refitted by the next training.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::optim::grad_desc::GradientDesc;
+    /// use rusty_machine::learning::toolkit::activ_fn::Linear;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let inputs = Matrix::new(3, 1, vec![1000.0, 2000.0, 3000.0]);
+    /// let targets = Matrix::new(3, 1, vec![1.0, 2.0, 3.0]);
+    ///
+    /// let mut net = NeuralNet::mlp(&[1, 1], MSECriterion::default(), GradientDesc::default(), Linear);
+    /// net.standardize_inputs();
+    /// net.train(&inputs, &targets).unwrap();
+    /// ```
+    pub fn standardize_inputs(&mut self) -> &mut NeuralNet<T, A, F> {
+        self.base.standardize = true;
+        self.base.input_stats = None;
+        self
+    }
+
+    /// Computes the cost of the network's predictions on the given data.
+    ///
+    /// This is the criterion's cost without any regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::NeuralNet;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let net = NeuralNet::default(&[2, 1]);
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///
+    /// assert!(net.cost(&inputs, &targets).unwrap() > 0.0);
+    /// ```
+    pub fn cost(&self, inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<f64> {
+        let outputs = try!(self.base.forward_prop(inputs));
+
+        if outputs.rows() != targets.rows() || outputs.cols() != targets.cols() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The targets do not match the shape of the network outputs."));
+        }
+
+        Ok(self.base.criterion.cost(&F::as_f64_matrix(&outputs), &F::as_f64_matrix(targets)))
+    }
+
+    /// Computes the objective minimized in training on the given data.
+    ///
+    /// This is the criterion's cost plus the regularization of the
+    /// current weights, which makes it comparable to the costs recorded
+    /// while training. Use `cost` for the unregularized cost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let criterion = MSECriterion::new(Regularization::L2(0.1));
+    /// let net = NeuralNet::mlp(&[2, 1], criterion, StochasticGD::default(), Sigmoid);
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///
+    /// assert!(net.evaluate(&inputs, &targets).unwrap() > net.cost(&inputs, &targets).unwrap());
+    /// ```
+    pub fn evaluate(&self, inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<f64> {
+        let cost = try!(self.cost(inputs, targets));
+        Ok(cost + self.base.regularize(&F::as_f64_slice(&self.base.weights), None))
+    }
+
+    /// Train the model and return a history of the optimization.
+    ///
+    /// The history records the cost and the update ratio `||Δw|| / ||w||`
+    /// of each iteration, which is useful for tuning the learning rate.
+    /// It also records the gradient norm of each layer, which shows
+    /// whether the gradients vanish or explode through the network.
+    /// `TrainingHistory::converged` and `TrainingHistory::diverged` tell
+    /// whether the optimization settled or went off the rails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///
+    /// let mut net = NeuralNet::mlp(&[2, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+    /// let history = net.train_with_history(&inputs, &targets).unwrap();
+    ///
+    /// assert!(history.update_ratios().iter().all(|r| r.is_finite()));
+    /// assert!(!history.diverged());
+    /// ```
+    pub fn train_with_history(&mut self,
+                              inputs: &Matrix<F>,
+                              targets: &Matrix<F>)
+                              -> LearningResult<TrainingHistory> {
+        try!(self.base.check_finite(inputs, targets));
+        try!(self.base.fit_input_stats(inputs));
+        let (optimal_w, history) = self.alg.optimize_with_history(&self.base,
+                                                                  &F::as_f64_slice(&self.base.weights),
+                                                                  inputs,
+                                                                  targets);
+        self.base.weights = F::from_f64_vec(optimal_w);
+        Ok(history)
+    }
+}
+
+impl<T, A, F> NeuralNet<T, A, F>
+    where T: Criterion,
+          A: ResumableOptimAlgorithm<BaseNeuralNet<T, F>>,
+          F: NetFloat
+{
+    /// Train the model for some iterations while keeping the optimizer state.
+    ///
+    /// Unlike `train`, the internal buffers of the optimization algorithm
+    /// (such as momentum or Adam's moment estimates) and its step count are
+    /// kept between calls, so training warm starts where the last call left
+    /// off. This allows the network to be trained on data which arrives in
+    /// batches. The first call starts from the current weights, just as
+    /// `train` does.
+    ///
+    /// Returns the history of the iterations of this call. The state can be
+    /// inspected or moved to another network with `optim_state` and
+    /// `set_optim_state`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let mut net = NeuralNet::mlp(&[2, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+    ///
+    /// // Train on each batch as it arrives
+    /// for _ in 0..3 {
+    ///     let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    ///     let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///     net.partial_train(&inputs, &targets, 1).unwrap();
+    /// }
+    ///
+    /// // Each pass takes a step for every row
+    /// assert_eq!(net.optim_state().steps(), 6);
+    /// ```
+    pub fn partial_train(&mut self,
+                         inputs: &Matrix<F>,
+                         targets: &Matrix<F>,
+                         iters: usize)
+                         -> LearningResult<TrainingHistory> {
+        try!(self.base.check_finite(inputs, targets));
+        try!(self.base.fit_input_stats(inputs));
+        let (optimal_w, history) = self.alg.resume(&self.base,
+                                             &F::as_f64_slice(&self.base.weights),
+                                             &mut self.optim_state,
+                                             inputs,
+                                             targets,
+                                             iters);
+        self.base.weights = F::from_f64_vec(optimal_w);
+        Ok(history)
+    }
+
+    /// Train the model, calling `callback` with the history after each iteration.
+    ///
+    /// Trains for at most `iters` iterations. The callback receives the
+    /// history of every iteration so far and returns whether to continue,
+    /// so it can log progress, plot learning curves or stop training early.
+    /// Returns the history of all iterations which were run.
+    ///
+    /// The algorithm runs one iteration at a time, so its own stopping
+    /// criteria, such as a tolerance, are not applied. The optimizer state
+    /// kept by `partial_train` is not used or modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    ///
+    /// let mut net = NeuralNet::mlp(&[2, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+    /// let history = net.train_with_callback(&inputs, &targets, 100, |history| {
+    ///     let cost = *history.costs().last().unwrap();
+    ///     println!("Iteration {}: cost {}", history.iterations(), cost);
+    ///     // Stop once the cost is small enough
+    ///     cost > 0.01
+    /// }).unwrap();
+    /// ```
+    pub fn train_with_callback<C>(&mut self,
+                                  inputs: &Matrix<F>,
+                                  targets: &Matrix<F>,
+                                  iters: usize,
+                                  mut callback: C)
+                                  -> LearningResult<TrainingHistory>
+        where C: FnMut(&TrainingHistory) -> bool
+    {
+        try!(self.base.check_finite(inputs, targets));
+        try!(self.base.fit_input_stats(inputs));
+        let mut state = OptimState::new();
+        let mut history = TrainingHistory::new();
+
+        for _ in 0..iters {
+            let (new_w, iter_history) = self.alg.resume(&self.base,
+                                                        &F::as_f64_slice(&self.base.weights),
+                                                        &mut state,
+                                                        inputs,
+                                                        targets,
+                                                        1);
+            self.base.weights = F::from_f64_vec(new_w);
+            history.append(iter_history);
+
+            if !callback(&history) {
+                break;
+            }
+        }
+        Ok(history)
+    }
+
+    /// Train the model until the cost on a validation set stops improving.
+    ///
+    /// The validation cost is computed before training and then after every
+    /// `eval_every` iterations. Training stops once the cost has not improved
+    /// for `patience` checks, and the weights with the lowest validation
+    /// cost are restored. The validation set may have any number of rows.
+    ///
+    /// Returns the validation cost of every check. The optimizer state kept
+    /// by `partial_train` is not used or modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion, EarlyStopping};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let inputs = Matrix::new(2, 2, vec![0., 1., 1., 0.]);
+    /// let targets = Matrix::new(2, 1, vec![1., 0.]);
+    /// let val_inputs = Matrix::new(1, 2, vec![0., 0.9]);
+    /// let val_targets = Matrix::new(1, 1, vec![1.]);
+    ///
+    /// let mut net = NeuralNet::mlp(&[2, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+    /// let val_costs = net.train_early_stopping(&inputs,
+    ///                                          &targets,
+    ///                                          &val_inputs,
+    ///                                          &val_targets,
+    ///                                          EarlyStopping::new(5, 3, 100))
+    ///                    .unwrap();
+    /// ```
+    pub fn train_early_stopping(&mut self,
+                                inputs: &Matrix<F>,
+                                targets: &Matrix<F>,
+                                val_inputs: &Matrix<F>,
+                                val_targets: &Matrix<F>,
+                                stopping: EarlyStopping)
+                                -> LearningResult<Vec<f64>> {
+        if val_inputs.rows() != val_targets.rows() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Validation inputs and targets must have the same number of rows."));
+        }
+
+        try!(self.base.check_finite(inputs, targets));
+        try!(self.base.fit_input_stats(inputs));
+        let mut state = OptimState::new();
+        let mut best_cost = try!(self.cost(val_inputs, val_targets));
+        let mut best_weights = self.base.weights.clone();
+        let mut val_costs = vec![best_cost];
+        let mut since_best = 0;
+        let mut iters = 0;
+
+        while iters < stopping.max_iters && since_best < stopping.patience {
+            let step = stopping.eval_every.min(stopping.max_iters - iters);
+            let (new_w, _) = self.alg.resume(&self.base,
+                                             &F::as_f64_slice(&self.base.weights),
+                                             &mut state,
+                                             inputs,
+                                             targets,
+                                             step);
+            self.base.weights = F::from_f64_vec(new_w);
+            iters += step;
+
+            let cost = try!(self.cost(val_inputs, val_targets));
+            val_costs.push(cost);
+
+            if cost < best_cost {
+                best_cost = cost;
+                best_weights.copy_from_slice(&self.base.weights);
+                since_best = 0;
+            } else {
+                since_best += 1;
+            }
+        }
+
+        self.base.weights = best_weights;
+        Ok(val_costs)
+    }
+
+    /// Train with early stopping on a validation set held out from the data.
+    ///
+    /// A random `val_fraction` of the rows, rounded to the nearest row, is
+    /// held out for validation and the network is trained on the rest
+    /// with `train_early_stopping`. Returns the validation cost of every check.
+    ///
+    /// Returns an `InvalidParameters` error if the fraction is not strictly
+    /// between 0 and 1, and an `InvalidData` error if either set would be
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, MSECriterion, EarlyStopping};
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::linalg::Matrix;
+    ///
+    /// let inputs = Matrix::new(5, 2, vec![0., 1., 1., 0., 0., 0.9, 0.9, 0.1, 0.1, 1.]);
+    /// let targets = Matrix::new(5, 1, vec![1., 0., 1., 0., 1.]);
+    ///
+    /// let mut net = NeuralNet::mlp(&[2, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+    /// // Hold out one of the five rows
+    /// let val_costs = net.train_validation_split(&inputs, &targets, 0.2, EarlyStopping::new(5, 3, 100))
+    ///                    .unwrap();
+    /// ```
+    pub fn train_validation_split(&mut self,
+                                  inputs: &Matrix<F>,
+                                  targets: &Matrix<F>,
+                                  val_fraction: f64,
+                                  stopping: EarlyStopping)
+                                  -> LearningResult<Vec<f64>> {
+        if !(val_fraction > 0f64 && val_fraction < 1f64) {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The validation fraction must be between 0 and 1."));
+        }
+        if inputs.rows() != targets.rows() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Inputs and targets must have the same number of rows."));
+        }
+
+        let n_val = (val_fraction * inputs.rows() as f64).round() as usize;
+        if n_val == 0 || n_val == inputs.rows() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  format!("Cannot hold out {} of {} rows for validation.",
+                                          val_fraction,
+                                          inputs.rows())));
+        }
+
+        let mut indices = (0..inputs.rows()).collect::<Vec<_>>();
+        rand_utils::in_place_fisher_yates(&mut indices);
+        let (val_idx, train_idx) = indices.split_at(n_val);
+
+        self.train_early_stopping(&inputs.select_rows(train_idx),
+                                  &targets.select_rows(train_idx),
+                                  &inputs.select_rows(val_idx),
+                                  &targets.select_rows(val_idx),
+                                  stopping)
+    }
+
+    /// Clears the optimizer state kept by `partial_train`.
+    pub fn reset_optim_state(&mut self) {
+        self.optim_state.reset();
+    }
+
+    /// The optimizer state kept by `partial_train`.
+    pub fn optim_state(&self) -> &OptimState {
+        &self.optim_state
+    }
+
+    /// Replaces the optimizer state used by the next `partial_train`.
+    ///
+    /// This continues training in a copy of the network, such as one
+    /// read back with `load`, with the optimizer state of the original.
+    /// The state must come from the same algorithm on a network with the
+    /// same number of parameters. Buffers of the wrong size are ignored
+    /// and start again from zero.
+    pub fn set_optim_state(&mut self, state: OptimState) {
+        self.optim_state = state;
+    }
+}
+
+/// Base Neural Network struct
+///
+/// This struct cannot be instantiated and is used internally only.
+#[derive(Debug)]
+pub struct BaseNeuralNet<T: Criterion, F: NetFloat = f64> {
+    layers: Vec<Box<NetLayer<F>>>,
+    /// For each layer, the layer whose transposed weights it uses
+    tied: Vec<Option<usize>>,
+    weights: Vec<F>,
+    criterion: T,
+    /// Whether the inputs are standardized
+    standardize: bool,
+    /// The mean and standard deviation of each input column
+    input_stats: Option<(Vector<f64>, Vector<f64>)>,
+    /// The initialization of the layer weights
+    weight_init: WeightInit,
+    /// For each layer, the initialization used instead of `weight_init`
+    inits: Vec<Option<WeightInit>>,
+    /// For each layer, whether its weights are excluded from training
+    frozen: Vec<bool>,
+    /// For each layer, the name given to it
+    labels: Vec<Option<String>>,
+    /// The last layer of the encoder of an autoencoder
+    bottleneck: Option<usize>,
+    /// The clipping of the training gradients
+    grad_clip: GradClip,
+    /// Whether predictions use the layers' training behavior
+    training: bool,
+}
+
+
+impl BaseNeuralNet<BCECriterion> {
+    /// Creates a base neural network with the specified layer sizes.
+    fn default<U>(layer_sizes: &[usize], activ_fn: U) -> BaseNeuralNet<BCECriterion>
+        where U: ActivationFunc + Sync + 'static {
+        BaseNeuralNet::mlp(layer_sizes, BCECriterion::default(), activ_fn)
+    }
+}
+
+
+impl<T: Criterion, F: NetFloat> BaseNeuralNet<T, F> {
     /// Create a base neural network with no layers
-    fn new(criterion: T) -> BaseNeuralNet<T> {
+    fn new(criterion: T) -> BaseNeuralNet<T, F> {
         BaseNeuralNet {
             layers: Vec::new(),
+            tied: Vec::new(),
+            weights: Vec::new(),
+            criterion: criterion,
+            standardize: false,
+            input_stats: None,
+            weight_init: WeightInit::Default,
+            inits: Vec::new(),
+            frozen: Vec::new(),
+            labels: Vec::new(),
+            bottleneck: None,
+            grad_clip: GradClip::None,
+            training: false,
+        }
+    } 
+
+    /// Create a multilayer perceptron with the specified layer sizes.
+    fn mlp<U>(layer_sizes: &[usize], criterion: T, activ_fn: U) -> BaseNeuralNet<T, F> 
+        where U: ActivationLayer<F> + 'static {
+        let mut mlp = BaseNeuralNet {
+            layers: Vec::with_capacity(2*(layer_sizes.len()-1)),
+            tied: Vec::with_capacity(2*(layer_sizes.len()-1)),
+            weights: Vec::new(),
+            criterion: criterion,
+            standardize: false,
+            input_stats: None,
+            weight_init: WeightInit::Default,
+            inits: Vec::with_capacity(2*(layer_sizes.len()-1)),
+            frozen: Vec::with_capacity(2*(layer_sizes.len()-1)),
+            labels: Vec::with_capacity(2*(layer_sizes.len()-1)),
+            bottleneck: None,
+            grad_clip: GradClip::None,
+            training: false,
+        };
+        for shape in layer_sizes.windows(2) {
+            mlp.add(Box::new(net_layer::Linear::new(shape[0], shape[1])));
+            mlp.add(Box::new(activ_fn.clone()));
+        }
+        mlp
+    }
+
+    /// Adds the specified layer to the end of the network
+    fn add<'a>(&'a mut self, layer: Box<NetLayer<F>>) -> &'a mut BaseNeuralNet<T, F> {
+        self.add_with_init(layer, None)
+    }
+
+    /// Adds a layer to the end of the network with its own initialization
+    fn add_with_init(&mut self, layer: Box<NetLayer<F>>, init: Option<WeightInit>) -> &mut BaseNeuralNet<T, F> {
+        self.weights.extend_from_slice(&layer.init_params(init.clone()
+            .unwrap_or_else(|| self.weight_init.clone())));
+        self.layers.push(layer);
+        self.tied.push(None);
+        self.inits.push(init);
+        self.frozen.push(false);
+        self.labels.push(None);
+        self
+    }
+
+    /// Redraws the weights of every layer from their default initialization
+    fn reset_weights(&mut self) {
+        self.weights = self.default_weights();
+    }
+
+    /// The initialization of the weights of every untied layer
+    fn default_weights(&self) -> Vec<F> {
+        self.layers
+            .iter()
+            .zip(self.tied.iter())
+            .zip(self.inits.iter())
+            .filter(|&((_, tied), _)| tied.is_none())
+            .flat_map(|((layer, _), init)| {
+                layer.init_params(init.clone().unwrap_or_else(|| self.weight_init.clone()))
+            })
+            .collect()
+    }
+
+    /// Describes each layer, without checking that the sizes fit together
+    fn summary(&self) -> NetSummary {
+        let num_params = (0..self.layers.len()).map(|i| self.own_params(i)).collect::<Vec<_>>();
+        NetSummary::new(&self.layers, None, &num_params, &self.frozen, &self.labels)
+    }
+
+    /// Whether each entry of the network weights is a bias term
+    fn layer_bias_mask(&self) -> Vec<bool> {
+        self.layers
+            .iter()
+            .zip(self.tied.iter())
+            .filter(|&(_, tied)| tied.is_none())
+            .flat_map(|(layer, _)| layer.bias_mask())
+            .collect()
+    }
+
+    /// The range of the network weights used by each layer.
+    ///
+    /// Tied layers share the range of the layer they are tied to.
+    fn layer_ranges(&self) -> Vec<Range<usize>> {
+        self.layer_offsets()
+            .into_iter()
+            .enumerate()
+            .map(|(i, offset)| {
+                let source = self.tied[i].unwrap_or(i);
+                offset..offset + self.layers[source].num_params()
+            })
+            .collect()
+    }
+
+    /// The regularization cost of the weights, adding its gradient to `gradients`
+    ///
+    /// Layers with their own regularization are regularized separately.
+    /// The criterion's regularization applies to the remaining weights,
+    /// but is still scaled by the total number of weights.
+    fn regularize(&self, weights: &[f64], mut gradients: Option<&mut Vec<f64>>) -> f64 {
+        let mut cost = 0f64;
+        let mut shared = vec![self.criterion.is_regularized(); weights.len()];
+
+        let ranges = self.layer_ranges();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let reg = match layer.regularization() {
+                Some(reg) if self.tied[i].is_none() => reg,
+                _ => continue,
+            };
+            let range = ranges[i].clone();
+            for x in &mut shared[range.clone()] {
+                *x = false;
+            }
+            if range.start == range.end {
+                continue;
+            }
+
+            let layer_weights = unsafe {
+                MatrixSlice::from_raw_parts(weights[range.clone()].as_ptr(), range.len(), 1, 1)
+            };
+            cost += reg.reg_cost(layer_weights);
+            if let Some(ref mut gradients) = gradients {
+                utils::in_place_vec_bin_op(&mut gradients[range],
+                                           reg.reg_grad(layer_weights).data(),
+                                           |x, &y| *x = *x + y);
+            }
+        }
+
+        if shared.iter().any(|&x| x) {
+            // The excluded weights are zeroed so they add nothing to the norms
+            let shared_weights = Matrix::new(weights.len(),
+                                             1,
+                                             weights.iter()
+                                                 .zip(&shared)
+                                                 .map(|(&w, &s)| if s { w } else { 0f64 })
+                                                 .collect::<Vec<_>>());
+            cost += self.criterion.reg_cost(shared_weights.as_slice());
+            if let Some(ref mut gradients) = gradients {
+                let grad = self.criterion.reg_cost_grad(shared_weights.as_slice());
+                for ((g, &r), &s) in gradients.iter_mut().zip(grad.data()).zip(&shared) {
+                    if s {
+                        *g += r;
+                    }
+                }
+            }
+        }
+        cost
+    }
+
+    /// Copies the layers and weight tying with freshly initialized weights
+    fn fresh_copy(&self) -> BaseNeuralNet<T, F>
+        where T: Clone
+    {
+        let mut net = BaseNeuralNet {
+            layers: self.layers.iter().map(|layer| layer.clone_box()).collect(),
+            tied: self.tied.clone(),
             weights: Vec::new(),
-            criterion: criterion
+            criterion: self.criterion.clone(),
+            standardize: self.standardize,
+            input_stats: None,
+            weight_init: self.weight_init.clone(),
+            inits: self.inits.clone(),
+            frozen: self.frozen.clone(),
+            labels: self.labels.clone(),
+            bottleneck: self.bottleneck,
+            grad_clip: self.grad_clip,
+            training: self.training,
+        };
+        net.reset_weights();
+        net
+    }
+
+    /// Sets whether the weights of a layer are excluded from training.
+    fn set_frozen(&mut self, idx: usize, frozen: bool) -> LearningResult<()> {
+        if idx >= self.layers.len() {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The layer index is out of range of the network layers."));
+        }
+        self.frozen[idx] = frozen;
+        Ok(())
+    }
+
+    /// Whether each of the weights belongs to a frozen layer.
+    ///
+    /// Freezing a tied layer freezes the weights it shares.
+    fn frozen_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; self.weights.len()];
+        for (range, &frozen) in self.layer_ranges().into_iter().zip(&self.frozen) {
+            if frozen {
+                for m in &mut mask[range] {
+                    *m = true;
+                }
+            }
+        }
+        mask
+    }
+
+    /// Makes layer `b` use the transpose of layer `a`'s weights.
+    ///
+    /// The weights of layer `b` are removed from the network.
+    fn tie_weights(&mut self, a: usize, b: usize) -> LearningResult<()> {
+        let layers = self.layers.len();
+        if a >= layers || b >= layers || a == b {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "Weights must be tied between two different layers of the network."));
+        }
+
+        if self.tied[a].is_some() || self.tied[b].is_some() ||
+           self.tied.iter().any(|t| *t == Some(b)) {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "A layer can only be tied once and cannot be tied to a tied layer."));
+        }
+
+        let shape_a = self.layers[a].param_shape();
+        if self.layers[b].param_shape() != (shape_a.1, shape_a.0) {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The weights of the tied layer must have the transposed shape."));
+        }
+
+        let start = self.layer_offsets()[b];
+        let end = start + self.layers[b].num_params();
+        self.weights.drain(start..end);
+        self.tied[b] = Some(a);
+        Ok(())
+    }
+
+    /// The number of parameters each layer stores in the weights.
+    ///
+    /// This is zero for tied layers.
+    fn own_params(&self, idx: usize) -> usize {
+        if self.tied[idx].is_some() {
+            0
+        } else {
+            self.layers[idx].num_params()
+        }
+    }
+
+    /// The offset of each layer's parameters within the weights.
+    ///
+    /// Tied layers share the offset of the layer they are tied to.
+    fn layer_offsets(&self) -> Vec<usize> {
+        let mut offsets = Vec::with_capacity(self.layers.len());
+        let mut index = 0;
+        for i in 0..self.layers.len() {
+            offsets.push(index);
+            index += self.own_params(i);
+        }
+
+        for (i, tied) in self.tied.iter().enumerate() {
+            if let Some(source) = *tied {
+                offsets[i] = offsets[source];
+            }
+        }
+        offsets
+    }
+
+    /// Computes the transposed weights used by each tied layer.
+    fn tied_params(&self, weights: &[F], offsets: &[usize]) -> Vec<Option<Matrix<F>>> {
+        self.tied
+            .iter()
+            .map(|tied| {
+                tied.map(|source| {
+                    params_at(weights, offsets[source], self.layers[source].param_shape())
+                        .transpose()
+                })
+            })
+            .collect()
+    }
+
+    /// Gets the parameters used by a layer in a forward pass.
+    fn layer_params<'a>(&self,
+                        weights: &'a [F],
+                        offsets: &[usize],
+                        tied_params: &'a [Option<Matrix<F>>],
+                        idx: usize)
+                        -> MatrixSlice<'a, F> {
+        match tied_params[idx] {
+            Some(ref params) => params.as_slice(),
+            None => params_at(weights, offsets[idx], self.layers[idx].param_shape()),
+        }
+    }
+
+    /// Adds multiple layers to the end of the network
+    fn add_layers<'a, U>(&'a mut self, layers: U) -> &'a mut BaseNeuralNet<T, F>
+        where U: IntoIterator<Item = Box<NetLayer<F>>> 
+    {
+        for layer in layers {
+            self.add(layer);
+        }
+        self
+    }
+
+    /// Gets matrix of weights for the specified layer for the weights.
+    ///
+    /// For a tied layer these are the weights of the layer it is tied to.
+    fn get_layer_weights<'a>(&self, weights: &'a [F], idx: usize) -> MatrixSlice<'a, F> {
+        debug_assert!(idx < self.layers.len());
+
+        // Check that the weights are the right size.
+        let full_size: usize = (0..self.layers.len()).map(|i| self.own_params(i)).sum();
+
+        debug_assert_eq!(full_size, weights.len());
+
+        let idx = self.tied[idx].unwrap_or(idx);
+        params_at(weights, self.layer_offsets()[idx], self.layers[idx].param_shape())
+    }
+
+    /// Compute the gradient using the back propagation algorithm.
+    ///
+    /// The weights and gradient are in `f64`, as used by the optimizers.
+    /// For an `f32` network this copies the weights into `f32` and the
+    /// gradient back into `f64` on every call.
+    ///
+    /// Returns an `InvalidData` error if the inputs do not match the network,
+    /// or the targets do not match the shape of the network outputs.
+    fn cost_grad(&self,
+                 weights: &[f64],
+                 inputs: &Matrix<F>,
+                 targets: &Matrix<F>)
+                 -> LearningResult<(f64, Vec<f64>)> {
+        let inputs = &*try!(self.scale_inputs(inputs));
+        let (cost, gradients) = try!(self.backprop(&F::from_f64_slice(weights), inputs, targets));
+        let mut gradients = F::into_f64_vec(gradients);
+        let cost = cost + self.regularize(weights, Some(&mut gradients));
+        for (g, frozen) in gradients.iter_mut().zip(self.frozen_mask()) {
+            if frozen {
+                *g = 0f64;
+            }
+        }
+        self.grad_clip.apply(&mut gradients);
+        Ok((cost, gradients))
+    }
+
+    /// Computes the unregularized cost and its gradient by back propagation.
+    ///
+    /// With the `parallel` feature the rows are split into chunks for
+    /// each thread, if every layer processes rows independently.
+    #[cfg(feature = "parallel")]
+    fn backprop(&self, weights: &[F], inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<(f64, Vec<F>)> {
+        let chunks = (inputs.rows() / PARALLEL_CHUNK_ROWS).min(rayon::current_num_threads());
+        let layers = self.layers.iter().map(|layer| layer.as_independent()).collect::<Option<Vec<_>>>();
+        match layers {
+            Some(ref layers) if chunks > 1 => self.par_backprop(layers, weights, inputs, targets, chunks),
+            _ => self.serial_backprop(weights, inputs, targets),
+        }
+    }
+
+    /// Computes the unregularized cost and its gradient by back propagation.
+    #[cfg(not(feature = "parallel"))]
+    fn backprop(&self, weights: &[F], inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<(f64, Vec<F>)> {
+        self.serial_backprop(weights, inputs, targets)
+    }
+
+    /// Computes the cost and its gradient on chunks of rows in parallel.
+    ///
+    /// Each chunk is propagated forward and backward on its own thread,
+    /// and the gradients of the chunks are summed. The criterion is applied
+    /// to the outputs of all of the rows at once. The `layers` are the
+    /// thread safe references to the network's layers.
+    #[cfg(feature = "parallel")]
+    fn par_backprop(&self,
+                    layers: &[&(NetLayer<F> + Sync)],
+                    weights: &[F],
+                    inputs: &Matrix<F>,
+                    targets: &Matrix<F>,
+                    chunks: usize)
+                    -> LearningResult<(f64, Vec<F>)> {
+        let offsets = self.layer_offsets();
+        let tied_params = self.tied_params(weights, &offsets);
+        let params = (0..self.layers.len())
+            .map(|i| self.layer_params(weights, &offsets, &tied_params, i).into_matrix())
+            .collect::<Vec<_>>();
+        let tied = &self.tied;
+
+        let chunk_rows = (inputs.rows() + chunks - 1) / chunks;
+        let bounds = (0..inputs.rows())
+            .step_by(chunk_rows)
+            .map(|start| (start, chunk_rows.min(inputs.rows() - start)))
+            .collect::<Vec<_>>();
+
+        // activations[c][i] is the input of layer[i] for chunk c
+        let activations = bounds.par_iter()
+            .map(|&(start, rows)| {
+                let mut chunk_activations = Vec::with_capacity(layers.len() + 1);
+                chunk_activations.push(inputs.sub_slice([start, 0], rows, inputs.cols()).into_matrix());
+                for (layer, params) in layers.iter().zip(&params) {
+                    let output = try!(layer.forward_train(chunk_activations.last().unwrap(), params.as_slice()));
+                    chunk_activations.push(output);
+                }
+                Ok(chunk_activations)
+            })
+            .collect::<Vec<LearningResult<_>>>();
+        let activations = try!(activations.into_iter().collect::<LearningResult<Vec<_>>>());
+
+        let output_cols = activations[0].last().unwrap().cols();
+        let output = Matrix::new(inputs.rows(),
+                                 output_cols,
+                                 activations.iter()
+                                     .flat_map(|chunk| chunk.last().unwrap().data().iter().cloned())
+                                     .collect::<Vec<_>>());
+        let (cost, cost_grad) = try!(self.criterion_cost_grad(&output, targets));
+
+        let gradients = activations.into_par_iter()
+            .zip(bounds)
+            .map(|(chunk_activations, (start, rows))| {
+                let mut gradients = vec![F::zero(); weights.len()];
+                let mut out_grad = cost_grad.sub_slice([start, 0], rows, output_cols).into_matrix();
+                for (i, layer) in layers.iter().enumerate().rev() {
+                    let (activation, result) = (&chunk_activations[i], &chunk_activations[i + 1]);
+                    let params = params[i].as_slice();
+
+                    let mut layer_grad = layer.back_params(&out_grad, activation, result, params);
+                    if tied[i].is_some() {
+                        layer_grad = layer_grad.transpose();
+                    }
+                    let grad_params = &mut gradients[offsets[i]..offsets[i] + layer.num_params()];
+                    utils::in_place_vec_bin_op(grad_params, layer_grad.data(), |x, &y| *x = *x + y);
+
+                    out_grad = layer.back_input(&out_grad, activation, result, params);
+                }
+                gradients
+            })
+            .reduce(|| vec![F::zero(); weights.len()], |mut total, gradients| {
+                utils::in_place_vec_bin_op(&mut total, &gradients, |x, &y| *x = *x + y);
+                total
+            });
+
+        Ok((cost, gradients))
+    }
+
+    /// Computes the cost and its gradient on all of the rows at once.
+    fn serial_backprop(&self,
+                       weights: &[F],
+                       inputs: &Matrix<F>,
+                       targets: &Matrix<F>)
+                       -> LearningResult<(f64, Vec<F>)> {
+        // Tied layers add to the gradient of the weights they share
+        let mut gradients = vec![F::zero(); weights.len()];
+        // activations[i] is the output of layer[i]
+        let mut activations = Vec::with_capacity(self.layers.len());
+        // params[i] is the weights for layer[i]
+        let mut params = Vec::with_capacity(self.layers.len());
+
+        let offsets = self.layer_offsets();
+        let tied_params = self.tied_params(weights, &offsets);
+
+        // Forward propagation
+        
+        for (i, layer) in self.layers.iter().enumerate() {
+            let slice = self.layer_params(weights, &offsets, &tied_params, i);
+
+            let output = if i == 0 {
+                try!(layer.forward_train(inputs, slice))
+            } else {
+                try!(layer.forward_train(activations.last().unwrap(), slice))
+            };
+
+            activations.push(output);
+            params.push(slice);
+        }
+        let (cost, mut out_grad) = try!(self.criterion_cost_grad(activations.last().unwrap(), targets));
+
+        // Backward propagation
+        
+        // out_grad is the gradient with respect to the current layer's output
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let activation = if i == 0 {inputs} else {&activations[i-1]};
+            let result = &activations[i];
+            let index = offsets[i];
+
+            let mut layer_grad = layer.back_params(&out_grad, activation, result, params[i]);
+            if self.tied[i].is_some() {
+                layer_grad = layer_grad.transpose();
+            }
+            let grad_params = &mut gradients[index..index+layer.num_params()];
+            utils::in_place_vec_bin_op(grad_params, layer_grad.data(), |x, &y| *x = *x + y);
+            
+            out_grad = layer.back_input(&out_grad, activation, result, params[i]);
+        }
+
+        Ok((cost, gradients))
+    }
+
+    /// The criterion's cost of the outputs and its gradient.
+    fn criterion_cost_grad(&self, outputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<(f64, Matrix<F>)> {
+        if outputs.rows() != targets.rows() || outputs.cols() != targets.cols() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The targets do not match the shape of the network outputs."));
+        }
+        let outputs = F::as_f64_matrix(outputs);
+        let targets = F::as_f64_matrix(targets);
+        let grad = self.criterion.cost_grad(&outputs, &targets);
+        Ok((self.criterion.cost(&outputs, &targets), from_f64_matrix(grad)))
+    }
+
+    /// Checks that the training data contains only finite values.
+    ///
+    /// `NaN` targets are allowed if the criterion treats them as missing.
+    fn check_finite(&self, inputs: &Matrix<F>, targets: &Matrix<F>) -> LearningResult<()> {
+        let allow_nan = self.criterion.allows_missing_targets();
+        let checks = [(inputs, "inputs", false), (targets, "targets", allow_nan)];
+
+        for &(mat, name, allow_nan) in &checks {
+            let bad = mat.iter()
+                .position(|x| !(x.is_finite() || allow_nan && x.is_nan()));
+            if let Some(idx) = bad {
+                return Err(Error::new(ErrorKind::InvalidData,
+                                      format!("The training {} contain {} at row {}, column {}.",
+                                              name,
+                                              mat.data()[idx],
+                                              idx / mat.cols(),
+                                              idx % mat.cols())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores the input statistics if standardizing and not yet fitted.
+    ///
+    /// The statistics are accumulated in `f64` whatever the float type.
+    /// Returns an `InvalidData` error if statistics are already stored for
+    /// a different number of columns.
+    fn fit_input_stats(&mut self, inputs: &Matrix<F>) -> LearningResult<()> {
+        if self.input_stats.is_some() {
+            return self.check_input_stats(inputs);
+        }
+        if !self.standardize || inputs.rows() == 0 {
+            return Ok(());
+        }
+
+        let n = inputs.rows() as f64;
+        let mut mean = vec![0f64; inputs.cols()];
+        for row in inputs.row_iter() {
+            for (m, &x) in mean.iter_mut().zip(row.raw_slice()) {
+                *m += x.as_f64() / n;
+            }
+        }
+
+        let mut std = vec![0f64; inputs.cols()];
+        for row in inputs.row_iter() {
+            for ((s, &x), &m) in std.iter_mut().zip(row.raw_slice()).zip(&mean) {
+                *s += (x.as_f64() - m) * (x.as_f64() - m) / n;
+            }
+        }
+        for s in &mut std {
+            *s = if *s > 0f64 { s.sqrt() } else { 1f64 };
+        }
+
+        self.input_stats = Some((Vector::new(mean), Vector::new(std)));
+        Ok(())
+    }
+
+    /// Checks that the inputs have a column for each stored input statistic.
+    fn check_input_stats<M: BaseMatrix<F>>(&self, inputs: &M) -> LearningResult<()> {
+        match self.input_stats {
+            Some((ref mean, _)) if inputs.cols() != mean.size() => {
+                Err(Error::new(ErrorKind::InvalidData,
+                               format!("The inputs have {} columns but the network standardizes {}.",
+                                       inputs.cols(),
+                                       mean.size())))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Standardizes the inputs using the stored statistics, if there are any.
+    ///
+    /// Returns an `InvalidData` error if the inputs do not match the statistics.
+    fn scale_inputs<'a>(&self, inputs: &'a Matrix<F>) -> LearningResult<Cow<'a, Matrix<F>>> {
+        match try!(self.standardized(inputs)) {
+            Some(scaled) => Ok(Cow::Owned(scaled)),
+            None => Ok(Cow::Borrowed(inputs)),
+        }
+    }
+
+    /// A standardized copy of the inputs, or `None` if there are no statistics.
+    ///
+    /// Returns an `InvalidData` error if the inputs do not match the statistics.
+    fn standardized<M: BaseMatrix<F>>(&self, inputs: &M) -> LearningResult<Option<Matrix<F>>> {
+        try!(self.check_input_stats(inputs));
+        Ok(self.input_stats.as_ref().map(|&(ref mean, ref std)| {
+            let mut scaled = Vec::with_capacity(inputs.rows() * inputs.cols());
+            for row in inputs.row_iter() {
+                for ((x, m), s) in row.raw_slice().iter().zip(mean.iter()).zip(std.iter()) {
+                    scaled.push(F::from_f64((x.as_f64() - m) / s));
+                }
+            }
+            Matrix::new(inputs.rows(), inputs.cols(), scaled)
+        }))
+    }
+
+    /// Back propagates a gradient of the outputs to the inputs.
+    ///
+    /// Uses the model weights and the mode of the network.
+    fn input_grad(&self, inputs: &Matrix<F>, out_grad: Matrix<F>) -> LearningResult<Matrix<F>> {
+        let inputs = &*try!(self.scale_inputs(inputs));
+        let offsets = self.layer_offsets();
+        let tied_params = self.tied_params(&self.weights, &offsets);
+
+        // activations[i] is the output of layer[i]
+        let mut activations = Vec::with_capacity(self.layers.len());
+        for (i, layer) in self.layers.iter().enumerate() {
+            let slice = self.layer_params(&self.weights, &offsets, &tied_params, i);
+            let output = try!(self.layer_forward(&**layer, activations.last().unwrap_or(inputs), slice));
+            activations.push(output);
+        }
+
+        let mut grad = out_grad;
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let activation = if i == 0 { inputs } else { &activations[i - 1] };
+            let slice = self.layer_params(&self.weights, &offsets, &tied_params, i);
+            grad = layer.back_input(&grad, activation, &activations[i], slice);
+        }
+
+        // Chain rule through the standardization
+        if let Some((_, ref std)) = self.input_stats {
+            for mut row in grad.row_iter_mut() {
+                for (g, &s) in row.raw_slice_mut().iter_mut().zip(std.data()) {
+                    *g = *g / F::from_f64(s);
+                }
+            }
+        }
+        Ok(grad)
+    }
+
+    /// Propagates data forward through a layer in the mode of the network.
+    fn layer_forward(&self,
+                     layer: &NetLayer<F>,
+                     input: &Matrix<F>,
+                     params: MatrixSlice<F>)
+                     -> LearningResult<Matrix<F>> {
+        if self.training {
+            layer.forward_train(input, params)
+        } else {
+            layer.forward(input, params)
+        }
+    }
+
+    /// Propagates borrowed data forward through the first layer in the mode of the network.
+    ///
+    /// Layers in training mode are given a copy of the inputs.
+    fn layer_forward_slice(&self,
+                           layer: &NetLayer<F>,
+                           input: MatrixSlice<F>,
+                           params: MatrixSlice<F>)
+                           -> LearningResult<Matrix<F>> {
+        if self.training {
+            layer.forward_train(&input.into_matrix(), params)
+        } else {
+            layer.forward_slice(input, params)
+        }
+    }
+
+    /// Forward propagation of the model weights to get the outputs.
+    fn forward_prop(&self, inputs: &Matrix<F>) -> LearningResult<Matrix<F>> {
+        self.forward_prop_with(&self.weights, inputs.as_slice())
+    }
+
+    /// Forward propagation of the given weights to get the outputs.
+    fn forward_prop_with(&self, weights: &[F], inputs: MatrixSlice<F>) -> LearningResult<Matrix<F>> {
+        if self.layers.is_empty() {
+            return self.standardized(&inputs).map(|scaled| scaled.unwrap_or_else(|| inputs.into_matrix()));
+        }
+        self.forward_prop_to(weights, inputs, self.layers.len() - 1)
+    }
+
+    /// Forward propagation of the given weights up to and including the given layer.
+    ///
+    /// The inputs are only copied if they are standardized.
+    fn forward_prop_to(&self,
+                       weights: &[F],
+                       inputs: MatrixSlice<F>,
+                       layer_idx: usize)
+                       -> LearningResult<Matrix<F>> {
+        if layer_idx >= self.layers.len() {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The layer index is out of range of the network layers."));
+        }
+
+        let scaled = try!(self.standardized(&inputs));
+        let offsets = self.layer_offsets();
+        let tied_params = self.tied_params(weights, &offsets);
+
+        let mut outputs = {
+            let slice = self.layer_params(weights, &offsets, &tied_params, 0);
+            match scaled {
+                Some(ref scaled) => try!(self.layer_forward(&*self.layers[0], scaled, slice)),
+                None => try!(self.layer_forward_slice(&*self.layers[0], inputs, slice)),
+            }
+        };
+
+        for (i, layer) in self.layers.iter().enumerate().take(layer_idx + 1).skip(1) {
+            let slice = self.layer_params(weights, &offsets, &tied_params, i);
+            
+            outputs = match self.layer_forward(&**layer, &outputs, slice) {
+                Ok(act) => act,
+                Err(_) => {return Err(Error::new(ErrorKind::InvalidParameters,
+                    "The network's layers do not line up correctly."))}
+            };
+        }
+        Ok(outputs)
+    }
+}
+
+/// A neural network with weights quantized to integers.
+///
+/// Created by `NeuralNet::quantize`. Each layer's weights are stored as
+/// signed integers with a scale factor for the layer. The network owns
+/// its layers, and the weights are converted back to floats once when it
+/// is created, so predicting costs the same as with the original network.
+#[derive(Debug)]
+pub struct QuantizedNet<T: Criterion> {
+    /// The network layers, holding the dequantized weights
+    base: BaseNeuralNet<T>,
+    /// The quantized weights, in the same layout as the network's weights
+    values: Vec<i16>,
+    /// The scale factor of each layer, `None` for tied layers
+    scales: Vec<Option<f64>>,
+    bits: u8,
+}
+
+impl<T: Criterion> QuantizedNet<T> {
+    /// Quantizes the network weights symmetrically within each layer.
+    fn new(mut base: BaseNeuralNet<T>, bits: u8) -> QuantizedNet<T> {
+        assert!(bits >= 2 && bits <= 16, "Weights must be quantized to between 2 and 16 bits.");
+
+        let max_level = ((1i32 << (bits - 1)) - 1) as f64;
+        let offsets = base.layer_offsets();
+        let mut values = vec![0i16; base.weights.len()];
+        let mut scales = Vec::with_capacity(base.layers.len());
+
+        for (i, &offset) in offsets.iter().enumerate() {
+            if base.tied[i].is_some() {
+                scales.push(None);
+                continue;
+            }
+
+            let weights = &base.weights[offset..offset + base.own_params(i)];
+            let max_abs = weights.iter().fold(0f64, |acc, w| acc.max(w.abs()));
+            let scale = if max_abs > 0f64 { max_abs / max_level } else { 1f64 };
+
+            for (q, w) in values[offset..].iter_mut().zip(weights) {
+                *q = (w / scale).round() as i16;
+            }
+            scales.push(Some(scale));
+        }
+
+        for (i, &offset) in offsets.iter().enumerate() {
+            if let Some(scale) = scales[i] {
+                let end = offset + base.own_params(i);
+                for (w, &q) in base.weights[offset..end].iter_mut().zip(&values[offset..end]) {
+                    *w = q as f64 * scale;
+                }
+            }
+        }
+
+        QuantizedNet {
+            base: base,
+            values: values,
+            scales: scales,
+            bits: bits,
+        }
+    }
+
+    /// The number of bits each weight was quantized to.
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// The scale factor of each layer's weights.
+    ///
+    /// A weight is recovered as its integer value times the scale.
+    /// Tied layers share the scale of the layer they are tied to.
+    pub fn scales(&self) -> Vec<f64> {
+        (0..self.scales.len())
+            .map(|i| {
+                let source = self.base.tied[i].unwrap_or(i);
+                self.scales[source].unwrap_or(1f64)
+            })
+            .collect()
+    }
+
+    /// The quantized value of each weight, in the layout of the network's weights.
+    pub fn values(&self) -> &[i16] {
+        &self.values
+    }
+
+    /// The weights of the network recovered from their quantized values.
+    pub fn dequantized_weights(&self) -> &[f64] {
+        &self.base.weights
+    }
+
+    /// Predict the network outputs using the quantized weights.
+    pub fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        self.base.forward_prop_with(&self.base.weights, inputs.as_slice())
+    }
+}
+
+/// Converts a matrix of `f64` values to the network's float type.
+fn from_f64_matrix<F: NetFloat>(mat: Matrix<f64>) -> Matrix<F> {
+    Matrix::new(mat.rows(), mat.cols(), F::from_f64_vec(mat.into_vec()))
+}
+
+/// Views part of the weights as a matrix of the given shape.
+fn params_at<F>(weights: &[F], offset: usize, shape: (usize, usize)) -> MatrixSlice<F> {
+    assert!(offset + shape.0 * shape.1 <= weights.len());
+    unsafe {
+        MatrixSlice::from_raw_parts(weights.as_ptr().offset(offset as isize),
+                                    shape.0,
+                                    shape.1,
+                                    shape.1)
+    }
+}
+
+/// Compute the gradient of the Neural Network using the
+/// back propagation algorithm.
+impl<T: Criterion, F: NetFloat> Optimizable for BaseNeuralNet<T, F> {
+    type Inputs = Matrix<F>;
+    type Targets = Matrix<F>;
+
+    /// Compute the gradient of the neural network.
+    ///
+    /// Panics if the inputs or targets do not match the network.
+    /// `NeuralNet::cost_and_grad` returns an error instead.
+    fn compute_grad(&self,
+                    params: &[f64],
+                    inputs: &Matrix<F>,
+                    targets: &Matrix<F>)
+                    -> (f64, Vec<f64>) {
+        self.cost_grad(params, inputs, targets).unwrap()
+    }
+
+    /// Excludes the bias terms of each layer, and frozen layers, from weight decay.
+    fn weight_decay_mask(&self, params: &[f64]) -> Vec<bool> {
+        self.bias_mask(params)
+            .into_iter()
+            .zip(self.frozen_mask())
+            .map(|(is_bias, frozen)| !is_bias && !frozen)
+            .collect()
+    }
+
+    fn bias_mask(&self, params: &[f64]) -> Vec<bool> {
+        debug_assert_eq!(self.weights.len(), params.len());
+        self.layer_bias_mask()
+    }
+
+    /// Groups the parameters by layer.
+    fn param_groups(&self, params: &[f64]) -> Vec<Range<usize>> {
+        debug_assert_eq!(self.weights.len(), params.len());
+        self.layer_ranges()
+    }
+}
+
+/// Criterion for Neural Networks
+///
+/// Specifies an activation function and a cost function.
+pub trait Criterion {
+    /// The cost function for the criterion.
+    type Cost: CostFunc<Matrix<f64>>;
+
+    /// The cost function.
+    ///
+    /// Returns a scalar cost, combined over the samples using the reduction.
+    fn cost(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        Self::Cost::cost(outputs, targets) * self.reduction().scale(outputs.rows())
+    }
+
+    /// The gradient of the cost function.
+    ///
+    /// Returns the gradient of `cost` with respect to the outputs.
+    fn cost_grad(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        Self::Cost::grad_cost(outputs, targets) * self.reduction().grad_scale(outputs.rows())
+    }
+
+    /// The cost of each sample, one per row of the outputs.
+    fn sample_costs(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Vector<f64> {
+        cost_fn::sample_costs::<Self::Cost>(outputs, targets)
+    }
+
+    /// Returns how the costs of the samples are combined.
+    ///
+    /// Will return `Reduction::Mean` by default.
+    fn reduction(&self) -> Reduction {
+        Reduction::Mean
+    }
+
+    /// Whether `NaN` targets are treated as missing by the cost function.
+    ///
+    /// Otherwise training rejects them along with other non-finite data.
+    /// Will return `false` by default.
+    fn allows_missing_targets(&self) -> bool {
+        false
+    }
+
+    /// Returns the regularization for this criterion.
+    ///
+    /// Will return `Regularization::None` by default.
+    fn regularization(&self) -> Regularization<f64> {
+        Regularization::None
+    }
+
+    /// Checks if the current criterion includes regularization.
+    ///
+    /// Will return `false` by default.
+    fn is_regularized(&self) -> bool {
+        match self.regularization() {
+            Regularization::None => false,
+            _ => true,
+        }
+    }
+
+    /// Returns the regularization cost for the criterion.
+    ///
+    /// Will return `0` by default.
+    ///
+    /// This method will not be invoked by the neural network
+    /// if there is explicitly no regularization.
+    fn reg_cost(&self, reg_weights: MatrixSlice<f64>) -> f64 {
+        self.regularization().reg_cost(reg_weights)
+    }
+
+    /// Returns the regularization gradient for the criterion.
+    ///
+    /// Will return a matrix of zeros by default.
+    ///
+    /// This method will not be invoked by the neural network
+    /// if there is explicitly no regularization.
+    fn reg_cost_grad(&self, reg_weights: MatrixSlice<f64>) -> Matrix<f64> {
+        self.regularization().reg_grad(reg_weights)
+    }
+}
+
+/// The binary cross entropy criterion.
+///
+/// Uses the Sigmoid activation function and the
+/// cross entropy error.
+#[derive(Clone, Copy, Debug)]
+pub struct BCECriterion {
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for BCECriterion {
+    type Cost = cost_fn::CrossEntropyError;
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+}
+
+/// Creates an MSE Criterion without any regularization.
+impl Default for BCECriterion {
+    fn default() -> Self {
+        BCECriterion::new(Regularization::None)
+    }
+}
+
+impl BCECriterion {
+    /// Constructs a new BCECriterion with the given regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::BCECriterion;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    ///
+    /// // Create a new BCE criterion with L2 regularization of 0.3.
+    /// let criterion = BCECriterion::new(Regularization::L2(0.3f64));
+    /// ```
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        BCECriterion {
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets how the costs of the samples are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::BCECriterion;
+    /// use rusty_machine::learning::toolkit::cost_fn::Reduction;
+    ///
+    /// // Create a BCE criterion using the total cost over each batch.
+    /// let criterion = BCECriterion::default().with_reduction(Reduction::Sum);
+    /// ```
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+/// The mean squared error criterion.
+///
+/// Uses the Linear activation function and the
+/// mean squared error.
+#[derive(Clone, Copy, Debug)]
+pub struct MSECriterion {
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for MSECriterion {
+    type Cost = cost_fn::MeanSqError;
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+}
+
+/// Creates an MSE Criterion without any regularization.
+impl Default for MSECriterion {
+    fn default() -> Self {
+        MSECriterion::new(Regularization::None)
+    }
+}
+
+impl MSECriterion {
+    /// Constructs a new BCECriterion with the given regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::MSECriterion;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    ///
+    /// // Create a new MSE criterion with L2 regularization of 0.3.
+    /// let criterion = MSECriterion::new(Regularization::L2(0.3f64));
+    /// ```
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        MSECriterion {
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets how the costs of the samples are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::MSECriterion;
+    /// use rusty_machine::learning::toolkit::cost_fn::Reduction;
+    ///
+    /// // Create an MSE criterion using the total cost over each batch.
+    /// let criterion = MSECriterion::default().with_reduction(Reduction::Sum);
+    /// ```
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+/// The mean squared error criterion, ignoring missing targets.
+///
+/// Uses the masked mean squared error, so `NaN` targets are treated as
+/// missing rather than rejected by training. Without missing targets it
+/// is the same as `MSECriterion`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::{NeuralNet, MaskedMSECriterion};
+/// use rusty_machine::learning::optim::grad_desc::GradientDesc;
+/// use rusty_machine::learning::toolkit::activ_fn::Linear;
+/// use rusty_machine::learning::SupModel;
+/// use rusty_machine::linalg::Matrix;
+/// use std::f64::NAN;
+///
+/// let inputs = Matrix::new(2, 1, vec![0.0, 1.0]);
+/// let targets = Matrix::new(2, 2, vec![0.0, NAN, 1.0, 2.0]);
+///
+/// let mut net = NeuralNet::mlp(&[1, 2], MaskedMSECriterion::default(), GradientDesc::default(), Linear);
+/// net.train(&inputs, &targets).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct MaskedMSECriterion {
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for MaskedMSECriterion {
+    type Cost = cost_fn::MaskedMeanSqError;
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+
+    fn allows_missing_targets(&self) -> bool {
+        true
+    }
+}
+
+/// Creates a masked MSE Criterion without any regularization.
+impl Default for MaskedMSECriterion {
+    fn default() -> Self {
+        MaskedMSECriterion::new(Regularization::None)
+    }
+}
+
+impl MaskedMSECriterion {
+    /// Constructs a new MaskedMSECriterion with the given regularization.
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        MaskedMSECriterion {
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets how the costs of the samples are combined.
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+/// The multiclass hinge loss criterion.
+///
+/// Should be used with a Linear output layer and
+/// the multiclass hinge error.
+#[derive(Clone, Copy, Debug)]
+pub struct MulticlassHingeCriterion {
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for MulticlassHingeCriterion {
+    type Cost = cost_fn::MulticlassHingeError;
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+}
+
+/// Creates a multiclass hinge criterion without any regularization.
+impl Default for MulticlassHingeCriterion {
+    fn default() -> Self {
+        MulticlassHingeCriterion::new(Regularization::None)
+    }
+}
+
+impl MulticlassHingeCriterion {
+    /// Constructs a new MulticlassHingeCriterion with the given regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::MulticlassHingeCriterion;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    ///
+    /// // Create a new hinge criterion with L2 regularization of 0.3.
+    /// let criterion = MulticlassHingeCriterion::new(Regularization::L2(0.3f64));
+    /// ```
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        MulticlassHingeCriterion {
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets how the costs of the samples are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::MulticlassHingeCriterion;
+    /// use rusty_machine::learning::toolkit::cost_fn::Reduction;
+    ///
+    /// // Create a hinge criterion using the total cost over each batch.
+    /// let criterion = MulticlassHingeCriterion::default().with_reduction(Reduction::Sum);
+    /// ```
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+/// The categorical cross entropy criterion.
+///
+/// Should be used with a `Softmax` output layer and one-hot targets.
+/// The gradient through the softmax is then `outputs - targets`.
+/// `SoftmaxCECriterion` computes the same cost from the scores before
+/// the softmax, which is more stable when the predictions are confident.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::{NeuralNet, CategoricalCECriterion};
+/// use rusty_machine::learning::nnet::net_layer::{NetLayer, Softmax};
+/// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+/// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+///
+/// // Sigmoid hidden layer with a softmax over 3 classes.
+/// let activations: Vec<Box<NetLayer>> = vec![Box::new(Sigmoid), Box::new(Softmax)];
+/// let net = NeuralNet::mlp_with_activations(&[4, 8, 3],
+///                                           activations,
+///                                           CategoricalCECriterion::default(),
+///                                           StochasticGD::default())
+///                     .unwrap();
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct CategoricalCECriterion {
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for CategoricalCECriterion {
+    type Cost = cost_fn::CategoricalCrossEntropyError;
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+}
+
+/// Creates a categorical cross entropy criterion without any regularization.
+impl Default for CategoricalCECriterion {
+    fn default() -> Self {
+        CategoricalCECriterion::new(Regularization::None)
+    }
+}
+
+impl CategoricalCECriterion {
+    /// Constructs a new CategoricalCECriterion with the given regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::CategoricalCECriterion;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    ///
+    /// // Create a new categorical cross entropy criterion with L2 regularization of 0.3.
+    /// let criterion = CategoricalCECriterion::new(Regularization::L2(0.3f64));
+    /// ```
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        CategoricalCECriterion {
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets how the costs of the samples are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::CategoricalCECriterion;
+    /// use rusty_machine::learning::toolkit::cost_fn::Reduction;
+    ///
+    /// // Create a criterion using the total cost over each batch.
+    /// let criterion = CategoricalCECriterion::default().with_reduction(Reduction::Sum);
+    /// ```
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+/// The softmax cross entropy criterion.
+///
+/// Should be used with a Linear output layer and one-hot targets. The
+/// outputs of the network are the class scores (logits), and the cost is
+/// the categorical cross entropy of their softmax. It is computed from the
+/// scores directly, which is stable for large scores, and the gradient of
+/// the scores is `softmax(outputs) - targets`.
+///
+/// The predictions of the network are the scores, so the highest scoring
+/// class is the predicted class. A `Softmax` layer gives the probabilities.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::{NeuralNet, SoftmaxCECriterion};
+/// use rusty_machine::learning::toolkit::activ_fn::Linear;
+/// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+///
+/// // A linear classifier over 3 classes.
+/// let net = NeuralNet::mlp(&[4, 3], SoftmaxCECriterion::default(), StochasticGD::default(), Linear);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct SoftmaxCECriterion {
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for SoftmaxCECriterion {
+    type Cost = cost_fn::SoftmaxCrossEntropyError;
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+}
+
+/// Creates a softmax cross entropy criterion without any regularization.
+impl Default for SoftmaxCECriterion {
+    fn default() -> Self {
+        SoftmaxCECriterion::new(Regularization::None)
+    }
+}
+
+impl SoftmaxCECriterion {
+    /// Constructs a new SoftmaxCECriterion with the given regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::SoftmaxCECriterion;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    ///
+    /// // Create a new softmax cross entropy criterion with L2 regularization of 0.3.
+    /// let criterion = SoftmaxCECriterion::new(Regularization::L2(0.3f64));
+    /// ```
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        SoftmaxCECriterion {
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets how the costs of the samples are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::SoftmaxCECriterion;
+    /// use rusty_machine::learning::toolkit::cost_fn::Reduction;
+    ///
+    /// // Create a criterion using the total cost over each batch.
+    /// let criterion = SoftmaxCECriterion::default().with_reduction(Reduction::Sum);
+    /// ```
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+/// The Huber loss criterion.
+///
+/// Should be used with a Linear output layer for regression. Residuals up
+/// to `delta` are penalized as in `MSECriterion`, and larger ones linearly,
+/// so outliers in the targets have less influence on the fit.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::{NeuralNet, HuberCriterion};
+/// use rusty_machine::learning::toolkit::activ_fn::Linear;
+/// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+///
+/// let criterion = HuberCriterion::default().with_delta(0.5);
+/// let net = NeuralNet::mlp(&[2, 1], criterion, StochasticGD::default(), Linear);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct HuberCriterion {
+    loss: cost_fn::HuberLoss,
+    regularization: Regularization<f64>,
+    reduction: Reduction,
+}
+
+impl Criterion for HuberCriterion {
+    type Cost = cost_fn::HuberLoss;
+
+    fn cost(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        self.loss.loss(outputs, targets) * self.reduction.scale(outputs.rows())
+    }
+
+    fn cost_grad(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        self.loss.grad_loss(outputs, targets) * self.reduction.grad_scale(outputs.rows())
+    }
+
+    fn sample_costs(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Vector<f64> {
+        let costs = (0..outputs.rows())
+            .map(|i| self.loss.loss(&outputs.select_rows(&[i]), &targets.select_rows(&[i])))
+            .collect::<Vec<_>>();
+        Vector::new(costs)
+    }
+
+    fn regularization(&self) -> Regularization<f64> {
+        self.regularization
+    }
+
+    fn reduction(&self) -> Reduction {
+        self.reduction
+    }
+}
+
+/// Creates a Huber criterion with a delta of 1 and without any regularization.
+impl Default for HuberCriterion {
+    fn default() -> Self {
+        HuberCriterion::new(Regularization::None)
+    }
+}
+
+impl HuberCriterion {
+    /// Constructs a new HuberCriterion with the given regularization.
+    ///
+    /// The delta is 1 until set with `with_delta`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::HuberCriterion;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    ///
+    /// // Create a new Huber criterion with L2 regularization of 0.3.
+    /// let criterion = HuberCriterion::new(Regularization::L2(0.3f64));
+    /// ```
+    pub fn new(regularization: Regularization<f64>) -> Self {
+        HuberCriterion {
+            loss: cost_fn::HuberLoss::default(),
+            regularization: regularization,
+            reduction: Reduction::Mean,
+        }
+    }
+
+    /// Sets the largest residual with a quadratic cost.
+    ///
+    /// # Panics
+    ///
+    /// - `delta` is not positive.
+    pub fn with_delta(mut self, delta: f64) -> Self {
+        self.loss = cost_fn::HuberLoss::new(delta);
+        self
+    }
+
+    /// Sets how the costs of the samples are combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::HuberCriterion;
+    /// use rusty_machine::learning::toolkit::cost_fn::Reduction;
+    ///
+    /// // Create a criterion using the total cost over each batch.
+    /// let criterion = HuberCriterion::default().with_reduction(Reduction::Sum);
+    /// ```
+    pub fn with_reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NeuralNet, NeuralNetBuilder, MSECriterion, BCECriterion, EarlyStopping, Criterion,
+                MulticlassHingeCriterion, CategoricalCECriterion, SoftmaxCECriterion, HuberCriterion,
+                GradClip, MaskedMSECriterion};
+    use learning::toolkit::regularization::Regularization;
+    use super::net_layer::{NetLayer, Linear, Softmax, WeightInit};
+    use learning::SupModel;
+    use learning::optim::grad_desc::{GradientDesc, StochasticGD};
+    use learning::toolkit::activ_fn::{Sigmoid, Swish};
+    use learning::toolkit::activ_fn::Linear as LinearActivation;
+    use learning::toolkit::cost_fn::Reduction;
+    use linalg::{Matrix, MatrixSlice, BaseMatrix};
+
+    use std::f64;
+
+    #[test]
+    fn test_forward_prop_matches_layers() {
+        let inputs = Matrix::new(4, 2, vec![0.1, 0.2, 0.5, -0.3, 1.2, 0.7, -0.4, 0.9]);
+        let model = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+
+        let outputs = model.predict(&inputs).unwrap();
+
+        // Propagate through each layer by hand, copying the inputs
+        let mut expected = inputs.clone();
+        let mut index = 0;
+        for layer in &model.base.layers {
+            let shape = layer.param_shape();
+            let params = unsafe {
+                MatrixSlice::from_raw_parts(model.base.weights.as_ptr().offset(index as isize),
+                                            shape.0,
+                                            shape.1,
+                                            shape.1)
+            };
+            expected = layer.forward(&expected, params).unwrap();
+            index += layer.num_params();
+        }
+
+        assert_eq!(outputs.data(), expected.data());
+        // The borrowed inputs are left untouched
+        assert_eq!(inputs.data(), &vec![0.1, 0.2, 0.5, -0.3, 1.2, 0.7, -0.4, 0.9]);
+    }
+
+    #[test]
+    fn test_partial_train_matches_train() {
+        // A single row makes the stochastic passes deterministic
+        let inputs = Matrix::new(1, 2, vec![0.3, -0.7]);
+        let targets = Matrix::new(1, 1, vec![0.9]);
+
+        let mut full = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), StochasticGD::new(0.1, 0.3, 10), Sigmoid);
+        let mut partial = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), StochasticGD::new(0.1, 0.3, 10), Sigmoid);
+        partial.base.weights = full.base.weights.clone();
+
+        full.train(&inputs, &targets).unwrap();
+        for _ in 0..10 {
+            partial.partial_train(&inputs, &targets, 1).unwrap();
+        }
+
+        assert_eq!(partial.optim_state.steps(), 10);
+        for (w_full, w_partial) in full.base.weights.iter().zip(partial.base.weights.iter()) {
+            assert!((w_full - w_partial).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_warm_start_copy() {
+        let inputs = Matrix::new(1, 2, vec![0.3, -0.7]);
+        let targets = Matrix::new(1, 1, vec![0.9]);
+        let alg = StochasticGD::new(0.1, 0.3, 10);
+
+        let mut net = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), alg, Sigmoid);
+        let history = net.partial_train(&inputs, &targets, 4).unwrap();
+        assert_eq!(history.iterations(), 4);
+
+        // A copy given the optimizer state continues exactly as the original
+        let mut copy = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), alg, Sigmoid);
+        copy.base.weights = net.base.weights.clone();
+        copy.set_optim_state(net.optim_state().clone());
+        let mut cold = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), alg, Sigmoid);
+        cold.base.weights = net.base.weights.clone();
+
+        net.partial_train(&inputs, &targets, 3).unwrap();
+        copy.partial_train(&inputs, &targets, 3).unwrap();
+        cold.partial_train(&inputs, &targets, 3).unwrap();
+
+        assert_eq!(copy.optim_state().steps(), 7);
+        assert_eq!(copy.base.weights, net.base.weights);
+        assert!(cold.base.weights != net.base.weights);
+    }
+
+    #[test]
+    fn test_reset_weights() {
+        let inputs = Matrix::new(2, 3, vec![0.5, -1.0, 2.0, 1.5, 0.3, -0.2]);
+        let mut model = NeuralNet::mlp(&[3, 4, 2], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+
+        let num_weights = model.base.weights.len();
+        let num_layers = model.base.layers.len();
+        let before = model.predict(&inputs).unwrap();
+
+        model.reset_weights();
+
+        assert_eq!(model.base.weights.len(), num_weights);
+        assert_eq!(model.base.layers.len(), num_layers);
+        let after = model.predict(&inputs).unwrap();
+        assert!(before.data() != after.data());
+    }
+
+    #[test]
+    fn test_early_stopping_restores_best() {
+        // Training pulls the slope towards 10 while the validation set
+        // prefers a slope of 1, so validation cost eventually rises.
+        let inputs = Matrix::new(4, 1, vec![-1.0, -0.5, 0.5, 1.0]);
+        let targets = Matrix::new(4, 1, vec![-10.0, -5.0, 5.0, 10.0]);
+        let val_inputs = Matrix::new(2, 1, vec![-1.0, 1.0]);
+        let val_targets = Matrix::new(2, 1, vec![-1.0, 1.0]);
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.1, 0));
+        net.add(Box::new(Linear::new(1, 1)));
+
+        let stopping = EarlyStopping::new(1, 3, 1000);
+        let val_costs = net.train_early_stopping(&inputs, &targets, &val_inputs, &val_targets, stopping)
+            .unwrap();
+
+        // Stopped well before the iteration limit
+        assert!(val_costs.len() < 100);
+
+        // The final checks did not improve on the best
+        let best = val_costs.iter().cloned().fold(f64::INFINITY, f64::min);
+        assert!(val_costs[val_costs.len() - 3..].iter().all(|&c| c > best));
+
+        // The restored weights give the best validation cost
+        assert_eq!(net.cost(&val_inputs, &val_targets).unwrap(), best);
+
+        // Validation targets must match the validation inputs
+        let bad_targets = Matrix::new(1, 1, vec![1.0]);
+        assert!(net.train_early_stopping(&inputs, &targets, &val_inputs, &bad_targets, stopping)
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_early_stopping_zero_patience() {
+        let _ = EarlyStopping::new(1, 0, 1000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_early_stopping_zero_eval_every() {
+        let _ = EarlyStopping::new(0, 3, 1000);
+    }
+
+    #[test]
+    fn test_train_with_callback() {
+        let inputs = Matrix::new(2, 1, vec![-1.0, 1.0]);
+        let targets = Matrix::new(2, 1, vec![-2.0, 2.0]);
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.1, 0));
+        net.add(Box::new(Linear::new(1, 1)));
+
+        let mut seen = Vec::new();
+        let history = net.train_with_callback(&inputs, &targets, 50, |history| {
+                seen.push(*history.costs().last().unwrap());
+                history.iterations() < 5
+            })
+            .unwrap();
+
+        // The callback stopped training after the fifth iteration
+        assert_eq!(history.iterations(), 5);
+        assert_eq!(history.costs(), &seen[..]);
+        assert!(seen.windows(2).all(|w| w[1] < w[0]));
+
+        // Without stopping every iteration runs
+        let history = net.train_with_callback(&inputs, &targets, 8, |_| true).unwrap();
+        assert_eq!(history.iterations(), 8);
+    }
+
+    #[test]
+    fn test_train_validation_split() {
+        let inputs = Matrix::new(10, 1, (0..10).map(|x| x as f64 / 10.0).collect::<Vec<_>>());
+        let targets = inputs.clone() * 2.0;
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.1, 0));
+        net.add(Box::new(Linear::new(1, 1)));
+
+        let stopping = EarlyStopping::new(2, 2, 20);
+        let val_costs = net.train_validation_split(&inputs, &targets, 0.3, stopping).unwrap();
+        assert!(val_costs.len() > 1 && val_costs.len() <= 11);
+
+        // Either set would be empty
+        assert!(net.train_validation_split(&inputs, &targets, 0.01, stopping).is_err());
+        assert!(net.train_validation_split(&inputs, &targets, 0.97, stopping).is_err());
+        assert!(net.train_validation_split(&inputs, &targets, 1.0, stopping).is_err());
+        assert!(net.train_validation_split(&inputs, &targets, 0.0, stopping).is_err());
+        assert!(net.train_validation_split(&inputs, &targets.select_rows(&[0]), 0.3, stopping)
+            .is_err());
+    }
+
+    #[test]
+    fn test_multiclass_hinge_criterion() {
+        let criterion = MulticlassHingeCriterion::default();
+        let targets = Matrix::new(2, 3, vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+
+        // The true class beats every other by at least the margin
+        let outputs = Matrix::new(2, 3, vec![3.0, 1.5, 2.0, -1.0, 0.0, 1.0]);
+        assert_eq!(criterion.cost(&outputs, &targets), 0.0);
+        assert_eq!(criterion.cost_grad(&outputs, &targets).into_vec(), vec![0.0; 6]);
+
+        // The second row violates the margin against class 1
+        let outputs = Matrix::new(2, 3, vec![3.0, 1.5, 2.0, -1.0, 0.5, 1.0]);
+        assert_eq!(criterion.cost(&outputs, &targets), 0.25);
+        assert_eq!(criterion.cost_grad(&outputs, &targets).into_vec(),
+                   vec![0.0, 0.0, 0.0, 0.0, 0.5, -0.5]);
+
+        // A net trained on the hinge loss separates the classes
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 2, vec![1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0]);
+        let mut net = NeuralNet::new(criterion, GradientDesc::new(0.5, 200));
+        net.add(Box::new(Linear::new(2, 2)));
+        net.train(&inputs, &targets).unwrap();
+
+        assert!(net.cost(&inputs, &targets).unwrap() < 0.5);
+    }
+
+    #[test]
+    fn test_predict_before_training() {
+        // Layers are initialized when added so the net can predict at once
+        let net = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), StochasticGD::default(), Sigmoid);
+        let outputs = net.predict(&Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0])).unwrap();
+        assert_eq!(outputs.rows(), 2);
+        assert_eq!(outputs.cols(), 1);
+
+        assert!(net.predict(&Matrix::new(1, 3, vec![0.0, 1.0, 1.0])).is_err());
+    }
+
+    #[test]
+    fn test_tied_weights_gradient() {
+        let inputs = Matrix::new(2, 3, vec![0.1, 0.9, 0.4, 0.7, 0.2, 0.5]);
+
+        let mut tied = NeuralNet::new(MSECriterion::default(), StochasticGD::default());
+        tied.add(Box::new(Linear::without_bias(3, 2)))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::without_bias(2, 3)));
+        assert_eq!(tied.num_params(), 12);
+
+        // Shapes must be transposes and layers can only be tied once
+        assert!(tied.tie_weights(0, 1).is_err());
+        tied.tie_weights(0, 2).unwrap();
+        assert!(tied.tie_weights(0, 2).is_err());
+        assert_eq!(tied.num_params(), 6);
+
+        // An untied net whose decoder holds the transposed encoder weights
+        let mut untied = NeuralNet::new(MSECriterion::default(), StochasticGD::default());
+        untied.add(Box::new(Linear::without_bias(3, 2)))
+              .add(Box::new(Sigmoid))
+              .add(Box::new(Linear::without_bias(2, 3)));
+        let encoder = Matrix::new(3, 2, tied.base.weights.clone());
+        let mut weights = tied.base.weights.clone();
+        weights.extend_from_slice(encoder.transpose().data());
+        untied.base.weights = weights;
+
+        assert_eq!(tied.predict(&inputs).unwrap(), untied.predict(&inputs).unwrap());
+
+        // The tied gradient sums the encoder and transposed decoder gradients
+        let (tied_cost, tied_grad) = tied.base.cost_grad(&tied.base.weights, &inputs, &inputs).unwrap();
+        let (cost, grad) = untied.base.cost_grad(&untied.base.weights, &inputs, &inputs).unwrap();
+        assert_eq!(tied_cost, cost);
+
+        let decoder_grad = Matrix::new(2, 3, grad[6..].to_vec()).transpose();
+        for i in 0..6 {
+            assert!((tied_grad[i] - (grad[i] + decoder_grad.data()[i])).abs() < 1e-12);
+        }
+
+        // And matches a finite difference estimate
+        assert!(tied.grad_check(&inputs, &inputs).iter().all(|&e| e < 1e-6));
+    }
+
+    #[test]
+    fn test_mlp_with_activations() {
+        use super::net_layer::NetLayer;
+        use learning::toolkit::activ_fn::ReLU;
+
+        let activations: Vec<Box<NetLayer>> = vec![Box::new(ReLU), Box::new(ReLU), Box::new(Sigmoid)];
+        let net = NeuralNet::mlp_with_activations(&[4, 8, 8, 2],
+                                                  activations,
+                                                  MSECriterion::default(),
+                                                  StochasticGD::default())
+            .unwrap();
+
+        let layers = net.base.layers.iter().map(|l| format!("{:?}", l)).collect::<Vec<_>>();
+        assert_eq!(layers.len(), 6);
+        assert!(layers[0].starts_with("Linear"));
+        assert_eq!(layers[1], "ReLU");
+        assert!(layers[2].starts_with("Linear"));
+        assert_eq!(layers[3], "ReLU");
+        assert!(layers[4].starts_with("Linear"));
+        assert_eq!(layers[5], "Sigmoid");
+
+        let outputs = net.predict(&Matrix::new(3, 4, vec![0.5; 12])).unwrap();
+        assert_eq!(outputs.cols(), 2);
+
+        let activations: Vec<Box<NetLayer>> = vec![Box::new(ReLU), Box::new(Sigmoid)];
+        assert!(NeuralNet::mlp_with_activations(&[4, 8, 8, 2],
+                                                activations,
+                                                MSECriterion::default(),
+                                                StochasticGD::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_mlp_parameterized_activations() {
+        use learning::toolkit::activ_fn::{LeakyReLU, ELU};
+
+        let net = NeuralNet::mlp(&[2, 3, 1],
+                                 MSECriterion::default(),
+                                 StochasticGD::default(),
+                                 LeakyReLU::new(0.1));
+        let layers = net.base.layers.iter().map(|l| format!("{:?}", l)).collect::<Vec<_>>();
+        assert_eq!(layers[1], "LeakyReLU { alpha: 0.1 }");
+        assert_eq!(layers[3], "LeakyReLU { alpha: 0.1 }");
+
+        let mut net = NeuralNet::new(MSECriterion::default(), StochasticGD::default());
+        net.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(ELU::default()))
+            .add(Box::new(Linear::new(3, 1)));
+
+        let inputs = Matrix::new(2, 2, vec![-1.0, 0.5, 0.3, -0.8]);
+        let targets = Matrix::new(2, 1, vec![0.4, -0.1]);
+        assert!(net.grad_check(&inputs, &targets).iter().all(|&e| e < 1e-6));
+    }
+
+    #[test]
+    fn test_huber_criterion() {
+        use learning::toolkit::activ_fn;
+        use learning::toolkit::cost_fn::HuberLoss;
+
+        let outputs = Matrix::new(3, 1, vec![0.1, 2.0, -0.4]);
+        let targets = Matrix::new(3, 1, vec![0.0, 0.0, 0.0]);
+        let criterion = HuberCriterion::default().with_delta(0.5);
+        let huber = HuberLoss::new(0.5);
+
+        assert_eq!(criterion.cost(&outputs, &targets), huber.loss(&outputs, &targets));
+        let grad = criterion.cost_grad(&outputs, &targets).into_vec();
+        for (g, e) in grad.iter().zip(&[0.1, 0.5, -0.4]) {
+            assert!((g - e / 3.0).abs() < 1e-12);
+        }
+
+        let total = criterion.sample_costs(&outputs, &targets).sum();
+        assert!((total - 3.0 * criterion.cost(&outputs, &targets)).abs() < 1e-12);
+
+        // An outlier pulls a linear fit less than with the squared error
+        let inputs = Matrix::new(6, 1, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let targets = Matrix::new(6, 1, vec![0.0, 1.0, 2.0, 3.0, 4.0, 50.0]);
+        let alg = GradientDesc::new(0.02, 3000);
+        let mut huber_net = NeuralNet::mlp(&[1, 1], criterion, alg, activ_fn::Linear);
+        let mut mse_net = NeuralNet::mlp(&[1, 1], MSECriterion::default(), alg, activ_fn::Linear);
+        huber_net.train(&inputs, &targets).unwrap();
+        mse_net.train(&inputs, &targets).unwrap();
+
+        let test = Matrix::new(1, 1, vec![2.0]);
+        let huber_err = (huber_net.predict(&test).unwrap()[[0, 0]] - 2.0).abs();
+        let mse_err = (mse_net.predict(&test).unwrap()[[0, 0]] - 2.0).abs();
+        assert!(huber_err < mse_err);
+    }
+
+    #[test]
+    fn test_saliency_linear() {
+        let inputs = Matrix::new(4, 3, vec![1.0, 0.0, 2.0,
+                                            0.0, 1.0, -1.0,
+                                            2.0, 1.0, 0.0,
+                                            1.0, -1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![1.0, -2.0, 0.5, 3.0]);
+
+        // A single linear layer is equivalent to linear regression
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.1, 50));
+        net.add(Box::new(Linear::new(3, 1)));
+        net.train(&inputs, &targets).unwrap();
+
+        let saliency = net.saliency(&inputs.select_rows(&[0]), 0).unwrap();
+        // The first row of the weights is the bias
+        let weights = net.get_net_weights(0);
+        for (s, w) in saliency.data().iter().zip(weights.iter().skip(1)) {
+            assert!((s - w.abs()).abs() < 1e-12);
+        }
+
+        assert!(net.saliency(&inputs.select_rows(&[0]), 1).is_err());
+        assert!(net.saliency(&inputs, 0).is_err());
+    }
+
+    #[test]
+    fn test_cost_and_grad() {
+        let inputs = Matrix::new(1, 3, vec![1., 2., 3.]);
+        let targets = Matrix::new(1, 3, vec![0., 1., 0.]);
+
+        let criterion = BCECriterion::new(Regularization::L1(0.1));
+        let net = NeuralNet::mlp(&[3, 5, 3], criterion, StochasticGD::default(), Sigmoid);
+
+        let (cost, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        assert_eq!(grad.len(), net.base.weights.len());
+
+        let weights = net.base.weights.clone();
+        assert!(net.grad_check(&inputs, &targets).iter().all(|&e| e < 1e-6));
+
+        // No step is taken
+        assert_eq!(net.base.weights, weights);
+        assert_eq!(net.cost_and_grad(&inputs, &targets).unwrap().0, cost);
+
+        // Mismatched data is an error, as for `cost`
+        assert!(net.cost_and_grad(&Matrix::new(1, 2, vec![1., 2.]), &targets).is_err());
+        assert!(net.cost_and_grad(&inputs, &Matrix::new(1, 2, vec![0., 1.])).is_err());
+    }
+
+    #[test]
+    fn test_swish_backprop() {
+        let inputs = Matrix::new(1, 3, vec![-3., 2., 4.]);
+        let targets = Matrix::new(1, 2, vec![0.5, -0.2]);
+
+        // Large weights give hidden inputs on both sides of the minimum of Swish
+        let mut net = NeuralNet::mlp(&[3, 6, 2], MSECriterion::default(), StochasticGD::default(), Swish);
+        net.weight_init(WeightInit::Normal { std: 1.0 });
+
+        assert!(net.grad_check(&inputs, &targets).iter().all(|&e| e < 1e-6));
+    }
+
+    #[test]
+    fn test_predict_chunked() {
+        let inputs = Matrix::from_fn(7, 3, |col, row| (row * 3 + col) as f64 / 10.0);
+        let net = NeuralNet::mlp(&[3, 4, 2], MSECriterion::default(), GradientDesc::default(), Sigmoid);
+        let outputs = net.predict(&inputs).unwrap();
+
+        // Evenly and unevenly divided rows, and a single chunk
+        for &chunk_rows in &[1, 3, 7, 10] {
+            assert_eq!(net.predict_chunked(&inputs, chunk_rows).unwrap(), outputs);
+        }
+
+        assert!(net.predict_chunked(&inputs, 0).is_err());
+    }
+
+    #[test]
+    fn test_standardize_inputs() {
+        // A poorly scaled column with a large offset
+        let inputs = Matrix::new(5, 2, vec![1000.0, 0.1,
+                                            1010.0, 0.3,
+                                            1020.0, 0.2,
+                                            1030.0, 0.5,
+                                            1040.0, 0.4]);
+        let targets = Matrix::new(5, 1, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let build = || {
+            let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.5, 200));
+            net.add(Box::new(Linear::new(2, 1)));
+            net
+        };
+
+        let mut plain = build();
+        plain.train(&inputs, &targets).unwrap();
+        let plain_cost = plain.cost(&inputs, &targets).unwrap();
+
+        let mut standardized = build();
+        standardized.standardize_inputs();
+        standardized.train(&inputs, &targets).unwrap();
+        let cost = standardized.cost(&inputs, &targets).unwrap();
+
+        assert!(cost < 1e-6);
+        // The unscaled network diverges or converges slowly
+        assert!(!(plain_cost < 1e-3));
+
+        // The statistics are kept after further training
+        let stats = standardized.base.input_stats.clone().unwrap();
+        assert_eq!(stats.0.data(), &vec![1020.0, 0.3]);
+        standardized.partial_train(&(&inputs * 2.0), &targets, 1).unwrap();
+        assert_eq!(standardized.base.input_stats.clone().unwrap().0, stats.0);
+
+        // Predictions apply the stored statistics
+        let test = Matrix::new(2, 2, vec![1005.0, 0.25, 990.0, 0.6]);
+        let outputs = standardized.predict(&test).unwrap();
+        let manual = Matrix::new(2, 2, vec![(1005.0 - 1020.0) / stats.1[0], (0.25 - 0.3) / stats.1[1],
+                                            (990.0 - 1020.0) / stats.1[0], (0.6 - 0.3) / stats.1[1]]);
+        standardized.base.input_stats = None;
+        let expected = standardized.predict(&manual).unwrap();
+        for (o, e) in outputs.iter().zip(expected.iter()) {
+            assert!((o - e).abs() < 1e-12);
+        }
+
+        // Inputs which do not match the statistics are rejected
+        standardized.base.input_stats = Some(stats);
+        let wide = Matrix::new(1, 3, vec![1000.0, 0.1, 5.0]);
+        let err = standardized.predict(&wide).unwrap_err();
+        assert_eq!(err.to_string(), "The inputs have 3 columns but the network standardizes 2.");
+        assert!(standardized.train(&wide, &Matrix::new(1, 1, vec![1.0])).is_err());
+    }
+
+    #[test]
+    fn test_builder() {
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+
+        let mut net = NeuralNetBuilder::new(2)
+            .layer(Box::new(Linear::new(2, 3)))
+            .layer(Box::new(Sigmoid))
+            .layer(Box::new(Linear::new(3, 1)))
+            .criterion(MSECriterion::default())
+            .optimizer(GradientDesc::new(0.5, 10))
+            .build()
+            .unwrap();
+        assert_eq!(net.num_params(), 13);
+        net.train(&inputs, &targets).unwrap();
+        assert_eq!(net.predict(&inputs).unwrap().cols(), 1);
+
+        let err = NeuralNetBuilder::new(2)
+            .layer(Box::new(Linear::new(2, 3)))
+            .layer(Box::new(Sigmoid))
+            .layer(Box::new(Linear::new(4, 1)))
+            .build()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Layer 2 cannot take inputs with 3 columns.");
+
+        // The first layer is checked against the input size
+        assert!(NeuralNetBuilder::new(3).layer(Box::new(Linear::new(2, 3))).build().is_err());
+    }
+
+    #[test]
+    fn test_builder_named_layers() {
+        let builder = NeuralNetBuilder::new(4)
+            .linear(4, 8).named("hidden")
+            .relu()
+            .dropout(0.5)
+            .linear(8, 3)
+            .softmax().named("probs");
+
+        let summary = builder.summary().unwrap();
+        let labels = summary.layers().iter().map(|l| l.label()).collect::<Vec<_>>();
+        assert_eq!(labels, vec![Some("hidden"), None, None, None, Some("probs")]);
+        assert_eq!(summary.layers()[3].input_size(), Some(8));
+        assert_eq!(summary.layers()[3].num_params(), 27);
+        assert_eq!(summary.num_params(), 40 + 27);
+
+        let net = builder.build().unwrap();
+        assert_eq!(net.layer_index("probs"), Some(4));
+        assert_eq!(net.layer_index("missing"), None);
+        assert_eq!(net.summary().unwrap().layers()[0].label(), Some("hidden"));
+
+        let shown = net.to_string();
+        assert!(shown.lines().nth(1).unwrap().ends_with("hidden"));
+
+        let mut net = NeuralNet::default(&[2, 1]);
+        assert!(net.set_layer_name(2, "none").is_err());
+
+        // Names which would not survive saving are rejected
+        for name in &["", " hidden", "hidden ", "two\nlines"] {
+            assert!(net.set_layer_name(0, *name).is_err());
+        }
+        net.set_layer_name(0, "output layer").unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_builder_name_without_layer() {
+        let _ = NeuralNetBuilder::new(4).named("input");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_builder_name_with_newline() {
+        let _ = NeuralNetBuilder::new(4).linear(4, 2).named("hidden\nlayer");
+    }
+
+    #[test]
+    fn test_rejects_non_finite_data() {
+        let inputs = Matrix::new(3, 2, vec![0.0, 1.0, 1.0, f64::NAN, 1.0, 1.0]);
+        let targets = Matrix::new(3, 1, vec![0.0, 1.0, 1.0]);
+
+        let mut net = NeuralNet::mlp(&[2, 1], MSECriterion::default(), GradientDesc::default(), Sigmoid);
+        let start = net.base.weights.clone();
+
+        let err = net.train(&inputs, &targets).unwrap_err();
+        assert_eq!(err.to_string(), "The training inputs contain NaN at row 1, column 1.");
+        assert_eq!(net.base.weights, start);
+
+        let inputs = Matrix::new(3, 2, vec![0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(3, 1, vec![0.0, f64::INFINITY, 1.0]);
+        let err = net.train(&inputs, &targets).unwrap_err();
+        assert_eq!(err.to_string(), "The training targets contain inf at row 1, column 0.");
+
+        // The check can be skipped
+        net.train_unchecked(&inputs, &targets).unwrap();
+        assert!(net.base.weights.iter().any(|w| !w.is_finite()));
+    }
+
+    #[test]
+    fn test_missing_targets() {
+        let inputs = Matrix::new(4, 1, vec![0.0, 1.0, 2.0, 3.0]);
+        let targets = Matrix::new(4, 2, vec![0.0, f64::NAN, 0.0, 1.0, 1.0, f64::NAN, 1.0, 0.0]);
+
+        let mut net = NeuralNet::mlp(&[1, 2], MSECriterion::default(), GradientDesc::default(), Sigmoid);
+        assert!(net.train(&inputs, &targets).is_err());
+
+        let mut net = NeuralNet::mlp(&[1, 2],
+                                     MaskedMSECriterion::default(),
+                                     GradientDesc::default(),
+                                     Sigmoid);
+        let (start_cost, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        assert!(start_cost.is_finite());
+        assert!(grad.iter().all(|g| g.is_finite()));
+
+        net.train(&inputs, &targets).unwrap();
+        assert!(net.base.weights.iter().all(|w| w.is_finite()));
+        assert!(net.cost_and_grad(&inputs, &targets).unwrap().0 < start_cost);
+    }
+
+    #[test]
+    fn test_display() {
+        let net = NeuralNet::default(&[3, 4, 2]);
+        let shown = net.to_string();
+        let lines = shown.lines().collect::<Vec<_>>();
+
+        // A header, one line per layer and the total
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].contains("Linear") && lines[1].contains("3 -> 4") && lines[1].contains("16 params"));
+        assert!(lines[2].contains("Sigmoid") && lines[2].contains("4 -> 4") && lines[2].contains("0 params"));
+        assert_eq!(lines[5], format!("Total parameters: {}", net.base.weights.len()));
+        assert_eq!(net.base.weights.len(), 26);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![0.0, 1.0, 1.0, 1.0]);
+
+        let criterion = MSECriterion::new(Regularization::L2(0.01));
+        let mut net = NeuralNet::mlp(&[2, 3, 1], criterion, GradientDesc::new(0.5, 50), Sigmoid);
+
+        let before = net.evaluate(&inputs, &targets).unwrap();
+        net.train(&inputs, &targets).unwrap();
+        let after = net.evaluate(&inputs, &targets).unwrap();
+        assert!(after < before);
+
+        // The regularization is added to the criterion's cost
+        assert!(after > net.cost(&inputs, &targets).unwrap());
+        assert!(net.evaluate(&inputs, &Matrix::zeros(4, 2)).is_err());
+    }
+
+    #[test]
+    fn test_tolerance_stops_early() {
+        let inputs = Matrix::new(5, 3, vec![1., 1., 1., 2., 2., 2., 3., 3., 3.,
+                                            4., 4., 4., 5., 5., 5.]);
+        let targets = Matrix::new(5, 3, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.,
+                                             0., 0., 1., 0., 0., 1.]);
+
+        let criterion = BCECriterion::new(Regularization::L2(0.1));
+        let sgd = StochasticGD::new(0.1, 0.1, 1000).with_tolerance(0.0, 1e-3, 3);
+        let mut model = NeuralNet::mlp(&[3, 5, 11, 7, 3], criterion, sgd, Sigmoid);
+
+        let history = model.train_with_history(&inputs, &targets).unwrap();
+        assert!(history.iterations() < 250);
+    }
+
+    #[test]
+    fn test_slice_inputs() {
+        let data = Matrix::new(30, 2, (0..60).map(|x| (x % 7) as f64 / 7.0).collect::<Vec<_>>());
+        let targets = Matrix::new(10, 1, vec![0., 1., 0., 1., 0., 1., 0., 1., 0., 1.]);
+
+        let slice = data.sub_slice([10, 0], 10, 2);
+        let copied = data.select_rows(&(10..20).collect::<Vec<_>>());
+
+        let mut net = NeuralNet::mlp(&[2, 3, 1], BCECriterion::default(), GradientDesc::new(0.3, 10), Sigmoid);
+        assert_eq!(net.predict_slice(slice).unwrap(), net.predict(&copied).unwrap());
+
+        // Standardized inputs are copied once
+        net.standardize_inputs();
+        net.train(&copied, &targets).unwrap();
+        assert_eq!(net.predict_slice(slice).unwrap(), net.predict(&copied).unwrap());
+        assert!(net.predict_slice(data.sub_slice([0, 0], 2, 1)).is_err());
+
+        // Layers in training mode are given a copy
+        let mut net = NeuralNet::mlp(&[2, 3, 1], BCECriterion::default(), GradientDesc::new(0.3, 10), Sigmoid);
+        net.set_training(true);
+        assert_eq!(net.predict_slice(slice).unwrap(), net.predict(&copied).unwrap());
+    }
+
+    #[test]
+    fn test_softmax_cross_entropy() {
+        let inputs = Matrix::new(6, 2, vec![0.0, 0.0, 0.1, 0.2,
+                                            1.0, 0.0, 0.9, 0.1,
+                                            0.0, 1.0, 0.2, 0.9]);
+        let targets = Matrix::new(6, 3, vec![1., 0., 0., 1., 0., 0.,
+                                             0., 1., 0., 0., 1., 0.,
+                                             0., 0., 1., 0., 0., 1.]);
+
+        let activations: Vec<Box<NetLayer>> = vec![Box::new(Sigmoid), Box::new(Softmax)];
+        let mut net = NeuralNet::mlp_with_activations(&[2, 6, 3],
+                                                      activations,
+                                                      CategoricalCECriterion::default(),
+                                                      GradientDesc::new(1.0, 2000))
+            .unwrap();
+
+        // The gradient of the softmax input is the outputs minus the targets
+        let outputs = net.predict(&inputs).unwrap();
+        let cost_grad = net.base.criterion.cost_grad(&outputs, &targets);
+        let in_grad = Softmax.back_input(&cost_grad, &outputs, &outputs, Matrix::new(0, 0, vec![]).as_slice());
+        for (g, e) in in_grad.iter().zip(((&outputs - &targets) / 6.0).iter()) {
+            assert!((g - e).abs() < 1e-12);
         }
-    } 
 
-    /// Create a multilayer perceptron with the specified layer sizes.
-    fn mlp<U>(layer_sizes: &[usize], criterion: T, activ_fn: U) -> BaseNeuralNet<T> 
-        where U: ActivationFunc + 'static {
-        let mut mlp = BaseNeuralNet {
-            layers: Vec::with_capacity(2*(layer_sizes.len()-1)),
-            weights: Vec::new(),
-            criterion: criterion
-        };
-        for shape in layer_sizes.windows(2) {
-            mlp.add(Box::new(net_layer::Linear::new(shape[0], shape[1])));
-            mlp.add(Box::new(activ_fn.clone()));
+        net.train(&inputs, &targets).unwrap();
+        let outputs = net.predict(&inputs).unwrap();
+        for (out, tar) in outputs.row_iter().zip(targets.row_iter()) {
+            let predicted = out.iter().enumerate().fold(0, |b, (j, &y)| if y > out[b] { j } else { b });
+            assert_eq!(tar[predicted], 1.0);
         }
-        mlp
     }
 
-    /// Adds the specified layer to the end of the network
-    fn add<'a>(&'a mut self, layer: Box<NetLayer>) -> &'a mut BaseNeuralNet<T> {
-        self.weights.extend_from_slice(&layer.default_params());
-        self.layers.push(layer);
-        self
-    }
+    #[test]
+    fn test_softmax_ce_criterion() {
+        let inputs = Matrix::new(6, 2, vec![0.0, 0.0, 0.1, 0.2,
+                                            1.0, 0.0, 0.9, 0.1,
+                                            0.0, 1.0, 0.2, 0.9]);
+        let targets = Matrix::new(6, 3, vec![1., 0., 0., 1., 0., 0.,
+                                             0., 1., 0., 0., 1., 0.,
+                                             0., 0., 1., 0., 0., 1.]);
 
-    /// Adds multiple layers to the end of the network
-    fn add_layers<'a, U>(&'a mut self, layers: U) -> &'a mut BaseNeuralNet<T>
-        where U: IntoIterator<Item = Box<NetLayer>> 
-    {
-        for layer in layers {
-            self.add(layer);
+        let activations: Vec<Box<NetLayer>> = vec![Box::new(Sigmoid), Box::new(Softmax)];
+        let softmax = NeuralNet::mlp_with_activations(&[2, 6, 3],
+                                                      activations,
+                                                      CategoricalCECriterion::default(),
+                                                      GradientDesc::default())
+            .unwrap();
+        let activations: Vec<Box<NetLayer>> = vec![Box::new(Sigmoid), Box::new(LinearActivation)];
+        let mut fused = NeuralNet::mlp_with_activations(&[2, 6, 3],
+                                                        activations,
+                                                        SoftmaxCECriterion::default(),
+                                                        GradientDesc::new(1.0, 2000))
+            .unwrap();
+        fused.base.weights = softmax.base.weights.clone();
+
+        // The fused criterion matches a softmax layer with the categorical criterion
+        let (cost, grad) = softmax.cost_and_grad(&inputs, &targets).unwrap();
+        let (fused_cost, fused_grad) = fused.cost_and_grad(&inputs, &targets).unwrap();
+        assert!((cost - fused_cost).abs() < 1e-12);
+        for (g, f) in grad.iter().zip(&fused_grad) {
+            assert!((g - f).abs() < 1e-12);
         }
-        self
-    }
 
-    /// Gets matrix of weights for the specified layer for the weights.
-    fn get_layer_weights(&self, weights: &[f64], idx: usize) -> MatrixSlice<f64> {
-        debug_assert!(idx < self.layers.len());
+        fused.train(&inputs, &targets).unwrap();
+        let outputs = fused.predict(&inputs).unwrap();
+        for (out, tar) in outputs.row_iter().zip(targets.row_iter()) {
+            let predicted = out.iter().enumerate().fold(0, |b, (j, &y)| if y > out[b] { j } else { b });
+            assert_eq!(tar[predicted], 1.0);
+        }
+    }
 
-        // Check that the weights are the right size.
-        let full_size: usize = self.layers.iter().map(|l| l.num_params()).sum();
+    #[test]
+    fn test_sum_reduction() {
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
 
-        debug_assert_eq!(full_size, weights.len());
+        let mean_net = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), GradientDesc::default(), Sigmoid);
+        let mut sum_net = NeuralNet::mlp(&[2, 3, 1],
+                                         MSECriterion::default().with_reduction(Reduction::Sum),
+                                         GradientDesc::default(),
+                                         Sigmoid);
+        sum_net.base.weights = mean_net.base.weights.clone();
 
-        let start: usize = self.layers.iter().take(idx).map(|l| l.num_params()).sum();
+        let (mean_cost, mean_grad) = mean_net.cost_and_grad(&inputs, &targets).unwrap();
+        let (sum_cost, sum_grad) = sum_net.cost_and_grad(&inputs, &targets).unwrap();
 
-        let shape = self.layers[idx].param_shape();
-        unsafe {
-            MatrixSlice::from_raw_parts(weights.as_ptr().offset(start as isize),
-                                        shape.0,
-                                        shape.1,
-                                        shape.1)
+        assert!((sum_cost - 4.0 * mean_cost).abs() < 1e-12);
+        for (s, m) in sum_grad.iter().zip(&mean_grad) {
+            assert!((s - 4.0 * m).abs() < 1e-12);
         }
+
+        // Per-sample costs add up to the total
+        let criterion = MSECriterion::default().with_reduction(Reduction::Sum);
+        let outputs = mean_net.predict(&inputs).unwrap();
+        let costs = criterion.sample_costs(&outputs, &targets);
+        assert_eq!(costs.size(), 4);
+        assert!((costs.sum() - criterion.cost(&outputs, &targets)).abs() < 1e-12);
     }
 
-    /// Compute the gradient using the back propagation algorithm.
-    fn compute_grad(&self,
-                    weights: &[f64],
-                    inputs: &Matrix<f64>,
-                    targets: &Matrix<f64>)
-                    -> (f64, Vec<f64>) {
-        let mut gradients = Vec::with_capacity(weights.len());
-        unsafe {
-            gradients.set_len(weights.len());
-        }
-        // activations[i] is the output of layer[i]
-        let mut activations = Vec::with_capacity(self.layers.len());
-        // params[i] is the weights for layer[i]
-        let mut params = Vec::with_capacity(self.layers.len());
+    #[test]
+    fn test_summary() {
+        let mut net = NeuralNet::mlp(&[3, 4, 3],
+                                     MSECriterion::default(),
+                                     GradientDesc::default(),
+                                     Sigmoid);
+        net.add(Box::new(Linear::without_bias(3, 4)))
+            .add(Box::new(Linear::without_bias(4, 3)));
+        net.tie_weights(4, 5).unwrap();
 
-        // Forward propagation
-        
-        let mut index = 0;
-        for (i, layer) in self.layers.iter().enumerate() {
-            let shape = layer.param_shape();
+        let summary = net.summary().unwrap();
+        let layers = summary.layers();
+        let names = layers.iter().map(|l| l.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Linear", "Sigmoid", "Linear", "Sigmoid", "Linear", "Linear"]);
+        assert_eq!(layers[0].input_size(), Some(3));
+        assert_eq!(layers[0].output_size(), Some(4));
+        assert_eq!(layers[0].param_shape(), (4, 4));
 
-            let slice = unsafe {
-                MatrixSlice::from_raw_parts(weights.as_ptr().offset(index as isize),
-                                            shape.0,
-                                            shape.1,
-                                            shape.1)
-            };
+        // Activations and tied layers own no parameters
+        assert_eq!(layers[1].num_params(), 0);
+        assert_eq!(layers[5].num_params(), 0);
+        assert_eq!(summary.num_params(), net.base.weights.len());
 
-            let output = if i == 0 {
-                layer.forward(inputs, slice).unwrap()
-            } else {
-                layer.forward(activations.last().unwrap(), slice).unwrap()
-            };
+        // The input size is taken from the first layer requiring one
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        net.add(Box::new(Sigmoid)).add(Box::new(Linear::new(3, 2)));
+        let layers = net.summary().unwrap().layers().to_vec();
+        assert_eq!(layers[0].input_size(), None);
+        assert_eq!(layers[1].input_size(), Some(3));
 
-            activations.push(output);
-            params.push(slice);
-            index += layer.num_params();
-        }
-        let output = activations.last().unwrap();
+        net.add(Box::new(Linear::new(3, 1)));
+        assert!(net.summary().is_err());
+        assert!(net.to_string().lines().nth(3).unwrap().contains("2 -> ?"));
+    }
 
-        // Backward propagation
-        
-        // The gradient with respect to the current layer's output
-        let mut out_grad = self.criterion.cost_grad(output, targets);
-        // at this point index == weights.len()
-        for (i, layer) in self.layers.iter().enumerate().rev() {
-            let activation = if i == 0 {inputs} else {&activations[i-1]};
-            let result = &activations[i];
-            index -= layer.num_params();
+    #[test]
+    fn test_layer_grad_norms() {
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
 
-            let grad_params = &mut gradients[index..index+layer.num_params()];
-            grad_params.copy_from_slice(layer.back_params(&out_grad, activation, result, params[i]).data());
-            
-            out_grad = layer.back_input(&out_grad, activation, result, params[i]);
-        }
+        let mut net = NeuralNet::mlp(&[2, 4, 4, 4, 4, 1],
+                                     MSECriterion::default(),
+                                     GradientDesc::new(0.1, 5),
+                                     Sigmoid);
+        let history = net.train_with_history(&inputs, &targets).unwrap();
+        let num_layers = net.base.layers.len();
+        assert_eq!(num_layers, 10);
 
-        let mut cost = self.criterion.cost(output, targets);
-        if self.criterion.is_regularized() {
-            let all_params = unsafe {
-                MatrixSlice::from_raw_parts(weights.as_ptr(), weights.len(), 1, 1)
-            };
-            utils::in_place_vec_bin_op(&mut gradients,
-                                       self.criterion.reg_cost_grad(all_params).data(),
-                                       |x, &y| *x = *x + y);
-            cost += self.criterion.reg_cost(all_params);
+        assert_eq!(history.grad_norms().len(), history.iterations());
+        for norms in history.grad_norms() {
+            assert_eq!(norms.len(), num_layers);
+            for (i, norm) in norms.iter().enumerate() {
+                // Only the linear layers have parameters
+                assert_eq!(*norm > 0.0, i % 2 == 0);
+            }
         }
-        (cost, gradients)
     }
 
-    /// Forward propagation of the model weights to get the outputs.
-    fn forward_prop(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
-        if self.layers.is_empty() {
-            return Ok(inputs.clone());
+    #[test]
+    fn test_forward_to_layer() {
+        let inputs = Matrix::new(3, 2, vec![0.5, -1.0, 2.0, 0.0, 1.0, 1.5]);
+        let net = NeuralNet::default(&[2, 3, 2]);
+
+        // The last layer gives the network output
+        assert_eq!(net.forward_to_layer(&inputs, 3).unwrap(), net.predict(&inputs).unwrap());
+
+        // The first layer gives its own output rather than the inputs
+        let first = net.forward_to_layer(&inputs, 0).unwrap();
+        let weights = net.get_net_weights(0).into_matrix();
+        let expected = Matrix::ones(3, 1).hcat(&inputs) * weights;
+        for (x, y) in first.iter().zip(expected.iter()) {
+            // The bias is added after the product, so rounding may differ
+            assert!((x - y).abs() < 1e-12);
         }
 
-        let mut outputs = unsafe {
-            let shape = self.layers[0].param_shape();
-            let slice = MatrixSlice::from_raw_parts(self.weights.as_ptr(),
-                                                    shape.0,
-                                                    shape.1,
-                                                    shape.1);
-            try!(self.layers[0].forward(inputs, slice))
+        assert!(net.forward_to_layer(&inputs, 4).is_err());
+    }
+
+    #[test]
+    fn test_weight_init() {
+        let std_of = |weights: &[f64]| {
+            let n = weights.len() as f64;
+            let mean = weights.iter().sum::<f64>() / n;
+            (mean, (weights.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n).sqrt())
         };
 
-        let mut index = self.layers[0].num_params();
-        for layer in self.layers.iter().skip(1) {
-            let shape = layer.param_shape();
+        let mut net = NeuralNet::default(&[200, 300]);
+        net.weight_init(WeightInit::Normal { std: 0.5 });
+        let (mean, std) = std_of(&net.base.weights);
+        assert!(mean.abs() < 0.01);
+        assert!((std - 0.5).abs() < 0.01);
 
-            let slice = unsafe {
-                MatrixSlice::from_raw_parts(self.weights.as_ptr().offset(index as isize),
-                                            shape.0,
-                                            shape.1,
-                                            shape.1)
-            };
-            
-            outputs = match layer.forward(&outputs, slice) {
-                Ok(act) => act,
-                Err(_) => {return Err(Error::new(ErrorKind::InvalidParameters,
-                    "The network's layers do not line up correctly."))}
-            };
+        // Layers added later use the same initialization
+        net.add(Box::new(Linear::new(300, 200)));
+        let (_, std) = std_of(&net.base.weights[net.base.weights.len() / 2..]);
+        assert!((std - 0.5).abs() < 0.01);
 
-            index += layer.num_params();
-        }
-        Ok(outputs)
-    }
-}
+        let net = NeuralNetBuilder::new(200)
+            .layer(Box::new(Linear::new(200, 300)))
+            .weight_init(WeightInit::Normal { std: 0.1 })
+            .build()
+            .unwrap();
+        let (_, std) = std_of(&net.base.weights);
+        assert!((std - 0.1).abs() < 0.005);
+        assert!((std_of(&net.fresh_copy().base.weights).1 - 0.1).abs() < 0.005);
 
-/// Compute the gradient of the Neural Network using the
-/// back propagation algorithm.
-impl<T: Criterion> Optimizable for BaseNeuralNet<T> {
-    type Inputs = Matrix<f64>;
-    type Targets = Matrix<f64>;
+        // Layers with their own initialization keep it
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        net.add(Box::new(Linear::without_bias(200, 300)))
+            .add_with_init(Box::new(Linear::without_bias(300, 300)),
+                           WeightInit::custom(|_, _| 2.0));
+        net.weight_init(WeightInit::Normal { std: 0.5 });
+        let (first, second) = net.base.weights.split_at(60000);
+        assert!((std_of(first).1 - 0.5).abs() < 0.01);
+        assert!(second.iter().all(|&w| w == 2.0));
+        assert!(net.fresh_copy().base.weights[60000..].iter().all(|&w| w == 2.0));
+    }
 
-    /// Compute the gradient of the neural network.
-    fn compute_grad(&self,
-                    params: &[f64],
-                    inputs: &Matrix<f64>,
-                    targets: &Matrix<f64>)
-                    -> (f64, Vec<f64>) {
-        self.compute_grad(params, inputs, targets)
+    #[test]
+    #[should_panic]
+    fn test_builder_zero_std() {
+        let _ = NeuralNetBuilder::new(2).linear(2, 1).weight_init(WeightInit::Normal { std: 0.0 });
     }
-}
 
-/// Criterion for Neural Networks
-///
-/// Specifies an activation function and a cost function.
-pub trait Criterion {
-    /// The cost function for the criterion.
-    type Cost: CostFunc<Matrix<f64>>;
+    #[test]
+    fn test_autoencoder() {
+        let inputs = Matrix::new(4, 6, (0..24).map(|x| (x % 5) as f64 / 5.0).collect::<Vec<_>>());
 
-    /// The cost function.
-    ///
-    /// Returns a scalar cost.
-    fn cost(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
-        Self::Cost::cost(outputs, targets)
-    }
+        let net = NeuralNet::autoencoder(&[6, 4, 2], MSECriterion::default(), GradientDesc::default(),
+                                         Sigmoid, false);
+        let sizes = net.summary().unwrap().layers().iter().map(|l| l.output_size().unwrap()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![4, 4, 2, 2, 4, 4, 6, 6]);
+        assert_eq!(net.num_params(), 7 * 4 + 5 * 2 + 3 * 4 + 5 * 6);
+        assert_eq!(net.encode(&inputs).unwrap(), net.forward_to_layer(&inputs, 3).unwrap());
 
-    /// The gradient of the cost function.
-    ///
-    /// Returns a matrix of cost gradients.
-    fn cost_grad(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
-        Self::Cost::grad_cost(outputs, targets)
+        // The decoder shares the transposed encoder weights
+        let mut tied = NeuralNet::autoencoder(&[6, 4, 2], MSECriterion::default(),
+                                              GradientDesc::default(), Sigmoid, true);
+        assert_eq!(tied.num_params(), 6 * 4 + 4 * 2);
+        assert_eq!(tied.base.tied, vec![None, None, None, None, Some(2), None, Some(0), None]);
+
+        let cost = tied.evaluate(&inputs, &inputs).unwrap();
+        tied.train(&inputs, &inputs).unwrap();
+        assert!(tied.evaluate(&inputs, &inputs).unwrap() < cost);
+        assert_eq!(tied.encode(&inputs).unwrap().cols(), 2);
+
+        // The bottleneck is kept by layers added later and by saving
+        tied.add(Box::new(Linear::without_bias(6, 6)));
+        assert_eq!(tied.encode(&inputs).unwrap(), tied.forward_to_layer(&inputs, 3).unwrap());
+        let mut buffer = Vec::new();
+        tied.save(&mut buffer).unwrap();
+        let loaded = NeuralNet::load(&buffer[..], MSECriterion::default(), GradientDesc::default())
+            .unwrap();
+        assert_eq!(loaded.encode(&inputs).unwrap(), tied.encode(&inputs).unwrap());
+
+        // Other networks have no encoder, even with an even number of layers
+        let mlp = NeuralNet::mlp(&[6, 3, 6], MSECriterion::default(), GradientDesc::default(), Sigmoid);
+        assert!(mlp.encode(&inputs).is_err());
+        let mut odd = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        odd.add(Box::new(Linear::new(6, 2)));
+        assert!(odd.encode(&inputs).is_err());
     }
 
-    /// Returns the regularization for this criterion.
-    ///
-    /// Will return `Regularization::None` by default.
-    fn regularization(&self) -> Regularization<f64> {
-        Regularization::None
+    #[test]
+    fn test_frozen_layers() {
+        let inputs = Matrix::new(4, 2, vec![0.1, 0.2, 0.5, -0.3, 1.2, 0.7, -0.4, 0.9]);
+        let targets = Matrix::new(4, 1, vec![1.0, 0.0, 1.0, 0.0]);
+        let alg = GradientDesc::default().with_weight_decay(0.1);
+        let mut net = NeuralNet::mlp(&[2, 3, 1], MSECriterion::default(), alg, Sigmoid);
+        let (_, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+
+        net.freeze_layer(0).unwrap();
+        let (_, frozen_grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        assert!(frozen_grad[..9].iter().all(|&g| g == 0.0));
+        assert_eq!(&frozen_grad[9..], &grad[9..]);
+
+        let summary = net.summary().unwrap();
+        assert!(summary.layers()[0].is_frozen());
+        assert!(!summary.layers()[2].is_frozen());
+
+        // Neither the gradient nor the weight decay moves the frozen weights
+        let weights = net.base.weights.clone();
+        net.train(&inputs, &targets).unwrap();
+        assert_eq!(&net.base.weights[..9], &weights[..9]);
+        assert!(net.base.weights[9..] != weights[9..]);
+
+        net.unfreeze_layer(0).unwrap();
+        net.train(&inputs, &targets).unwrap();
+        assert!(net.base.weights[..9] != weights[..9]);
+
+        assert!(net.freeze_layer(4).is_err());
     }
 
-    /// Checks if the current criterion includes regularization.
-    ///
-    /// Will return `false` by default.
-    fn is_regularized(&self) -> bool {
-        match self.regularization() {
-            Regularization::None => false,
-            _ => true,
+    #[test]
+    fn test_grad_clip() {
+        let inputs = Matrix::new(2, 2, vec![3.0, -4.0, 5.0, 1.0]);
+        let targets = Matrix::new(2, 1, vec![40.0, -30.0]);
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        net.add(Box::new(Linear::new(2, 1)));
+        let (_, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        let norm = grad.iter().fold(0f64, |acc, g| acc + g * g).sqrt();
+        assert!(norm > 10.0);
+
+        // Clipping by norm keeps the direction
+        net.grad_clip(GradClip::Norm(1.0));
+        let (_, clipped) = net.cost_and_grad(&inputs, &targets).unwrap();
+        let clipped_norm = clipped.iter().fold(0f64, |acc, g| acc + g * g).sqrt();
+        assert!((clipped_norm - 1.0).abs() < 1e-12);
+        for (c, g) in clipped.iter().zip(&grad) {
+            assert!((c - g / norm).abs() < 1e-12);
         }
+
+        net.grad_clip(GradClip::Value(0.5));
+        let (_, clipped) = net.cost_and_grad(&inputs, &targets).unwrap();
+        for (c, g) in clipped.iter().zip(&grad) {
+            assert_eq!(*c, g.max(-0.5).min(0.5));
+        }
+
+        // Small gradients are unchanged
+        net.grad_clip(GradClip::Norm(2.0 * norm));
+        assert_eq!(net.cost_and_grad(&inputs, &targets).unwrap().1, grad);
+
+        // The builder and copies keep the clipping
+        let net = NeuralNetBuilder::new(2)
+            .layer(Box::new(Linear::new(2, 1)))
+            .grad_clip(GradClip::Value(0.1))
+            .build()
+            .unwrap();
+        assert_eq!(net.fresh_copy().base.grad_clip, GradClip::Value(0.1));
     }
 
-    /// Returns the regularization cost for the criterion.
-    ///
-    /// Will return `0` by default.
-    ///
-    /// This method will not be invoked by the neural network
-    /// if there is explicitly no regularization.
-    fn reg_cost(&self, reg_weights: MatrixSlice<f64>) -> f64 {
-        self.regularization().reg_cost(reg_weights)
+    #[test]
+    #[should_panic]
+    fn test_grad_clip_not_positive() {
+        let mut net = NeuralNet::default(&[2, 1]);
+        net.grad_clip(GradClip::Norm(0.0));
     }
 
-    /// Returns the regularization gradient for the criterion.
-    ///
-    /// Will return a matrix of zeros by default.
-    ///
-    /// This method will not be invoked by the neural network
-    /// if there is explicitly no regularization.
-    fn reg_cost_grad(&self, reg_weights: MatrixSlice<f64>) -> Matrix<f64> {
-        self.regularization().reg_grad(reg_weights)
+    #[test]
+    fn test_fresh_copy() {
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.5, 20));
+        net.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::new(3, 1)));
+        net.train(&inputs, &targets).unwrap();
+
+        let copy = net.fresh_copy();
+        assert_eq!(format!("{:?}", copy.base.layers), format!("{:?}", net.base.layers));
+        assert_eq!(copy.num_params(), net.num_params());
+        assert!(copy.base.weights != net.base.weights);
+        assert!(copy.predict(&inputs).is_ok());
     }
-}
 
-/// The binary cross entropy criterion.
-///
-/// Uses the Sigmoid activation function and the
-/// cross entropy error.
-#[derive(Clone, Copy, Debug)]
-pub struct BCECriterion {
-    regularization: Regularization<f64>,
-}
+    #[test]
+    fn test_layer_regularization() {
+        use super::net_layer::layer_from_spec;
 
-impl Criterion for BCECriterion {
-    type Cost = cost_fn::CrossEntropyError;
+        let inputs = Matrix::new(2, 2, vec![0.5, -1.0, 1.5, 0.2]);
+        let targets = Matrix::new(2, 1, vec![1.0, 0.0]);
 
-    fn regularization(&self) -> Regularization<f64> {
-        self.regularization
+        let hidden = Linear::new(2, 3).with_reg(Regularization::L2(0.5));
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        net.add(Box::new(hidden))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::new(3, 1)));
+        let mut plain = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        plain.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::new(3, 1)));
+        plain.base.weights = net.base.weights.clone();
+
+        // Only the hidden layer is regularized
+        let (cost, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        let (plain_cost, plain_grad) = plain.cost_and_grad(&inputs, &targets).unwrap();
+        assert!(cost > plain_cost);
+        assert!((net.evaluate(&inputs, &targets).unwrap() - cost).abs() < 1e-12);
+        for i in 0..9 {
+            let expected = plain_grad[i] + 0.5 * net.base.weights[i] / 9.0;
+            assert!((grad[i] - expected).abs() < 1e-12);
+        }
+        assert_eq!(&grad[9..], &plain_grad[9..]);
+
+        // The layer's regularization replaces that of the criterion, which
+        // is still scaled by all 13 weights
+        let mut net = NeuralNet::new(MSECriterion::new(Regularization::L2(0.5)), GradientDesc::default());
+        net.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::new(3, 1).with_reg(Regularization::None)));
+        net.base.weights = plain.base.weights.clone();
+        let (net_cost, net_grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        let hidden_sq = net.base.weights[..9].iter().map(|w| w * w).sum::<f64>();
+        assert!((net_cost - plain_cost - 0.5 * hidden_sq / 26.0).abs() < 1e-12);
+        for i in 0..9 {
+            let expected = plain_grad[i] + 0.5 * net.base.weights[i] / 13.0;
+            assert!((net_grad[i] - expected).abs() < 1e-12);
+        }
+        assert_eq!(&net_grad[9..], &plain_grad[9..]);
+
+        let spec = NetLayer::<f64>::spec(&hidden).unwrap();
+        assert_eq!(layer_from_spec(&spec).unwrap().spec().unwrap(), spec);
     }
-}
 
-/// Creates an MSE Criterion without any regularization.
-impl Default for BCECriterion {
-    fn default() -> Self {
-        BCECriterion { regularization: Regularization::None }
+    #[test]
+    fn test_training_mode() {
+        use super::net_layer::Dropout;
+
+        let inputs = Matrix::new(10, 2, (0..20).map(|x| x as f64 + 1.0).collect::<Vec<_>>());
+        let mut net = NeuralNet::new(MSECriterion::default(), StochasticGD::default());
+        net.add(Box::new(Linear::without_bias(2, 4)))
+            .add(Box::new(Dropout::new(0.5)));
+        net.base.weights = vec![1.0; 8];
+        assert!(!net.is_training());
+
+        // Dropout is the identity when predicting
+        let outputs = net.predict(&inputs).unwrap();
+        assert!(outputs.iter().all(|&x| x > 0.0));
+        assert_eq!(net.predict(&inputs).unwrap(), outputs);
+
+        // In training mode the kept outputs are scaled and the rest dropped
+        net.set_training(true);
+        let sampled = net.predict(&inputs).unwrap();
+        assert!(sampled.iter().any(|&x| x == 0.0));
+        for (&y, &s) in outputs.iter().zip(sampled.iter()) {
+            assert!(s == 0.0 || s == 2.0 * y);
+        }
+        assert!(net.fresh_copy().is_training());
+
+        net.set_training(false);
+        assert_eq!(net.predict(&inputs).unwrap(), outputs);
     }
-}
 
-impl BCECriterion {
-    /// Constructs a new BCECriterion with the given regularization.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use rusty_machine::learning::nnet::BCECriterion;
-    /// use rusty_machine::learning::toolkit::regularization::Regularization;
-    ///
-    /// // Create a new BCE criterion with L2 regularization of 0.3.
-    /// let criterion = BCECriterion::new(Regularization::L2(0.3f64));
-    /// ```
-    pub fn new(regularization: Regularization<f64>) -> Self {
-        BCECriterion { regularization: regularization }
+    #[test]
+    fn test_f32_network() {
+        let inputs = Matrix::new(4, 2, vec![0.1, 0.2, 0.5, -0.3, 1.2, 0.7, -0.4, 0.9]);
+        let targets = Matrix::new(4, 1, vec![1.0, 0.0, 1.0, 0.0]);
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        net.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::new(3, 1)));
+        let mut single = NeuralNet::<_, _, f32>::with_precision(MSECriterion::default(),
+                                                                GradientDesc::default());
+        single.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(Sigmoid))
+            .add(Box::new(Linear::new(3, 1)));
+        single.base.weights = net.base.weights.iter().map(|&w| w as f32).collect();
+        net.base.weights = single.base.weights.iter().map(|&w| w as f64).collect();
+
+        let single_inputs = Matrix::new(4, 2, inputs.iter().map(|&x| x as f32).collect::<Vec<_>>());
+        let single_targets = Matrix::new(4, 1, targets.iter().map(|&x| x as f32).collect::<Vec<_>>());
+
+        // The gradients agree up to single precision
+        let (cost, grad) = net.cost_and_grad(&inputs, &targets).unwrap();
+        let (single_cost, single_grad) = single.cost_and_grad(&single_inputs, &single_targets).unwrap();
+        assert!((cost - single_cost).abs() < 1e-5);
+        for (g, s) in grad.iter().zip(&single_grad) {
+            assert!((g - s).abs() < 1e-5);
+        }
+
+        single.train(&single_inputs, &single_targets).unwrap();
+        assert!(single.evaluate(&single_inputs, &single_targets).unwrap() < single_cost);
+        let outputs: Matrix<f32> = single.predict(&single_inputs).unwrap();
+        assert_eq!(outputs.rows(), 4);
     }
-}
 
-/// The mean squared error criterion.
-///
-/// Uses the Linear activation function and the
-/// mean squared error.
-#[derive(Clone, Copy, Debug)]
-pub struct MSECriterion {
-    regularization: Regularization<f64>,
-}
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_parallel_gradient() {
+        use super::net_layer::{BatchNorm, Residual};
+        use learning::toolkit::activ_fn::Tanh;
 
-impl Criterion for MSECriterion {
-    type Cost = cost_fn::MeanSqError;
+        let inputs = Matrix::new(200, 2, (0..400).map(|x| (x as f64 * 0.37).sin()).collect::<Vec<_>>());
+        let targets = Matrix::new(200, 2, (0..400).map(|x| (x % 3 == 0) as usize as f64).collect::<Vec<_>>());
 
-    fn regularization(&self) -> Regularization<f64> {
-        self.regularization
+        let mut net = NeuralNet::new(BCECriterion::default(), GradientDesc::default());
+        net.add(Box::new(Linear::new(2, 4)))
+            .add(Box::new(Tanh))
+            .add(Box::new(Linear::without_bias(4, 3)))
+            .add(Box::new(Linear::without_bias(3, 4)))
+            .add(Box::new(Linear::new(4, 2)))
+            .add(Box::new(Sigmoid));
+        net.tie_weights(2, 3).unwrap();
+        let weights = &net.base.weights;
+
+        let layers = net.base.layers.iter().map(|layer| layer.as_independent().unwrap()).collect::<Vec<_>>();
+
+        let (cost, grad) = net.base.serial_backprop(weights, &inputs, &targets).unwrap();
+        for chunks in 2..5 {
+            let (par_cost, par_grad) = net.base.par_backprop(&layers, weights, &inputs, &targets, chunks).unwrap();
+            assert_eq!(par_cost, cost);
+            for (p, g) in par_grad.iter().zip(&grad) {
+                assert!((p - g).abs() < 1e-10);
+            }
+        }
+
+        // Layers using the whole batch, or which may not be thread safe,
+        // are computed serially
+        assert!(BatchNorm::new(2).as_independent().is_none());
+        assert!(NetLayer::<f64>::as_independent(&Residual::new(Box::new(Tanh))).is_none());
     }
-}
 
-/// Creates an MSE Criterion without any regularization.
-impl Default for MSECriterion {
-    fn default() -> Self {
-        MSECriterion { regularization: Regularization::None }
+    #[test]
+    fn test_grad_check() {
+        use learning::toolkit::activ_fn::Tanh;
+
+        let inputs = Matrix::new(3, 2, vec![0.5, -1.0, 0.2, 0.3, -0.7, 0.8]);
+        let targets = Matrix::new(3, 2, vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+
+        // The mean cost over a batch, with regularization, matches its gradient
+        for &reduction in &[Reduction::Mean, Reduction::Sum] {
+            let criterion = BCECriterion::new(Regularization::L2(0.3)).with_reduction(reduction);
+            let mut net = NeuralNet::new(criterion, GradientDesc::default());
+            net.add(Box::new(Linear::new(2, 3)))
+                .add(Box::new(Tanh))
+                .add(Box::new(Swish))
+                .add(Box::new(Linear::new(3, 2)))
+                .add(Box::new(Sigmoid));
+
+            let errors = net.grad_check(&inputs, &targets);
+            assert_eq!(errors.len(), 5);
+            assert!(errors.iter().all(|&e| e < 1e-6));
+            assert_eq!(errors[1], 0.0);
+        }
     }
-}
 
-impl MSECriterion {
-    /// Constructs a new BCECriterion with the given regularization.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use rusty_machine::learning::nnet::MSECriterion;
-    /// use rusty_machine::learning::toolkit::regularization::Regularization;
-    ///
-    /// // Create a new MSE criterion with L2 regularization of 0.3.
-    /// let criterion = MSECriterion::new(Regularization::L2(0.3f64));
-    /// ```
-    pub fn new(regularization: Regularization<f64>) -> Self {
-        MSECriterion { regularization: regularization }
+    #[test]
+    fn test_save_load() {
+        use super::net_layer::{BatchNorm, Dropout, Residual};
+        use learning::toolkit::activ_fn::{Temperature, LeakyReLU};
+
+        let inputs = Matrix::new(4, 2, vec![0.0, 10.0, 0.0, 11.0, 1.0, 10.0, 1.0, 11.0]);
+        let targets = Matrix::new(4, 2, vec![0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::new(0.1, 20));
+        net.add(Box::new(Linear::new(2, 3)))
+            .add(Box::new(BatchNorm::new(3)))
+            .add(Box::new(Residual::new(Box::new(LeakyReLU::new(0.1)))))
+            .add(Box::new(Dropout::new(0.8)))
+            .add(Box::new(Linear::without_bias(3, 2)))
+            .add(Box::new(Linear::without_bias(2, 3)))
+            .add(Box::new(Linear::new(3, 2)))
+            .add(Box::new(Sigmoid));
+        net.tie_weights(4, 5).unwrap();
+        net.set_layer_name(4, "encoder output").unwrap();
+        net.standardize_inputs();
+        net.train(&inputs, &targets).unwrap();
+
+        let mut buffer = Vec::new();
+        net.save(&mut buffer).unwrap();
+        let loaded = NeuralNet::load(&buffer[..], MSECriterion::default(), GradientDesc::default())
+            .unwrap();
+
+        let specs = |net: &NeuralNet<_, _>| {
+            net.base.layers.iter().map(|layer| layer.spec()).collect::<Vec<_>>()
+        };
+        assert_eq!(specs(&loaded), specs(&net));
+        assert_eq!(loaded.base.tied, net.base.tied);
+        assert_eq!(loaded.layer_index("encoder output"), Some(4));
+        assert_eq!(loaded.base.weights, net.base.weights);
+        assert_eq!(loaded.base.input_stats, net.base.input_stats);
+        assert_eq!(loaded.predict(&inputs).unwrap(), net.predict(&inputs).unwrap());
+
+        // Malformed networks are rejected
+        let text = String::from_utf8(buffer).unwrap();
+        let truncated = &text[..text.rfind(' ').unwrap()];
+        assert!(NeuralNet::load(truncated.as_bytes(), MSECriterion::default(), GradientDesc::default())
+            .is_err());
+        assert!(NeuralNet::load("layer Softmax\nname 1 out\nweights\n".as_bytes(),
+                                MSECriterion::default(),
+                                GradientDesc::default())
+            .is_err());
+        assert!(NeuralNet::load("layer Pooling 3\nweights\n".as_bytes(),
+                                MSECriterion::default(),
+                                GradientDesc::default())
+            .is_err());
+
+        // Settings which the layer constructors reject are errors
+        for layer in &["Dropout 0", "Dropout NaN", "Dropout 1.5", "LeakyReLU -1", "ELU 0",
+                       "Conv2d 1 4 4 2 0 1 0", "Conv2d 1 4 4 2 5 1 0",
+                       "Conv2d 1 4 4 2 3 1 18446744073709551615", "MaxPool2d 1 4 4 5 1",
+                       "RNN 0 2 3 false", "GRU 2 2 0 true",
+                       "BatchNorm 9223372036854775807 0.9 0.00001", "BatchNorm 1 1 0.00001 0 1",
+                       "BatchNorm 1 0.9 0 0 1"] {
+            let text = format!("layer {}\nweights\n", layer);
+            assert!(NeuralNet::load(text.as_bytes(), MSECriterion::default(), GradientDesc::default())
+                .is_err());
+        }
+
+        // Layers without a description cannot be saved
+        let mut net = NeuralNet::new(MSECriterion::default(), GradientDesc::default());
+        net.add(Box::new(Temperature::new(Sigmoid, 2.0)));
+        assert!(net.save(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_quantized_predictions() {
+        let inputs = Matrix::new(4, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+        let targets = Matrix::new(4, 1, vec![0.0, 1.0, 1.0, 0.0]);
+
+        let mut net = NeuralNet::mlp(&[2, 4, 1], MSECriterion::default(), GradientDesc::new(0.5, 200), Sigmoid);
+        net.train(&inputs, &targets).unwrap();
+        let outputs = net.predict(&inputs).unwrap();
+        let weights = net.base.weights.clone();
+
+        let quantized = net.quantize(8);
+        assert_eq!(quantized.bits(), 8);
+        assert_eq!(quantized.scales().len(), 4);
+        assert!(quantized.values().iter().all(|&q| q.abs() <= 127));
+
+        // Each weight is within half of the largest quantization step
+        let max_scale = quantized.scales().iter().fold(0f64, |acc, &x| acc.max(x));
+        for (w, q) in weights.iter().zip(quantized.dequantized_weights()) {
+            assert!((w - q).abs() <= 0.5 * max_scale + 1e-12);
+        }
+
+        let quantized_outputs = quantized.predict(&inputs).unwrap();
+        for (y, q) in outputs.data().iter().zip(quantized_outputs.data()) {
+            assert!((y - q).abs() < 1e-2);
+        }
     }
 }
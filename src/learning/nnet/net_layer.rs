@@ -1,39 +1,632 @@
 //! Neural Network Layers
 
-use linalg::{Matrix, MatrixSlice, BaseMatrix};
+use linalg::{Matrix, MatrixSlice, BaseMatrix, BaseMatrixMut, Vector};
 
 use learning::LearningResult;
 use learning::error::{Error, ErrorKind};
-use learning::toolkit::activ_fn::ActivationFunc;
+use learning::toolkit::activ_fn;
+use learning::toolkit::regularization::Regularization;
+use learning::toolkit::activ_fn::{ActivationFunc, Sigmoid, Tanh, Temperature, LeakyReLU, ELU,
+                                  Exp, ReLU, Swish, Softplus};
 
 use rand::thread_rng;
-use rand::distributions::Sample;
+use rand::distributions::{Range, Sample};
 use rand::distributions::normal::Normal;
 
+use libnum::Float;
+
+use std::borrow::Cow;
+use std::f64;
+use std::fmt;
 use std::fmt::Debug;
+use std::mem;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// The floating point types which neural networks compute in
+///
+/// This is implemented for `f32` and `f64`. Activation functions, criteria,
+/// regularization and weight initialization are defined in `f64`, so values
+/// are converted to and from `f64` where they meet the network. None of
+/// the conversions copy any data for `f64`.
+pub trait NetFloat: Float + Debug + fmt::Display + Send + Sync + 'static {
+    /// Converts from `f64`, rounding to the nearest value
+    fn from_f64(x: f64) -> Self;
+
+    /// Converts to `f64`
+    fn as_f64(self) -> f64;
+
+    /// Converts a slice of `f64` values
+    fn from_f64_slice<'a>(xs: &'a [f64]) -> Cow<'a, [Self]> {
+        Cow::Owned(xs.iter().map(|&x| Self::from_f64(x)).collect())
+    }
+
+    /// Converts a vector of `f64` values
+    fn from_f64_vec(xs: Vec<f64>) -> Vec<Self> {
+        xs.into_iter().map(Self::from_f64).collect()
+    }
+
+    /// Converts a slice of values to `f64`
+    fn as_f64_slice<'a>(xs: &'a [Self]) -> Cow<'a, [f64]> {
+        Cow::Owned(xs.iter().map(|&x| x.as_f64()).collect())
+    }
+
+    /// Converts a vector of values to `f64`
+    fn into_f64_vec(xs: Vec<Self>) -> Vec<f64> {
+        xs.into_iter().map(Self::as_f64).collect()
+    }
+
+    /// Converts a matrix of values to `f64`
+    fn as_f64_matrix<'a>(mat: &'a Matrix<Self>) -> Cow<'a, Matrix<f64>> {
+        Cow::Owned(Matrix::new(mat.rows(), mat.cols(), Self::as_f64_slice(mat.data()).into_owned()))
+    }
+
+    /// Applies an activation function to each value
+    fn apply_activation<A: ActivationFunc>(xs: &mut [Self]) {
+        for x in xs {
+            *x = Self::from_f64(A::func(x.as_f64()));
+        }
+    }
+}
+
+impl NetFloat for f32 {
+    fn from_f64(x: f64) -> f32 {
+        x as f32
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl NetFloat for f64 {
+    fn from_f64(x: f64) -> f64 {
+        x
+    }
+
+    fn as_f64(self) -> f64 {
+        self
+    }
+
+    fn from_f64_slice<'a>(xs: &'a [f64]) -> Cow<'a, [f64]> {
+        Cow::Borrowed(xs)
+    }
+
+    fn from_f64_vec(xs: Vec<f64>) -> Vec<f64> {
+        xs
+    }
+
+    fn as_f64_slice<'a>(xs: &'a [f64]) -> Cow<'a, [f64]> {
+        Cow::Borrowed(xs)
+    }
+
+    fn into_f64_vec(xs: Vec<f64>) -> Vec<f64> {
+        xs
+    }
+
+    fn as_f64_matrix<'a>(mat: &'a Matrix<f64>) -> Cow<'a, Matrix<f64>> {
+        Cow::Borrowed(mat)
+    }
+
+    fn apply_activation<A: ActivationFunc>(xs: &mut [f64]) {
+        A::apply_to_slice(xs)
+    }
+}
 
 /// Trait for neural net layers
-pub trait NetLayer : Debug {
+///
+/// Layers compute in the float type `T`, which is `f64` by default.
+/// `Linear`, `Softmax` and the activation functions can be used in
+/// either `f32` or `f64` networks. The other layers are `f64` only.
+///
+/// As those layers implement the trait for both float types, calling
+/// a method which does not mention the float type directly on them
+/// needs the type to be given:
+///
+/// ```
+/// use rusty_machine::learning::nnet::net_layer::{NetLayer, Linear};
+///
+/// let linear = Linear::new(2, 3);
+/// let mask = NetLayer::<f64>::bias_mask(&linear);
+/// assert_eq!(mask.len(), 9);
+/// ```
+pub trait NetLayer<T: NetFloat = f64> : Debug + CloneLayer<T> {
     /// The result of propogating data forward through this layer
-    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>>;
+    fn forward(&self, input: &Matrix<T>, params: MatrixSlice<T>) -> LearningResult<Matrix<T>>;
+
+    /// The result of propogating data forward through this layer during training
+    ///
+    /// Layers which behave differently while the network is being trained,
+    /// such as `BatchNorm`, should override this. By default it is the same as `forward`.
+    ///
+    /// The network uses this when training, and also when predicting
+    /// after `NeuralNet::set_training(true)`.
+    fn forward_train(&self, input: &Matrix<T>, params: MatrixSlice<T>) -> LearningResult<Matrix<T>> {
+        self.forward(input, params)
+    }
+
+    /// The result of propogating borrowed data forward through this layer
+    ///
+    /// The network uses this for its first layer when predicting on a
+    /// `MatrixSlice`. By default the input is copied and passed to `forward`.
+    fn forward_slice(&self, input: MatrixSlice<T>, params: MatrixSlice<T>) -> LearningResult<Matrix<T>> {
+        self.forward(&input.into_matrix(), params)
+    }
 
     /// The gradient of the output of this layer with respect to its input
-    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, output: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64>;
+    fn back_input(&self, out_grad: &Matrix<T>, input: &Matrix<T>, output: &Matrix<T>, params: MatrixSlice<T>) -> Matrix<T>;
     
     /// The gradient of the output of this layer with respect to its parameters
-    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, output: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64>;
+    fn back_params(&self, out_grad: &Matrix<T>, input: &Matrix<T>, output: &Matrix<T>, params: MatrixSlice<T>) -> Matrix<T>;
 
     /// The default value of the parameters of this layer before training
-    fn default_params(&self) -> Vec<f64>;
+    fn default_params(&self) -> Vec<T>;
 
     /// The shape of the parameters used by this layer
     fn param_shape(&self) -> (usize, usize);
 
+    /// The number of output columns for inputs with `input_size` columns
+    ///
+    /// Returns `None` if the layer cannot take inputs of this size. By
+    /// default the layer is assumed to preserve the size of its input.
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        Some(input_size)
+    }
+
+    /// The number of input columns this layer requires
+    ///
+    /// Returns `None` if the layer takes inputs of more than one size,
+    /// which is the default.
+    fn input_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// The type name of this layer, such as `Linear`
+    ///
+    /// This is shown by `NeuralNet::summary`. By default it is the start
+    /// of the `Debug` output of the layer, up to the first character which
+    /// cannot be part of an identifier.
+    fn name(&self) -> String {
+        format!("{:?}", self)
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect()
+    }
+
     /// The number of parameters used by this layer
     fn num_params(&self) -> usize {
         let shape = self.param_shape();
         shape.0 * shape.1
     }
+
+    /// Which of the parameters of this layer are bias terms
+    ///
+    /// Bias terms are excluded from weight decay. By default there are none.
+    fn bias_mask(&self) -> Vec<bool> {
+        vec![false; self.num_params()]
+    }
+
+    /// The value of the parameters of this layer before training using the given initialization
+    ///
+    /// Layers without connection weights, such as activations and
+    /// `BatchNorm`, ignore the initialization. By default this is
+    /// `default_params`.
+    fn init_params(&self, _: WeightInit) -> Vec<T> {
+        self.default_params()
+    }
+
+    /// A single line description from which the layer can be rebuilt
+    ///
+    /// This is used to save neural networks, and is read back by
+    /// `layer_from_spec`. Layers which return `None`, the default,
+    /// cannot be saved.
+    fn spec(&self) -> Option<String> {
+        None
+    }
+
+    /// The regularization of this layer's parameters
+    ///
+    /// When this is `Some` the layer's parameters are regularized by it
+    /// instead of by the regularization of the network's criterion.
+    /// By default it is `None`.
+    fn regularization(&self) -> Option<Regularization<f64>> {
+        None
+    }
+
+    /// The layer as a thread safe reference, if each row of the input is
+    /// processed independently in training
+    ///
+    /// With the `parallel` feature, the gradient of a network whose layers
+    /// all return `Some` is computed on chunks of rows in parallel, which
+    /// requires the layers to be `Sync`. This is `None` by default, and for
+    /// layers like `BatchNorm` which use statistics of the whole batch or
+    /// `Dropout` which keep state between the forward and backward passes.
+    fn as_independent(&self) -> Option<&(NetLayer<T> + Sync)> {
+        None
+    }
+}
+
+/// Cloning of neural net layers into a new boxed layer
+///
+/// This is implemented for every layer which is `Clone`. Other layers,
+/// and layers which should not copy all of their state, implement it
+/// themselves.
+pub trait CloneLayer<T: NetFloat = f64> {
+    /// Clones the configuration of this layer into a new boxed layer
+    ///
+    /// State learned during training, such as the running statistics of
+    /// `BatchNorm`, is not copied.
+    fn clone_box(&self) -> Box<NetLayer<T>>;
+}
+
+impl<T: NetFloat, L: NetLayer<T> + Clone + 'static> CloneLayer<T> for L {
+    fn clone_box(&self) -> Box<NetLayer<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// The activation function in `activ_fn` with the given name, if any.
+fn builtin_activation<F: NetFloat>(name: &str) -> Option<Box<NetLayer<F>>> {
+    match name {
+        "Sigmoid" => Some(Box::new(Sigmoid)),
+        "Linear" => Some(Box::new(activ_fn::Linear)),
+        "Exp" => Some(Box::new(Exp)),
+        "Tanh" => Some(Box::new(Tanh)),
+        "ReLU" => Some(Box::new(ReLU)),
+        "Swish" => Some(Box::new(Swish)),
+        "Softplus" => Some(Box::new(Softplus)),
+        _ => None,
+    }
+}
+
+/// Rebuilds a layer from the description given by `NetLayer::spec`
+///
+/// All of the layers in this module can be rebuilt, as can the activation
+/// functions in `activ_fn` except for `Temperature`. The running
+/// statistics of `BatchNorm` are restored, but the layer parameters are
+/// not part of the description.
+///
+/// Returns an `InvalidData` error if the description is not recognised,
+/// or if its settings would make the layer constructor panic.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::net_layer::{NetLayer, Linear, layer_from_spec};
+///
+/// let linear: Box<NetLayer> = Box::new(Linear::new(3, 4));
+/// let spec = linear.spec().unwrap();
+/// let layer = layer_from_spec(&spec).unwrap();
+/// assert_eq!(layer.param_shape(), (4, 4));
+/// ```
+pub fn layer_from_spec(spec: &str) -> LearningResult<Box<NetLayer>> {
+    let spec = spec.trim();
+    let (name, rest) = match spec.find(char::is_whitespace) {
+        Some(idx) => (&spec[..idx], &spec[idx..]),
+        None => (spec, ""),
+    };
+    if name == "Residual" {
+        return Ok(Box::new(Residual::new(try!(layer_from_spec(rest)))));
+    }
+
+    let args = rest.split_whitespace().collect::<Vec<_>>();
+    let arity = |n: usize| -> LearningResult<()> {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidData,
+                           format!("Expected {} values for a {} layer, found {}.",
+                                   n,
+                                   name,
+                                   args.len())))
+        }
+    };
+    // The constructors panic on invalid settings, so these are checked first
+    let check = |valid: bool, msg: &str| -> LearningResult<()> {
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidData, format!("Invalid {} layer: {}.", name, msg)))
+        }
+    };
+
+    let layer: Box<NetLayer> = match name {
+        "Linear" => {
+            let reg = match args.get(3) {
+                None => None,
+                Some(&"None") => {
+                    try!(arity(4));
+                    Some(Regularization::None)
+                }
+                Some(&"L1") => {
+                    try!(arity(5));
+                    Some(Regularization::L1(try!(spec_arg(&args, 4))))
+                }
+                Some(&"L2") => {
+                    try!(arity(5));
+                    Some(Regularization::L2(try!(spec_arg(&args, 4))))
+                }
+                Some(&"ElasticNet") => {
+                    try!(arity(6));
+                    Some(Regularization::ElasticNet(try!(spec_arg(&args, 4)), try!(spec_arg(&args, 5))))
+                }
+                Some(other) => {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          format!("Unknown regularization {}.", other)))
+                }
+            };
+            if reg.is_none() {
+                try!(arity(3));
+            }
+
+            let (input_size, output_size) = (try!(spec_arg(&args, 0)), try!(spec_arg(&args, 1)));
+            let mut layer = if try!(spec_arg(&args, 2)) {
+                if input_size == 0 {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          "A Linear layer with a bias needs an input."));
+                }
+                Linear::new(input_size - 1, output_size)
+            } else {
+                Linear::without_bias(input_size, output_size)
+            };
+            layer.reg = reg;
+            Box::new(layer)
+        }
+        "Activation" => {
+            try!(arity(1));
+            match builtin_activation(args[0]) {
+                Some(layer) => layer,
+                None => {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          format!("Unknown activation function {}. Only the \
+                                                   activation functions in activ_fn can be \
+                                                   loaded.",
+                                                  args[0])))
+                }
+            }
+        }
+        "LeakyReLU" | "ELU" => {
+            try!(arity(1));
+            let alpha: f64 = try!(spec_arg(&args, 0));
+            try!(check(alpha > 0f64, "the slope must be positive"));
+            if name == "ELU" {
+                Box::new(ELU::new(alpha))
+            } else {
+                Box::new(LeakyReLU::new(alpha))
+            }
+        }
+        "Softmax" => {
+            try!(arity(0));
+            Box::new(Softmax)
+        }
+        "BatchNorm" => {
+            let size: usize = try!(spec_arg(&args, 0));
+            match size.checked_mul(2).and_then(|n| n.checked_add(3)) {
+                Some(n) => try!(arity(n)),
+                None => try!(check(false, "the size is too large")),
+            }
+            let (momentum, epsilon): (f64, f64) = (try!(spec_arg(&args, 1)), try!(spec_arg(&args, 2)));
+            try!(check(0f64 <= momentum && momentum < 1f64, "the momentum must be in [0, 1)"));
+            try!(check(epsilon > 0f64, "epsilon must be positive"));
+            let layer = BatchNorm::with_momentum(size, momentum, epsilon);
+            let stats = try!((3..args.len()).map(|i| spec_arg(&args, i)).collect::<LearningResult<Vec<f64>>>());
+            *layer.running_stats.lock().unwrap() = (Vector::new(&stats[..size]),
+                                                 Vector::new(&stats[size..]));
+            Box::new(layer)
+        }
+        "Dropout" => {
+            try!(arity(1));
+            let keep_prob: f64 = try!(spec_arg(&args, 0));
+            try!(check(0f64 < keep_prob && keep_prob <= 1f64, "the keep probability must be in (0, 1]"));
+            Box::new(Dropout::new(keep_prob))
+        }
+        "Embedding" => {
+            try!(arity(2));
+            Box::new(Embedding::new(try!(spec_arg(&args, 0)), try!(spec_arg(&args, 1))))
+        }
+        "Conv2d" => {
+            try!(arity(7));
+            let sizes = try!((0..7).map(|i| spec_arg(&args, i)).collect::<LearningResult<Vec<usize>>>());
+            try!(check(sizes[4] > 0 && sizes[5] > 0, "the kernel size and stride must be positive"));
+            let padded = |n: usize| sizes[6].checked_mul(2).and_then(|p| p.checked_add(n));
+            try!(check(padded(sizes[1]).map_or(false, |h| sizes[4] <= h) &&
+                       padded(sizes[2]).map_or(false, |w| sizes[4] <= w),
+                       "the kernel must fit inside the padded image"));
+            Box::new(Conv2d::new((sizes[0], sizes[1], sizes[2]), sizes[3], sizes[4], sizes[5], sizes[6]))
+        }
+        "MaxPool2d" => {
+            try!(arity(5));
+            let sizes = try!((0..5).map(|i| spec_arg(&args, i)).collect::<LearningResult<Vec<usize>>>());
+            try!(check(sizes[3] > 0 && sizes[4] > 0, "the kernel size and stride must be positive"));
+            try!(check(sizes[3] <= sizes[1] && sizes[3] <= sizes[2],
+                       "the kernel must fit inside the feature map"));
+            Box::new(MaxPool2d::new(sizes[0], sizes[1], sizes[2], sizes[3], sizes[4]))
+        }
+        "RNN" => {
+            try!(arity(4));
+            let sizes = try!((0..3).map(|i| spec_arg(&args, i)).collect::<LearningResult<Vec<usize>>>());
+            try!(check(sizes.iter().all(|&n| n > 0), "the sizes and timesteps must be positive"));
+            let layer = RNN::new(sizes[0], sizes[1], sizes[2]);
+            if try!(spec_arg(&args, 3)) {
+                Box::new(layer.with_sequence_output())
+            } else {
+                Box::new(layer)
+            }
+        }
+        "GRU" => {
+            try!(arity(4));
+            let sizes = try!((0..3).map(|i| spec_arg(&args, i)).collect::<LearningResult<Vec<usize>>>());
+            try!(check(sizes.iter().all(|&n| n > 0), "the sizes and timesteps must be positive"));
+            let layer = GRU::new(sizes[0], sizes[1], sizes[2]);
+            if try!(spec_arg(&args, 3)) {
+                Box::new(layer.with_sequence_output())
+            } else {
+                Box::new(layer)
+            }
+        }
+        _ => {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  format!("Unknown layer description: {}", spec)))
+        }
+    };
+    Ok(layer)
+}
+
+/// Parses one value of a layer description
+fn spec_arg<F: FromStr>(args: &[&str], idx: usize) -> LearningResult<F> {
+    args.get(idx)
+        .and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData,
+                       format!("Invalid layer description value {:?}.", args.get(idx)))
+        })
+}
+
+/// The initialization of the weights of a layer
+///
+/// Except for `Default`, the biases of the layer are initialized to zero.
+/// Layers whose parameters are not connection weights, such as the scale
+/// and shift of `BatchNorm`, keep their own initialization.
+#[derive(Clone, Debug)]
+pub enum WeightInit {
+    /// The layer's own initialization given by `default_params`
+    Default,
+    /// Zero-mean gaussian weights with the given standard deviation and zero biases
+    Normal {
+        /// The standard deviation of the weights, which must be positive
+        std: f64,
+    },
+    /// Glorot (Xavier) uniform weights and zero biases
+    ///
+    /// The weights are drawn uniformly from `[-a, a]` with
+    /// `a = sqrt(6 / (fan_in + fan_out))`, where the fans count the
+    /// connections of each unit and exclude the bias. This matches the
+    /// initialization of other frameworks.
+    ///
+    /// The default initialization of `Linear` is a different formula. It
+    /// draws gaussian weights and biases with variance
+    /// `2 / (fan_in + 1 + fan_out)`, counting the bias as an input.
+    GlorotUniform,
+    /// Glorot (Xavier) gaussian weights
+    ///
+    /// The weights have variance `2 / (fan_in + fan_out)`.
+    GlorotNormal,
+    /// He gaussian weights
+    ///
+    /// The weights have variance `2 / fan_in`. This keeps the scale of
+    /// the activations of deep networks using `ReLU`.
+    HeNormal,
+    /// He uniform weights
+    ///
+    /// The weights are drawn uniformly from `[-a, a]` with `a = sqrt(6 / fan_in)`.
+    HeUniform,
+    /// Weights drawn by a custom initializer
+    Custom(Rc<WeightInitializer>),
+}
+
+/// A custom initialization of the weights of a layer
+///
+/// This is implemented for closures which take the fan-in and fan-out
+/// of a layer and draw one of its weights.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::nnet::NeuralNet;
+/// use rusty_machine::learning::nnet::net_layer::WeightInit;
+///
+/// let mut net = NeuralNet::default(&[3, 4, 2]);
+///
+/// // Every weight starts at the same small value
+/// net.weight_init(WeightInit::custom(|fan_in, _| 0.1 / fan_in as f64));
+/// ```
+pub trait WeightInitializer {
+    /// Draws one weight of a layer with the given fan-in and fan-out
+    fn weight(&self, fan_in: usize, fan_out: usize) -> f64;
+}
+
+impl<F: Fn(usize, usize) -> f64> WeightInitializer for F {
+    fn weight(&self, fan_in: usize, fan_out: usize) -> f64 {
+        self(fan_in, fan_out)
+    }
+}
+
+impl Debug for WeightInitializer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WeightInitializer")
+    }
+}
+
+/// Uses the default initialization of each layer.
+impl Default for WeightInit {
+    fn default() -> WeightInit {
+        WeightInit::Default
+    }
+}
+
+impl WeightInit {
+    /// Uses a custom initializer for the weights.
+    pub fn custom<W: WeightInitializer + 'static>(init: W) -> WeightInit {
+        WeightInit::Custom(Rc::new(init))
+    }
+
+    /// Draws the parameters of a layer, or `None` for the layer's default initialization
+    ///
+    /// The layer has one parameter per entry of the bias mask, and
+    /// `fans` are the fan-in and fan-out of its weights.
+    fn sample(&self, fans: (usize, usize), bias_mask: &[bool]) -> Option<Vec<f64>> {
+        let mut rng = thread_rng();
+        match *self {
+            WeightInit::Default => None,
+            WeightInit::Normal { std } => {
+                let mut distro = Normal::new(0.0, std);
+                Some(zero_biases(bias_mask, || distro.sample(&mut rng)))
+            }
+            WeightInit::GlorotUniform => {
+                let bound = glorot_bound(fans.0, fans.1);
+                let mut distro = Range::new(-bound, bound);
+                Some(zero_biases(bias_mask, || distro.sample(&mut rng)))
+            }
+            WeightInit::GlorotNormal => {
+                let mut distro = Normal::new(0.0, (2f64 / (fans.0 + fans.1) as f64).sqrt());
+                Some(zero_biases(bias_mask, || distro.sample(&mut rng)))
+            }
+            WeightInit::HeNormal => {
+                let mut distro = Normal::new(0.0, (2f64 / fans.0 as f64).sqrt());
+                Some(zero_biases(bias_mask, || distro.sample(&mut rng)))
+            }
+            WeightInit::HeUniform => {
+                let bound = (6f64 / fans.0 as f64).sqrt();
+                let mut distro = Range::new(-bound, bound);
+                Some(zero_biases(bias_mask, || distro.sample(&mut rng)))
+            }
+            WeightInit::Custom(ref init) => {
+                Some(zero_biases(bias_mask, || init.weight(fans.0, fans.1)))
+            }
+        }
+    }
+}
+
+/// Draws each weight, leaving the biases at zero
+fn zero_biases<F: FnMut() -> f64>(bias_mask: &[bool], mut weight: F) -> Vec<f64> {
+    bias_mask.iter()
+        .map(|&is_bias| if is_bias { 0f64 } else { weight() })
+        .collect()
+}
+
+/// The bound of the Glorot uniform distribution
+fn glorot_bound(fan_in: usize, fan_out: usize) -> f64 {
+    (6f64 / (fan_in + fan_out) as f64).sqrt()
+}
+
+/// Marks the first row of a parameter matrix as biases
+fn first_row_bias(shape: (usize, usize)) -> Vec<bool> {
+    let mut mask = vec![false; shape.0 * shape.1];
+    for x in mask.iter_mut().take(shape.1) {
+        *x = true;
+    }
+    mask
 }
 
 /// Linear network layer
@@ -50,6 +643,8 @@ pub struct Linear {
     output_size: usize,
     /// Whether or not to include a bias term
     has_bias: bool,
+    /// The regularization used in place of the criterion's
+    reg: Option<Regularization<f64>>,
 }
 
 impl Linear {
@@ -58,7 +653,8 @@ impl Linear {
         Linear {
             input_size: input_size + 1, 
             output_size: output_size,
-            has_bias: true
+            has_bias: true,
+            reg: None,
         }
     }
 
@@ -67,12 +663,40 @@ impl Linear {
         Linear {
             input_size: input_size, 
             output_size: output_size,
-            has_bias: false
+            has_bias: false,
+            reg: None,
         }
     }
+
+    /// Regularizes the weights of this layer, including the bias
+    ///
+    /// This replaces the regularization of the network's criterion for
+    /// this layer. Use `Regularization::None` to exclude the layer from
+    /// the criterion's regularization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::{NeuralNet, BCECriterion};
+    /// use rusty_machine::learning::nnet::net_layer::Linear;
+    /// use rusty_machine::learning::toolkit::activ_fn::Sigmoid;
+    /// use rusty_machine::learning::toolkit::regularization::Regularization;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Regularize the hidden layer but not the output layer
+    /// let mut net = NeuralNet::new(BCECriterion::default(), StochasticGD::default());
+    /// net.add(Box::new(Linear::new(4, 8).with_reg(Regularization::L2(0.01))))
+    ///    .add(Box::new(Sigmoid))
+    ///    .add(Box::new(Linear::new(8, 1)))
+    ///    .add(Box::new(Sigmoid));
+    /// ```
+    pub fn with_reg(mut self, reg: Regularization<f64>) -> Linear {
+        self.reg = Some(reg);
+        self
+    }
 }
 
-fn remove_first_col(mat: Matrix<f64>) -> Matrix<f64>
+fn remove_first_col<T: Copy>(mat: Matrix<T>) -> Matrix<T>
 {
     let rows = mat.rows();
     let cols = mat.cols();
@@ -97,28 +721,44 @@ fn remove_first_col(mat: Matrix<f64>) -> Matrix<f64>
     Matrix::new(rows, cols - 1, data)
 }
 
-impl NetLayer for Linear {
+impl<T: NetFloat> NetLayer<T> for Linear {
     /// Computes a matrix product
     ///
     /// input should have dimensions N x I
     /// where N is the number of samples and I is the dimensionality of the input
-    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+    fn forward(&self, input: &Matrix<T>, params: MatrixSlice<T>) -> LearningResult<Matrix<T>> {
+        self.forward_slice(input.as_slice(), params)
+    }
+
+    /// Computes the matrix product without copying the input
+    ///
+    /// The bias is added to each row of the product rather than
+    /// prepending a column of ones to the input.
+    fn forward_slice(&self, input: MatrixSlice<T>, params: MatrixSlice<T>) -> LearningResult<Matrix<T>> {
         if self.has_bias {
             if input.cols()+1 != params.rows() {
                 Err(Error::new(ErrorKind::InvalidData, "The input had the wrong number of columns"))
             } else {
-                Ok(&Matrix::ones(input.rows(), 1).hcat(input) * &params)
+                let weights = params.sub_slice([1, 0], input.cols(), params.cols());
+                let mut output = &input * &weights;
+                let bias = params.row(0);
+                for mut row in output.row_iter_mut() {
+                    for (x, &b) in row.raw_slice_mut().iter_mut().zip(bias.raw_slice()) {
+                        *x = *x + b;
+                    }
+                }
+                Ok(output)
             }
         } else {
             if input.cols() != params.rows() {
                 Err(Error::new(ErrorKind::InvalidData, "The input had the wrong number of columns"))
             } else {
-                Ok(input * &params)
+                Ok(&input * &params)
             }
         }
     }
     
-    fn back_input(&self, out_grad: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+    fn back_input(&self, out_grad: &Matrix<T>, _: &Matrix<T>, _: &Matrix<T>, params: MatrixSlice<T>) -> Matrix<T> {
         debug_assert_eq!(out_grad.cols(), params.cols());
         let gradient = out_grad * &params.transpose();
         if self.has_bias {
@@ -128,7 +768,7 @@ impl NetLayer for Linear {
         }
     }
     
-    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+    fn back_params(&self, out_grad: &Matrix<T>, input: &Matrix<T>, _: &Matrix<T>, _: MatrixSlice<T>) -> Matrix<T> {
         debug_assert_eq!(input.rows(), out_grad.rows());
         if self.has_bias {
             &Matrix::ones(input.rows(), 1).hcat(input).transpose() * out_grad
@@ -140,46 +780,2280 @@ impl NetLayer for Linear {
     /// Initializes weights using Xavier initialization
     ///
     /// weights drawn from gaussian distribution with 0 mean and variance 2/(input_size+output_size)
-    fn default_params(&self) -> Vec<f64> {
+    fn default_params(&self) -> Vec<T> {
         let mut distro = Normal::new(0.0, (2.0/(self.input_size+self.output_size) as f64).sqrt());
         let mut rng = thread_rng();
 
-        (0..self.input_size*self.output_size).map(|_| distro.sample(&mut rng))
+        (0..self.input_size*self.output_size).map(|_| T::from_f64(distro.sample(&mut rng)))
                                              .collect()
     }
 
+    fn init_params(&self, init: WeightInit) -> Vec<T> {
+        let fan_in = if self.has_bias { self.input_size - 1 } else { self.input_size };
+        let bias_mask = NetLayer::<T>::bias_mask(self);
+        init.sample((fan_in, self.output_size), &bias_mask)
+            .map(T::from_f64_vec)
+            .unwrap_or_else(|| self.default_params())
+    }
+
     fn param_shape(&self) -> (usize, usize) {
         (self.input_size, self.output_size)
     }
+
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        let expected = if self.has_bias { self.input_size - 1 } else { self.input_size };
+        if input_size == expected { Some(self.output_size) } else { None }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(if self.has_bias { self.input_size - 1 } else { self.input_size })
+    }
+
+    fn name(&self) -> String {
+        "Linear".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer<T> + Sync)> {
+        Some(self)
+    }
+
+    /// The regularization, if any, follows the sizes
+    fn spec(&self) -> Option<String> {
+        let spec = format!("Linear {} {} {}", self.input_size, self.output_size, self.has_bias);
+        Some(match self.reg {
+            Some(Regularization::L1(x)) => format!("{} L1 {}", spec, x),
+            Some(Regularization::L2(x)) => format!("{} L2 {}", spec, x),
+            Some(Regularization::ElasticNet(x, y)) => format!("{} ElasticNet {} {}", spec, x, y),
+            Some(Regularization::None) => format!("{} None", spec),
+            None => spec,
+        })
+    }
+
+    fn regularization(&self) -> Option<Regularization<f64>> {
+        self.reg
+    }
+
+    /// The first row of the weights is the bias, if there is one
+    fn bias_mask(&self) -> Vec<bool> {
+        let shape = (self.input_size, self.output_size);
+        if self.has_bias {
+            first_row_bias(shape)
+        } else {
+            vec![false; shape.0 * shape.1]
+        }
+    }
 }
 
-impl<T: ActivationFunc> NetLayer for T {
+/// Neural net layers which apply an activation function to each element
+///
+/// These layers have no parameters, so they can be repeated after each
+/// `Linear` layer of `NeuralNet::mlp`. This is implemented for every
+/// `ActivationFunc`, and for the activations with settings such as
+/// `LeakyReLU`, `ELU` and `Temperature`.
+pub trait ActivationLayer<T: NetFloat = f64>: NetLayer<T> + Clone {}
+
+impl<F: NetFloat, T: ActivationFunc + Sync + 'static> ActivationLayer<F> for T {}
+
+impl<F: NetFloat, T: ActivationFunc + Sync + 'static> NetLayer<F> for T {
     /// Applies the activation function to each element of the input
-    fn forward(&self, input: &Matrix<f64>, _: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
-        let mut output = Vec::with_capacity(input.rows()*input.cols());
-        for val in input.data() {
-            output.push(T::func(*val));
-        }
-        Ok(Matrix::new(input.rows(), input.cols(), output))
+    fn forward(&self, input: &Matrix<F>, _: MatrixSlice<F>) -> LearningResult<Matrix<F>> {
+        let mut output = input.clone();
+        F::apply_activation::<T>(output.mut_data());
+        Ok(output)
     }
 
-    fn back_input(&self, out_grad: &Matrix<f64>, _: &Matrix<f64>, output: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+    fn back_input(&self, out_grad: &Matrix<F>, input: &Matrix<F>, output: &Matrix<F>, _: MatrixSlice<F>) -> Matrix<F> {
         let mut in_grad = Vec::with_capacity(output.rows()*output.cols());
-        for (y, g) in output.data().iter().zip(out_grad.data()) {
-            in_grad.push(T::func_grad_from_output(*y) * g);
+        for ((x, y), g) in input.data().iter().zip(output.data()).zip(out_grad.data()) {
+            in_grad.push(F::from_f64(T::func_grad_from_input_output(x.as_f64(), y.as_f64())) * *g);
         }
         Matrix::new(output.rows(), output.cols(), in_grad)
     }
     
-    fn back_params(&self, _: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+    fn back_params(&self, _: &Matrix<F>, _: &Matrix<F>, _: &Matrix<F>, _: MatrixSlice<F>) -> Matrix<F> {
         Matrix::new(0, 0, Vec::new())
     }
 
-    fn default_params(&self) -> Vec<f64> {
+    fn default_params(&self) -> Vec<F> {
         Vec::new()
     }
 
     fn param_shape(&self) -> (usize, usize) {
         (0, 0)
     }
+
+    fn name(&self) -> String {
+        T::name().to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer<F> + Sync)> {
+        Some(self)
+    }
+
+    /// Custom activation functions cannot be rebuilt, so have no spec.
+    fn spec(&self) -> Option<String> {
+        builtin_activation::<F>(T::name()).map(|_| format!("Activation {}", T::name()))
+    }
+}
+
+/// Implements `NetLayer` and `ActivationLayer` for an activation function
+/// whose settings are part of its value, such as the slope of `LeakyReLU`.
+///
+/// The type must have `func` and `func_grad_from_input_output` methods.
+/// The layer is shown as `$name`, and `$spec` gives its `spec`.
+macro_rules! activation_layer {
+    ($ty:ty, [$($gen:tt)*], $name:expr, $spec:expr) => {
+        impl<F: NetFloat, $($gen)*> NetLayer<F> for $ty {
+            /// Applies the activation function to each element of the input
+            fn forward(&self, input: &Matrix<F>, _: MatrixSlice<F>) -> LearningResult<Matrix<F>> {
+                Ok(input.clone().apply(&|x| F::from_f64(self.func(x.as_f64()))))
+            }
+
+            fn back_input(&self, out_grad: &Matrix<F>, input: &Matrix<F>, output: &Matrix<F>, _: MatrixSlice<F>) -> Matrix<F> {
+                let in_grad = input.iter()
+                    .zip(output.iter())
+                    .zip(out_grad.iter())
+                    .map(|((x, y), g)| F::from_f64(self.func_grad_from_input_output(x.as_f64(), y.as_f64())) * *g)
+                    .collect::<Vec<_>>();
+                Matrix::new(output.rows(), output.cols(), in_grad)
+            }
+
+            fn back_params(&self, _: &Matrix<F>, _: &Matrix<F>, _: &Matrix<F>, _: MatrixSlice<F>) -> Matrix<F> {
+                Matrix::new(0, 0, Vec::new())
+            }
+
+            fn default_params(&self) -> Vec<F> {
+                Vec::new()
+            }
+
+            fn param_shape(&self) -> (usize, usize) {
+                (0, 0)
+            }
+
+            fn name(&self) -> String {
+                $name.to_owned()
+            }
+
+            fn as_independent(&self) -> Option<&(NetLayer<F> + Sync)> {
+                Some(self)
+            }
+
+            fn spec(&self) -> Option<String> {
+                let spec: fn(&$ty) -> Option<String> = $spec;
+                spec(self)
+            }
+        }
+
+        impl<F: NetFloat, $($gen)*> ActivationLayer<F> for $ty {}
+    }
+}
+
+activation_layer!(Temperature<T>, [T: ActivationFunc + Sync + 'static], "Temperature", |_| None);
+activation_layer!(LeakyReLU, [], "LeakyReLU", |a| Some(format!("LeakyReLU {}", a.alpha())));
+activation_layer!(ELU, [], "ELU", |a| Some(format!("ELU {}", a.alpha())));
+
+/// Softmax layer
+///
+/// Maps each row of the input to a probability distribution,
+/// `y_j = exp(x_j) / sum_k exp(x_k)`. The row maximum is subtracted
+/// before exponentiating, so large inputs do not overflow.
+///
+/// Use as the output layer with `CategoricalCECriterion` for
+/// multiclass classification.
+///
+/// This layer has no parameters.
+#[derive(Debug, Clone, Copy)]
+pub struct Softmax;
+
+impl<T: NetFloat> NetLayer<T> for Softmax {
+    fn forward(&self, input: &Matrix<T>, _: MatrixSlice<T>) -> LearningResult<Matrix<T>> {
+        let mut output = input.clone();
+        for mut row in output.row_iter_mut() {
+            let row = row.raw_slice_mut();
+            let max = row.iter().cloned().fold(T::neg_infinity(), T::max);
+            let mut total = T::zero();
+            for y in row.iter_mut() {
+                *y = (*y - max).exp();
+                total = total + *y;
+            }
+            for y in row.iter_mut() {
+                *y = *y / total;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Multiplies the gradient by the Jacobian of each row, `diag(y) - y y^T`
+    fn back_input(&self, out_grad: &Matrix<T>, _: &Matrix<T>, output: &Matrix<T>, _: MatrixSlice<T>) -> Matrix<T> {
+        let mut in_grad = out_grad.clone();
+        for (mut grad_row, out_row) in in_grad.row_iter_mut().zip(output.row_iter()) {
+            let (grad_row, out_row) = (grad_row.raw_slice_mut(), out_row.raw_slice());
+            let dot = grad_row.iter().zip(out_row).fold(T::zero(), |acc, (&g, &y)| acc + g * y);
+            for (g, y) in grad_row.iter_mut().zip(out_row) {
+                *g = *y * (*g - dot);
+            }
+        }
+        in_grad
+    }
+
+    fn back_params(&self, _: &Matrix<T>, _: &Matrix<T>, _: &Matrix<T>, _: MatrixSlice<T>) -> Matrix<T> {
+        Matrix::new(0, 0, Vec::new())
+    }
+
+    fn default_params(&self) -> Vec<T> {
+        Vec::new()
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn name(&self) -> String {
+        "Softmax".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer<T> + Sync)> {
+        Some(self)
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some("Softmax".to_owned())
+    }
+}
+
+/// Batch normalization layer
+///
+/// During training each feature is normalized using the mean and variance
+/// of the current batch, before applying a learned scale (`gamma`) and
+/// shift (`beta`). Running averages of the batch statistics are kept and
+/// used in place of the batch statistics when predicting.
+///
+/// The parameters are a 2 x N matrix where N is the number of features.
+/// The first row holds `gamma` and the second holds `beta`.
+///
+/// Note that a batch containing a single row has zero variance and so
+/// is normalized to zero - this layer is best used with batch training.
+#[derive(Debug)]
+pub struct BatchNorm {
+    /// The number of features being normalized
+    size: usize,
+    /// How much of the previous running statistics are kept after each batch
+    momentum: f64,
+    /// Small value added to the variance to avoid dividing by zero
+    epsilon: f64,
+    /// The running mean and variance of each feature
+    running_stats: Mutex<(Vector<f64>, Vector<f64>)>,
+}
+
+impl BatchNorm {
+    /// Construct a new BatchNorm layer
+    ///
+    /// Uses a momentum of 0.9 and epsilon of 1e-5.
+    pub fn new(size: usize) -> BatchNorm {
+        BatchNorm::with_momentum(size, 0.9, 1e-5)
+    }
+
+    /// Construct a new BatchNorm layer with the given momentum and epsilon
+    ///
+    /// The momentum controls how much of the running statistics are kept
+    /// after each training batch and must lie in `[0, 1)`.
+    pub fn with_momentum(size: usize, momentum: f64, epsilon: f64) -> BatchNorm {
+        assert!(0f64 <= momentum && momentum < 1f64, "The momentum must be in [0, 1)");
+        assert!(epsilon > 0f64, "Epsilon must be positive");
+
+        BatchNorm {
+            size: size,
+            momentum: momentum,
+            epsilon: epsilon,
+            running_stats: Mutex::new((Vector::zeros(size), Vector::ones(size))),
+        }
+    }
+
+    /// The running mean of each feature used when predicting
+    pub fn running_mean(&self) -> Vector<f64> {
+        self.running_stats.lock().unwrap().0.clone()
+    }
+
+    /// The running variance of each feature used when predicting
+    pub fn running_variance(&self) -> Vector<f64> {
+        self.running_stats.lock().unwrap().1.clone()
+    }
+
+    /// Computes the mean and (biased) variance of each column of the input
+    fn batch_stats(input: &Matrix<f64>) -> (Vec<f64>, Vec<f64>) {
+        let n = input.rows() as f64;
+        let mut mean = vec![0f64; input.cols()];
+        let mut var = vec![0f64; input.cols()];
+
+        for row in input.row_iter() {
+            for (m, x) in mean.iter_mut().zip(row.raw_slice()) {
+                *m += *x / n;
+            }
+        }
+        for row in input.row_iter() {
+            for ((v, m), x) in var.iter_mut().zip(&mean).zip(row.raw_slice()) {
+                *v += (*x - *m) * (*x - *m) / n;
+            }
+        }
+        (mean, var)
+    }
+
+    /// Normalizes the input using the given per column mean and variance
+    fn normalize(&self, input: &Matrix<f64>, mean: &[f64], var: &[f64]) -> Matrix<f64> {
+        let cols = input.cols();
+        let data: Vec<f64> = input.data().iter().enumerate().map(|(i, x)| {
+            let j = i % cols;
+            (*x - mean[j]) / (var[j] + self.epsilon).sqrt()
+        }).collect();
+        Matrix::new(input.rows(), cols, data)
+    }
+
+    /// Applies the scale and shift parameters to the normalized input
+    fn scale_shift(normalized: Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        let cols = normalized.cols();
+        let gamma = params.row(0).raw_slice();
+        let beta = params.row(1).raw_slice();
+        let rows = normalized.rows();
+        let data: Vec<f64> = normalized.into_vec().into_iter().enumerate().map(|(i, x)| {
+            let j = i % cols;
+            gamma[j] * x + beta[j]
+        }).collect();
+        Matrix::new(rows, cols, data)
+    }
+
+    fn check_input(&self, input: &Matrix<f64>) -> LearningResult<()> {
+        if input.cols() != self.size {
+            Err(Error::new(ErrorKind::InvalidData, "The input had the wrong number of columns"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl NetLayer for BatchNorm {
+    /// Normalizes the input using the running statistics
+    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        try!(self.check_input(input));
+        let stats = self.running_stats.lock().unwrap();
+        let normalized = self.normalize(input, stats.0.data(), stats.1.data());
+        Ok(BatchNorm::scale_shift(normalized, params))
+    }
+
+    /// Normalizes the input using the batch statistics and updates the running statistics
+    fn forward_train(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        try!(self.check_input(input));
+        let (mean, var) = BatchNorm::batch_stats(input);
+
+        {
+            let mut stats = self.running_stats.lock().unwrap();
+            let momentum = self.momentum;
+            for (r, m) in stats.0.mut_data().iter_mut().zip(&mean) {
+                *r = momentum * *r + (1f64 - momentum) * m;
+            }
+            for (r, v) in stats.1.mut_data().iter_mut().zip(&var) {
+                *r = momentum * *r + (1f64 - momentum) * v;
+            }
+        }
+
+        let normalized = self.normalize(input, &mean, &var);
+        Ok(BatchNorm::scale_shift(normalized, params))
+    }
+
+    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        let (mean, var) = BatchNorm::batch_stats(input);
+        let normalized = self.normalize(input, &mean, &var);
+        let gamma = params.row(0).raw_slice();
+        let n = input.rows() as f64;
+        let cols = input.cols();
+
+        // Per column sums of the gradient and of the gradient times the normalized input
+        let mut grad_sum = vec![0f64; cols];
+        let mut grad_norm_sum = vec![0f64; cols];
+        for (i, (g, x)) in out_grad.data().iter().zip(normalized.data()).enumerate() {
+            grad_sum[i % cols] += *g;
+            grad_norm_sum[i % cols] += *g * *x;
+        }
+
+        let data: Vec<f64> = out_grad.data().iter().zip(normalized.data()).enumerate().map(|(i, (g, x))| {
+            let j = i % cols;
+            gamma[j] / (var[j] + self.epsilon).sqrt() *
+                (*g - grad_sum[j] / n - *x * grad_norm_sum[j] / n)
+        }).collect();
+        Matrix::new(input.rows(), cols, data)
+    }
+
+    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        let (mean, var) = BatchNorm::batch_stats(input);
+        let normalized = self.normalize(input, &mean, &var);
+        let cols = input.cols();
+
+        let mut grads = vec![0f64; 2 * cols];
+        for (i, (g, x)) in out_grad.data().iter().zip(normalized.data()).enumerate() {
+            grads[i % cols] += *g * *x;
+            grads[cols + i % cols] += *g;
+        }
+        Matrix::new(2, cols, grads)
+    }
+
+    /// Initializes the scale to one and the shift to zero
+    fn default_params(&self) -> Vec<f64> {
+        let mut params = vec![1f64; self.size];
+        params.extend(vec![0f64; self.size]);
+        params
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (2, self.size)
+    }
+
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        if input_size == self.size { Some(input_size) } else { None }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.size)
+    }
+
+    fn name(&self) -> String {
+        "BatchNorm".to_owned()
+    }
+
+    /// Includes the running statistics used when predicting
+    fn spec(&self) -> Option<String> {
+        let stats = self.running_stats.lock().unwrap();
+        let mut spec = format!("BatchNorm {} {} {}", self.size, self.momentum, self.epsilon);
+        for x in stats.0.iter().chain(stats.1.iter()) {
+            spec.push_str(&format!(" {}", x));
+        }
+        Some(spec)
+    }
+
+    /// The shift `beta` acts as a bias
+    fn bias_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; self.size];
+        mask.extend(vec![true; self.size]);
+        mask
+    }
+}
+
+impl CloneLayer for BatchNorm {
+    /// The running statistics of the new layer are reset
+    fn clone_box(&self) -> Box<NetLayer> {
+        Box::new(BatchNorm::with_momentum(self.size, self.momentum, self.epsilon))
+    }
+}
+
+/// Dropout layer
+///
+/// During training each input is set to zero with probability
+/// `1 - keep_prob`, and the kept inputs are scaled by `1 / keep_prob`.
+/// The scaling keeps the expected activations the same, so when
+/// predicting the layer is the identity.
+///
+/// The mask drawn in the last training pass is used for the gradient, so
+/// the gradient must be for the same batch. Before any training pass the
+/// gradient is passed through unchanged.
+///
+/// This layer has no parameters.
+#[derive(Debug)]
+pub struct Dropout {
+    /// The probability of keeping each input
+    keep_prob: f64,
+    /// The scale of each input in the last training pass, zero if dropped
+    mask: Mutex<Matrix<f64>>,
+}
+
+impl Dropout {
+    /// Construct a new Dropout layer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::net_layer::Dropout;
+    ///
+    /// // Drop half of the inputs while training
+    /// let layer = Dropout::new(0.5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - The keep probability is not in `(0, 1]`.
+    pub fn new(keep_prob: f64) -> Dropout {
+        assert!(0f64 < keep_prob && keep_prob <= 1f64,
+                "The keep probability must be in (0, 1]");
+
+        Dropout {
+            keep_prob: keep_prob,
+            mask: Mutex::new(Matrix::new(0, 0, Vec::new())),
+        }
+    }
+}
+
+impl NetLayer for Dropout {
+    /// The identity
+    fn forward(&self, input: &Matrix<f64>, _: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        Ok(input.clone())
+    }
+
+    /// Drops each input at random, scaling the rest
+    fn forward_train(&self, input: &Matrix<f64>, _: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        let mut rng = thread_rng();
+        let mut distro = Range::new(0f64, 1f64);
+        let scale = 1f64 / self.keep_prob;
+        let mask = (0..input.rows() * input.cols())
+            .map(|_| if distro.sample(&mut rng) < self.keep_prob { scale } else { 0f64 })
+            .collect::<Vec<_>>();
+        let mask = Matrix::new(input.rows(), input.cols(), mask);
+
+        let output = input.elemul(&mask);
+        *self.mask.lock().unwrap() = mask;
+        Ok(output)
+    }
+
+    /// Routes the gradient through the inputs kept in the last training pass
+    ///
+    /// Without a previous training pass this is the identity. Panics if
+    /// the gradient does not have the shape of the last training batch.
+    fn back_input(&self, out_grad: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        let mask = self.mask.lock().unwrap();
+        if mask.rows() == 0 {
+            return out_grad.clone();
+        }
+
+        assert!(mask.rows() == out_grad.rows() && mask.cols() == out_grad.cols(),
+                "The gradient does not match the shape of the last training pass.");
+        out_grad.elemul(&mask)
+    }
+
+    fn back_params(&self, _: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        Matrix::new(0, 0, Vec::new())
+    }
+
+    fn default_params(&self) -> Vec<f64> {
+        Vec::new()
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn name(&self) -> String {
+        "Dropout".to_owned()
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("Dropout {}", self.keep_prob))
+    }
+}
+
+impl CloneLayer for Dropout {
+    fn clone_box(&self) -> Box<NetLayer> {
+        Box::new(Dropout::new(self.keep_prob))
+    }
+}
+
+/// Embedding lookup layer
+///
+/// Maps categorical inputs, given as integer indices stored in `f64`,
+/// to dense learned vectors. Each input value is rounded to the nearest
+/// integer and must lie in `[0, vocab_size)`.
+///
+/// An input of size N x K produces an output of size N x (K * D), where D
+/// is the embedding dimension, by concatenating the embeddings of each index.
+///
+/// The parameters are a V x D matrix where V is the vocabulary size.
+/// Row `i` holds the embedding of index `i`.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    /// The number of distinct indices
+    vocab_size: usize,
+    /// The dimension of each embedding
+    embed_dim: usize,
+}
+
+impl Embedding {
+    /// Construct a new Embedding layer
+    pub fn new(vocab_size: usize, embed_dim: usize) -> Embedding {
+        Embedding {
+            vocab_size: vocab_size,
+            embed_dim: embed_dim,
+        }
+    }
+
+    /// Converts an input value to an index into the embedding table
+    fn index(&self, x: f64) -> Option<usize> {
+        let idx = x.round();
+        if idx >= 0f64 && idx < self.vocab_size as f64 {
+            Some(idx as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl NetLayer for Embedding {
+    /// Gathers the embedding rows for each input index
+    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        let mut data = Vec::with_capacity(input.rows() * input.cols() * self.embed_dim);
+        for x in input.iter() {
+            match self.index(*x) {
+                Some(idx) => data.extend_from_slice(params.row(idx).raw_slice()),
+                None => {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          "Embedding index is not within the vocabulary"))
+                }
+            }
+        }
+        Ok(Matrix::new(input.rows(), input.cols() * self.embed_dim, data))
+    }
+
+    /// The indices are not differentiable, so the input gradient is zero
+    fn back_input(&self, _: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        Matrix::zeros(input.rows(), input.cols())
+    }
+
+    /// Scatter-adds the output gradient into the rows which were looked up
+    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        debug_assert_eq!(out_grad.cols(), input.cols() * self.embed_dim);
+        let mut grad = Matrix::zeros(self.vocab_size, self.embed_dim);
+
+        for (in_row, grad_row) in input.row_iter().zip(out_grad.row_iter()) {
+            for (k, x) in in_row.iter().enumerate() {
+                let idx = self.index(*x).expect("Embedding index was validated in forward");
+                let incoming = &grad_row.raw_slice()[k * self.embed_dim..(k + 1) * self.embed_dim];
+                for (g, d) in grad.row_mut(idx).raw_slice_mut().iter_mut().zip(incoming) {
+                    *g += *d;
+                }
+            }
+        }
+        grad
+    }
+
+    /// Initializes the embeddings from a standard normal distribution
+    fn default_params(&self) -> Vec<f64> {
+        let mut distro = Normal::new(0.0, 1.0);
+        let mut rng = thread_rng();
+
+        (0..self.vocab_size * self.embed_dim).map(|_| distro.sample(&mut rng))
+                                             .collect()
+    }
+
+    fn init_params(&self, init: WeightInit) -> Vec<f64> {
+        init.sample((self.vocab_size, self.embed_dim), &self.bias_mask())
+            .unwrap_or_else(|| self.default_params())
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (self.vocab_size, self.embed_dim)
+    }
+
+    /// Each input column is replaced by its embedding
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        Some(input_size * self.embed_dim)
+    }
+
+    fn name(&self) -> String {
+        "Embedding".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer + Sync)> {
+        Some(self)
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("Embedding {} {}", self.vocab_size, self.embed_dim))
+    }
+}
+
+/// 2D convolutional layer
+///
+/// Applies a bank of learned filters to a batch of images using
+/// cross-correlation. Each input row holds one sample's images flattened
+/// in channel, row, column order, so has `channels * height * width` columns.
+/// The output rows hold one feature map per filter, flattened in the same order.
+///
+/// The parameters are a (C * K * K + 1) x F matrix where C is the number of
+/// input channels, K the kernel size and F the number of filters. As with
+/// `Linear` the first row holds the bias of each filter. Row `1 + (c * K + i) * K + j`
+/// holds the kernel weight at channel `c`, kernel row `i` and kernel column `j`.
+#[derive(Debug, Clone)]
+pub struct Conv2d {
+    /// The number of input channels
+    in_channels: usize,
+    /// The number of filters, and so output channels
+    out_channels: usize,
+    /// The height of each input image
+    height: usize,
+    /// The width of each input image
+    width: usize,
+    /// The height and width of each filter
+    kernel_size: usize,
+    /// The distance between neighbouring filter positions
+    stride: usize,
+    /// The number of zeros added to each side of the images
+    padding: usize,
+}
+
+impl Conv2d {
+    /// Construct a new Conv2d layer
+    ///
+    /// The input images have shape `(in_channels, height, width)`.
+    /// Filter positions which do not fit entirely inside the padded
+    /// image are dropped.
+    pub fn new(input_shape: (usize, usize, usize),
+               out_channels: usize,
+               kernel_size: usize,
+               stride: usize,
+               padding: usize)
+               -> Conv2d {
+        let (in_channels, height, width) = input_shape;
+        assert!(kernel_size > 0, "The kernel size must be positive");
+        assert!(stride > 0, "The stride must be positive");
+        assert!(kernel_size <= height + 2 * padding && kernel_size <= width + 2 * padding,
+                "The kernel must fit inside the padded image");
+
+        Conv2d {
+            in_channels: in_channels,
+            out_channels: out_channels,
+            height: height,
+            width: width,
+            kernel_size: kernel_size,
+            stride: stride,
+            padding: padding,
+        }
+    }
+
+    /// The shape `(channels, height, width)` of the output feature maps
+    pub fn output_shape(&self) -> (usize, usize, usize) {
+        (self.out_channels,
+         (self.height + 2 * self.padding - self.kernel_size) / self.stride + 1,
+         (self.width + 2 * self.padding - self.kernel_size) / self.stride + 1)
+    }
+
+    /// The number of values in each column of the unrolled input
+    fn patch_size(&self) -> usize {
+        self.in_channels * self.kernel_size * self.kernel_size
+    }
+
+    /// Calls `f(patch_idx, input_idx)` for each in-bounds input pixel
+    /// covered by the filter at output position `(oh, ow)`.
+    fn for_each_patch_pixel<F: FnMut(usize, usize)>(&self, oh: usize, ow: usize, mut f: F) {
+        let k = self.kernel_size;
+        for c in 0..self.in_channels {
+            for i in 0..k {
+                // Position in the unpadded image
+                let ih = oh * self.stride + i;
+                if ih < self.padding || ih - self.padding >= self.height {
+                    continue;
+                }
+                let ih = ih - self.padding;
+                for j in 0..k {
+                    let iw = ow * self.stride + j;
+                    if iw < self.padding || iw - self.padding >= self.width {
+                        continue;
+                    }
+                    let iw = iw - self.padding;
+                    f((c * k + i) * k + j, (c * self.height + ih) * self.width + iw);
+                }
+            }
+        }
+    }
+
+    /// Unrolls the input (im2col) into a matrix with a leading column of ones
+    ///
+    /// Row `n * P + p` holds the patch of sample `n` at output position `p`,
+    /// where P is the number of output positions.
+    fn unroll(&self, input: &Matrix<f64>) -> Matrix<f64> {
+        let (_, out_h, out_w) = self.output_shape();
+        let cols = self.patch_size() + 1;
+        let mut data = vec![0f64; input.rows() * out_h * out_w * cols];
+
+        let mut row_start = 0;
+        for row in input.row_iter() {
+            let row = row.raw_slice();
+            for oh in 0..out_h {
+                for ow in 0..out_w {
+                    data[row_start] = 1f64;
+                    self.for_each_patch_pixel(oh, ow, |patch_idx, input_idx| {
+                        data[row_start + 1 + patch_idx] = row[input_idx];
+                    });
+                    row_start += cols;
+                }
+            }
+        }
+        Matrix::new(input.rows() * out_h * out_w, cols, data)
+    }
+
+    /// Reshapes the N x (F * P) output gradient to (N * P) x F
+    fn positions_by_filter(&self, out_grad: &Matrix<f64>) -> Matrix<f64> {
+        let (filters, out_h, out_w) = self.output_shape();
+        let positions = out_h * out_w;
+        Matrix::from_fn(out_grad.rows() * positions, filters, |f, r| {
+            out_grad[[r / positions, f * positions + r % positions]]
+        })
+    }
+}
+
+impl NetLayer for Conv2d {
+    /// Computes the cross-correlation of the inputs with each filter
+    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        if input.cols() != self.in_channels * self.height * self.width {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The input had the wrong number of columns"));
+        }
+        if params.rows() != self.patch_size() + 1 || params.cols() != self.out_channels {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The parameters had the wrong shape"));
+        }
+
+        // (N * P) x F
+        let by_position = &self.unroll(input) * &params;
+
+        let (filters, out_h, out_w) = self.output_shape();
+        let positions = out_h * out_w;
+        Ok(Matrix::from_fn(input.rows(), filters * positions, |c, r| {
+            by_position[[r * positions + c % positions, c / positions]]
+        }))
+    }
+
+    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        // Gradient with respect to each unrolled patch, including the bias column
+        let patch_grad = &self.positions_by_filter(out_grad) * &params.transpose();
+
+        // Scatter the patch gradients back to the input pixels (col2im)
+        let (_, out_h, out_w) = self.output_shape();
+        let mut in_grad = Matrix::zeros(input.rows(), input.cols());
+        let mut patch_row = 0;
+        for mut row in in_grad.row_iter_mut() {
+            let row = row.raw_slice_mut();
+            for oh in 0..out_h {
+                for ow in 0..out_w {
+                    let grad = patch_grad.row(patch_row).raw_slice();
+                    self.for_each_patch_pixel(oh, ow, |patch_idx, input_idx| {
+                        row[input_idx] += grad[1 + patch_idx];
+                    });
+                    patch_row += 1;
+                }
+            }
+        }
+        in_grad
+    }
+
+    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        debug_assert_eq!(input.rows(), out_grad.rows());
+        &self.unroll(input).transpose() * &self.positions_by_filter(out_grad)
+    }
+
+    /// Initializes weights using Xavier initialization
+    ///
+    /// weights drawn from gaussian distribution with 0 mean and variance
+    /// 2/(fan_in+fan_out) where the fans count the kernel entries per filter
+    fn default_params(&self) -> Vec<f64> {
+        let kernel_area = self.kernel_size * self.kernel_size;
+        let fans = (self.in_channels + self.out_channels) * kernel_area;
+        let mut distro = Normal::new(0.0, (2.0 / fans as f64).sqrt());
+        let mut rng = thread_rng();
+
+        (0..(self.patch_size() + 1) * self.out_channels).map(|_| distro.sample(&mut rng))
+                                                         .collect()
+    }
+
+    /// Each output unit is connected to a patch of every input channel
+    fn init_params(&self, init: WeightInit) -> Vec<f64> {
+        let window = self.kernel_size * self.kernel_size;
+        init.sample((self.patch_size(), self.out_channels * window), &self.bias_mask())
+            .unwrap_or_else(|| self.default_params())
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (self.patch_size() + 1, self.out_channels)
+    }
+
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        if input_size == self.in_channels * self.height * self.width {
+            let (channels, height, width) = self.output_shape();
+            Some(channels * height * width)
+        } else {
+            None
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.in_channels * self.height * self.width)
+    }
+
+    fn bias_mask(&self) -> Vec<bool> {
+        first_row_bias(self.param_shape())
+    }
+
+    fn name(&self) -> String {
+        "Conv2d".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer + Sync)> {
+        Some(self)
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("Conv2d {} {} {} {} {} {} {}",
+                     self.in_channels,
+                     self.height,
+                     self.width,
+                     self.out_channels,
+                     self.kernel_size,
+                     self.stride,
+                     self.padding))
+    }
+}
+
+/// 2D max pooling layer
+///
+/// Takes the maximum over square windows of each channel of a batch of
+/// feature maps. Each input row holds one sample's feature maps flattened
+/// in channel, row, column order, so has `channels * height * width` columns.
+/// The output rows are flattened in the same order.
+///
+/// The gradient is routed only to the position of each window's maximum.
+/// When several positions tie for the maximum, the first one in row-major
+/// order within the window receives the gradient.
+///
+/// This layer has no parameters.
+#[derive(Debug, Clone)]
+pub struct MaxPool2d {
+    /// The number of channels in each sample
+    channels: usize,
+    /// The height of each input feature map
+    height: usize,
+    /// The width of each input feature map
+    width: usize,
+    /// The height and width of the pooling window
+    kernel_size: usize,
+    /// The distance between neighbouring windows
+    stride: usize,
+}
+
+impl MaxPool2d {
+    /// Construct a new MaxPool2d layer
+    ///
+    /// The input feature maps have shape `(channels, height, width)`.
+    /// Windows of size `kernel_size` which do not fit entirely inside
+    /// the feature map are dropped.
+    pub fn new(channels: usize,
+               height: usize,
+               width: usize,
+               kernel_size: usize,
+               stride: usize)
+               -> MaxPool2d {
+        assert!(kernel_size > 0, "The kernel size must be positive");
+        assert!(stride > 0, "The stride must be positive");
+        assert!(kernel_size <= height && kernel_size <= width,
+                "The kernel must fit inside the feature map");
+
+        MaxPool2d {
+            channels: channels,
+            height: height,
+            width: width,
+            kernel_size: kernel_size,
+            stride: stride,
+        }
+    }
+
+    /// The shape `(channels, height, width)` of the output feature maps
+    pub fn output_shape(&self) -> (usize, usize, usize) {
+        (self.channels,
+         (self.height - self.kernel_size) / self.stride + 1,
+         (self.width - self.kernel_size) / self.stride + 1)
+    }
+
+    /// Finds the index within the input row of each window's maximum
+    fn argmax_windows(&self, row: &[f64]) -> Vec<usize> {
+        let (channels, out_h, out_w) = self.output_shape();
+        let mut indices = Vec::with_capacity(channels * out_h * out_w);
+
+        for c in 0..channels {
+            let channel_start = c * self.height * self.width;
+            for i in 0..out_h {
+                for j in 0..out_w {
+                    let mut best = channel_start + i * self.stride * self.width + j * self.stride;
+                    for di in 0..self.kernel_size {
+                        for dj in 0..self.kernel_size {
+                            let idx = channel_start + (i * self.stride + di) * self.width +
+                                      j * self.stride + dj;
+                            if row[idx] > row[best] {
+                                best = idx;
+                            }
+                        }
+                    }
+                    indices.push(best);
+                }
+            }
+        }
+        indices
+    }
+}
+
+impl NetLayer for MaxPool2d {
+    /// Takes the maximum over each window
+    fn forward(&self, input: &Matrix<f64>, _: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        if input.cols() != self.channels * self.height * self.width {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The input had the wrong number of columns"));
+        }
+
+        let (channels, out_h, out_w) = self.output_shape();
+        let mut output = Vec::with_capacity(input.rows() * channels * out_h * out_w);
+        for row in input.row_iter() {
+            let row = row.raw_slice();
+            output.extend(self.argmax_windows(row).into_iter().map(|idx| row[idx]));
+        }
+        Ok(Matrix::new(input.rows(), channels * out_h * out_w, output))
+    }
+
+    /// Routes each output gradient to the maximum of its window
+    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        let mut in_grad = Matrix::zeros(input.rows(), input.cols());
+
+        for ((in_row, grad_row), mut in_grad_row) in input.row_iter()
+                                                           .zip(out_grad.row_iter())
+                                                           .zip(in_grad.row_iter_mut()) {
+            let in_grad_row = in_grad_row.raw_slice_mut();
+            for (idx, g) in self.argmax_windows(in_row.raw_slice()).into_iter().zip(grad_row.raw_slice()) {
+                // Overlapping windows may share a maximum
+                in_grad_row[idx] += *g;
+            }
+        }
+        in_grad
+    }
+
+    fn back_params(&self, _: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+        Matrix::new(0, 0, Vec::new())
+    }
+
+    fn default_params(&self) -> Vec<f64> {
+        Vec::new()
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        if input_size == self.channels * self.height * self.width {
+            let (channels, height, width) = self.output_shape();
+            Some(channels * height * width)
+        } else {
+            None
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.channels * self.height * self.width)
+    }
+
+    fn name(&self) -> String {
+        "MaxPool2d".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer + Sync)> {
+        Some(self)
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("MaxPool2d {} {} {} {} {}",
+                     self.channels,
+                     self.height,
+                     self.width,
+                     self.kernel_size,
+                     self.stride))
+    }
+}
+
+/// Residual connection layer
+///
+/// Wraps an inner layer and adds the input to the inner layer's output,
+/// giving `x + f(x)`. The inner layer must produce an output with the same
+/// shape as its input.
+///
+/// The parameters are those of the inner layer. On the backward pass the
+/// gradient through the inner layer is summed with the identity gradient
+/// of the skip connection.
+#[derive(Debug)]
+pub struct Residual {
+    /// The layer on the non-identity path
+    inner: Box<NetLayer>,
+}
+
+impl Residual {
+    /// Construct a new Residual layer around the given layer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::net_layer::{Residual, Linear};
+    ///
+    /// // Computes x + W·x for inputs with 4 columns
+    /// let layer = Residual::new(Box::new(Linear::new(4, 4)));
+    /// ```
+    pub fn new(inner: Box<NetLayer>) -> Residual {
+        Residual { inner: inner }
+    }
+
+    /// Adds the skip connection to the inner layer's output
+    fn add_input(input: &Matrix<f64>, inner_output: Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        if inner_output.rows() != input.rows() || inner_output.cols() != input.cols() {
+            Err(Error::new(ErrorKind::InvalidData,
+                           "The inner layer of a residual must preserve the input shape"))
+        } else {
+            Ok(inner_output + input)
+        }
+    }
+}
+
+impl NetLayer for Residual {
+    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        let inner_output = try!(self.inner.forward(input, params));
+        Residual::add_input(input, inner_output)
+    }
+
+    fn forward_train(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        let inner_output = try!(self.inner.forward_train(input, params));
+        Residual::add_input(input, inner_output)
+    }
+
+    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, output: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        // Recover the inner layer's output by removing the skip connection
+        let inner_output = output - input;
+        self.inner.back_input(out_grad, input, &inner_output, params) + out_grad
+    }
+
+    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, output: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        let inner_output = output - input;
+        self.inner.back_params(out_grad, input, &inner_output, params)
+    }
+
+    fn default_params(&self) -> Vec<f64> {
+        self.inner.default_params()
+    }
+
+    fn init_params(&self, init: WeightInit) -> Vec<f64> {
+        self.inner.init_params(init)
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        self.inner.param_shape()
+    }
+
+    /// The inner layer must preserve the size of its input
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        match self.inner.output_size(input_size) {
+            Some(size) if size == input_size => Some(size),
+            _ => None,
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        self.inner.input_size()
+    }
+
+    fn bias_mask(&self) -> Vec<bool> {
+        self.inner.bias_mask()
+    }
+
+    fn name(&self) -> String {
+        "Residual".to_owned()
+    }
+
+    /// The inner layer need not be thread safe, so residual layers are
+    /// always computed serially
+    fn as_independent(&self) -> Option<&(NetLayer + Sync)> {
+        None
+    }
+
+    fn spec(&self) -> Option<String> {
+        self.inner.spec().map(|inner| format!("Residual {}", inner))
+    }
+}
+
+impl CloneLayer for Residual {
+    fn clone_box(&self) -> Box<NetLayer> {
+        Box::new(Residual::new(self.inner.clone_box()))
+    }
+}
+
+/// Joins the hidden states of each timestep side by side
+fn join_states(rows: usize, states: Vec<Matrix<f64>>) -> Matrix<f64> {
+    states.iter().fold(Matrix::new(rows, 0, vec![]), |acc, state| acc.hcat(state))
+}
+
+/// Simple recurrent (Elman) layer
+///
+/// Processes a batch of sequences, carrying a hidden state of size H
+/// from one timestep to the next. The inputs and outputs are laid out
+/// as for `GRU`: each input row holds T timesteps of F features
+/// flattened in time order, and the hidden state starts at zero.
+///
+/// At each timestep with input `x` and previous hidden state `h` the
+/// new hidden state is `h' = tanh(x·W + h·U + b)`.
+///
+/// The output is the final hidden state, of size N x H. With
+/// `with_sequence_output` it is instead the hidden state of every
+/// timestep, of size N x (T * H).
+///
+/// The parameters are a (1 + F + H) x H matrix. The first row holds
+/// the biases, followed by the F rows of `W` and the H rows of `U`.
+///
+/// The gradients are computed by backpropagation through time.
+#[derive(Debug, Clone)]
+pub struct RNN {
+    /// The number of features at each timestep
+    input_size: usize,
+    /// The size of the hidden state
+    hidden_size: usize,
+    /// The number of timesteps in each sequence
+    timesteps: usize,
+    /// Whether to output the hidden state of every timestep
+    sequence_output: bool,
+}
+
+impl RNN {
+    /// Construct a new RNN layer
+    ///
+    /// The layer takes sequences of `timesteps` inputs, each with
+    /// `input_size` features, and has a hidden state of `hidden_size`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::net_layer::{NetLayer, RNN};
+    ///
+    /// // Sequences of 5 timesteps with 3 features each
+    /// let layer = RNN::new(3, 8, 5);
+    /// assert_eq!(layer.output_size(15), Some(8));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - Any of the sizes is zero.
+    pub fn new(input_size: usize, hidden_size: usize, timesteps: usize) -> RNN {
+        assert!(input_size > 0, "The input size must be positive");
+        assert!(hidden_size > 0, "The hidden size must be positive");
+        assert!(timesteps > 0, "There must be at least one timestep");
+
+        RNN {
+            input_size: input_size,
+            hidden_size: hidden_size,
+            timesteps: timesteps,
+            sequence_output: false,
+        }
+    }
+
+    /// Output the hidden state of every timestep instead of only the last
+    ///
+    /// This allows recurrent layers to be stacked.
+    pub fn with_sequence_output(mut self) -> RNN {
+        self.sequence_output = true;
+        self
+    }
+
+    /// Runs the sequences through the layer
+    ///
+    /// Returns the input `[1 | x | h]` of each timestep and the hidden
+    /// state after it.
+    fn steps(&self, input: &Matrix<f64>, params: &MatrixSlice<f64>) -> Vec<(Matrix<f64>, Matrix<f64>)> {
+        let (f, h) = (self.input_size, self.hidden_size);
+        let rows = input.rows();
+
+        let mut steps: Vec<(Matrix<f64>, Matrix<f64>)> = Vec::with_capacity(self.timesteps);
+        for t in 0..self.timesteps {
+            let step_input = {
+                let bias_input = Matrix::ones(rows, 1).hcat(&input.sub_slice([0, t * f], rows, f));
+                match steps.last() {
+                    Some(&(_, ref hidden)) => bias_input.hcat(hidden),
+                    None => bias_input.hcat(&Matrix::zeros(rows, h)),
+                }
+            };
+            let hidden = (&step_input * params).apply(&Tanh::func);
+            steps.push((step_input, hidden));
+        }
+        steps
+    }
+
+    /// The gradients with respect to the input and the parameters
+    fn backward(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, params: &MatrixSlice<f64>) -> (Matrix<f64>, Matrix<f64>) {
+        let (f, h) = (self.input_size, self.hidden_size);
+        let rows = input.rows();
+
+        let mut in_grad = Matrix::zeros(rows, input.cols());
+        let mut params_grad = Matrix::zeros(params.rows(), h);
+        // The gradient with respect to the hidden state of the current timestep
+        let mut hidden_grad = Matrix::zeros(rows, h);
+
+        for (t, (step_input, hidden)) in self.steps(input, params).into_iter().enumerate().rev() {
+            if self.sequence_output {
+                hidden_grad += out_grad.sub_slice([0, t * h], rows, h);
+            } else if t + 1 == self.timesteps {
+                hidden_grad += out_grad;
+            }
+
+            let pre_grad = hidden_grad.elemul(&hidden.apply(&|y| 1f64 - y * y));
+            params_grad += &step_input.transpose() * &pre_grad;
+            let step_input_grad = &pre_grad * &params.transpose();
+
+            for (mut in_row, grad_row) in in_grad.row_iter_mut().zip(step_input_grad.row_iter()) {
+                in_row.raw_slice_mut()[t * f..(t + 1) * f]
+                    .copy_from_slice(&grad_row.raw_slice()[1..1 + f]);
+            }
+            hidden_grad = step_input_grad.sub_slice([0, 1 + f], rows, h).into_matrix();
+        }
+        (in_grad, params_grad)
+    }
+}
+
+impl NetLayer for RNN {
+    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        if input.cols() != self.timesteps * self.input_size {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The input had the wrong number of columns"));
+        }
+        if params.rows() != 1 + self.input_size + self.hidden_size ||
+           params.cols() != self.hidden_size {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The parameters had the wrong shape"));
+        }
+
+        let mut states = self.steps(input, &params).into_iter().map(|(_, h)| h).collect::<Vec<_>>();
+        if self.sequence_output {
+            Ok(join_states(input.rows(), states))
+        } else {
+            Ok(states.pop().unwrap())
+        }
+    }
+
+    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        self.backward(out_grad, input, &params).0
+    }
+
+    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        self.backward(out_grad, input, &params).1
+    }
+
+    /// Initializes weights using Xavier initialization
+    ///
+    /// weights drawn from gaussian distribution with 0 mean and variance
+    /// 2/(fan_in+fan_out) where the input comes from the features and
+    /// the hidden state
+    fn default_params(&self) -> Vec<f64> {
+        let fans = self.input_size + 2 * self.hidden_size;
+        let mut distro = Normal::new(0.0, (2.0 / fans as f64).sqrt());
+        let mut rng = thread_rng();
+
+        (0..self.num_params()).map(|_| distro.sample(&mut rng)).collect()
+    }
+
+    fn init_params(&self, init: WeightInit) -> Vec<f64> {
+        init.sample((self.input_size + self.hidden_size, self.hidden_size), &self.bias_mask())
+            .unwrap_or_else(|| self.default_params())
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (1 + self.input_size + self.hidden_size, self.hidden_size)
+    }
+
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        if input_size != self.timesteps * self.input_size {
+            None
+        } else if self.sequence_output {
+            Some(self.timesteps * self.hidden_size)
+        } else {
+            Some(self.hidden_size)
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.timesteps * self.input_size)
+    }
+
+    fn bias_mask(&self) -> Vec<bool> {
+        first_row_bias(self.param_shape())
+    }
+
+    fn name(&self) -> String {
+        "RNN".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer + Sync)> {
+        Some(self)
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("RNN {} {} {} {}",
+                     self.input_size,
+                     self.hidden_size,
+                     self.timesteps,
+                     self.sequence_output))
+    }
+}
+
+/// Gated recurrent unit (GRU) layer
+///
+/// Processes a batch of sequences, carrying a hidden state of size H
+/// from one timestep to the next. Each input row holds one sequence of
+/// T timesteps flattened in time order, so has `T * F` columns where F
+/// is the number of features at each timestep. The hidden state starts
+/// at zero.
+///
+/// At each timestep with input `x` and previous hidden state `h`:
+///
+/// - `z = sigmoid(x·Wz + h·Uz + bz)` is the update gate,
+/// - `r = sigmoid(x·Wr + h·Ur + br)` is the reset gate,
+/// - `n = tanh(x·Wn + (r * h)·Un + bn)` is the candidate state,
+/// - `h' = (1 - z) * n + z * h` is the new hidden state.
+///
+/// The output is the final hidden state, of size N x H. With
+/// `with_sequence_output` it is instead the hidden state of every
+/// timestep, of size N x (T * H), flattened in time order.
+///
+/// Compared with the simpler `RNN` layer the gates let gradients flow
+/// across many timesteps without vanishing.
+///
+/// The parameters are a (1 + F + H) x 3H matrix. As with `Linear` the
+/// first row holds the biases, followed by the F rows of input weights
+/// and the H rows of recurrent weights. The columns hold the update,
+/// reset and candidate gates in that order.
+///
+/// The gradients are computed by backpropagation through time, which
+/// recomputes the gates of each timestep from the input.
+#[derive(Debug, Clone)]
+pub struct GRU {
+    /// The number of features at each timestep
+    input_size: usize,
+    /// The size of the hidden state
+    hidden_size: usize,
+    /// The number of timesteps in each sequence
+    timesteps: usize,
+    /// Whether to output the hidden state of every timestep
+    sequence_output: bool,
+}
+
+/// The intermediate values of one GRU timestep
+struct GRUStep {
+    /// The input of the gates, `[1 | x | h]`
+    gate_input: Matrix<f64>,
+    /// The previous hidden state
+    prev: Matrix<f64>,
+    /// The update gate
+    update: Matrix<f64>,
+    /// The reset gate
+    reset: Matrix<f64>,
+    /// The candidate state
+    candidate: Matrix<f64>,
+}
+
+impl GRU {
+    /// Construct a new GRU layer
+    ///
+    /// The layer takes sequences of `timesteps` inputs, each with
+    /// `input_size` features, and has a hidden state of `hidden_size`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::nnet::net_layer::{NetLayer, GRU};
+    ///
+    /// // Sequences of 5 timesteps with 3 features each
+    /// let layer = GRU::new(3, 8, 5);
+    /// assert_eq!(layer.output_size(15), Some(8));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - Any of the sizes is zero.
+    pub fn new(input_size: usize, hidden_size: usize, timesteps: usize) -> GRU {
+        assert!(input_size > 0, "The input size must be positive");
+        assert!(hidden_size > 0, "The hidden size must be positive");
+        assert!(timesteps > 0, "There must be at least one timestep");
+
+        GRU {
+            input_size: input_size,
+            hidden_size: hidden_size,
+            timesteps: timesteps,
+            sequence_output: false,
+        }
+    }
+
+    /// Output the hidden state of every timestep instead of only the last
+    ///
+    /// This allows GRU layers to be stacked.
+    pub fn with_sequence_output(mut self) -> GRU {
+        self.sequence_output = true;
+        self
+    }
+
+    /// Runs the sequences through the layer, keeping the values of each timestep
+    ///
+    /// Returns the steps and the final hidden state.
+    fn steps(&self, input: &Matrix<f64>, params: &MatrixSlice<f64>) -> (Vec<GRUStep>, Matrix<f64>) {
+        let (f, h) = (self.input_size, self.hidden_size);
+        let rows = input.rows();
+        let gates = params.sub_slice([0, 0], params.rows(), 2 * h);
+        let cand_bias_input = params.sub_slice([0, 2 * h], 1 + f, h);
+        let cand_hidden = params.sub_slice([1 + f, 2 * h], h, h);
+
+        let mut steps = Vec::with_capacity(self.timesteps);
+        let mut hidden = Matrix::zeros(rows, h);
+        for t in 0..self.timesteps {
+            let bias_input = Matrix::ones(rows, 1).hcat(&input.sub_slice([0, t * f], rows, f));
+            let gate_input = bias_input.hcat(&hidden);
+
+            let gate_values = (&gate_input * &gates).apply(&Sigmoid::func);
+            let update = gate_values.sub_slice([0, 0], rows, h).into_matrix();
+            let reset = gate_values.sub_slice([0, h], rows, h).into_matrix();
+            let candidate = (&bias_input * &cand_bias_input +
+                             &reset.elemul(&hidden) * &cand_hidden)
+                .apply(&Tanh::func);
+
+            let next = &candidate + update.elemul(&(&hidden - &candidate));
+            steps.push(GRUStep {
+                gate_input: gate_input,
+                prev: mem::replace(&mut hidden, next),
+                update: update,
+                reset: reset,
+                candidate: candidate,
+            });
+        }
+        (steps, hidden)
+    }
+
+    /// The gradients with respect to the input and the parameters
+    fn backward(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, params: &MatrixSlice<f64>) -> (Matrix<f64>, Matrix<f64>) {
+        let (f, h) = (self.input_size, self.hidden_size);
+        let rows = input.rows();
+        let gates = params.sub_slice([0, 0], params.rows(), 2 * h);
+        let cand_input = params.sub_slice([1, 2 * h], f, h);
+        let cand_hidden = params.sub_slice([1 + f, 2 * h], h, h);
+
+        let (steps, _) = self.steps(input, params);
+        let mut in_grad = Matrix::zeros(rows, input.cols());
+        let mut gates_grad = Matrix::zeros(params.rows(), 2 * h);
+        let mut cand_grad = Matrix::zeros(params.rows(), h);
+        // The gradient with respect to the hidden state of the current timestep
+        let mut hidden_grad = Matrix::zeros(rows, h);
+
+        for (t, step) in steps.iter().enumerate().rev() {
+            if self.sequence_output {
+                hidden_grad += out_grad.sub_slice([0, t * h], rows, h);
+            } else if t + 1 == self.timesteps {
+                hidden_grad += out_grad;
+            }
+
+            let update_pre = hidden_grad.elemul(&(&step.prev - &step.candidate))
+                .elemul(&step.update.clone().apply(&|z| z * (1f64 - z)));
+            let cand_pre = hidden_grad.elemul(&step.update.clone().apply(&|z| 1f64 - z))
+                .elemul(&step.candidate.clone().apply(&|n| 1f64 - n * n));
+
+            // The candidate sees the reset previous state
+            let reset_prev = step.reset.elemul(&step.prev);
+            let cand_input_rows = step.gate_input.sub_slice([0, 0], rows, 1 + f).hcat(&reset_prev);
+            cand_grad += &cand_input_rows.transpose() * &cand_pre;
+            let reset_prev_grad = &cand_pre * &cand_hidden.transpose();
+            let reset_pre = reset_prev_grad.elemul(&step.prev)
+                .elemul(&step.reset.clone().apply(&|r| r * (1f64 - r)));
+
+            let gates_pre = update_pre.hcat(&reset_pre);
+            gates_grad += &step.gate_input.transpose() * &gates_pre;
+            let gate_input_grad = &gates_pre * &gates.transpose();
+
+            let x_grad = gate_input_grad.sub_slice([0, 1], rows, f).into_matrix() +
+                         &cand_pre * &cand_input.transpose();
+            for (mut in_row, x_row) in in_grad.row_iter_mut().zip(x_grad.row_iter()) {
+                in_row.raw_slice_mut()[t * f..(t + 1) * f].copy_from_slice(x_row.raw_slice());
+            }
+
+            hidden_grad = hidden_grad.elemul(&step.update) +
+                          reset_prev_grad.elemul(&step.reset) +
+                          gate_input_grad.sub_slice([0, 1 + f], rows, h).into_matrix();
+        }
+        (in_grad, gates_grad.hcat(&cand_grad))
+    }
+}
+
+impl NetLayer for GRU {
+    fn forward(&self, input: &Matrix<f64>, params: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+        if input.cols() != self.timesteps * self.input_size {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The input had the wrong number of columns"));
+        }
+        if params.rows() != 1 + self.input_size + self.hidden_size ||
+           params.cols() != 3 * self.hidden_size {
+            return Err(Error::new(ErrorKind::InvalidParameters,
+                                  "The parameters had the wrong shape"));
+        }
+
+        let (steps, last) = self.steps(input, &params);
+        if !self.sequence_output {
+            return Ok(last);
+        }
+
+        let mut states = steps.into_iter().skip(1).map(|s| s.prev).collect::<Vec<_>>();
+        states.push(last);
+        Ok(join_states(input.rows(), states))
+    }
+
+    fn back_input(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        self.backward(out_grad, input, &params).0
+    }
+
+    fn back_params(&self, out_grad: &Matrix<f64>, input: &Matrix<f64>, _: &Matrix<f64>, params: MatrixSlice<f64>) -> Matrix<f64> {
+        self.backward(out_grad, input, &params).1
+    }
+
+    /// Initializes weights using Xavier initialization
+    ///
+    /// weights drawn from gaussian distribution with 0 mean and variance
+    /// 2/(fan_in+fan_out) where each gate has an input from the features
+    /// and the hidden state
+    fn default_params(&self) -> Vec<f64> {
+        let fans = self.input_size + 2 * self.hidden_size;
+        let mut distro = Normal::new(0.0, (2.0 / fans as f64).sqrt());
+        let mut rng = thread_rng();
+
+        (0..self.num_params()).map(|_| distro.sample(&mut rng)).collect()
+    }
+
+    fn init_params(&self, init: WeightInit) -> Vec<f64> {
+        init.sample((self.input_size + self.hidden_size, self.hidden_size), &self.bias_mask())
+            .unwrap_or_else(|| self.default_params())
+    }
+
+    fn param_shape(&self) -> (usize, usize) {
+        (1 + self.input_size + self.hidden_size, 3 * self.hidden_size)
+    }
+
+    fn output_size(&self, input_size: usize) -> Option<usize> {
+        if input_size != self.timesteps * self.input_size {
+            None
+        } else if self.sequence_output {
+            Some(self.timesteps * self.hidden_size)
+        } else {
+            Some(self.hidden_size)
+        }
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.timesteps * self.input_size)
+    }
+
+    fn bias_mask(&self) -> Vec<bool> {
+        first_row_bias(self.param_shape())
+    }
+
+    fn name(&self) -> String {
+        "GRU".to_owned()
+    }
+
+    fn as_independent(&self) -> Option<&(NetLayer + Sync)> {
+        Some(self)
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("GRU {} {} {} {}",
+                     self.input_size,
+                     self.hidden_size,
+                     self.timesteps,
+                     self.sequence_output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetLayer, CloneLayer, BatchNorm, Dropout, Embedding, MaxPool2d, Conv2d, Residual, Linear,
+                GRU, RNN, Softmax, WeightInit, glorot_bound, layer_from_spec};
+    use learning::toolkit::activ_fn::ActivationFunc;
+    use learning::LearningResult;
+    use learning::optim::Optimizable;
+    use learning::optim::grad_check::grad_check;
+    use linalg::{Matrix, MatrixSlice, BaseMatrix, Axes};
+    use std::ops::Range;
+
+    fn batch() -> Matrix<f64> {
+        Matrix::new(4, 2, vec![1.0, 10.0,
+                               2.0, 30.0,
+                               4.0, 20.0,
+                               5.0, 0.0])
+    }
+
+    /// A weighted sum of the training outputs of a layer
+    ///
+    /// The parameters are the input followed by the layer parameters, so
+    /// `grad_check` checks both `back_input` and `back_params`.
+    struct WeightedOutput<'a, L: 'a> {
+        layer: &'a L,
+        input_shape: (usize, usize),
+        weights: Matrix<f64>,
+    }
+
+    impl<'a, L: NetLayer> WeightedOutput<'a, L> {
+        fn split(&self, params: &[f64]) -> (Matrix<f64>, Matrix<f64>) {
+            let (rows, cols) = self.input_shape;
+            let shape = self.layer.param_shape();
+            (Matrix::new(rows, cols, params[..rows * cols].to_vec()),
+             Matrix::new(shape.0, shape.1, params[rows * cols..].to_vec()))
+        }
+    }
+
+    impl<'a, L: NetLayer> Optimizable for WeightedOutput<'a, L> {
+        type Inputs = ();
+        type Targets = ();
+
+        fn compute_grad(&self, params: &[f64], _: &(), _: &()) -> (f64, Vec<f64>) {
+            let (input, layer_params) = self.split(params);
+            let output = self.layer.forward_train(&input, layer_params.as_slice()).unwrap();
+            let in_grad = self.layer.back_input(&self.weights, &input, &output, layer_params.as_slice());
+            let param_grad = self.layer.back_params(&self.weights, &input, &output, layer_params.as_slice());
+            assert_eq!(param_grad.rows() * param_grad.cols(), self.layer.num_params());
+
+            let mut grad = in_grad.into_vec();
+            grad.extend(param_grad.into_vec());
+            (output.elemul(&self.weights).sum(), grad)
+        }
+
+        fn param_groups(&self, params: &[f64]) -> Vec<Range<usize>> {
+            let inputs = self.input_shape.0 * self.input_shape.1;
+            vec![0..inputs, inputs..params.len()]
+        }
+    }
+
+    /// Checks the gradients of a layer against finite differences
+    fn check_gradients<L: NetLayer>(layer: &L, input: &Matrix<f64>, params: &[f64]) {
+        let shape = layer.param_shape();
+        let layer_params = Matrix::new(shape.0, shape.1, params.to_vec());
+        let output = layer.forward_train(input, layer_params.as_slice()).unwrap();
+        let weights = Matrix::new(output.rows(), output.cols(),
+                                  (0..output.rows() * output.cols()).map(|x| ((x * 3) % 7) as f64 - 3.0)
+                                                                    .collect::<Vec<_>>());
+        let cost = WeightedOutput {
+            layer: layer,
+            input_shape: (input.rows(), input.cols()),
+            weights: weights,
+        };
+
+        let mut all = input.data().clone();
+        all.extend_from_slice(params);
+        let errors = grad_check(&cost, &all, &(), &(), 1e-5);
+        assert!(errors.iter().all(|&e| e < 1e-6), "{:?}", errors);
+    }
+
+    #[test]
+    fn batch_norm_normalizes_batch() {
+        let layer = BatchNorm::new(2);
+        let params = Matrix::new(2, 2, layer.default_params());
+
+        let output = layer.forward_train(&batch(), params.as_slice()).unwrap();
+
+        let mean = output.mean(Axes::Row);
+        assert!(mean.data().iter().all(|x| x.abs() < 1e-8));
+        for j in 0..2 {
+            let var = output.col(j).iter().map(|x| x * x).sum::<f64>() / 4.0;
+            assert!((var - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn batch_norm_scale_shift() {
+        let layer = BatchNorm::new(2);
+        let params = Matrix::new(2, 2, vec![2.0, 2.0, 3.0, 3.0]);
+
+        let output = layer.forward_train(&batch(), params.as_slice()).unwrap();
+
+        let mean = output.mean(Axes::Row);
+        assert!(mean.data().iter().all(|x| (x - 3.0).abs() < 1e-8));
+    }
+
+    #[test]
+    fn batch_norm_running_stats() {
+        let layer = BatchNorm::with_momentum(2, 0.5, 1e-5);
+        let params = Matrix::new(2, 2, layer.default_params());
+
+        for _ in 0..50 {
+            let _ = layer.forward_train(&batch(), params.as_slice()).unwrap();
+        }
+
+        let mean = layer.running_mean();
+        assert!((mean[0] - 3.0).abs() < 1e-8);
+        assert!((mean[1] - 15.0).abs() < 1e-8);
+
+        // Once the running statistics match the batch, prediction matches training
+        let train_out = layer.forward_train(&batch(), params.as_slice()).unwrap();
+        let eval_out = layer.forward(&batch(), params.as_slice()).unwrap();
+        assert!(train_out.data().iter().zip(eval_out.data()).all(|(x, y)| (x - y).abs() < 1e-8));
+    }
+
+    #[test]
+    fn batch_norm_gradients() {
+        check_gradients(&BatchNorm::new(2), &batch(), &[1.5, 0.5, -0.3, 0.2]);
+    }
+
+    #[test]
+    fn softmax_rows_are_distributions() {
+        let params = Matrix::<f64>::new(0, 0, Vec::new());
+        let input = Matrix::new(2, 3, vec![1.0, 2.0, 3.0,
+                                           1000.0, 1001.0, 1002.0]);
+
+        let output = Softmax.forward(&input, params.as_slice()).unwrap();
+        for row in output.row_iter() {
+            assert!((row.sum() - 1.0).abs() < 1e-12);
+        }
+        // Shifting a row does not change the result, even when exp overflows
+        for j in 0..3 {
+            assert!((output[[0, j]] - output[[1, j]]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn softmax_gradients() {
+        let input = Matrix::new(2, 3, vec![0.5, -1.0, 2.0, 1.5, 0.0, -0.5]);
+        check_gradients::<Softmax>(&Softmax, &input, &[]);
+    }
+
+    #[test]
+    fn dropout_train_and_predict() {
+        let layer = Dropout::new(0.25);
+        let params = Matrix::new(0, 0, Vec::new());
+        let input = Matrix::new(100, 100, vec![2.0; 10000]);
+
+        // Prediction is the identity
+        assert_eq!(layer.forward(&input, params.as_slice()).unwrap(), input);
+
+        // Training keeps about a quarter of the inputs, scaled to keep the mean
+        let output = layer.forward_train(&input, params.as_slice()).unwrap();
+        assert!(output.iter().all(|&x| x == 0.0 || x == 8.0));
+        let kept = output.iter().filter(|&&x| x != 0.0).count() as f64 / 10000.0;
+        assert!((kept - 0.25).abs() < 0.03);
+
+        // The gradient flows only through the kept inputs
+        let out_grad = Matrix::ones(100, 100);
+        let in_grad = layer.back_input(&out_grad, &input, &output, params.as_slice());
+        assert_eq!(in_grad * 2.0, output);
+    }
+
+    #[test]
+    fn dropout_keep_all() {
+        let layer = Dropout::new(1.0);
+        let params = Matrix::new(0, 0, Vec::new());
+
+        assert_eq!(layer.forward_train(&batch(), params.as_slice()).unwrap(), batch());
+        assert_eq!(layer.num_params(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dropout_back_input_other_batch() {
+        let layer = Dropout::new(0.5);
+        let params = Matrix::new(0, 0, Vec::new());
+        let input = Matrix::ones(4, 3);
+
+        // Without a training pass the gradient is unchanged
+        let out_grad = Matrix::ones(2, 3);
+        assert_eq!(layer.back_input(&out_grad, &input, &input, params.as_slice()), out_grad);
+
+        let output = layer.forward_train(&input, params.as_slice()).unwrap();
+        let _ = layer.back_input(&out_grad, &input, &output, params.as_slice());
+    }
+
+    #[test]
+    #[should_panic]
+    fn dropout_zero_keep_prob() {
+        let _ = Dropout::new(0.0);
+    }
+
+    #[test]
+    fn embedding_forward_gathers_rows() {
+        let layer = Embedding::new(3, 2);
+        let params = Matrix::new(3, 2, vec![0.0, 1.0,
+                                            2.0, 3.0,
+                                            4.0, 5.0]);
+        let input = Matrix::new(2, 2, vec![2.0, 0.0,
+                                           1.0, 1.0]);
+
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        assert_eq!(output.rows(), 2);
+        assert_eq!(output.cols(), 4);
+        assert_eq!(output.into_vec(), vec![4.0, 5.0, 0.0, 1.0,
+                                           2.0, 3.0, 2.0, 3.0]);
+
+        let bad_input = Matrix::new(1, 1, vec![3.0]);
+        assert!(layer.forward(&bad_input, params.as_slice()).is_err());
+        let bad_input = Matrix::new(1, 1, vec![-1.0]);
+        assert!(layer.forward(&bad_input, params.as_slice()).is_err());
+    }
+
+    #[test]
+    fn embedding_backward_accumulates_repeats() {
+        let layer = Embedding::new(4, 2);
+        let params = Matrix::new(4, 2, layer.default_params());
+        // Index 1 appears twice, index 3 is never used
+        let input = Matrix::new(3, 1, vec![1.0, 0.0, 1.0]);
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        let out_grad = Matrix::new(3, 2, vec![1.0, 2.0,
+                                              0.5, 0.5,
+                                              3.0, -1.0]);
+
+        let grad = layer.back_params(&out_grad, &input, &output, params.as_slice());
+        assert_eq!(grad.into_vec(), vec![0.5, 0.5,
+                                         4.0, 1.0,
+                                         0.0, 0.0,
+                                         0.0, 0.0]);
+
+        let in_grad = layer.back_input(&out_grad, &input, &output, params.as_slice());
+        assert!(in_grad.data().iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn max_pool_forward() {
+        let layer = MaxPool2d::new(1, 4, 4, 2, 2);
+        let params = Matrix::new(0, 0, Vec::new());
+        let input = Matrix::new(1, 16, vec![1.0, 3.0, 2.0, 0.0,
+                                            4.0, 2.0, 1.0, 5.0,
+                                            -1.0, -2.0, 7.0, 7.0,
+                                            -3.0, -0.5, 6.0, 1.0]);
+
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        assert_eq!(layer.output_shape(), (1, 2, 2));
+        assert_eq!(output.into_vec(), vec![4.0, 5.0, -0.5, 7.0]);
+
+        assert!(layer.forward(&Matrix::zeros(1, 15), params.as_slice()).is_err());
+    }
+
+    #[test]
+    fn max_pool_backward_routes_to_argmax() {
+        let layer = MaxPool2d::new(2, 2, 2, 2, 2);
+        let params = Matrix::new(0, 0, Vec::new());
+        // The second channel ties everywhere
+        let input = Matrix::new(1, 8, vec![1.0, 9.0, 3.0, 2.0,
+                                           5.0, 5.0, 5.0, 5.0]);
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        let out_grad = Matrix::new(1, 2, vec![2.5, -1.0]);
+
+        let in_grad = layer.back_input(&out_grad, &input, &output, params.as_slice());
+        // Ties route to the first position in the window
+        assert_eq!(in_grad.into_vec(), vec![0.0, 2.5, 0.0, 0.0,
+                                            -1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(layer.num_params(), 0);
+    }
+
+    #[test]
+    fn conv_forward_hand_computed() {
+        let layer = Conv2d::new((1, 5, 5), 1, 3, 1, 0);
+        // Bias 0.5, kernel weight 1 at (0, 1) and 2 at (2, 0)
+        let mut params = vec![0.0; 10];
+        params[0] = 0.5;
+        params[1 + 1] = 1.0;
+        params[1 + 6] = 2.0;
+        let params = Matrix::new(10, 1, params);
+        let input = Matrix::new(1, 25, (0..25).map(|x| x as f64).collect::<Vec<_>>());
+
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        assert_eq!(layer.output_shape(), (1, 3, 3));
+
+        // x[oh][ow + 1] + 2 * x[oh + 2][ow] + 0.5 where x[i][j] = 5i + j
+        let expected = (0..9).map(|p| {
+            let (oh, ow) = ((p / 3) as f64, (p % 3) as f64);
+            15.0 * oh + 3.0 * ow + 21.5
+        }).collect::<Vec<_>>();
+        assert_eq!(output.into_vec(), expected);
+
+        // With padding the kernel also covers the zeros around the image
+        let padded = Conv2d::new((1, 5, 5), 1, 3, 1, 1);
+        let output = padded.forward(&input, params.as_slice()).unwrap();
+        assert_eq!(padded.output_shape(), (1, 5, 5));
+        assert_eq!(output[[0, 0]], 0.5);
+        assert_eq!(output[[0, 1]], 10.5);
+    }
+
+    #[test]
+    fn conv_gradients() {
+        let layer = Conv2d::new((2, 4, 3), 2, 2, 2, 1);
+        let input = Matrix::new(2, 24, (0..48).map(|x| ((x * 7) % 11) as f64 / 5.0 - 1.0).collect::<Vec<_>>());
+        check_gradients(&layer, &input, &layer.default_params());
+    }
+
+    #[test]
+    fn residual_linear_forward() {
+        let layer = Residual::new(Box::new(Linear::without_bias(2, 2)));
+        let params = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let input = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, -1.0]);
+
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        // x + x·W
+        assert_eq!(output.into_vec(), vec![2.0, 2.0, -1.0, -3.0]);
+
+        // The inner layer must preserve the shape
+        let layer = Residual::new(Box::new(Linear::without_bias(2, 3)));
+        let params = Matrix::new(2, 3, vec![0.0; 6]);
+        assert!(layer.forward(&input, params.as_slice()).is_err());
+    }
+
+    #[test]
+    fn output_sizes() {
+        let linear: &NetLayer = &Linear::new(3, 5);
+        assert_eq!(linear.output_size(3), Some(5));
+        assert_eq!(linear.output_size(4), None);
+        assert_eq!(linear.input_size(), Some(3));
+        let linear: &NetLayer = &Linear::without_bias(3, 5);
+        assert_eq!(linear.input_size(), Some(3));
+        assert_eq!(linear.output_size(3), Some(5));
+        assert_eq!(BatchNorm::new(4).output_size(4), Some(4));
+        assert_eq!(Embedding::new(10, 3).output_size(2), Some(6));
+        assert_eq!(Conv2d::new((2, 5, 5), 4, 3, 1, 0).output_size(50), Some(36));
+        assert_eq!(MaxPool2d::new(1, 4, 4, 2, 2).output_size(16), Some(4));
+        assert_eq!(MaxPool2d::new(1, 4, 4, 2, 2).output_size(15), None);
+        assert_eq!(Residual::new(Box::new(Linear::new(3, 3))).output_size(3), Some(3));
+        assert_eq!(Residual::new(Box::new(Linear::new(3, 2))).output_size(3), None);
+        assert_eq!(GRU::new(2, 3, 4).output_size(8), Some(3));
+        assert_eq!(GRU::new(2, 3, 4).with_sequence_output().output_size(8), Some(12));
+        assert_eq!(GRU::new(2, 3, 4).output_size(6), None);
+        assert_eq!(RNN::new(2, 3, 4).output_size(8), Some(3));
+        assert_eq!(RNN::new(2, 3, 4).with_sequence_output().output_size(8), Some(12));
+    }
+
+    #[test]
+    fn bias_masks() {
+        // The first row of a linear layer's weights is its bias
+        let mask = NetLayer::<f64>::bias_mask(&Linear::new(2, 3));
+        assert_eq!(mask, vec![true, true, true, false, false, false, false, false, false]);
+        assert!(NetLayer::<f64>::bias_mask(&Linear::without_bias(2, 3)).iter().all(|&b| !b));
+
+        // BatchNorm's shift follows its scale
+        assert_eq!(BatchNorm::new(2).bias_mask(), vec![false, false, true, true]);
+    }
+
+    #[test]
+    fn normal_weight_init() {
+        let layer: &NetLayer = &Linear::new(300, 200);
+        let params = layer.init_params(WeightInit::Normal { std: 0.2 });
+        assert_eq!(params.len(), layer.num_params());
+
+        let n = params.len() as f64;
+        let mean = params.iter().sum::<f64>() / n;
+        let std = (params.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n).sqrt();
+        assert!(mean.abs() < 0.005);
+        assert!((std - 0.2).abs() < 0.005);
+
+        // The biases are zero
+        assert!(params[..200].iter().all(|&b| b == 0.0));
+
+        // Layers without connection weights keep their own initialization
+        let norm = BatchNorm::new(2);
+        assert_eq!(norm.init_params(WeightInit::Normal { std: 0.2 }), norm.default_params());
+    }
+
+    #[test]
+    fn glorot_uniform_init() {
+        // The fans of the weights exclude the bias
+        let layer: &NetLayer = &Linear::new(30, 20);
+        let bound = (6f64 / 50f64).sqrt();
+        assert_eq!(glorot_bound(30, 20), bound);
+
+        let params = layer.init_params(WeightInit::GlorotUniform);
+        let (biases, weights) = params.split_at(20);
+        assert!(biases.iter().all(|&b| b == 0f64));
+        assert!(weights.iter().all(|w| w.abs() <= bound));
+        // The weights fill the range
+        assert!(weights.iter().any(|w| w.abs() > 0.95 * bound));
+
+        // A 3x3 convolution from 2 to 4 channels has fans of 18 and 36
+        let conv = Conv2d::new((2, 5, 5), 4, 3, 1, 0);
+        let params = conv.init_params(WeightInit::GlorotUniform);
+        assert!(params.iter().all(|w| w.abs() <= glorot_bound(18, 36)));
+    }
+
+    #[test]
+    fn he_and_custom_init() {
+        let std_of = |weights: &[f64]| {
+            (weights.iter().map(|x| x * x).sum::<f64>() / weights.len() as f64).sqrt()
+        };
+        let layer: &NetLayer = &Linear::new(200, 400);
+
+        let params = layer.init_params(WeightInit::HeNormal);
+        let (biases, weights) = params.split_at(400);
+        assert!(biases.iter().all(|&b| b == 0f64));
+        assert!((std_of(weights) - 0.1).abs() < 0.005);
+
+        let params = layer.init_params(WeightInit::GlorotNormal);
+        assert!((std_of(&params[400..]) - (2f64 / 600f64).sqrt()).abs() < 0.005);
+
+        let bound = (6f64 / 200f64).sqrt();
+        let params = layer.init_params(WeightInit::HeUniform);
+        assert!(params.iter().all(|w| w.abs() <= bound));
+        assert!(params.iter().any(|w| w.abs() > 0.95 * bound));
+
+        // Custom initializers are given the fans of the layer
+        let params = layer.init_params(WeightInit::custom(|fan_in, fan_out| {
+            (fan_in + fan_out) as f64
+        }));
+        assert!(params[..400].iter().all(|&b| b == 0f64));
+        assert!(params[400..].iter().all(|&w| w == 600f64));
+    }
+
+    #[test]
+    fn residual_gradients() {
+        let layer = Residual::new(Box::new(Linear::new(3, 3)));
+        let input = Matrix::new(2, 3, vec![0.5, -1.0, 2.0, 1.5, 0.0, -0.5]);
+        check_gradients(&layer, &input, &layer.default_params());
+    }
+
+    #[test]
+    fn gru_single_step() {
+        let layer = GRU::new(1, 1, 1);
+        // Biases, input weights and recurrent weights of the z, r and n gates
+        let params = Matrix::new(3, 3, vec![0.0, 0.0, 0.5,
+                                            1.0, 0.0, 2.0,
+                                            0.0, 0.0, 0.0]);
+        let input = Matrix::new(1, 1, vec![0.5]);
+
+        // With a zero initial state h' = (1 - z) * n
+        let z = 1.0 / (1.0 + (-0.5f64).exp());
+        let n = (0.5f64 + 1.0).tanh();
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        assert!((output[[0, 0]] - (1.0 - z) * n).abs() < 1e-12);
+
+        assert!(layer.forward(&Matrix::zeros(1, 2), params.as_slice()).is_err());
+    }
+
+    /// Checks the gradients of a sequence layer on two sequences of 2 timesteps with 2 features
+    fn sequence_gradient_check<L: NetLayer>(layer: L) {
+        let input = Matrix::new(2, 4, vec![0.5, -1.0, 0.3, 0.8,
+                                           -0.2, 0.4, 1.5, -0.6]);
+        check_gradients(&layer, &input, &layer.default_params());
+    }
+
+    #[test]
+    fn gru_gradients() {
+        sequence_gradient_check(GRU::new(2, 3, 2));
+    }
+
+    #[test]
+    fn gru_sequence_gradients() {
+        let layer = GRU::new(2, 3, 2).with_sequence_output();
+        let params = Matrix::new(6, 9, layer.default_params());
+        let input = Matrix::new(1, 4, vec![0.5, -1.0, 0.3, 0.8]);
+
+        // The last hidden state matches the layer without sequence output
+        let all = layer.forward(&input, params.as_slice()).unwrap();
+        let last = GRU::new(2, 3, 2).forward(&input, params.as_slice()).unwrap();
+        assert_eq!(all.cols(), 6);
+        assert_eq!(&all.data()[3..], last.data());
+
+        sequence_gradient_check(layer);
+    }
+
+    #[test]
+    fn rnn_forward() {
+        let layer = RNN::new(1, 1, 2).with_sequence_output();
+        // Bias, input weight and recurrent weight
+        let params = Matrix::new(3, 1, vec![0.1, 0.5, -2.0]);
+        let input = Matrix::new(1, 2, vec![1.0, 3.0]);
+
+        let h1 = (0.1f64 + 0.5).tanh();
+        let h2 = (0.1 + 1.5 - 2.0 * h1).tanh();
+        let output = layer.forward(&input, params.as_slice()).unwrap();
+        assert!((output[[0, 0]] - h1).abs() < 1e-12);
+        assert!((output[[0, 1]] - h2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rnn_gradients() {
+        sequence_gradient_check(RNN::new(2, 3, 2));
+        sequence_gradient_check(RNN::new(2, 3, 2).with_sequence_output());
+    }
+
+    /// A layer which relies on `Clone` for `clone_box`
+    #[derive(Clone, Debug)]
+    struct Doubler;
+
+    impl NetLayer for Doubler {
+        fn forward(&self, input: &Matrix<f64>, _: MatrixSlice<f64>) -> LearningResult<Matrix<f64>> {
+            Ok(input * 2.0)
+        }
+
+        fn back_input(&self, out_grad: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+            out_grad * 2.0
+        }
+
+        fn back_params(&self, _: &Matrix<f64>, _: &Matrix<f64>, _: &Matrix<f64>, _: MatrixSlice<f64>) -> Matrix<f64> {
+            Matrix::new(0, 0, Vec::new())
+        }
+
+        fn default_params(&self) -> Vec<f64> {
+            Vec::new()
+        }
+
+        fn param_shape(&self) -> (usize, usize) {
+            (0, 0)
+        }
+    }
+
+    #[test]
+    fn clone_box() {
+        let layer: Box<NetLayer> = Box::new(Doubler);
+        let copy = layer.clone_box();
+        assert_eq!(copy.name(), "Doubler");
+
+        // Training state is not copied
+        let dropout = Dropout::new(0.5);
+        dropout.forward_train(&batch(), Matrix::new(0, 0, Vec::new()).as_slice()).unwrap();
+        assert_eq!(format!("{:?}", dropout.clone_box()), format!("{:?}", Dropout::new(0.5)));
+    }
+
+    #[test]
+    fn linear_forward_slice() {
+        let data: Matrix<f64> = Matrix::new(3, 3, vec![0.5, -1.0, 2.0, 1.5, 0.0, -0.5, 0.2, 0.3, 0.4]);
+        let inputs = data.sub_slice([1, 0], 2, 3);
+        let params = Matrix::new(4, 2, vec![0.1, -0.2, 0.3, 0.4, -0.5, 0.6, 0.7, -0.8]);
+
+        let ones = Matrix::ones(2, 1).hcat(&inputs.into_matrix());
+        let cases = vec![(Linear::new(3, 2), params.as_slice(), &ones * &params),
+                         (Linear::without_bias(3, 2), params.sub_slice([1, 0], 3, 2), &inputs * &params.sub_slice([1, 0], 3, 2))];
+        for (layer, params, expected) in cases {
+            let output = layer.forward_slice(inputs, params).unwrap();
+            assert_eq!((output.rows(), output.cols()), (2, 2));
+            for (x, y) in output.iter().zip(expected.iter()) {
+                assert!((x - y).abs() < 1e-12);
+            }
+
+            assert!(layer.forward_slice(data.sub_slice([0, 0], 2, 2), params).is_err());
+        }
+    }
+
+    /// An activation function which does not name itself
+    #[derive(Clone, Debug)]
+    struct Cube;
+
+    impl ActivationFunc for Cube {
+        fn func(x: f64) -> f64 {
+            x * x * x
+        }
+
+        fn func_grad(x: f64) -> f64 {
+            3.0 * x * x
+        }
+
+        fn func_grad_from_output(y: f64) -> f64 {
+            3.0 * y.cbrt() * y.cbrt()
+        }
+
+        fn func_inv(x: f64) -> f64 {
+            x.cbrt()
+        }
+    }
+
+    #[test]
+    fn custom_activation_spec() {
+        let layer: Box<NetLayer> = Box::new(Cube);
+        assert_eq!(layer.name(), "Custom");
+        assert!(layer.spec().is_none());
+
+        let err = layer_from_spec("Activation Custom").unwrap_err();
+        assert!(err.to_string().contains("Only the activation functions in activ_fn"));
+    }
 }
@@ -0,0 +1,124 @@
+//! Gradient Checking
+//!
+//! Contains the `grad_check` function which compares the gradient
+//! computed by an `Optimizable` model against a finite difference
+//! estimate of the cost. This is useful when implementing a new model,
+//! or a new layer or criterion for a neural network.
+//!
+//! The relative error is reported for each parameter group given by
+//! `Optimizable::param_groups`, which for a neural network is each layer.
+//! A correct gradient typically gives errors below `1e-6`, while a
+//! mistake in the gradient gives errors close to 1.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::learning::optim::Optimizable;
+//! use rusty_machine::learning::optim::grad_check::grad_check;
+//!
+//! // The cost (x - 3)^2 summed over each parameter
+//! struct Quadratic;
+//!
+//! impl Optimizable for Quadratic {
+//!     type Inputs = ();
+//!     type Targets = ();
+//!
+//!     fn compute_grad(&self, params: &[f64], _: &(), _: &()) -> (f64, Vec<f64>) {
+//!         let cost = params.iter().map(|x| (x - 3.0) * (x - 3.0)).sum();
+//!         (cost, params.iter().map(|x| 2.0 * (x - 3.0)).collect())
+//!     }
+//! }
+//!
+//! let errors = grad_check(&Quadratic, &[1.0, -2.0], &(), &(), 1e-5);
+//! assert!(errors[0] < 1e-6);
+//! ```
+
+use super::Optimizable;
+
+/// Compares the gradient of a model with a central finite difference estimate.
+///
+/// Each parameter is moved by `h` in both directions to estimate the
+/// derivative of the cost. The relative error of each parameter group is
+/// `|g - n| / (|g| + |n|)`, where `g` is the computed gradient and `n` the
+/// estimate over the group. A group whose gradient and estimate are both
+/// zero has no error.
+///
+/// The cost must be deterministic, so layers which behave randomly while
+/// training, such as `Dropout`, should not be checked.
+///
+/// # Panics
+///
+/// - `h` is not positive.
+pub fn grad_check<M: Optimizable>(model: &M,
+                                  params: &[f64],
+                                  inputs: &M::Inputs,
+                                  targets: &M::Targets,
+                                  h: f64)
+                                  -> Vec<f64> {
+    assert!(h > 0f64, "The finite difference step must be positive.");
+
+    let (_, grad) = model.compute_grad(params, inputs, targets);
+    let mut shifted = params.to_vec();
+    let numeric = (0..params.len())
+        .map(|i| {
+            shifted[i] = params[i] + h;
+            let plus = model.compute_grad(&shifted, inputs, targets).0;
+            shifted[i] = params[i] - h;
+            let minus = model.compute_grad(&shifted, inputs, targets).0;
+            shifted[i] = params[i];
+            (plus - minus) / (2f64 * h)
+        })
+        .collect::<Vec<_>>();
+
+    model.param_groups(params)
+        .into_iter()
+        .map(|group| {
+            let norm = |xs: &[f64]| xs.iter().fold(0f64, |acc, x| acc + x * x).sqrt();
+            let diff = grad[group.clone()]
+                .iter()
+                .zip(&numeric[group.clone()])
+                .map(|(g, n)| g - n)
+                .collect::<Vec<_>>();
+            let scale = norm(&grad[group.clone()]) + norm(&numeric[group]);
+            if scale == 0f64 { 0f64 } else { norm(&diff) / scale }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::grad_check;
+    use learning::optim::Optimizable;
+    use std::ops::Range;
+
+    /// The cost `sum(x^3)`, with a mistake in the gradient of the second group
+    struct Cubic;
+
+    impl Optimizable for Cubic {
+        type Inputs = ();
+        type Targets = ();
+
+        fn compute_grad(&self, params: &[f64], _: &(), _: &()) -> (f64, Vec<f64>) {
+            let cost = params.iter().map(|x| x * x * x).sum();
+            let grad = params.iter()
+                .enumerate()
+                .map(|(i, x)| if i < 2 { 3.0 * x * x } else { 2.0 * x * x })
+                .collect();
+            (cost, grad)
+        }
+
+        fn param_groups(&self, _: &[f64]) -> Vec<Range<usize>> {
+            vec![0..2, 2..4, 4..4]
+        }
+    }
+
+    #[test]
+    fn finds_wrong_group() {
+        let errors = grad_check(&Cubic, &[1.0, -2.0, 0.5, 3.0], &(), &(), 1e-5);
+
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0] < 1e-8);
+        assert!((errors[1] - 0.2).abs() < 1e-6);
+        assert_eq!(errors[2], 0.0);
+    }
+}
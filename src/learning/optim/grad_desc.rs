@@ -8,15 +8,107 @@
 //! optimization algorithm but there is flexibility to introduce new
 //! algorithms and git them into the same scheme easily.
 
-use learning::optim::{Optimizable, OptimAlgorithm};
+use learning::optim::{Optimizable, OptimAlgorithm, ResumableOptimAlgorithm};
+use learning::optim::{LrSchedule, OptimState, TrainingHistory};
 use linalg::Vector;
 use linalg::{Matrix, BaseMatrix};
-use rulinalg::utils;
 
+use learning::toolkit::plateau::PlateauDetector;
 use learning::toolkit::rand_utils;
 
+use rand::{Rng, SeedableRng, StdRng};
+
+use std::f64;
+use std::mem;
+
 const LEARNING_EPS: f64 = 1e-20;
 
+/// The parameters to decay, empty if there is no weight decay.
+fn decay_mask<M: Optimizable>(model: &M, weight_decay: f64, params: &[f64]) -> Vec<bool> {
+    if weight_decay > 0f64 {
+        model.weight_decay_mask(params)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Shrinks the masked parameters by `rate * w`.
+fn apply_weight_decay(params: &mut Vector<f64>, mask: &[bool], rate: f64) {
+    for (p, &decay) in params.mut_data().iter_mut().zip(mask) {
+        if decay {
+            *p -= rate * *p;
+        }
+    }
+}
+
+/// Adds the gradient to the running sum.
+fn add_to(sum: &mut [f64], grad: &[f64]) {
+    for (s, g) in sum.iter_mut().zip(grad) {
+        *s += *g;
+    }
+}
+
+/// Divides the summed gradients by the number of samples.
+fn mean(mut sum: Vec<f64>, n: usize) -> Vec<f64> {
+    for s in &mut sum {
+        *s /= n as f64;
+    }
+    sum
+}
+
+/// Checks the parameters of a learning rate schedule.
+fn check_schedule(schedule: &LrSchedule) {
+    match *schedule {
+        LrSchedule::Constant => {}
+        LrSchedule::StepDecay { step_size, gamma } => {
+            assert!(step_size > 0, "The decay interval must be at least one step.");
+            assert!(gamma > 0f64, "The decay factor must be positive.");
+        }
+        LrSchedule::ExponentialDecay { gamma } => {
+            assert!(gamma > 0f64, "The decay factor must be positive.");
+        }
+        LrSchedule::InverseTimeDecay { decay } => {
+            assert!(decay >= 0f64, "The decay rate cannot be negative.");
+        }
+        LrSchedule::CosineAnnealing { t_max, eta_min } |
+        LrSchedule::CosineWarmRestarts { t_max, eta_min } => {
+            assert!(t_max > 0, "The cosine cycle must be at least one step.");
+            assert!(eta_min >= 0f64, "The minimum step size cannot be negative.");
+        }
+    }
+}
+
+/// Tracks the cost over the iterations of an optimization.
+///
+/// The optimization always stops once an iteration leaves the cost
+/// unchanged, and also when the optional plateau detector fires.
+struct Convergence {
+    /// The cost of the previous iteration, infinite before the first.
+    prev_cost: f64,
+    /// Detects a plateau within the optimizer's tolerance.
+    plateau: Option<PlateauDetector>,
+}
+
+impl Convergence {
+    fn new(plateau: Option<PlateauDetector>) -> Convergence {
+        Convergence {
+            prev_cost: f64::INFINITY,
+            plateau: plateau,
+        }
+    }
+
+    /// Records the cost of an iteration, returning whether to stop.
+    fn update(&mut self, cost: f64) -> bool {
+        let unchanged = (self.prev_cost - cost).abs() < LEARNING_EPS;
+        self.prev_cost = cost;
+        let plateaued = match self.plateau {
+            Some(ref mut plateau) => plateau.update(cost),
+            None => false,
+        };
+        unchanged || plateaued
+    }
+}
+
 /// Batch Gradient Descent algorithm
 #[derive(Clone, Copy, Debug)]
 pub struct GradientDesc {
@@ -24,6 +116,10 @@ pub struct GradientDesc {
     alpha: f64,
     /// The number of iterations to run.
     iters: usize,
+    /// The decoupled weight decay rate.
+    weight_decay: f64,
+    /// Stops the optimization once the cost stops improving.
+    plateau: Option<PlateauDetector>,
 }
 
 /// The default gradient descent algorithm.
@@ -37,6 +133,8 @@ impl Default for GradientDesc {
         GradientDesc {
             alpha: 0.3,
             iters: 100,
+            weight_decay: 0f64,
+            plateau: None,
         }
     }
 }
@@ -61,8 +159,44 @@ impl GradientDesc {
         GradientDesc {
             alpha: alpha,
             iters: iters,
+            weight_decay: 0f64,
+            plateau: None,
         }
     }
+
+    /// Apply decoupled weight decay after each step.
+    ///
+    /// Each decayed parameter is shrunk by `alpha * weight_decay * w`
+    /// after the gradient step. Unlike regularization in the cost this
+    /// is independent of the gradient. Parameters excluded by the model's
+    /// `weight_decay_mask`, such as biases, are not decayed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::GradientDesc;
+    ///
+    /// let gd = GradientDesc::new(0.3, 10000).with_weight_decay(1e-4);
+    /// ```
+    pub fn with_weight_decay(mut self, weight_decay: f64) -> GradientDesc {
+        assert!(weight_decay >= 0f64, "The weight decay cannot be negative.");
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    /// Stop once the cost has converged.
+    ///
+    /// See `StochasticGD::with_tolerance`. The iteration count is the
+    /// maximum number of steps.
+    pub fn with_tolerance(mut self,
+                          abs_tolerance: f64,
+                          rel_tolerance: f64,
+                          patience: usize)
+                          -> GradientDesc {
+        self.plateau = Some(PlateauDetector::new(rel_tolerance, patience)
+            .with_abs_tolerance(abs_tolerance));
+        self
+    }
 }
 
 impl<M: Optimizable> OptimAlgorithm<M> for GradientDesc {
@@ -72,27 +206,58 @@ impl<M: Optimizable> OptimAlgorithm<M> for GradientDesc {
                 inputs: &M::Inputs,
                 targets: &M::Targets)
                 -> Vec<f64> {
+        self.optimize_with_history(model, start, inputs, targets).0
+    }
+
+    fn optimize_with_history(&self,
+                             model: &M,
+                             start: &[f64],
+                             inputs: &M::Inputs,
+                             targets: &M::Targets)
+                             -> (Vec<f64>, TrainingHistory) {
+        self.resume(model, start, &mut OptimState::new(), inputs, targets, self.iters)
+    }
+}
+
+impl<M: Optimizable> ResumableOptimAlgorithm<M> for GradientDesc {
+    fn resume(&self,
+              model: &M,
+              start: &[f64],
+              state: &mut OptimState,
+              inputs: &M::Inputs,
+              targets: &M::Targets,
+              iters: usize)
+              -> (Vec<f64>, TrainingHistory) {
+        let mut history = TrainingHistory::new();
 
         // Create the initial optimal parameters
         let mut optimizing_val = Vector::new(start.to_vec());
-        // The cost at the start of each iteration
-        let mut start_iter_cost = 0f64;
+        // The parameters subject to weight decay
+        let mask = decay_mask(model, self.weight_decay, start);
+        // The parameter groups whose gradient norms are recorded
+        let groups = model.param_groups(start);
+        // Tracks the change in the cost
+        let mut convergence = Convergence::new(self.plateau);
 
-        for _ in 0..self.iters {
+        for _ in 0..iters {
             // Compute the cost and gradient for the current parameters
             let (cost, grad) = model.compute_grad(optimizing_val.data(), inputs, targets);
 
             // Early stopping
-            if (start_iter_cost - cost).abs() < LEARNING_EPS {
+            if convergence.update(cost) {
+                history.record_convergence();
                 break;
             } else {
+                history.record_grad_norms(&grad, &groups);
                 // Update the optimal parameters using gradient descent
-                optimizing_val = &optimizing_val - Vector::new(grad) * self.alpha;
-                // Update the latest cost
-                start_iter_cost = cost;
+                let mut next_val = &optimizing_val - Vector::new(grad) * self.alpha;
+                apply_weight_decay(&mut next_val, &mask, self.alpha * self.weight_decay);
+                history.record_iteration(cost, optimizing_val.data(), next_val.data());
+                optimizing_val = next_val;
+                state.add_steps(1);
             }
         }
-        optimizing_val.into_vec()
+        (optimizing_val.into_vec(), history)
     }
 }
 
@@ -107,6 +272,22 @@ pub struct StochasticGD {
     mu: f64,
     /// The number of passes through the data.
     iters: usize,
+    /// The decoupled weight decay rate.
+    weight_decay: f64,
+    /// Stops the optimization once the cost stops improving.
+    plateau: Option<PlateauDetector>,
+    /// The factor applied to the learning rate of bias terms.
+    bias_lr_multiplier: f64,
+    /// The schedule of the step size.
+    schedule: LrSchedule,
+    /// The number of samples whose gradients are averaged for each update.
+    accum_steps: usize,
+    /// The number of samples in each gradient computation.
+    batch_size: usize,
+    /// Whether the samples are reshuffled on each pass.
+    shuffle: bool,
+    /// The seed of the shuffles, if they are deterministic.
+    seed: Option<usize>,
 }
 
 /// The default Stochastic GD algorithm.
@@ -122,6 +303,14 @@ impl Default for StochasticGD {
             alpha: 0.1,
             mu: 0.1,
             iters: 20,
+            weight_decay: 0f64,
+            plateau: None,
+            bias_lr_multiplier: 1f64,
+            schedule: LrSchedule::Constant,
+            accum_steps: 1,
+            batch_size: 1,
+            shuffle: true,
+            seed: None,
         }
     }
 }
@@ -149,12 +338,201 @@ impl StochasticGD {
             alpha: alpha,
             mu: mu,
             iters: iters,
+            weight_decay: 0f64,
+            plateau: None,
+            bias_lr_multiplier: 1f64,
+            schedule: LrSchedule::Constant,
+            accum_steps: 1,
+            batch_size: 1,
+            shuffle: true,
+            seed: None,
         }
     }
+
+    /// Apply decoupled weight decay after each step.
+    ///
+    /// Each decayed parameter is shrunk by `mu * weight_decay * w`
+    /// after the momentum step. Parameters excluded by the model's
+    /// `weight_decay_mask`, such as biases, are not decayed.
+    pub fn with_weight_decay(mut self, weight_decay: f64) -> StochasticGD {
+        assert!(weight_decay >= 0f64, "The weight decay cannot be negative.");
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    /// Stop once the cost has converged.
+    ///
+    /// The optimization stops before the maximum number of iterations
+    /// when the cost of a pass improves on the best cost so far by at most
+    /// `abs_tolerance`, or by a relative amount of at most `rel_tolerance`,
+    /// for `patience` consecutive passes. Set either tolerance to zero to
+    /// use only the other. See `PlateauDetector` for details. The cost of
+    /// each pass is noisy, so a patience of a few passes avoids stopping on
+    /// a momentary plateau.
+    ///
+    /// By default the optimization stops only when the cost is unchanged
+    /// by a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Run at most 1000 passes, stopping after 3 passes
+    /// // which improve the cost by at most 0.1%
+    /// let sgd = StochasticGD::new(0.1, 0.3, 1000).with_tolerance(0.0, 1e-3, 3);
+    ///
+    /// // Or which improve the cost by at most 1e-6
+    /// let sgd = StochasticGD::new(0.1, 0.3, 1000).with_tolerance(1e-6, 0.0, 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - Either tolerance is negative.
+    /// - The patience is zero.
+    pub fn with_tolerance(mut self,
+                          abs_tolerance: f64,
+                          rel_tolerance: f64,
+                          patience: usize)
+                          -> StochasticGD {
+        self.plateau = Some(PlateauDetector::new(rel_tolerance, patience)
+            .with_abs_tolerance(abs_tolerance));
+        self
+    }
+
+    /// Scale the learning rate of bias terms.
+    ///
+    /// The gradients of the parameters marked by the model's `bias_mask`
+    /// are multiplied by `multiplier`, so that biases step `multiplier`
+    /// times as far as weights with the same gradient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Biases learn twice as fast as weights
+    /// let sgd = StochasticGD::new(0.1, 0.3, 5).with_bias_lr_multiplier(2.0);
+    /// ```
+    pub fn with_bias_lr_multiplier(mut self, multiplier: f64) -> StochasticGD {
+        assert!(multiplier > 0f64, "The bias learning rate multiplier must be greater than 0.");
+        self.bias_lr_multiplier = multiplier;
+        self
+    }
+
+    /// Vary the step size (mu) with the number of updates.
+    ///
+    /// The schedule continues from the step count of the optimizer
+    /// state, so `partial_train` resumes it where it left off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::LrSchedule;
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Restart the cosine cycle every 1000 updates
+    /// let schedule = LrSchedule::CosineWarmRestarts { t_max: 1000, eta_min: 1e-4 };
+    /// let sgd = StochasticGD::new(0.1, 0.3, 5).with_schedule(schedule);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - A step decay has an interval of zero steps.
+    /// - A step or exponential decay has a factor which is not positive.
+    /// - An inverse time decay has a negative rate.
+    /// - A cosine schedule has a cycle of zero steps.
+    /// - A cosine schedule has a negative minimum step size.
+    pub fn with_schedule(mut self, schedule: LrSchedule) -> StochasticGD {
+        check_schedule(&schedule);
+        self.schedule = schedule;
+        self
+    }
+
+    /// Accumulate the gradients of several samples before each update.
+    ///
+    /// The gradients of `accum_steps` consecutive samples are averaged
+    /// and applied in a single update, which reduces the noise of the
+    /// updates without holding a larger batch in memory. When the number
+    /// of samples is not a multiple of `accum_steps` the last, smaller
+    /// group of each pass is still applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Update once every 4 samples
+    /// let sgd = StochasticGD::new(0.1, 0.3, 5).with_grad_accumulation(4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - The number of accumulation steps is zero.
+    pub fn with_grad_accumulation(mut self, accum_steps: usize) -> StochasticGD {
+        assert!(accum_steps > 0, "Must accumulate at least one gradient per update.");
+        self.accum_steps = accum_steps;
+        self
+    }
+
+    /// Compute each gradient from a mini-batch of samples.
+    ///
+    /// Each pass splits the (shuffled) samples into batches of
+    /// `batch_size` rows, and the last batch holds any remaining rows.
+    /// The gradient of each batch is computed by the model in a single
+    /// call. With gradient accumulation, the gradients of several
+    /// batches are averaged before each update.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// // Update once for every 32 samples
+    /// let sgd = StochasticGD::new(0.1, 0.3, 5).with_batch_size(32);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - The batch size is zero.
+    pub fn with_batch_size(mut self, batch_size: usize) -> StochasticGD {
+        assert!(batch_size > 0, "The batch size must be at least one.");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set whether the samples are reshuffled before each pass.
+    ///
+    /// Without shuffling the samples are visited in the order of the
+    /// rows, which makes the optimization deterministic. Shuffling is
+    /// enabled by default.
+    pub fn with_shuffle(mut self, shuffle: bool) -> StochasticGD {
+        self.shuffle = shuffle;
+        self
+    }
+
+    /// Shuffle the samples deterministically from a seed.
+    ///
+    /// Each call to `optimize` then visits the samples in the same
+    /// orders. A resumed optimization draws its shuffles from the seed
+    /// and the number of steps taken so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::StochasticGD;
+    ///
+    /// let sgd = StochasticGD::new(0.1, 0.3, 5).with_batch_size(16).with_seed(42);
+    /// ```
+    pub fn with_seed(mut self, seed: usize) -> StochasticGD {
+        self.seed = Some(seed);
+        self
+    }
 }
 
-impl<M> OptimAlgorithm<M> for StochasticGD
-    where M: Optimizable<Inputs = Matrix<f64>, Targets = Matrix<f64>>
+impl<M, T> OptimAlgorithm<M> for StochasticGD
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
 {
     fn optimize(&self,
                 model: &M,
@@ -162,61 +540,150 @@ impl<M> OptimAlgorithm<M> for StochasticGD
                 inputs: &M::Inputs,
                 targets: &M::Targets)
                 -> Vec<f64> {
+        self.optimize_with_history(model, start, inputs, targets).0
+    }
+
+    fn optimize_with_history(&self,
+                             model: &M,
+                             start: &[f64],
+                             inputs: &M::Inputs,
+                             targets: &M::Targets)
+                             -> (Vec<f64>, TrainingHistory) {
+        self.resume(model, start, &mut OptimState::new(), inputs, targets, self.iters)
+    }
+}
+
+impl<M, T> ResumableOptimAlgorithm<M> for StochasticGD
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
+    fn resume(&self,
+              model: &M,
+              start: &[f64],
+              state: &mut OptimState,
+              inputs: &M::Inputs,
+              targets: &M::Targets,
+              iters: usize)
+              -> (Vec<f64>, TrainingHistory) {
+        let mut history = TrainingHistory::new();
 
         // Create the initial optimal parameters
         let mut optimizing_val = Vector::new(start.to_vec());
-        // Create the momentum based gradient distance
-        let mut delta_w = Vector::zeros(start.len());
+        // Continue from the previous momentum based gradient distance
+        let mut delta_w = state.take_buffers(1, start.len()).pop().unwrap();
+        // The parameters subject to weight decay
+        let mask = decay_mask(model, self.weight_decay, start);
+        // The parameter groups whose gradient norms are recorded
+        let groups = model.param_groups(start);
+        // The parameters whose gradients are scaled as biases
+        let bias_mask = if self.bias_lr_multiplier != 1f64 {
+            model.bias_mask(start)
+        } else {
+            Vec::new()
+        };
 
         // Set up the indices for permutation
         let mut permutation = (0..inputs.rows()).collect::<Vec<_>>();
-        // The cost at the start of each iteration
-        let mut start_iter_cost = 0f64;
+        // The number of batches in each pass
+        let n_batches = (inputs.rows() + self.batch_size - 1) / self.batch_size;
+        // The generator of deterministic shuffles
+        let mut rng = self.seed.map(|seed| StdRng::from_seed(&[seed, state.steps()]));
+        // Tracks the change in the cost
+        let mut convergence = Convergence::new(self.plateau);
 
-        for _ in 0..self.iters {
+        for _ in 0..iters {
+            // The parameters at the start of this pass
+            let start_val = optimizing_val.clone();
+            // The sum of the gradients over this pass
+            let mut grad_sum = vec![0f64; start.len()];
+            // The gradients accumulated since the last update
+            let mut accum_grad = vec![0f64; start.len()];
+            let mut accum_count = 0;
             // The cost at the end of each stochastic gd pass
             let mut end_cost = 0f64;
             // Permute the indices
-            rand_utils::in_place_fisher_yates(&mut permutation);
-            for i in &permutation {
-                // Compute the cost and gradient for this data pair
-                let (cost, vec_data) = model.compute_grad(optimizing_val.data(),
-                                                          &inputs.select_rows(&[*i]),
-                                                          &targets.select_rows(&[*i]));
+            if self.shuffle {
+                match rng {
+                    Some(ref mut rng) => rng.shuffle(&mut permutation),
+                    None => rand_utils::in_place_fisher_yates(&mut permutation),
+                }
+            }
+            for (pos, batch) in permutation.chunks(self.batch_size).enumerate() {
+                // Compute the cost and gradient for this batch
+                let (cost, mut vec_data) = model.compute_grad(optimizing_val.data(),
+                                                              &inputs.select_rows(batch),
+                                                              &targets.select_rows(batch));
+                add_to(&mut grad_sum, &vec_data);
+                // Set the end cost (this is only used after the last iteration)
+                end_cost += cost * batch.len() as f64;
+
+                if self.accum_steps > 1 {
+                    add_to(&mut accum_grad, &vec_data);
+                    accum_count += 1;
+                    // Update once the group is full or the pass is over
+                    if accum_count < self.accum_steps && pos + 1 < n_batches {
+                        continue;
+                    }
+                    vec_data = mean(mem::replace(&mut accum_grad, vec![0f64; start.len()]),
+                                    accum_count);
+                    accum_count = 0;
+                }
+
+                for (g, &is_bias) in vec_data.iter_mut().zip(&bias_mask) {
+                    if is_bias {
+                        *g *= self.bias_lr_multiplier;
+                    }
+                }
 
+                // The step size of this update
+                let mu = self.schedule.learning_rate(self.mu, state.steps());
                 // Backup previous velocity
                 let prev_w = delta_w.clone();
                 // Compute the difference in gradient using Nesterov momentum
-                delta_w = Vector::new(vec_data) * self.mu + &delta_w * self.alpha;
+                delta_w = Vector::new(vec_data) * mu + &delta_w * self.alpha;
                 // Update the parameters
                 optimizing_val = &optimizing_val -
                     (&prev_w * (-self.alpha) + &delta_w * (1. + self.alpha));
-                // Set the end cost (this is only used after the last iteration)
-                end_cost += cost;
+                apply_weight_decay(&mut optimizing_val, &mask, mu * self.weight_decay);
+                state.add_steps(1);
             }
 
             end_cost /= inputs.rows() as f64;
+            history.record_iteration(end_cost, start_val.data(), optimizing_val.data());
+            history.record_grad_norms(&mean(grad_sum, n_batches), &groups);
 
             // Early stopping
-            if (start_iter_cost - end_cost).abs() < LEARNING_EPS {
+            if convergence.update(end_cost) {
+                history.record_convergence();
                 break;
-            } else {
-                // Update the cost
-                start_iter_cost = end_cost;
             }
         }
-        optimizing_val.into_vec()
+        state.set_buffers(vec![delta_w]);
+        (optimizing_val.into_vec(), history)
     }
 }
 
 /// Adaptive Gradient Descent
 ///
 /// The adaptive gradient descent algorithm (Duchi et al. 2010).
-#[derive(Debug)]
+///
+/// Each parameter keeps a running sum of its squared gradients and
+/// steps by `alpha * grad / (tau + sqrt(sum))`. The sum only ever
+/// grows, so the effective learning rate of every parameter decreases
+/// monotonically over training. Parameters which see consistently
+/// large gradients slow down fastest.
+#[derive(Clone, Copy, Debug)]
 pub struct AdaGrad {
+    /// The base step size
     alpha: f64,
+    /// The adaptive scaling constant, guards against division by zero
     tau: f64,
+    /// The number of passes through the data
     iters: usize,
+    /// The decoupled weight decay rate
+    weight_decay: f64,
+    /// Stops the optimization once the cost stops improving
+    plateau: Option<PlateauDetector>,
 }
 
 impl AdaGrad {
@@ -240,71 +707,151 @@ impl AdaGrad {
             alpha: alpha,
             tau: tau,
             iters: iters,
+            weight_decay: 0f64,
+            plateau: None,
+        }
+    }
+
+    /// Apply decoupled weight decay after each step.
+    ///
+    /// Each decayed parameter is shrunk by `alpha * weight_decay * w`
+    /// after the adaptive step. Parameters excluded by the model's
+    /// `weight_decay_mask`, such as biases, are not decayed.
+    pub fn with_weight_decay(mut self, weight_decay: f64) -> AdaGrad {
+        assert!(weight_decay >= 0f64, "The weight decay cannot be negative.");
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    /// Stop once the cost has converged.
+    ///
+    /// See `StochasticGD::with_tolerance`. The iteration count is the
+    /// maximum number of passes.
+    pub fn with_tolerance(mut self,
+                          abs_tolerance: f64,
+                          rel_tolerance: f64,
+                          patience: usize)
+                          -> AdaGrad {
+        self.plateau = Some(PlateauDetector::new(rel_tolerance, patience)
+            .with_abs_tolerance(abs_tolerance));
+        self
+    }
+
+    /// Apply a single AdaGrad step to the parameters.
+    ///
+    /// Adds the squared gradient to the accumulated sum in place.
+    fn update(&self, params: &mut Vector<f64>, ada_s: &mut Vector<f64>, grad: &[f64]) {
+        for ((p, s), g) in params.mut_data().iter_mut().zip(ada_s.mut_data().iter_mut()).zip(grad) {
+            *s += g * g;
+            *p -= self.alpha * (g / (self.tau + s.sqrt()));
         }
     }
 }
 
+/// The default AdaGrad configuration
+///
+/// The defaults are:
+///
+/// - alpha = 1
+/// - tau = 3
+/// - iters = 100
 impl Default for AdaGrad {
     fn default() -> AdaGrad {
         AdaGrad {
             alpha: 1f64,
             tau: 3f64,
             iters: 100,
+            weight_decay: 0f64,
+            plateau: None,
         }
     }
 }
 
-impl<M: Optimizable<Inputs = Matrix<f64>, Targets = Matrix<f64>>> OptimAlgorithm<M> for AdaGrad {
+impl<M, T> OptimAlgorithm<M> for AdaGrad
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
     fn optimize(&self,
                 model: &M,
                 start: &[f64],
                 inputs: &M::Inputs,
                 targets: &M::Targets)
                 -> Vec<f64> {
+        self.optimize_with_history(model, start, inputs, targets).0
+    }
 
-        // Initialize the adaptive scaling
-        let mut ada_s = Vector::zeros(start.len());
+    fn optimize_with_history(&self,
+                             model: &M,
+                             start: &[f64],
+                             inputs: &M::Inputs,
+                             targets: &M::Targets)
+                             -> (Vec<f64>, TrainingHistory) {
+        self.resume(model, start, &mut OptimState::new(), inputs, targets, self.iters)
+    }
+}
+
+impl<M, T> ResumableOptimAlgorithm<M> for AdaGrad
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
+    fn resume(&self,
+              model: &M,
+              start: &[f64],
+              state: &mut OptimState,
+              inputs: &M::Inputs,
+              targets: &M::Targets,
+              iters: usize)
+              -> (Vec<f64>, TrainingHistory) {
+        let mut history = TrainingHistory::new();
+
+        // Continue from the previous adaptive scaling
+        let mut ada_s = state.take_buffers(1, start.len()).pop().unwrap();
         // Initialize the optimal parameters
         let mut optimizing_val = Vector::new(start.to_vec());
+        // The parameters subject to weight decay
+        let mask = decay_mask(model, self.weight_decay, start);
+        // The parameter groups whose gradient norms are recorded
+        let groups = model.param_groups(start);
 
         // Set up the indices for permutation
         let mut permutation = (0..inputs.rows()).collect::<Vec<_>>();
-        // The cost at the start of each iteration
-        let mut start_iter_cost = 0f64;
+        // Tracks the change in the cost
+        let mut convergence = Convergence::new(self.plateau);
 
-        for _ in 0..self.iters {
+        for _ in 0..iters {
+            // The parameters at the start of this pass
+            let start_val = optimizing_val.clone();
+            // The sum of the gradients over this pass
+            let mut grad_sum = vec![0f64; start.len()];
             // The cost at the end of each stochastic gd pass
             let mut end_cost = 0f64;
             // Permute the indices
             rand_utils::in_place_fisher_yates(&mut permutation);
             for i in &permutation {
                 // Compute the cost and gradient for this data pair
-                let (cost, mut vec_data) = model.compute_grad(optimizing_val.data(),
-                                                              &inputs.select_rows(&[*i]),
-                                                              &targets.select_rows(&[*i]));
-                // Update the adaptive scaling by adding the gradient squared
-                utils::in_place_vec_bin_op(ada_s.mut_data(), &vec_data, |x, &y| *x += y * y);
-
-                // Compute the change in gradient
-                utils::in_place_vec_bin_op(&mut vec_data, ada_s.data(), |x, &y| {
-                    *x = self.alpha * (*x / (self.tau + (y).sqrt()))
-                });
-                // Update the parameters
-                optimizing_val = &optimizing_val - Vector::new(vec_data);
+                let (cost, grad) = model.compute_grad(optimizing_val.data(),
+                                                      &inputs.select_rows(&[*i]),
+                                                      &targets.select_rows(&[*i]));
+                add_to(&mut grad_sum, &grad);
+                // Update the adaptive scaling and the parameters
+                self.update(&mut optimizing_val, &mut ada_s, &grad);
+                apply_weight_decay(&mut optimizing_val, &mask, self.alpha * self.weight_decay);
+                state.add_steps(1);
                 // Set the end cost (this is only used after the last iteration)
                 end_cost += cost;
             }
             end_cost /= inputs.rows() as f64;
+            history.record_iteration(end_cost, start_val.data(), optimizing_val.data());
+            history.record_grad_norms(&mean(grad_sum, inputs.rows()), &groups);
 
             // Early stopping
-            if (start_iter_cost - end_cost).abs() < LEARNING_EPS {
+            if convergence.update(end_cost) {
+                history.record_convergence();
                 break;
-            } else {
-                // Update the cost
-                start_iter_cost = end_cost;
             }
         }
-        optimizing_val.into_vec()
+        state.set_buffers(vec![ada_s]);
+        (optimizing_val.into_vec(), history)
     }
 }
 
@@ -321,6 +868,12 @@ pub struct RMSProp {
     epsilon: f64,
     /// The number of passes through the data
     iters: usize,
+    /// The decoupled weight decay rate
+    weight_decay: f64,
+    /// Stops the optimization once the cost stops improving
+    plateau: Option<PlateauDetector>,
+    /// The schedule of the learning rate
+    schedule: LrSchedule,
 }
 
 /// The default RMSProp configuration
@@ -337,7 +890,10 @@ impl Default for RMSProp {
             learning_rate: 0.01,
             decay_rate: 0.9,
             epsilon: 1.0e-5,
-            iters: 50
+            iters: 50,
+            weight_decay: 0f64,
+            plateau: None,
+            schedule: LrSchedule::Constant,
         }
     }
 }
@@ -352,7 +908,7 @@ impl RMSProp {
     /// ```
     /// use rusty_machine::learning::optim::grad_desc::RMSProp;
     ///
-    /// let rms = RMSProp::new(0.99, 0.01, 1e-5, 20);
+    /// let rms = RMSProp::new(0.01, 0.9, 1e-5, 20);
     /// ```
     pub fn new(learning_rate: f64, decay_rate: f64, epsilon: f64, iters: usize) -> RMSProp {
         assert!(0f64 < learning_rate, "The learning rate must be positive");
@@ -363,30 +919,122 @@ impl RMSProp {
             decay_rate: decay_rate,
             learning_rate: learning_rate,
             epsilon: epsilon,
-            iters: iters
+            iters: iters,
+            weight_decay: 0f64,
+            plateau: None,
+            schedule: LrSchedule::Constant,
+        }
+    }
+
+    /// Apply decoupled weight decay after each step.
+    ///
+    /// Each decayed parameter is shrunk by `learning_rate * weight_decay * w`
+    /// after the RMSProp step. Parameters excluded by the model's
+    /// `weight_decay_mask`, such as biases, are not decayed.
+    pub fn with_weight_decay(mut self, weight_decay: f64) -> RMSProp {
+        assert!(weight_decay >= 0f64, "The weight decay cannot be negative.");
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    /// Stop once the cost has converged.
+    ///
+    /// See `StochasticGD::with_tolerance`. The iteration count is the
+    /// maximum number of passes.
+    pub fn with_tolerance(mut self,
+                          abs_tolerance: f64,
+                          rel_tolerance: f64,
+                          patience: usize)
+                          -> RMSProp {
+        self.plateau = Some(PlateauDetector::new(rel_tolerance, patience)
+            .with_abs_tolerance(abs_tolerance));
+        self
+    }
+
+    /// Vary the learning rate with the number of updates.
+    ///
+    /// See `StochasticGD::with_schedule`.
+    ///
+    /// # Panics
+    ///
+    /// - A step decay has an interval of zero steps.
+    /// - A step or exponential decay has a factor which is not positive.
+    /// - An inverse time decay has a negative rate.
+    /// - A cosine schedule has a cycle of zero steps.
+    /// - A cosine schedule has a negative minimum step size.
+    pub fn with_schedule(mut self, schedule: LrSchedule) -> RMSProp {
+        check_schedule(&schedule);
+        self.schedule = schedule;
+        self
+    }
+
+    /// Apply a single RMSProp step with learning rate `lr` to the parameters.
+    ///
+    /// Updates the running average of squared gradients in place and
+    /// scales each gradient by the root of its running average.
+    fn update(&self, params: &mut Vector<f64>, cache: &mut Vector<f64>, grad: &[f64], lr: f64) {
+        for ((p, c), g) in params.mut_data().iter_mut().zip(cache.mut_data().iter_mut()).zip(grad) {
+            *c = self.decay_rate * *c + (1.0 - self.decay_rate) * g * g;
+            *p -= lr * g / (c.sqrt() + self.epsilon);
         }
     }
 }
 
-impl<M> OptimAlgorithm<M> for RMSProp
-    where M: Optimizable<Inputs = Matrix<f64>, Targets = Matrix<f64>> {
+impl<M, T> OptimAlgorithm<M> for RMSProp
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
     fn optimize(&self,
                 model: &M,
                 start: &[f64],
                 inputs: &M::Inputs,
                 targets: &M::Targets)
                 -> Vec<f64> {
+        self.optimize_with_history(model, start, inputs, targets).0
+    }
+
+    fn optimize_with_history(&self,
+                             model: &M,
+                             start: &[f64],
+                             inputs: &M::Inputs,
+                             targets: &M::Targets)
+                             -> (Vec<f64>, TrainingHistory) {
+        self.resume(model, start, &mut OptimState::new(), inputs, targets, self.iters)
+    }
+}
+
+impl<M, T> ResumableOptimAlgorithm<M> for RMSProp
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
+    fn resume(&self,
+              model: &M,
+              start: &[f64],
+              state: &mut OptimState,
+              inputs: &M::Inputs,
+              targets: &M::Targets,
+              iters: usize)
+              -> (Vec<f64>, TrainingHistory) {
+        let mut history = TrainingHistory::new();
         // Initial parameters
         let mut params = Vector::new(start.to_vec());
-        // Running average of squared gradients
-        let mut rmsprop_cache = Vector::zeros(start.len());
+        // Continue from the previous running average of squared gradients
+        let mut rmsprop_cache = state.take_buffers(1, start.len()).pop().unwrap();
+        // The parameters subject to weight decay
+        let mask = decay_mask(model, self.weight_decay, start);
+        // The parameter groups whose gradient norms are recorded
+        let groups = model.param_groups(start);
 
         // Set up indices for permutation
         let mut permutation = (0..inputs.rows()).collect::<Vec<_>>();
-        // The cost from the previous iteration
-        let mut prev_cost = 0f64;
+        // Tracks the change in the cost
+        let mut convergence = Convergence::new(self.plateau);
 
-        for _ in 0..self.iters {
+        for _ in 0..iters {
+            // The parameters at the start of this pass
+            let start_val = params.clone();
+            // The sum of the gradients over this pass
+            let mut grad_sum = vec![0f64; start.len()];
             // The cost at end of each pass
             let mut end_cost = 0f64;
             // Permute the vertices
@@ -395,36 +1043,339 @@ impl<M> OptimAlgorithm<M> for RMSProp
                 let (cost, grad) = model.compute_grad(params.data(),
                                                       &inputs.select_rows(&[*i]),
                                                       &targets.select_rows(&[*i]));
+                add_to(&mut grad_sum, &grad);
 
-                let mut grad = Vector::new(grad);
-                let grad_squared = grad.clone().apply(&|x| x*x);
-                // Update cached average of squared gradients
-                rmsprop_cache = &rmsprop_cache*self.decay_rate + &grad_squared*(1.0 - self.decay_rate);
-                // RMSProp update rule 
-                utils::in_place_vec_bin_op(grad.mut_data(), rmsprop_cache.data(), |x, &y| {
-                    *x = *x * self.learning_rate / (y + self.epsilon).sqrt();
-                });
-                params = &params - &grad;
+                // The learning rate of this update
+                let lr = self.schedule.learning_rate(self.learning_rate, state.steps());
+                self.update(&mut params, &mut rmsprop_cache, &grad, lr);
+                apply_weight_decay(&mut params, &mask, lr * self.weight_decay);
+                state.add_steps(1);
 
                 end_cost += cost;
             }
             end_cost /= inputs.rows() as f64;
+            history.record_iteration(end_cost, start_val.data(), params.data());
+            history.record_grad_norms(&mean(grad_sum, inputs.rows()), &groups);
 
             // Early stopping
-            if (prev_cost - end_cost).abs() < LEARNING_EPS {
+            if convergence.update(end_cost) {
+                history.record_convergence();
                 break;
-            } else {
-                prev_cost = end_cost;
             }
         }
-        params.into_vec()
+        state.set_buffers(vec![rmsprop_cache]);
+        (params.into_vec(), history)
+    }
+}
+
+/// Adam
+///
+/// The Adam algorithm (Kingma and Ba 2014).
+///
+/// Keeps running averages of the gradient and of the squared gradient
+/// for each parameter, corrected for their bias towards zero in the first
+/// steps, and scales each step by their ratio.
+#[derive(Debug, Clone, Copy)]
+pub struct Adam {
+    /// The base step size of gradient descent steps
+    learning_rate: f64,
+    /// Rate at which the average of the gradients decays
+    beta1: f64,
+    /// Rate at which the average of the squared gradients decays
+    beta2: f64,
+    /// Small value used to avoid divide by zero
+    epsilon: f64,
+    /// The number of passes through the data
+    iters: usize,
+    /// The decoupled weight decay rate
+    weight_decay: f64,
+    /// Stops the optimization once the cost stops improving
+    plateau: Option<PlateauDetector>,
+    /// The schedule of the learning rate
+    schedule: LrSchedule,
+}
+
+/// The default Adam configuration
+///
+/// The defaults are:
+///
+/// - learning_rate = 0.001
+/// - beta1 = 0.9
+/// - beta2 = 0.999
+/// - epsilon = 1.0e-8
+/// - iters = 50
+impl Default for Adam {
+    fn default() -> Adam {
+        Adam {
+            learning_rate: 0.001,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1.0e-8,
+            iters: 50,
+            weight_decay: 0f64,
+            plateau: None,
+            schedule: LrSchedule::Constant,
+        }
+    }
+}
+
+impl Adam {
+    /// Construct an Adam algorithm.
+    ///
+    /// Requires learning rate, the decay rates of the first and second
+    /// moment estimates, epsilon, and iteration count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::optim::grad_desc::Adam;
+    ///
+    /// let adam = Adam::new(0.001, 0.9, 0.999, 1e-8, 20);
+    /// ```
+    pub fn new(learning_rate: f64, beta1: f64, beta2: f64, epsilon: f64, iters: usize) -> Adam {
+        assert!(0f64 < learning_rate, "The learning rate must be positive");
+        assert!(0f64 <= beta1 && beta1 < 1f64, "beta1 must be in [0, 1)");
+        assert!(0f64 <= beta2 && beta2 < 1f64, "beta2 must be in [0, 1)");
+        assert!(0f64 < epsilon, "Epsilon must be positive");
+
+        Adam {
+            learning_rate: learning_rate,
+            beta1: beta1,
+            beta2: beta2,
+            epsilon: epsilon,
+            iters: iters,
+            weight_decay: 0f64,
+            plateau: None,
+            schedule: LrSchedule::Constant,
+        }
+    }
+
+    /// Apply decoupled weight decay after each step (AdamW).
+    ///
+    /// Each decayed parameter is shrunk by `learning_rate * weight_decay * w`
+    /// after the Adam step. Parameters excluded by the model's
+    /// `weight_decay_mask`, such as biases, are not decayed.
+    pub fn with_weight_decay(mut self, weight_decay: f64) -> Adam {
+        assert!(weight_decay >= 0f64, "The weight decay cannot be negative.");
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    /// Stop once the cost has converged.
+    ///
+    /// See `StochasticGD::with_tolerance`. The iteration count is the
+    /// maximum number of passes.
+    pub fn with_tolerance(mut self,
+                          abs_tolerance: f64,
+                          rel_tolerance: f64,
+                          patience: usize)
+                          -> Adam {
+        self.plateau = Some(PlateauDetector::new(rel_tolerance, patience)
+            .with_abs_tolerance(abs_tolerance));
+        self
+    }
+
+    /// Vary the learning rate with the number of updates.
+    ///
+    /// See `StochasticGD::with_schedule`.
+    ///
+    /// # Panics
+    ///
+    /// - A step decay has an interval of zero steps.
+    /// - A step or exponential decay has a factor which is not positive.
+    /// - An inverse time decay has a negative rate.
+    /// - A cosine schedule has a cycle of zero steps.
+    /// - A cosine schedule has a negative minimum step size.
+    pub fn with_schedule(mut self, schedule: LrSchedule) -> Adam {
+        check_schedule(&schedule);
+        self.schedule = schedule;
+        self
+    }
+
+    /// Apply the `step`-th Adam step, counting from 1, with learning rate `lr`.
+    ///
+    /// Updates the moment estimates in place.
+    fn update(&self,
+              params: &mut Vector<f64>,
+              moments: &mut [Vector<f64>],
+              grad: &[f64],
+              step: usize,
+              lr: f64) {
+        let (first, second) = moments.split_at_mut(1);
+        let (m, v) = (first[0].mut_data(), second[0].mut_data());
+        // The bias corrections of the moment estimates
+        let m_corr = 1f64 - self.beta1.powi(step as i32);
+        let v_corr = 1f64 - self.beta2.powi(step as i32);
+
+        for (((p, m), v), g) in params.mut_data().iter_mut().zip(m.iter_mut()).zip(v.iter_mut()).zip(grad) {
+            *m = self.beta1 * *m + (1f64 - self.beta1) * g;
+            *v = self.beta2 * *v + (1f64 - self.beta2) * g * g;
+            *p -= lr * (*m / m_corr) / ((*v / v_corr).sqrt() + self.epsilon);
+        }
+    }
+}
+
+impl<M, T> OptimAlgorithm<M> for Adam
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
+    fn optimize(&self,
+                model: &M,
+                start: &[f64],
+                inputs: &M::Inputs,
+                targets: &M::Targets)
+                -> Vec<f64> {
+        self.optimize_with_history(model, start, inputs, targets).0
+    }
+
+    fn optimize_with_history(&self,
+                             model: &M,
+                             start: &[f64],
+                             inputs: &M::Inputs,
+                             targets: &M::Targets)
+                             -> (Vec<f64>, TrainingHistory) {
+        self.resume(model, start, &mut OptimState::new(), inputs, targets, self.iters)
+    }
+}
+
+impl<M, T> ResumableOptimAlgorithm<M> for Adam
+    where M: Optimizable<Inputs = Matrix<T>, Targets = Matrix<T>>,
+          T: Copy
+{
+    fn resume(&self,
+              model: &M,
+              start: &[f64],
+              state: &mut OptimState,
+              inputs: &M::Inputs,
+              targets: &M::Targets,
+              iters: usize)
+              -> (Vec<f64>, TrainingHistory) {
+        let mut history = TrainingHistory::new();
+        // Initial parameters
+        let mut params = Vector::new(start.to_vec());
+        // Continue from the previous moment estimates
+        let mut moments = state.take_buffers(2, start.len());
+        // The parameters subject to weight decay
+        let mask = decay_mask(model, self.weight_decay, start);
+        // The parameter groups whose gradient norms are recorded
+        let groups = model.param_groups(start);
+
+        // Set up indices for permutation
+        let mut permutation = (0..inputs.rows()).collect::<Vec<_>>();
+        // Tracks the change in the cost
+        let mut convergence = Convergence::new(self.plateau);
+
+        for _ in 0..iters {
+            // The parameters at the start of this pass
+            let start_val = params.clone();
+            // The sum of the gradients over this pass
+            let mut grad_sum = vec![0f64; start.len()];
+            // The cost at end of each pass
+            let mut end_cost = 0f64;
+            // Permute the vertices
+            rand_utils::in_place_fisher_yates(&mut permutation);
+            for i in &permutation {
+                let (cost, grad) = model.compute_grad(params.data(),
+                                                      &inputs.select_rows(&[*i]),
+                                                      &targets.select_rows(&[*i]));
+                add_to(&mut grad_sum, &grad);
+
+                // The learning rate of this update
+                let lr = self.schedule.learning_rate(self.learning_rate, state.steps());
+                // The bias correction continues from the resumed step count
+                state.add_steps(1);
+                self.update(&mut params, &mut moments, &grad, state.steps(), lr);
+                apply_weight_decay(&mut params, &mask, lr * self.weight_decay);
+
+                end_cost += cost;
+            }
+            end_cost /= inputs.rows() as f64;
+            history.record_iteration(end_cost, start_val.data(), params.data());
+            history.record_grad_norms(&mean(grad_sum, inputs.rows()), &groups);
+
+            // Early stopping
+            if convergence.update(end_cost) {
+                history.record_convergence();
+                break;
+            }
+        }
+        state.set_buffers(moments);
+        (params.into_vec(), history)
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{GradientDesc, StochasticGD, AdaGrad, RMSProp};
+    use super::{GradientDesc, StochasticGD, AdaGrad, RMSProp, Adam, Convergence};
+    use learning::toolkit::plateau::PlateauDetector;
+    use learning::optim::{Optimizable, OptimAlgorithm, ResumableOptimAlgorithm};
+    use learning::optim::{LrSchedule, OptimState};
+    use linalg::{Matrix, BaseMatrix, Vector};
+
+    /// A model with a unit gradient whose first parameter is a bias.
+    struct UnitGradient;
+
+    impl Optimizable for UnitGradient {
+        type Inputs = Matrix<f64>;
+        type Targets = Matrix<f64>;
+
+        fn compute_grad(&self,
+                        params: &[f64],
+                        _: &Matrix<f64>,
+                        _: &Matrix<f64>)
+                        -> (f64, Vec<f64>) {
+            (params.iter().sum(), vec![1f64; params.len()])
+        }
+
+        fn bias_mask(&self, params: &[f64]) -> Vec<bool> {
+            let mut mask = vec![false; params.len()];
+            mask[0] = true;
+            mask
+        }
+    }
+
+    /// A model pulling every parameter towards the first input.
+    struct Quadratic;
+
+    impl Optimizable for Quadratic {
+        type Inputs = Matrix<f64>;
+        type Targets = Matrix<f64>;
+
+        fn compute_grad(&self,
+                        params: &[f64],
+                        inputs: &Matrix<f64>,
+                        _: &Matrix<f64>)
+                        -> (f64, Vec<f64>) {
+            let x = inputs[[0, 0]];
+            let cost = params.iter().map(|p| 0.5 * (p - x) * (p - x)).sum();
+            (cost, params.iter().map(|p| p - x).collect())
+        }
+    }
+
+    /// A model with no gradient whose first parameter is a bias.
+    struct NoGradient;
+
+    impl Optimizable for NoGradient {
+        type Inputs = Matrix<f64>;
+        type Targets = Matrix<f64>;
+
+        fn compute_grad(&self,
+                        params: &[f64],
+                        _: &Matrix<f64>,
+                        _: &Matrix<f64>)
+                        -> (f64, Vec<f64>) {
+            // The cost changes with the parameters to avoid early stopping
+            (params.iter().sum(), vec![0f64; params.len()])
+        }
+
+        // The bias is excluded from weight decay by default
+        fn bias_mask(&self, params: &[f64]) -> Vec<bool> {
+            let mut mask = vec![false; params.len()];
+            mask[0] = true;
+            mask
+        }
+    }
 
     #[test]
     #[should_panic]
@@ -444,6 +1395,30 @@ mod tests {
         let _ = StochasticGD::new(0.5, -1f64, 0);
     }
 
+    #[test]
+    #[should_panic]
+    fn stochastic_gd_neg_bias_multiplier() {
+        let _ = StochasticGD::new(0.5, 1f64, 0).with_bias_lr_multiplier(-1f64);
+    }
+
+    #[test]
+    fn bias_lr_multiplier() {
+        let inputs = Matrix::zeros(3, 1);
+        let targets = Matrix::zeros(3, 1);
+
+        let sgd = StochasticGD::new(0.5, 0.1, 4).with_bias_lr_multiplier(2f64);
+        let params = sgd.optimize(&UnitGradient, &[0f64; 3], &inputs, &targets);
+
+        // The bias moves twice as far as the weights with the same gradient
+        assert!(params[1] < 0f64);
+        assert_eq!(params[1], params[2]);
+        assert!((params[0] - 2f64 * params[1]).abs() < 1e-10);
+
+        // Without a multiplier every parameter moves the same distance
+        let params = StochasticGD::new(0.5, 0.1, 4).optimize(&UnitGradient, &[0f64; 3], &inputs, &targets);
+        assert_eq!(params[0], params[1]);
+    }
+
     #[test]
     #[should_panic]
     fn adagrad_neg_stepsize() {
@@ -473,4 +1448,296 @@ mod tests {
     fn rmsprop_neg_learning_rate() {
         let _ = RMSProp::new(0.5, -0.005, 1.0e-5, 0);
     }
+
+    #[test]
+    fn adagrad_updates_shrink() {
+        let ada = AdaGrad::new(0.5, 1e-8, 1);
+        let mut params = Vector::new(vec![0.0, 0.0]);
+        let mut ada_s = Vector::zeros(2);
+
+        // The first parameter always sees a large gradient, the second a small one
+        let mut prev_steps = (f64::INFINITY, f64::INFINITY);
+        for _ in 0..5 {
+            let before = params.clone();
+            ada.update(&mut params, &mut ada_s, &[10.0, 0.1]);
+
+            let steps = ((before[0] - params[0]).abs(), (before[1] - params[1]).abs());
+            assert!(steps.0 < prev_steps.0);
+            assert!(steps.1 < prev_steps.1);
+            prev_steps = steps;
+        }
+
+        // The accumulated sums are the totals of the squared gradients
+        assert!((ada_s[0] - 500.0).abs() < 1e-10);
+        assert!((ada_s[1] - 0.05).abs() < 1e-10);
+    }
+
+    #[test]
+    fn rmsprop_first_update_from_zero_cache() {
+        let rms = RMSProp::new(0.1, 0.9, 1e-8, 1);
+        let mut params = Vector::new(vec![1.0, 1.0]);
+        let mut cache = Vector::zeros(2);
+
+        rms.update(&mut params, &mut cache, &[2.0, -1.0], 0.1);
+
+        // cache = (1 - decay) * grad^2 after a single step
+        assert!((cache[0] - 0.4).abs() < 1e-12);
+        assert!((cache[1] - 0.1).abs() < 1e-12);
+
+        let expected_0 = 1.0 - 0.1 * 2.0 / (0.4f64.sqrt() + 1e-8);
+        let expected_1 = 1.0 + 0.1 * 1.0 / (0.1f64.sqrt() + 1e-8);
+        assert!((params[0] - expected_0).abs() < 1e-12);
+        assert!((params[1] - expected_1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn adam_bias_corrected_steps() {
+        let adam = Adam::new(0.1, 0.9, 0.999, 1e-8, 1);
+        let mut params = Vector::new(vec![1.0, 1.0]);
+        let mut moments = vec![Vector::zeros(2), Vector::zeros(2)];
+
+        // The corrected first step has the size of the learning rate
+        adam.update(&mut params, &mut moments, &[2.0, -0.01], 1, 0.1);
+        assert!((params[0] - 0.9).abs() < 1e-6);
+        assert!((params[1] - 1.1).abs() < 1e-4);
+        assert!((moments[0][0] - 0.2).abs() < 1e-12);
+        assert!((moments[1][0] - 0.004).abs() < 1e-12);
+
+        // And so does a constant gradient at later steps
+        for step in 2..6 {
+            let before = params[0];
+            adam.update(&mut params, &mut moments, &[2.0, -0.01], step, 0.1);
+            assert!((before - params[0] - 0.1).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn adam_resume_continues_moments() {
+        let inputs = Matrix::new(1, 1, vec![3.0]);
+        let targets = Matrix::zeros(1, 1);
+        let start = [0.0, 10.0];
+        let adam = Adam::new(0.2, 0.9, 0.999, 1e-8, 6);
+
+        let full = adam.optimize(&Quadratic, &start, &inputs, &targets);
+
+        let mut state = OptimState::new();
+        let (half, _) = adam.resume(&Quadratic, &start, &mut state, &inputs, &targets, 3);
+        let (resumed, _) = adam.resume(&Quadratic, &half, &mut state, &inputs, &targets, 3);
+
+        assert_eq!(state.steps(), 6);
+        assert_eq!(full, resumed);
+    }
+
+    #[test]
+    #[should_panic]
+    fn adam_beta_out_of_range() {
+        let _ = Adam::new(0.001, 1.0, 0.999, 1e-8, 1);
+    }
+
+    #[test]
+    fn weight_decay_shrinks_geometrically() {
+        let inputs = Matrix::zeros(1, 1);
+        let targets = Matrix::zeros(1, 1);
+        let start = [1.0, 2.0, -4.0];
+
+        let gd = GradientDesc::new(0.5, 10).with_weight_decay(0.1);
+        let params = gd.optimize(&NoGradient, &start, &inputs, &targets);
+
+        let factor = (1f64 - 0.5 * 0.1).powi(10);
+        assert_eq!(params[0], 1.0);
+        assert!((params[1] - 2.0 * factor).abs() < 1e-12);
+        assert!((params[2] + 4.0 * factor).abs() < 1e-12);
+
+        let sgd = StochasticGD::new(0.1, 0.2, 10).with_weight_decay(0.1);
+        let params = sgd.optimize(&NoGradient, &start, &inputs, &targets);
+
+        let factor = (1f64 - 0.2 * 0.1).powi(10);
+        assert_eq!(params[0], 1.0);
+        assert!((params[1] - 2.0 * factor).abs() < 1e-12);
+        assert!((params[2] + 4.0 * factor).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sgd_follows_schedule() {
+        let inputs = Matrix::zeros(1, 1);
+        let targets = Matrix::zeros(1, 1);
+        let schedule = LrSchedule::CosineWarmRestarts { t_max: 4, eta_min: 0.05 };
+
+        // The decay of each step is proportional to the scheduled step size
+        let sgd = StochasticGD::new(0.1, 0.2, 10).with_weight_decay(0.1).with_schedule(schedule);
+        let params = sgd.optimize(&NoGradient, &[1.0, 2.0], &inputs, &targets);
+
+        let factor = (0..10).fold(1f64, |acc, t| acc * (1f64 - schedule.learning_rate(0.2, t) * 0.1));
+        assert!((params[1] - 2.0 * factor).abs() < 1e-12);
+    }
+
+    #[test]
+    fn adaptive_optimizers_follow_schedule() {
+        let inputs = Matrix::zeros(1, 1);
+        let targets = Matrix::zeros(1, 1);
+        let schedule = LrSchedule::StepDecay { step_size: 3, gamma: 0.5 };
+        let factor = (0..10).fold(1f64, |acc, t| acc * (1f64 - schedule.learning_rate(0.2, t) * 0.1));
+
+        // Without a gradient only the scheduled weight decay moves the parameters
+        let rms = RMSProp::new(0.2, 0.9, 1e-5, 10).with_weight_decay(0.1).with_schedule(schedule);
+        let params = rms.optimize(&NoGradient, &[1.0, 2.0], &inputs, &targets);
+        assert!((params[1] - 2.0 * factor).abs() < 1e-12);
+
+        let adam = Adam::new(0.2, 0.9, 0.999, 1e-8, 10).with_weight_decay(0.1).with_schedule(schedule);
+        let params = adam.optimize(&NoGradient, &[1.0, 2.0], &inputs, &targets);
+        assert!((params[1] - 2.0 * factor).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn schedule_zero_decay_interval() {
+        let schedule = LrSchedule::StepDecay { step_size: 0, gamma: 0.5 };
+        let _ = Adam::default().with_schedule(schedule);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sgd_schedule_zero_cycle() {
+        let schedule = LrSchedule::CosineAnnealing { t_max: 0, eta_min: 0.0 };
+        let _ = StochasticGD::default().with_schedule(schedule);
+    }
+
+    #[test]
+    fn sgd_grad_accumulation() {
+        let start = [1.0, -2.0];
+        let targets = Matrix::zeros(4, 1);
+
+        // Four identical samples accumulate to the gradient of one
+        let inputs = Matrix::new(4, 1, vec![0.5; 4]);
+        let sgd = StochasticGD::new(0.1, 0.2, 1).with_grad_accumulation(4);
+        let accumulated = sgd.optimize(&Quadratic, &start, &inputs, &targets);
+
+        let single = StochasticGD::new(0.1, 0.2, 1)
+            .optimize(&Quadratic, &start, &inputs.select_rows(&[0]), &targets.select_rows(&[0]));
+        for (a, s) in accumulated.iter().zip(single.iter()) {
+            assert!((a - s).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn sgd_grad_accumulation_partial_group() {
+        let inputs = Matrix::new(5, 1, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        let targets = Matrix::zeros(5, 1);
+        let mut state = OptimState::new();
+
+        // The last sample of each pass is applied on its own
+        let sgd = StochasticGD::new(0.1, 0.2, 3).with_grad_accumulation(4);
+        let _ = sgd.resume(&Quadratic, &[1.0], &mut state, &inputs, &targets, 3);
+        assert_eq!(state.steps(), 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sgd_zero_grad_accumulation() {
+        let _ = StochasticGD::default().with_grad_accumulation(0);
+    }
+
+    #[test]
+    fn sgd_mini_batches() {
+        let inputs = Matrix::new(7, 1, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let targets = Matrix::zeros(7, 1);
+        let mut state = OptimState::new();
+
+        // Batches of 3, 3 and 1 rows in each pass
+        let sgd = StochasticGD::new(0.1, 0.2, 4).with_batch_size(3);
+        let _ = sgd.resume(&Quadratic, &[1.0], &mut state, &inputs, &targets, 4);
+        assert_eq!(state.steps(), 12);
+
+        // Batches are grouped by gradient accumulation
+        let mut state = OptimState::new();
+        let sgd = sgd.with_grad_accumulation(2);
+        let _ = sgd.resume(&Quadratic, &[1.0], &mut state, &inputs, &targets, 4);
+        assert_eq!(state.steps(), 8);
+    }
+
+    #[test]
+    fn sgd_deterministic_shuffles() {
+        let inputs = Matrix::new(6, 1, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let targets = Matrix::zeros(6, 1);
+        let start = [10.0, -3.0];
+
+        let seeded = StochasticGD::new(0.1, 0.2, 5).with_batch_size(2).with_seed(7);
+        assert_eq!(seeded.optimize(&Quadratic, &start, &inputs, &targets),
+                   seeded.optimize(&Quadratic, &start, &inputs, &targets));
+
+        let unshuffled = StochasticGD::new(0.1, 0.2, 5).with_shuffle(false);
+        let params = unshuffled.optimize(&Quadratic, &start, &inputs, &targets);
+        assert_eq!(params, unshuffled.optimize(&Quadratic, &start, &inputs, &targets));
+
+        // A different seed visits the rows in different orders
+        let other = StochasticGD::new(0.1, 0.2, 5).with_batch_size(2).with_seed(8);
+        assert!(seeded.optimize(&Quadratic, &start, &inputs, &targets) !=
+                other.optimize(&Quadratic, &start, &inputs, &targets));
+    }
+
+    #[test]
+    #[should_panic]
+    fn sgd_zero_batch_size() {
+        let _ = StochasticGD::default().with_batch_size(0);
+    }
+
+    #[test]
+    fn convergence_stops_on_unchanged_cost() {
+        // Without a tolerance only an unchanged cost stops the optimization
+        let mut convergence = Convergence::new(None);
+        assert!(!convergence.update(0.0));
+        assert!(!convergence.update(1.0));
+        assert!(!convergence.update(1.5));
+        assert!(convergence.update(1.5));
+
+        // The plateau detector stops once the cost no longer improves
+        let mut convergence = Convergence::new(Some(PlateauDetector::new(0.1, 2)));
+        assert!(!convergence.update(1.0));
+        assert!(!convergence.update(0.95));
+        assert!(convergence.update(0.97));
+    }
+
+    #[test]
+    fn sgd_stops_at_tolerance() {
+        let inputs = Matrix::new(4, 1, vec![1.0; 4]);
+        let targets = Matrix::zeros(4, 1);
+
+        let sgd = StochasticGD::new(0.1, 0.1, 1000).with_tolerance(0.0, 1e-4, 3);
+        let (_, history) = sgd.resume(&Quadratic, &[5.0], &mut OptimState::new(), &inputs, &targets, 1000);
+        assert!(history.iterations() < 200);
+        assert!(history.converged());
+
+        // An absolute tolerance stops it as well
+        let sgd_abs = StochasticGD::new(0.1, 0.1, 1000).with_tolerance(1e-6, 0.0, 3);
+        let (_, history) = sgd_abs.resume(&Quadratic, &[5.0], &mut OptimState::new(), &inputs, &targets, 1000);
+        assert!(history.iterations() < 1000);
+        assert!(history.converged());
+
+        // Running out of iterations is not convergence
+        let (_, history) = sgd.resume(&Quadratic, &[5.0], &mut OptimState::new(), &inputs, &targets, 5);
+        assert_eq!(history.iterations(), 5);
+        assert!(!history.converged());
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_convergence_patience() {
+        let _ = RMSProp::default().with_tolerance(0.0, 1e-3, 0);
+    }
+
+    #[test]
+    fn no_weight_decay_by_default() {
+        let inputs = Matrix::zeros(1, 1);
+        let targets = Matrix::zeros(1, 1);
+        let start = [1.0, 2.0, -4.0];
+
+        let params = RMSProp::default().optimize(&NoGradient, &start, &inputs, &targets);
+        assert_eq!(params, start.to_vec());
+    }
+
+    #[test]
+    #[should_panic]
+    fn weight_decay_negative() {
+        let _ = AdaGrad::default().with_weight_decay(-0.1);
+    }
 }
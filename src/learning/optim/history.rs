@@ -0,0 +1,193 @@
+//! Training History
+//!
+//! Contains the `TrainingHistory` struct which records diagnostics
+//! for each iteration of an optimization algorithm, and whether the
+//! optimization converged.
+//!
+//! The history is returned by `OptimAlgorithm::optimize_with_history`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::learning::optim::TrainingHistory;
+//!
+//! let mut history = TrainingHistory::new();
+//! history.record_iteration(0.5, &[1.0, 0.0], &[0.999, 0.0]);
+//!
+//! assert_eq!(history.costs(), &[0.5]);
+//! assert_eq!(history.final_cost(), Some(0.5));
+//! assert!(!history.converged());
+//! assert!((history.update_ratios()[0] - 1e-3).abs() < 1e-12);
+//!
+//! // The gradient norm of each parameter group
+//! history.record_grad_norms(&[3.0, 4.0], &[0..1, 0..2]);
+//! assert_eq!(history.grad_norms()[0], vec![3.0, 5.0]);
+//! ```
+
+use std::ops::Range;
+
+/// Diagnostics recorded for each iteration of an optimization.
+#[derive(Clone, Debug, Default)]
+pub struct TrainingHistory {
+    /// The cost at each iteration
+    costs: Vec<f64>,
+    /// The ratio of the update norm to the parameter norm at each iteration
+    update_ratios: Vec<f64>,
+    /// The gradient norm of each parameter group at each iteration
+    grad_norms: Vec<Vec<f64>>,
+    /// Whether the optimization stopped before its last iteration
+    converged: bool,
+}
+
+impl TrainingHistory {
+    /// Constructs an empty training history.
+    pub fn new() -> TrainingHistory {
+        TrainingHistory::default()
+    }
+
+    /// Record an iteration from its cost and the parameters before and after it.
+    pub fn record_iteration(&mut self, cost: f64, before: &[f64], after: &[f64]) {
+        self.costs.push(cost);
+        self.update_ratios.push(update_ratio(before, after));
+    }
+
+    /// Record the L2 norm of the gradient within each group of parameters.
+    ///
+    /// The groups are given by `Optimizable::param_groups`.
+    pub fn record_grad_norms(&mut self, grad: &[f64], groups: &[Range<usize>]) {
+        self.grad_norms.push(groups.iter()
+            .map(|r| grad[r.clone()].iter().fold(0f64, |acc, g| acc + g * g).sqrt())
+            .collect());
+    }
+
+    /// Record that the optimization converged.
+    ///
+    /// Called by the algorithms when they stop early because the cost
+    /// is unchanged or has plateaued.
+    pub fn record_convergence(&mut self) {
+        self.converged = true;
+    }
+
+    /// Appends the iterations recorded in another history.
+    ///
+    /// This joins the histories of an optimization which was resumed.
+    /// The joined history has converged if the later one has.
+    pub fn append(&mut self, mut other: TrainingHistory) {
+        self.costs.append(&mut other.costs);
+        self.update_ratios.append(&mut other.update_ratios);
+        self.grad_norms.append(&mut other.grad_norms);
+        self.converged = other.converged;
+    }
+
+    /// The number of recorded iterations.
+    pub fn iterations(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// The cost of each iteration.
+    ///
+    /// For stochastic algorithms this is the average cost over the pass.
+    pub fn costs(&self) -> &[f64] {
+        &self.costs
+    }
+
+    /// The cost of the last iteration, `None` if no iterations were recorded.
+    pub fn final_cost(&self) -> Option<f64> {
+        self.costs.last().cloned()
+    }
+
+    /// Whether the optimization converged.
+    ///
+    /// This is true when the algorithm stopped before running all of its
+    /// iterations, because the cost stopped changing or plateaued within
+    /// the algorithm's tolerance. It is false when it ran out of iterations.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Whether the cost became infinite or `NaN`.
+    ///
+    /// This usually means that the learning rate is too high.
+    pub fn diverged(&self) -> bool {
+        self.costs.iter().any(|c| !c.is_finite())
+    }
+
+    /// The ratio `||Δw|| / ||w||` of each iteration.
+    ///
+    /// A healthy learning rate typically gives ratios around `1e-3`.
+    /// Much larger ratios suggest the learning rate is too high, much
+    /// smaller ones that it is too low.
+    pub fn update_ratios(&self) -> &[f64] {
+        &self.update_ratios
+    }
+
+    /// The gradient norm of each parameter group at each iteration.
+    ///
+    /// For stochastic algorithms this is the norm of the mean gradient
+    /// over the pass. For a neural network each group is a layer, so
+    /// norms shrinking towards the input layers indicate vanishing
+    /// gradients and growing norms exploding gradients.
+    pub fn grad_norms(&self) -> &[Vec<f64>] {
+        &self.grad_norms
+    }
+}
+
+/// Computes `||after - before|| / ||before||`.
+///
+/// When the parameters start at zero the norm of the updated parameters
+/// is used instead, and a zero update of zero parameters has ratio zero.
+fn update_ratio(before: &[f64], after: &[f64]) -> f64 {
+    debug_assert_eq!(before.len(), after.len());
+    let update_norm = before.iter()
+        .zip(after)
+        .fold(0f64, |acc, (b, a)| acc + (a - b) * (a - b))
+        .sqrt();
+    let before_norm = before.iter().fold(0f64, |acc, x| acc + x * x).sqrt();
+
+    if before_norm > 0f64 {
+        update_norm / before_norm
+    } else {
+        let after_norm = after.iter().fold(0f64, |acc, x| acc + x * x).sqrt();
+        if after_norm > 0f64 {
+            update_norm / after_norm
+        } else {
+            0f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{update_ratio, TrainingHistory};
+    use std::f64;
+
+    #[test]
+    fn update_ratio_zero_params() {
+        assert_eq!(update_ratio(&[0.0, 0.0], &[0.0, 0.0]), 0.0);
+        assert_eq!(update_ratio(&[0.0, 0.0], &[3.0, 4.0]), 1.0);
+        assert_eq!(update_ratio(&[3.0, 4.0], &[3.0, 4.5]), 0.1);
+    }
+
+    #[test]
+    fn append_histories() {
+        let mut history = TrainingHistory::new();
+        history.record_iteration(1.0, &[1.0], &[0.5]);
+        history.record_grad_norms(&[2.0], &[0..1]);
+
+        let mut other = TrainingHistory::new();
+        other.record_iteration(0.5, &[0.5], &[0.5]);
+        other.record_grad_norms(&[1.0], &[0..1]);
+        other.record_convergence();
+
+        history.append(other);
+        assert!(history.converged());
+        assert_eq!(history.final_cost(), Some(0.5));
+        assert_eq!(history.costs(), &[1.0, 0.5]);
+        assert_eq!(history.update_ratios(), &[0.5, 0.0]);
+        assert_eq!(history.grad_norms(), &[vec![2.0], vec![1.0]]);
+        assert!(!history.diverged());
+
+        history.record_iteration(f64::NAN, &[0.5], &[0.5]);
+        assert!(history.diverged());
+    }
+}
@@ -0,0 +1,152 @@
+//! Learning Rate Schedules
+//!
+//! Contains the `LrSchedule` enum which varies the learning rate of
+//! an optimization algorithm with the number of parameter updates.
+//! It is used by `StochasticGD`, `RMSProp` and `Adam`.
+//!
+//! A schedule is a pure function of the step count, so an optimization
+//! resumed from an `OptimState` continues the schedule where it left off.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::learning::optim::LrSchedule;
+//!
+//! let schedule = LrSchedule::CosineWarmRestarts { t_max: 100, eta_min: 0.001 };
+//!
+//! // The rate decays to the minimum, then restarts from the maximum
+//! assert_eq!(schedule.learning_rate(0.1, 0), 0.1);
+//! assert!(schedule.learning_rate(0.1, 99) < 0.002);
+//! assert_eq!(schedule.learning_rate(0.1, 100), 0.1);
+//! ```
+
+use std::f64::consts::PI;
+
+/// The learning rate at each step of an optimization.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LrSchedule {
+    /// The learning rate does not change.
+    Constant,
+    /// Step decay.
+    ///
+    /// The learning rate is multiplied by `gamma` every `step_size` steps.
+    StepDecay {
+        /// The number of steps between each decay
+        step_size: usize,
+        /// The factor applied at each decay
+        gamma: f64,
+    },
+    /// Exponential decay.
+    ///
+    /// At step `t` the rate is `lr * gamma^t`.
+    ExponentialDecay {
+        /// The factor applied at each step
+        gamma: f64,
+    },
+    /// Inverse time (1/t) decay.
+    ///
+    /// At step `t` the rate is `lr / (1 + decay * t)`.
+    InverseTimeDecay {
+        /// How quickly the rate decays
+        decay: f64,
+    },
+    /// Cosine annealing from the learning rate down to `eta_min`.
+    ///
+    /// At step `t` the rate is
+    /// `eta_min + 0.5 * (lr - eta_min) * (1 + cos(pi * t / t_max))`,
+    /// and it stays at `eta_min` after `t_max` steps.
+    CosineAnnealing {
+        /// The number of steps taken to reach the minimum
+        t_max: usize,
+        /// The minimum learning rate
+        eta_min: f64,
+    },
+    /// Cosine annealing with warm restarts (SGDR).
+    ///
+    /// The cosine cycle of `CosineAnnealing` restarts from the full
+    /// learning rate every `t_max` steps.
+    CosineWarmRestarts {
+        /// The number of steps in each cycle
+        t_max: usize,
+        /// The minimum learning rate
+        eta_min: f64,
+    },
+}
+
+/// Keeps the learning rate constant.
+impl Default for LrSchedule {
+    fn default() -> LrSchedule {
+        LrSchedule::Constant
+    }
+}
+
+impl LrSchedule {
+    /// The learning rate after `step` parameter updates, starting from `lr`.
+    pub fn learning_rate(&self, lr: f64, step: usize) -> f64 {
+        match *self {
+            LrSchedule::Constant => lr,
+            LrSchedule::StepDecay { step_size, gamma } => {
+                lr * gamma.powi((step / step_size) as i32)
+            }
+            LrSchedule::ExponentialDecay { gamma } => lr * gamma.powi(step as i32),
+            LrSchedule::InverseTimeDecay { decay } => lr / (1f64 + decay * step as f64),
+            LrSchedule::CosineAnnealing { t_max, eta_min } => {
+                cosine(lr, eta_min, step.min(t_max), t_max)
+            }
+            LrSchedule::CosineWarmRestarts { t_max, eta_min } => {
+                cosine(lr, eta_min, step % t_max, t_max)
+            }
+        }
+    }
+}
+
+/// The cosine annealed learning rate at step `t` of a cycle of `t_max` steps.
+fn cosine(lr: f64, eta_min: f64, t: usize, t_max: usize) -> f64 {
+    eta_min + 0.5 * (lr - eta_min) * (1f64 + (PI * t as f64 / t_max as f64).cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LrSchedule;
+
+    #[test]
+    fn cosine_annealing_closed_form() {
+        let schedule = LrSchedule::CosineAnnealing { t_max: 10, eta_min: 0.01 };
+
+        assert_eq!(schedule.learning_rate(0.1, 0), 0.1);
+        assert!((schedule.learning_rate(0.1, 5) - 0.055).abs() < 1e-12);
+        assert!((schedule.learning_rate(0.1, 10) - 0.01).abs() < 1e-12);
+
+        // The rate stays at the minimum
+        assert!((schedule.learning_rate(0.1, 25) - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn decay_schedules() {
+        let step = LrSchedule::StepDecay { step_size: 3, gamma: 0.5 };
+        assert_eq!(step.learning_rate(0.1, 0), 0.1);
+        assert_eq!(step.learning_rate(0.1, 2), 0.1);
+        assert_eq!(step.learning_rate(0.1, 3), 0.05);
+        assert_eq!(step.learning_rate(0.1, 7), 0.025);
+
+        let exp = LrSchedule::ExponentialDecay { gamma: 0.9 };
+        assert_eq!(exp.learning_rate(0.1, 0), 0.1);
+        assert!((exp.learning_rate(0.1, 2) - 0.081).abs() < 1e-12);
+
+        let inv = LrSchedule::InverseTimeDecay { decay: 0.5 };
+        assert_eq!(inv.learning_rate(0.1, 0), 0.1);
+        assert!((inv.learning_rate(0.1, 4) - 0.1 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn warm_restarts() {
+        let schedule = LrSchedule::CosineWarmRestarts { t_max: 10, eta_min: 0.01 };
+
+        assert!((schedule.learning_rate(0.1, 5) - 0.055).abs() < 1e-12);
+        assert!(schedule.learning_rate(0.1, 9) < 0.015);
+        // Each cycle starts again from the full rate
+        assert_eq!(schedule.learning_rate(0.1, 10), 0.1);
+        assert_eq!(schedule.learning_rate(0.1, 30), 0.1);
+        assert_eq!(schedule.learning_rate(0.1, 15), schedule.learning_rate(0.1, 5));
+    }
+}
@@ -0,0 +1,94 @@
+//! Optimizer State
+//!
+//! Contains the `OptimState` struct which holds the internal buffers
+//! of an optimization algorithm between calls, such as the momentum
+//! of stochastic gradient descent.
+//!
+//! The state is used by `ResumableOptimAlgorithm` to continue an
+//! optimization where a previous call left off.
+
+use linalg::Vector;
+
+/// The internal buffers of an optimization algorithm.
+///
+/// Each algorithm decides how many buffers it needs and what they
+/// mean. A new state is empty and algorithms initialize the buffers
+/// to zero on first use.
+#[derive(Clone, Debug, Default)]
+pub struct OptimState {
+    /// Per-parameter buffers, e.g. velocities or moment estimates
+    buffers: Vec<Vector<f64>>,
+    /// The number of parameter updates taken so far
+    steps: usize,
+}
+
+impl OptimState {
+    /// Constructs an empty state.
+    pub fn new() -> OptimState {
+        OptimState::default()
+    }
+
+    /// Whether the state has not yet been used by an algorithm.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty() && self.steps == 0
+    }
+
+    /// The number of parameter updates taken so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Record that some parameter updates were taken.
+    pub fn add_steps(&mut self, steps: usize) {
+        self.steps += steps;
+    }
+
+    /// Takes the buffers out of the state.
+    ///
+    /// If the state does not hold `count` buffers of length `len`, for
+    /// example because it is new, then `count` zeroed buffers are returned.
+    pub fn take_buffers(&mut self, count: usize, len: usize) -> Vec<Vector<f64>> {
+        let buffers = ::std::mem::replace(&mut self.buffers, Vec::new());
+        if buffers.len() == count && buffers.iter().all(|b| b.size() == len) {
+            buffers
+        } else {
+            (0..count).map(|_| Vector::zeros(len)).collect()
+        }
+    }
+
+    /// Stores the buffers in the state for the next call.
+    pub fn set_buffers(&mut self, buffers: Vec<Vector<f64>>) {
+        self.buffers = buffers;
+    }
+
+    /// Clears the buffers and step count.
+    pub fn reset(&mut self) {
+        self.buffers.clear();
+        self.steps = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OptimState;
+    use linalg::Vector;
+
+    #[test]
+    fn take_buffers_initializes_zeros() {
+        let mut state = OptimState::new();
+        assert!(state.is_empty());
+
+        let buffers = state.take_buffers(2, 3);
+        assert_eq!(buffers.len(), 2);
+        assert!(buffers.iter().all(|b| b.data() == &[0.0; 3]));
+
+        state.set_buffers(vec![Vector::new(vec![1.0, 2.0, 3.0])]);
+        state.add_steps(4);
+        assert!(!state.is_empty());
+        assert_eq!(state.take_buffers(1, 3)[0].data(), &[1.0, 2.0, 3.0]);
+
+        // Mismatched sizes are reinitialized
+        state.set_buffers(vec![Vector::new(vec![1.0, 2.0, 3.0])]);
+        assert_eq!(state.take_buffers(1, 2)[0].data(), &[0.0; 2]);
+    }
+}
@@ -26,6 +26,11 @@
 
 use linalg::{Matrix, BaseMatrix, Axes};
 use linalg::Vector;
+use linalg::norm::Euclidean;
+
+use rand::thread_rng;
+use rand::distributions::Sample;
+use rand::distributions::normal::Normal;
 
 use learning::{LearningResult, UnSupModel};
 use learning::error::{Error, ErrorKind};
@@ -87,8 +92,105 @@ impl PCA {
             Some(ref rot) => { Ok(rot) }
         }
     }
+
+    /// Trains the model using power iteration instead of a full SVD.
+    ///
+    /// Each of the top `n_components` principal components is found by
+    /// repeatedly multiplying a vector by the covariance of the inputs,
+    /// removing any projection onto the components which were already
+    /// found (deflation). Every component is iterated at most `n_iter`
+    /// times and stops early once it has converged.
+    ///
+    /// This avoids decomposing the full matrix and so is much cheaper
+    /// when only a few components of a large dataset are required.
+    ///
+    /// The rate of convergence is governed by the ratio between successive
+    /// eigenvalues. When two eigenvalues are close together the matching
+    /// components converge slowly and, if `n_iter` is too small, may be
+    /// returned as a mixture of the true eigenvectors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::pca::PCA;
+    /// use rusty_machine::linalg::{Matrix, BaseMatrix};
+    ///
+    /// let inputs = Matrix::new(4, 2, vec![1., 2.,
+    ///                                     2., 4.1,
+    ///                                     3., 5.9,
+    ///                                     4., 8.]);
+    ///
+    /// let mut pca = PCA::default();
+    /// pca.fit_power_iteration(&inputs, 1, 100).unwrap();
+    ///
+    /// assert_eq!(pca.components().unwrap().cols(), 1);
+    /// ```
+    pub fn fit_power_iteration(&mut self,
+                               inputs: &Matrix<f64>,
+                               n_components: usize,
+                               n_iter: usize)
+                               -> LearningResult<()> {
+        if n_components > inputs.cols() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                       "Input data must have equal or larger number of columns than n"));
+        }
+
+        let data = self.prepare_inputs(inputs);
+        let data_t = data.transpose();
+
+        let mut distro = Normal::new(0.0, 1.0);
+        let mut rng = thread_rng();
+
+        let mut found: Vec<Vector<f64>> = Vec::with_capacity(n_components);
+        for _ in 0..n_components {
+            let mut v = Vector::from_fn(inputs.cols(), |_| distro.sample(&mut rng));
+            deflate(&mut v, &found);
+            v = normalize(v);
+
+            for _ in 0..n_iter {
+                let mut w = &data_t * (&data * &v);
+                deflate(&mut w, &found);
+
+                // The remaining data has no variance in any new direction.
+                if w.norm(Euclidean) == 0.0 {
+                    break;
+                }
+
+                let w = normalize(w);
+                let change = (&w - &v).norm(Euclidean);
+                v = w;
+
+                if change < POWER_ITER_TOL {
+                    break;
+                }
+            }
+            found.push(v);
+        }
+
+        self.components = Some(Matrix::from_fn(inputs.cols(), n_components,
+                                               |c, r| found[c][r]));
+        self.n = Some(n_components);
+        self.n_features = Some(inputs.cols());
+        self.inv = false;
+        Ok(())
+    }
+
+    /// Centers the inputs if required, storing the centers.
+    fn prepare_inputs(&mut self, inputs: &Matrix<f64>) -> Matrix<f64> {
+        if self.center == true {
+            let centers = inputs.mean(Axes::Row);
+            let m = unsafe { centering(inputs, &centers) };
+            self.centers = Some(centers);
+            m
+        } else {
+            inputs.clone()
+        }
+    }
 }
 
+/// Convergence tolerance for the power iteration.
+const POWER_ITER_TOL: f64 = 1e-10;
+
 /// The default PCA.
 ///
 /// Parameters:
@@ -173,14 +275,7 @@ impl UnSupModel<Matrix<f64>, Matrix<f64>> for PCA {
             }
         }
 
-        let data = if self.center == true {
-            let centers = inputs.mean(Axes::Row);
-            let m = unsafe { centering(inputs, &centers) };
-            self.centers = Some(centers);
-            m
-        } else {
-            inputs.clone()
-        };
+        let data = self.prepare_inputs(inputs);
         let (_, _, mut v) = data.svd().unwrap();
         if inputs.cols() > inputs.rows() {
             v = v.transpose();
@@ -199,6 +294,20 @@ impl UnSupModel<Matrix<f64>, Matrix<f64>> for PCA {
     }
 }
 
+/// Removes the projection of `v` onto each of the orthonormal `basis` vectors.
+fn deflate(v: &mut Vector<f64>, basis: &[Vector<f64>]) {
+    for b in basis {
+        let proj = v.dot(b);
+        *v -= b * proj;
+    }
+}
+
+/// Scales the vector to unit length.
+fn normalize(v: Vector<f64>) -> Vector<f64> {
+    let norm = v.norm(Euclidean);
+    v / norm
+}
+
 /// Subtract center Vector from each rows
 unsafe fn centering(inputs: &Matrix<f64>, centers: &Vector<f64>) -> Matrix<f64> {
     // Number of inputs columns and centers length must be the same
@@ -0,0 +1,682 @@
+//! Random Forests
+//!
+//! Contains a random forest classifier. The forest is an ensemble of
+//! decision trees, each grown on a bootstrap sample of the training data.
+//! At every split a tree only considers a random subset of the features,
+//! which decorrelates the trees. Predictions are the majority vote of
+//! the trees.
+//!
+//! The trees are grown greedily by minimizing the Gini impurity, until
+//! their leaves are pure or the depth and size limits are reached.
+//!
+//! The training data left out of each bootstrap sample gives an estimate
+//! of the prediction error without a separate validation set, see
+//! `RandomForest::oob_error`. With the `parallel` feature the trees are
+//! grown on several threads.
+//!
+//! # Usage
+//!
+//! ```
+//! use rusty_machine::learning::random_forest::RandomForest;
+//! use rusty_machine::learning::SupModel;
+//! use rusty_machine::linalg::{Matrix, Vector};
+//!
+//! let inputs = Matrix::new(6, 2, vec![1.0, 1.0,
+//!                                     1.5, 2.0,
+//!                                     2.0, 1.5,
+//!                                     6.0, 5.0,
+//!                                     7.0, 6.5,
+//!                                     6.5, 7.0]);
+//! let targets = Vector::new(vec![0, 0, 0, 1, 1, 1]);
+//!
+//! let mut forest = RandomForest::new(20).with_seed(1);
+//! forest.train(&inputs, &targets).unwrap();
+//!
+//! let outputs = forest.predict(&Matrix::new(2, 2, vec![1.2, 1.4, 6.8, 6.1])).unwrap();
+//! assert_eq!(outputs, Vector::new(vec![0, 1]));
+//! ```
+
+use linalg::{Matrix, BaseMatrix, Vector};
+use learning::{LearningResult, SupModel};
+use learning::error::{Error, ErrorKind};
+
+use rand::{thread_rng, Rng, SeedableRng, StdRng};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Random Forest classifier
+///
+/// The classes are given as integers from zero, as for `KNNClassifier`.
+#[derive(Debug)]
+pub struct RandomForest {
+    /// The number of trees
+    n_trees: usize,
+    /// The number of features considered at each split
+    max_features: Option<usize>,
+    /// The largest depth of each tree
+    max_depth: Option<usize>,
+    /// The fewest samples which a node needs to be split
+    min_samples_split: usize,
+    /// The seed of the bootstrap samples and feature subsets
+    seed: Option<usize>,
+    /// The trained forest
+    fit: Option<Forest>,
+}
+
+/// A trained forest.
+#[derive(Debug)]
+struct Forest {
+    trees: Vec<Tree>,
+    /// The number of input columns
+    n_features: usize,
+    /// The number of classes seen in training
+    n_classes: usize,
+    /// The out-of-bag misclassification rate
+    oob_error: Option<f64>,
+    /// The normalized impurity decrease of each feature
+    importances: Vector<f64>,
+}
+
+/// Constructs an untrained forest of 100 trees.
+impl Default for RandomForest {
+    fn default() -> RandomForest {
+        RandomForest::new(100)
+    }
+}
+
+impl RandomForest {
+    /// Constructs an untrained forest with the given number of trees.
+    ///
+    /// By default each split considers the square root of the number of
+    /// features, and the trees are grown until their leaves are pure.
+    ///
+    /// # Panics
+    ///
+    /// - The number of trees is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::random_forest::RandomForest;
+    ///
+    /// let forest = RandomForest::new(50).with_max_depth(8);
+    /// ```
+    pub fn new(n_trees: usize) -> RandomForest {
+        assert!(n_trees > 0, "A random forest needs at least one tree.");
+
+        RandomForest {
+            n_trees: n_trees,
+            max_features: None,
+            max_depth: None,
+            min_samples_split: 2,
+            seed: None,
+            fit: None,
+        }
+    }
+
+    /// Sets the number of features considered at each split.
+    ///
+    /// When none of these features can split a node, further features
+    /// are tried. Considering every feature gives bagged decision trees.
+    ///
+    /// # Panics
+    ///
+    /// - The number of features is zero.
+    pub fn with_max_features(mut self, max_features: usize) -> RandomForest {
+        assert!(max_features > 0, "At least one feature must be considered at each split.");
+        self.max_features = Some(max_features);
+        self
+    }
+
+    /// Sets the largest depth of each tree.
+    pub fn with_max_depth(mut self, max_depth: usize) -> RandomForest {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the fewest samples which a node needs to be split.
+    ///
+    /// # Panics
+    ///
+    /// - The number of samples is less than two.
+    pub fn with_min_samples_split(mut self, min_samples_split: usize) -> RandomForest {
+        assert!(min_samples_split >= 2, "A node needs at least two samples to be split.");
+        self.min_samples_split = min_samples_split;
+        self
+    }
+
+    /// Sets the seed of the bootstrap samples and feature subsets.
+    ///
+    /// A forest trained with a seed is the same each time, with or
+    /// without the `parallel` feature.
+    pub fn with_seed(mut self, seed: usize) -> RandomForest {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The number of trees in the forest.
+    pub fn n_trees(&self) -> usize {
+        self.n_trees
+    }
+
+    /// The out-of-bag estimate of the misclassification rate.
+    ///
+    /// Each training sample is classified by the trees whose bootstrap
+    /// sample left it out, and the error is the fraction of these samples
+    /// which are misclassified. This estimates the error on unseen data.
+    ///
+    /// Returns `None` if the forest is untrained, or if every sample was
+    /// in the bootstrap sample of every tree.
+    pub fn oob_error(&self) -> Option<f64> {
+        self.fit.as_ref().and_then(|fit| fit.oob_error)
+    }
+
+    /// The importance of each feature.
+    ///
+    /// This is the decrease in Gini impurity of the splits on each feature,
+    /// weighted by the number of samples reaching the splits and summed
+    /// over the trees. The importances are normalized to sum to one, or are
+    /// all zero if no tree was split.
+    ///
+    /// Returns `None` if the forest is untrained.
+    pub fn feature_importances(&self) -> Option<&Vector<f64>> {
+        self.fit.as_ref().map(|fit| &fit.importances)
+    }
+
+    /// The fraction of the trees voting for each class.
+    ///
+    /// The result has a row for each input and a column for each class
+    /// seen in training.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::random_forest::RandomForest;
+    /// use rusty_machine::learning::SupModel;
+    /// use rusty_machine::linalg::{Matrix, BaseMatrix, Vector};
+    ///
+    /// let inputs = Matrix::new(4, 1, vec![0.0, 1.0, 2.0, 3.0]);
+    /// let targets = Vector::new(vec![0, 0, 1, 2]);
+    ///
+    /// let mut forest = RandomForest::new(10);
+    /// forest.train(&inputs, &targets).unwrap();
+    ///
+    /// let votes = forest.predict_proba(&Matrix::new(1, 1, vec![0.5])).unwrap();
+    /// assert_eq!(votes.cols(), 3);
+    /// ```
+    pub fn predict_proba(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        let fit = try!(self.trained(inputs));
+        let mut votes = try!(fit.votes(inputs));
+        let n_trees = fit.trees.len() as f64;
+        for v in votes.mut_data() {
+            *v /= n_trees;
+        }
+        Ok(votes)
+    }
+
+    /// The trained forest, checking the inputs match it.
+    fn trained(&self, inputs: &Matrix<f64>) -> LearningResult<&Forest> {
+        match self.fit {
+            Some(ref fit) => {
+                if inputs.cols() != fit.n_features {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          "Input data must have the same number of columns as \
+                                           training data."));
+                }
+                Ok(fit)
+            }
+            None => Err(Error::new_untrained()),
+        }
+    }
+
+    /// Grows each tree of the forest.
+    #[cfg(not(feature = "parallel"))]
+    fn grow_trees(&self, data: &TrainingData, seed: usize) -> Vec<GrownTree> {
+        (0..self.n_trees).map(|idx| data.grow_tree(seed, idx)).collect()
+    }
+
+    /// Grows the trees of the forest in parallel.
+    #[cfg(feature = "parallel")]
+    fn grow_trees(&self, data: &TrainingData, seed: usize) -> Vec<GrownTree> {
+        (0..self.n_trees).into_par_iter().map(|idx| data.grow_tree(seed, idx)).collect()
+    }
+}
+
+impl SupModel<Matrix<f64>, Vector<usize>> for RandomForest {
+    /// Predict the class of each input by the majority vote of the trees.
+    ///
+    /// Ties go to the smallest class.
+    fn predict(&self, inputs: &Matrix<f64>) -> LearningResult<Vector<usize>> {
+        let fit = try!(self.trained(inputs));
+        let votes = try!(fit.votes(inputs));
+        Ok(Vector::new(votes.row_iter().map(|row| argmax(row.raw_slice())).collect::<Vec<_>>()))
+    }
+
+    /// Train the forest on the inputs and their classes.
+    ///
+    /// Returns an `InvalidData` error if there is no data, the number of
+    /// targets does not match the inputs, or the inputs are not finite.
+    /// Returns an `InvalidParameters` error if more features are to be
+    /// considered at each split than the inputs have.
+    fn train(&mut self, inputs: &Matrix<f64>, targets: &Vector<usize>) -> LearningResult<()> {
+        if inputs.rows() == 0 || inputs.cols() == 0 {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Cannot train a random forest without data."));
+        }
+        if inputs.rows() != targets.size() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Number of inputs does not match the number of targets."));
+        }
+        if inputs.data().iter().any(|x| !x.is_finite()) {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The training inputs must be finite."));
+        }
+
+        let n_features = inputs.cols();
+        let max_features = match self.max_features {
+            Some(m) if m > n_features => {
+                return Err(Error::new(ErrorKind::InvalidParameters,
+                                      format!("Cannot consider {} of {} features at each split.",
+                                              m,
+                                              n_features)))
+            }
+            Some(m) => m,
+            None => ((n_features as f64).sqrt().round() as usize).max(1),
+        };
+
+        let data = TrainingData {
+            inputs: inputs,
+            targets: targets.data(),
+            n_classes: targets.iter().cloned().max().unwrap() + 1,
+            max_features: max_features,
+            max_depth: self.max_depth,
+            min_samples_split: self.min_samples_split,
+        };
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+        let grown = self.grow_trees(&data, seed);
+
+        // Combine the out-of-bag votes and the importances of the trees
+        let mut oob_votes = vec![0usize; inputs.rows() * data.n_classes];
+        let mut importances = vec![0f64; n_features];
+        let mut trees = Vec::with_capacity(grown.len());
+        for tree in grown {
+            for (sample, class) in tree.oob_predictions {
+                oob_votes[sample * data.n_classes + class] += 1;
+            }
+            for (total, imp) in importances.iter_mut().zip(tree.importances) {
+                *total += imp;
+            }
+            trees.push(tree.tree);
+        }
+
+        let mut counted = 0;
+        let mut wrong = 0;
+        for (votes, &target) in oob_votes.chunks(data.n_classes).zip(targets.data()) {
+            if votes.iter().any(|&v| v > 0) {
+                counted += 1;
+                if argmax(votes) != target {
+                    wrong += 1;
+                }
+            }
+        }
+
+        let total = importances.iter().fold(0f64, |acc, x| acc + x);
+        if total > 0f64 {
+            for imp in &mut importances {
+                *imp /= total;
+            }
+        }
+
+        self.fit = Some(Forest {
+            trees: trees,
+            n_features: n_features,
+            n_classes: data.n_classes,
+            oob_error: if counted > 0 {
+                Some(wrong as f64 / counted as f64)
+            } else {
+                None
+            },
+            importances: Vector::new(importances),
+        });
+        Ok(())
+    }
+}
+
+impl Forest {
+    /// The number of trees voting for each class of each input.
+    fn votes(&self, inputs: &Matrix<f64>) -> LearningResult<Matrix<f64>> {
+        if inputs.data().iter().any(|x| x.is_nan()) {
+            return Err(Error::new(ErrorKind::InvalidData, "The inputs contain NaN."));
+        }
+
+        let mut votes = vec![0f64; inputs.rows() * self.n_classes];
+        for (row, row_votes) in inputs.row_iter().zip(votes.chunks_mut(self.n_classes)) {
+            for tree in &self.trees {
+                row_votes[tree.predict(row.raw_slice())] += 1f64;
+            }
+        }
+        Ok(Matrix::new(inputs.rows(), self.n_classes, votes))
+    }
+}
+
+/// The index of the largest value, the first if there are several.
+fn argmax<T: PartialOrd + Copy>(values: &[T]) -> usize {
+    let mut best = 0;
+    for (i, &v) in values.iter().enumerate() {
+        if v > values[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+/// A node of a decision tree.
+#[derive(Clone, Debug)]
+enum Node {
+    /// Predicts a class
+    Leaf(usize),
+    /// Sends inputs with `feature <= threshold` to the left child
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+/// A classification tree, with the root as the first node.
+#[derive(Clone, Debug)]
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// The class predicted for an input row.
+    fn predict(&self, row: &[f64]) -> usize {
+        let mut node = 0;
+        loop {
+            match self.nodes[node] {
+                Node::Leaf(class) => return class,
+                Node::Split { feature, threshold, left, right } => {
+                    node = if row[feature] <= threshold { left } else { right };
+                }
+            }
+        }
+    }
+}
+
+/// A tree with the statistics needed by the forest.
+struct GrownTree {
+    tree: Tree,
+    /// The weighted impurity decrease of each feature
+    importances: Vec<f64>,
+    /// The sample index and predicted class of each out-of-bag sample
+    oob_predictions: Vec<(usize, usize)>,
+}
+
+/// The best split of a node found so far.
+struct Split {
+    feature: usize,
+    threshold: f64,
+    /// The impurity decrease weighted by the number of samples
+    decrease: f64,
+}
+
+/// The training data and settings shared by the trees.
+struct TrainingData<'a> {
+    inputs: &'a Matrix<f64>,
+    targets: &'a [usize],
+    n_classes: usize,
+    max_features: usize,
+    max_depth: Option<usize>,
+    min_samples_split: usize,
+}
+
+impl<'a> TrainingData<'a> {
+    fn value(&self, sample: usize, feature: usize) -> f64 {
+        self.inputs[[sample, feature]]
+    }
+
+    /// Grows the tree with the given index on a bootstrap sample.
+    fn grow_tree(&self, seed: usize, idx: usize) -> GrownTree {
+        let mut rng = StdRng::from_seed(&[seed, idx]);
+        let n = self.inputs.rows();
+        let mut samples = (0..n).map(|_| rng.gen_range(0, n)).collect::<Vec<_>>();
+        let mut in_bag = vec![false; n];
+        for &s in &samples {
+            in_bag[s] = true;
+        }
+
+        let mut importances = vec![0f64; self.inputs.cols()];
+        let mut nodes = vec![Node::Leaf(0)];
+
+        // Each entry is a node with its range of samples and its depth
+        let mut stack = vec![(0, 0, n, 0)];
+        while let Some((node, start, end, depth)) = stack.pop() {
+            let node_samples = &mut samples[start..end];
+            let counts = self.class_counts(node_samples);
+            nodes[node] = Node::Leaf(argmax(&counts));
+
+            let pure = counts.iter().filter(|&&c| c > 0).count() < 2;
+            if pure || node_samples.len() < self.min_samples_split ||
+               self.max_depth.map_or(false, |d| depth >= d) {
+                continue;
+            }
+
+            if let Some(split) = self.best_split(node_samples, &counts, &mut rng) {
+                importances[split.feature] += split.decrease;
+
+                // Move the samples going left to the front
+                let mut mid = 0;
+                for i in 0..node_samples.len() {
+                    if self.value(node_samples[i], split.feature) <= split.threshold {
+                        node_samples.swap(i, mid);
+                        mid += 1;
+                    }
+                }
+
+                let left = nodes.len();
+                nodes.push(Node::Leaf(0));
+                nodes.push(Node::Leaf(0));
+                nodes[node] = Node::Split {
+                    feature: split.feature,
+                    threshold: split.threshold,
+                    left: left,
+                    right: left + 1,
+                };
+                stack.push((left, start, start + mid, depth + 1));
+                stack.push((left + 1, start + mid, end, depth + 1));
+            }
+        }
+
+        let tree = Tree { nodes: nodes };
+        let oob_predictions = (0..n)
+            .filter(|&s| !in_bag[s])
+            .map(|s| (s, tree.predict(self.inputs.row(s).raw_slice())))
+            .collect();
+
+        GrownTree {
+            tree: tree,
+            importances: importances,
+            oob_predictions: oob_predictions,
+        }
+    }
+
+    fn class_counts(&self, samples: &[usize]) -> Vec<usize> {
+        let mut counts = vec![0; self.n_classes];
+        for &s in samples {
+            counts[self.targets[s]] += 1;
+        }
+        counts
+    }
+
+    /// Finds the split with the lowest Gini impurity on a random subset of the features.
+    ///
+    /// Features which cannot split the samples, because they take a single
+    /// value, do not count towards `max_features`. Returns `None` if no
+    /// feature can split the samples.
+    fn best_split(&self, samples: &[usize], counts: &[usize], rng: &mut StdRng) -> Option<Split> {
+        let n = samples.len();
+        let parent = n as f64 * gini(counts, n);
+
+        let mut features = (0..self.inputs.cols()).collect::<Vec<_>>();
+        rng.shuffle(&mut features);
+
+        let mut best: Option<Split> = None;
+        let mut order = samples.to_vec();
+        let mut tried = 0;
+        for &feature in &features {
+            if tried == self.max_features {
+                break;
+            }
+
+            order.sort_by(|&a, &b| {
+                self.value(a, feature).partial_cmp(&self.value(b, feature)).unwrap()
+            });
+            if self.value(order[0], feature) == self.value(order[n - 1], feature) {
+                continue;
+            }
+            tried += 1;
+
+            let mut left = vec![0; self.n_classes];
+            let mut right = counts.to_vec();
+            for i in 0..n - 1 {
+                let class = self.targets[order[i]];
+                left[class] += 1;
+                right[class] -= 1;
+
+                let value = self.value(order[i], feature);
+                let next = self.value(order[i + 1], feature);
+                if value == next {
+                    continue;
+                }
+
+                let n_left = i + 1;
+                let n_right = n - n_left;
+                let decrease = parent - n_left as f64 * gini(&left, n_left) -
+                               n_right as f64 * gini(&right, n_right);
+                if best.as_ref().map_or(true, |b| decrease > b.decrease) {
+                    // The midpoint can round up to the next value
+                    let mid = value + (next - value) / 2f64;
+                    best = Some(Split {
+                        feature: feature,
+                        threshold: if mid < next { mid } else { value },
+                        decrease: decrease,
+                    });
+                }
+            }
+        }
+        best
+    }
+}
+
+/// The Gini impurity of `n` samples with the given class counts.
+fn gini(counts: &[usize], n: usize) -> f64 {
+    let n = n as f64;
+    1f64 - counts.iter().fold(0f64, |acc, &c| acc + (c as f64 / n) * (c as f64 / n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RandomForest, gini};
+    use learning::SupModel;
+    use linalg::{Matrix, BaseMatrix, Vector};
+
+    /// Two features, of which only the first determines the class.
+    fn noisy_data() -> (Matrix<f64>, Vector<usize>) {
+        let mut data = Vec::new();
+        let mut targets = Vec::new();
+        for i in 0..60 {
+            data.push(i as f64);
+            data.push(((i * 37) % 11) as f64);
+            targets.push(if i < 30 { 0 } else { 1 });
+        }
+        (Matrix::new(60, 2, data), Vector::new(targets))
+    }
+
+    #[test]
+    fn gini_impurity() {
+        assert_eq!(gini(&[4, 0], 4), 0.0);
+        assert_eq!(gini(&[2, 2], 4), 0.5);
+        assert!((gini(&[1, 1, 1], 3) - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn learns_xor() {
+        let inputs = Matrix::new(8, 2, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0,
+                                            0.1, 0.1, 0.1, 0.9, 0.9, 0.1, 0.9, 0.9]);
+        let targets = Vector::new(vec![0, 1, 1, 0, 0, 1, 1, 0]);
+
+        let mut forest = RandomForest::new(50).with_max_features(2).with_seed(3);
+        forest.train(&inputs, &targets).unwrap();
+        assert_eq!(forest.predict(&inputs).unwrap(), targets);
+    }
+
+    #[test]
+    fn importances_and_oob_error() {
+        let (inputs, targets) = noisy_data();
+        let mut forest = RandomForest::new(30).with_seed(7);
+        forest.train(&inputs, &targets).unwrap();
+
+        let importances = forest.feature_importances().unwrap();
+        assert!((importances.sum() - 1.0).abs() < 1e-12);
+        assert!(importances[0] > importances[1]);
+
+        let oob = forest.oob_error().unwrap();
+        assert!(oob >= 0.0 && oob < 0.2);
+
+        // The votes of each row sum to one
+        let proba = forest.predict_proba(&inputs).unwrap();
+        for row in proba.row_iter() {
+            assert!((row.sum() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn seeded_forests_match() {
+        let (inputs, targets) = noisy_data();
+        let mut a = RandomForest::new(5).with_max_features(1).with_seed(11);
+        let mut b = RandomForest::new(5).with_max_features(1).with_seed(11);
+        a.train(&inputs, &targets).unwrap();
+        b.train(&inputs, &targets).unwrap();
+
+        assert_eq!(a.predict_proba(&inputs).unwrap(), b.predict_proba(&inputs).unwrap());
+        assert_eq!(a.feature_importances(), b.feature_importances());
+        assert_eq!(a.oob_error(), b.oob_error());
+    }
+
+    #[test]
+    fn depth_limit() {
+        let (inputs, targets) = noisy_data();
+
+        // A single split per tree, so the only split is on a single feature
+        let mut forest = RandomForest::new(10).with_max_depth(1).with_max_features(2).with_seed(2);
+        forest.train(&inputs, &targets).unwrap();
+        assert_eq!(forest.fit.as_ref().unwrap().trees.iter().map(|t| t.nodes.len()).max(), Some(3));
+
+        let mut stump = RandomForest::new(3).with_max_depth(0);
+        stump.train(&inputs, &targets).unwrap();
+        assert_eq!(stump.feature_importances().unwrap().data(), &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn invalid_data() {
+        let (inputs, targets) = noisy_data();
+        let mut forest = RandomForest::new(3);
+        assert!(forest.predict(&inputs).is_err());
+        assert!(forest.oob_error().is_none());
+
+        assert!(forest.train(&inputs, &Vector::new(vec![0, 1])).is_err());
+        assert!(RandomForest::new(3).with_max_features(3).train(&inputs, &targets).is_err());
+
+        forest.train(&inputs, &targets).unwrap();
+        assert!(forest.predict(&Matrix::new(1, 3, vec![0.0; 3])).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_trees() {
+        let _ = RandomForest::new(0);
+    }
+}
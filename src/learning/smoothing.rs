@@ -0,0 +1,325 @@
+//! Time Series Smoothing
+//!
+//! Provides a moving average which smooths a series of observations
+//! by replacing each one with the mean of the trailing window ending
+//! at it. This is a simple baseline for time series and can also be
+//! used to smooth the noisy outputs of other models.
+//!
+//! A moving average over `window` observations lags changes in the
+//! series by roughly `window / 2` steps.
+//!
+//! Also provides Holt-Winters triple exponential smoothing, which
+//! forecasts a series from smoothed estimates of its level, trend and
+//! seasonal pattern.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::learning::smoothing::MovingAverage;
+//! use rusty_machine::learning::UnSupModel;
+//! use rusty_machine::linalg::Vector;
+//!
+//! let model = MovingAverage::new(3);
+//!
+//! let series = Vector::new(vec![3.0, 6.0, 9.0, 0.0, 3.0]);
+//! let smoothed = model.predict(&series).unwrap();
+//!
+//! // The first values average over the observations seen so far
+//! assert_eq!(smoothed.into_vec(), vec![3.0, 4.5, 6.0, 5.0, 4.0]);
+//! ```
+//!
+//! Training on earlier observations lets the model average over them
+//! at the start of a new series.
+//!
+//! ```
+//! use rusty_machine::learning::smoothing::MovingAverage;
+//! use rusty_machine::learning::UnSupModel;
+//! use rusty_machine::linalg::Vector;
+//!
+//! let mut model = MovingAverage::new(3);
+//! model.train(&Vector::new(vec![1.0, 2.0, 3.0, 6.0])).unwrap();
+//!
+//! let smoothed = model.predict(&Vector::new(vec![0.0, 3.0])).unwrap();
+//! assert_eq!(smoothed.into_vec(), vec![3.0, 3.0]);
+//! ```
+//!
+//! Forecasting a seasonal series with Holt-Winters.
+//!
+//! ```
+//! use rusty_machine::learning::smoothing::HoltWinters;
+//! use rusty_machine::linalg::Vector;
+//!
+//! // Two years of quarterly observations
+//! let series = Vector::new(vec![12.0, 8.0, 5.0, 11.0, 14.0, 10.0, 7.0, 13.0]);
+//!
+//! let mut model = HoltWinters::new(0.5, 0.1, 0.3, 4);
+//! model.fit(&series).unwrap();
+//!
+//! // Forecast the next year
+//! let forecast = model.forecast(4).unwrap();
+//! ```
+
+use learning::{LearningResult, UnSupModel};
+use learning::error::{Error, ErrorKind};
+use linalg::Vector;
+
+/// Moving Average
+///
+/// Smooths a series using the mean of a trailing window of observations.
+#[derive(Clone, Debug)]
+pub struct MovingAverage {
+    /// The number of observations averaged
+    window: usize,
+    /// The observations preceding the series passed to `predict`
+    history: Vec<f64>,
+}
+
+impl MovingAverage {
+    /// Constructs a moving average over `window` observations.
+    ///
+    /// # Panics
+    ///
+    /// - The window is zero.
+    pub fn new(window: usize) -> MovingAverage {
+        assert!(window > 0, "The window must contain at least one observation.");
+
+        MovingAverage {
+            window: window,
+            history: Vec::new(),
+        }
+    }
+
+    /// The number of observations averaged.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+impl UnSupModel<Vector<f64>, Vector<f64>> for MovingAverage {
+    /// Stores the last observations of the series.
+    ///
+    /// These precede the series passed to `predict`, so that it can be
+    /// smoothed as a continuation of the training series.
+    fn train(&mut self, inputs: &Vector<f64>) -> LearningResult<()> {
+        if inputs.data().iter().any(|x| !x.is_finite()) {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The observations must be finite."));
+        }
+
+        let data = inputs.data();
+        let keep = (self.window - 1).min(data.len());
+        self.history = data[data.len() - keep..].to_vec();
+        Ok(())
+    }
+
+    /// Smooths the series.
+    ///
+    /// Each output is the mean of the window of observations ending at
+    /// the corresponding input. When fewer observations are available,
+    /// such as at the start of an untrained model's series, the mean is
+    /// taken over those available.
+    fn predict(&self, inputs: &Vector<f64>) -> LearningResult<Vector<f64>> {
+        let mut series = self.history.clone();
+        series.extend_from_slice(inputs.data());
+
+        let offset = self.history.len();
+        let mut sum = 0f64;
+        let mut outputs = Vec::with_capacity(inputs.size());
+
+        for (i, x) in series.iter().enumerate() {
+            sum += *x;
+            if i >= self.window {
+                sum -= series[i - self.window];
+            }
+
+            if i >= offset {
+                outputs.push(sum / (i + 1).min(self.window) as f64);
+            }
+        }
+
+        Ok(Vector::new(outputs))
+    }
+}
+
+/// How the seasonal pattern combines with the level of a series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Seasonality {
+    /// The seasonal pattern is added to the level.
+    ///
+    /// The size of the seasonal swings does not depend on the level.
+    Additive,
+    /// The level is scaled by the seasonal pattern.
+    ///
+    /// The seasonal swings grow with the level. The series must be positive.
+    Multiplicative,
+}
+
+/// Holt-Winters Model
+///
+/// Triple exponential smoothing with level, trend and seasonal components.
+///
+/// At each observation the level, trend and seasonal components are
+/// updated using the smoothing factors `alpha`, `beta` and `gamma`
+/// respectively. Larger factors weight recent observations more heavily.
+/// The initial trend is estimated from the change in the mean between
+/// the first two seasons, and is zero with only one season of data. The
+/// initial level and seasonal pattern are estimated from the first season
+/// after removing this trend. Smoothing starts from the second season.
+#[derive(Clone, Debug)]
+pub struct HoltWinters {
+    /// The smoothing factor of the level
+    alpha: f64,
+    /// The smoothing factor of the trend
+    beta: f64,
+    /// The smoothing factor of the seasonal pattern
+    gamma: f64,
+    /// The number of observations in a season
+    season_length: usize,
+    seasonality: Seasonality,
+    /// The level, trend and seasonal pattern after the last observation
+    components: Option<Components>,
+}
+
+/// The fitted components of a Holt-Winters model.
+#[derive(Clone, Debug)]
+struct Components {
+    level: f64,
+    trend: f64,
+    /// The seasonal pattern indexed by the position within the season
+    seasonal: Vec<f64>,
+    /// The number of observations fitted
+    len: usize,
+}
+
+impl HoltWinters {
+    /// Constructs an untrained Holt-Winters model with additive seasonality.
+    ///
+    /// # Panics
+    ///
+    /// - Any of the smoothing factors is outside [0, 1].
+    /// - The season length is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::smoothing::HoltWinters;
+    ///
+    /// // Monthly observations with a yearly pattern
+    /// let model = HoltWinters::new(0.3, 0.1, 0.2, 12);
+    /// ```
+    pub fn new(alpha: f64, beta: f64, gamma: f64, season_length: usize) -> HoltWinters {
+        for &factor in &[alpha, beta, gamma] {
+            assert!(0f64 <= factor && factor <= 1f64,
+                    "The smoothing factors must be between 0 and 1.");
+        }
+        assert!(season_length > 0, "The season length must be at least 1.");
+
+        HoltWinters {
+            alpha: alpha,
+            beta: beta,
+            gamma: gamma,
+            season_length: season_length,
+            seasonality: Seasonality::Additive,
+            components: None,
+        }
+    }
+
+    /// Sets how the seasonal pattern combines with the level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::smoothing::{HoltWinters, Seasonality};
+    ///
+    /// let model = HoltWinters::new(0.3, 0.1, 0.2, 12)
+    ///     .with_seasonality(Seasonality::Multiplicative);
+    /// ```
+    pub fn with_seasonality(mut self, seasonality: Seasonality) -> HoltWinters {
+        self.seasonality = seasonality;
+        self
+    }
+
+    /// Fits the level, trend and seasonal pattern to the series.
+    ///
+    /// The series must contain at least one full season of finite
+    /// observations, which must be positive for multiplicative seasonality.
+    pub fn fit(&mut self, series: &Vector<f64>) -> LearningResult<()> {
+        let data = series.data();
+        let m = self.season_length;
+        let multiplicative = self.seasonality == Seasonality::Multiplicative;
+
+        if data.len() < m {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The series must contain at least one full season."));
+        }
+        if data.iter().any(|x| !x.is_finite()) {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "The observations must be finite."));
+        }
+        if multiplicative && data.iter().any(|&x| x <= 0f64) {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  "Multiplicative seasonality requires a positive series."));
+        }
+
+        let first_mean = data[..m].iter().sum::<f64>() / m as f64;
+        let mut trend = if data.len() >= 2 * m {
+            let second_mean = data[m..2 * m].iter().sum::<f64>() / m as f64;
+            (second_mean - first_mean) / m as f64
+        } else {
+            0f64
+        };
+
+        // The mean of the first season is the level at its midpoint
+        let midpoint = (m - 1) as f64 / 2f64;
+        let mut level = first_mean + midpoint * trend;
+        let mut seasonal = data[..m]
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let trended = first_mean + (i as f64 - midpoint) * trend;
+                if multiplicative { x / trended } else { x - trended }
+            })
+            .collect::<Vec<_>>();
+
+        for (t, &x) in data.iter().enumerate().skip(m) {
+            let s = seasonal[t % m];
+            let prev_level = level;
+
+            if multiplicative {
+                level = self.alpha * x / s + (1f64 - self.alpha) * (level + trend);
+                seasonal[t % m] = self.gamma * x / level + (1f64 - self.gamma) * s;
+            } else {
+                level = self.alpha * (x - s) + (1f64 - self.alpha) * (level + trend);
+                seasonal[t % m] = self.gamma * (x - level) + (1f64 - self.gamma) * s;
+            }
+            trend = self.beta * (level - prev_level) + (1f64 - self.beta) * trend;
+        }
+
+        self.components = Some(Components {
+            level: level,
+            trend: trend,
+            seasonal: seasonal,
+            len: data.len(),
+        });
+        Ok(())
+    }
+
+    /// Forecasts the next `h` observations after the fitted series.
+    pub fn forecast(&self, h: usize) -> LearningResult<Vector<f64>> {
+        if let Some(ref c) = self.components {
+            let forecast = (1..h + 1)
+                .map(|k| {
+                    let trended = c.level + k as f64 * c.trend;
+                    let s = c.seasonal[(c.len - 1 + k) % self.season_length];
+                    match self.seasonality {
+                        Seasonality::Additive => trended + s,
+                        Seasonality::Multiplicative => trended * s,
+                    }
+                })
+                .collect::<Vec<_>>();
+            Ok(Vector::new(forecast))
+        } else {
+            Err(Error::new_untrained())
+        }
+    }
+}
@@ -8,7 +8,9 @@
 //! You can also create your own custom activation Functions for use in your models.
 //! Just create a unit struct implementing the `ActivationFunc` trait.
 
+use std::f64;
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
 /// Trait for activation functions in models.
 pub trait ActivationFunc: Clone + Debug {
@@ -22,8 +24,40 @@ pub trait ActivationFunc: Clone + Debug {
     /// Calculates f'(x) given f(x) as an input
     fn func_grad_from_output(y: f64) -> f64;
 
+    /// The gradient of the activation function given both its input and output.
+    ///
+    /// This is used by neural networks during backpropagation. By default it
+    /// is `func_grad_from_output`, which avoids recomputing the function.
+    /// Activation functions whose gradient cannot be recovered from their
+    /// output, such as `Swish`, should override this.
+    fn func_grad_from_input_output(_: f64, y: f64) -> f64 {
+        Self::func_grad_from_output(y)
+    }
+
     /// The inverse of the activation function.
     fn func_inv(x: f64) -> f64;
+
+    /// The name of the activation function, such as `Sigmoid`.
+    ///
+    /// This names the activation layers of neural networks, and is
+    /// written when they are saved. Only the activation functions in
+    /// this module can be loaded again. Will return `Custom` by default.
+    fn name() -> &'static str {
+        "Custom"
+    }
+
+    /// Applies the activation function to each element of the slice in place.
+    ///
+    /// This is used by neural networks to apply the activation to a whole
+    /// layer at once. By default `func` is applied element-wise. Activation
+    /// functions can override this with a loop which the compiler is able
+    /// to vectorize, but it must give the same results as `func`, up to
+    /// rounding.
+    fn apply_to_slice(xs: &mut [f64]) {
+        for x in xs {
+            *x = Self::func(*x);
+        }
+    }
 }
 
 /// Sigmoid activation function.
@@ -52,6 +86,23 @@ impl ActivationFunc for Sigmoid {
     fn func_inv(x: f64) -> f64 {
         (x / (1f64 - x)).ln()
     }
+
+    fn name() -> &'static str {
+        "Sigmoid"
+    }
+
+    /// Applies the sigmoid function to the slice in place.
+    ///
+    /// The exponentials are taken in a separate pass, so that the
+    /// divisions compile to vector instructions.
+    fn apply_to_slice(xs: &mut [f64]) {
+        for x in xs.iter_mut() {
+            *x = (-*x).exp();
+        }
+        for x in xs {
+            *x = 1.0 / (1.0 + *x);
+        }
+    }
 }
 
 /// Linear activation function.
@@ -63,6 +114,8 @@ impl ActivationFunc for Linear {
         x
     }
 
+    fn apply_to_slice(_: &mut [f64]) {}
+
     fn func_grad(_: f64) -> f64 {
         1f64
     }
@@ -74,6 +127,10 @@ impl ActivationFunc for Linear {
     fn func_inv(x: f64) -> f64 {
         x
     }
+
+    fn name() -> &'static str {
+        "Linear"
+    }
 }
 
 /// Exponential activation function.
@@ -96,6 +153,10 @@ impl ActivationFunc for Exp {
     fn func_inv(x: f64) -> f64 {
         x.ln()
     }
+
+    fn name() -> &'static str {
+        "Exp"
+    }
 }
 
 /// Hyperbolic tangent activation function
@@ -119,4 +180,593 @@ impl ActivationFunc for Tanh {
     fn func_inv(x: f64) -> f64 {
         0.5*((1.0+x)/(1.0-x)).ln()
     }
-}
\ No newline at end of file
+
+    fn name() -> &'static str {
+        "Tanh"
+    }
+
+    /// Applies the tanh function to the slice in place.
+    ///
+    /// Uses `tanh(x) = expm1(2x) / (expm1(2x) + 2)`, with the exponentials
+    /// in a separate pass so that the clamping and divisions compile to
+    /// vector instructions. The result agrees with `func` up to rounding.
+    fn apply_to_slice(xs: &mut [f64]) {
+        // tanh rounds to +-1 beyond 20, and expm1 would overflow
+        for x in xs.iter_mut() {
+            *x = if *x > 20.0 {
+                20.0
+            } else if *x < -20.0 {
+                -20.0
+            } else {
+                *x
+            };
+        }
+        for x in xs.iter_mut() {
+            *x = (2.0 * *x).exp_m1();
+        }
+        for x in xs {
+            *x = *x / (*x + 2.0);
+        }
+    }
+}
+
+/// Rectified linear unit activation function.
+#[derive(Clone, Copy, Debug)]
+pub struct ReLU;
+
+impl ActivationFunc for ReLU {
+    /// ReLU function.
+    ///
+    /// Returns max(0, x).
+    fn func(x: f64) -> f64 {
+        x.max(0f64)
+    }
+
+    /// Gradient of the ReLU function.
+    ///
+    /// Taken to be 0 at x = 0.
+    fn func_grad(x: f64) -> f64 {
+        if x > 0f64 { 1f64 } else { 0f64 }
+    }
+
+    fn func_grad_from_output(y: f64) -> f64 {
+        Self::func_grad(y)
+    }
+
+    /// Applies the ReLU function to the slice in place.
+    ///
+    /// Unlike `f64::max` the comparison compiles to vector instructions.
+    /// NaN is mapped to zero, as with `func`.
+    fn apply_to_slice(xs: &mut [f64]) {
+        for x in xs {
+            *x = if *x > 0f64 { *x } else { 0f64 };
+        }
+    }
+
+    /// The inverse of the positive part of the ReLU function.
+    ///
+    /// ReLU is not invertible for negative inputs, so this is the identity.
+    fn func_inv(x: f64) -> f64 {
+        x
+    }
+
+    fn name() -> &'static str {
+        "ReLU"
+    }
+}
+
+/// Swish (or SiLU) activation function.
+///
+/// Computes `x * sigmoid(x)`. Unlike ReLU it is smooth, and it is not
+/// monotonic: it decreases to a minimum of about -0.278 at x ≈ -1.278.
+#[derive(Clone, Copy, Debug)]
+pub struct Swish;
+
+/// The input at which Swish is smallest.
+const SWISH_MIN_INPUT: f64 = -1.2784645427610738;
+
+/// The sigmoid function, computed without overflow for inputs of any size.
+fn stable_sigmoid(x: f64) -> f64 {
+    if x >= 0f64 {
+        1f64 / (1f64 + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1f64 + e)
+    }
+}
+
+impl ActivationFunc for Swish {
+    /// Swish function.
+    ///
+    /// Returns x * sigmoid(x).
+    fn func(x: f64) -> f64 {
+        x * stable_sigmoid(x)
+    }
+
+    /// Gradient of the Swish function.
+    ///
+    /// Evaluates to sigmoid(x) + x * sigmoid(x) * (1 - sigmoid(x)).
+    fn func_grad(x: f64) -> f64 {
+        let s = stable_sigmoid(x);
+        s + x * s * (1f64 - s)
+    }
+
+    /// The gradient assuming the input is above the minimum of Swish.
+    ///
+    /// Swish is not invertible, so the input is recovered using `func_inv`.
+    /// Neural networks use `func_grad_from_input_output` instead, which is
+    /// exact for every input.
+    fn func_grad_from_output(y: f64) -> f64 {
+        Self::func_grad(Self::func_inv(y))
+    }
+
+    fn func_grad_from_input_output(x: f64, _: f64) -> f64 {
+        Self::func_grad(x)
+    }
+
+    /// The inverse of the increasing part of the Swish function.
+    ///
+    /// Returns the input above the minimum of Swish with the given output,
+    /// or NaN if the output is below the minimum.
+    fn func_inv(y: f64) -> f64 {
+        let min = Self::func(SWISH_MIN_INPUT);
+        if y < min {
+            return f64::NAN;
+        } else if y == min {
+            return SWISH_MIN_INPUT;
+        }
+
+        // Swish is convex and increasing here, so Newton's method started
+        // above the root decreases monotonically towards it.
+        let mut x = y + 1f64;
+        for _ in 0..100 {
+            let step = (Self::func(x) - y) / Self::func_grad(x);
+            x -= step;
+            if step.abs() <= 1e-12 * x.abs().max(1f64) {
+                break;
+            }
+        }
+        x.max(SWISH_MIN_INPUT)
+    }
+
+    fn name() -> &'static str {
+        "Swish"
+    }
+}
+
+/// Softplus activation function.
+///
+/// Computes `ln(1 + exp(x))`, a smooth approximation of ReLU whose
+/// gradient is the sigmoid function.
+#[derive(Clone, Copy, Debug)]
+pub struct Softplus;
+
+impl ActivationFunc for Softplus {
+    /// Softplus function.
+    ///
+    /// Computed as max(x, 0) + ln(1 + exp(-|x|)) so large inputs do not overflow.
+    fn func(x: f64) -> f64 {
+        x.max(0f64) + (-x.abs()).exp().ln_1p()
+    }
+
+    /// Gradient of the Softplus function.
+    ///
+    /// Evaluates to sigmoid(x).
+    fn func_grad(x: f64) -> f64 {
+        stable_sigmoid(x)
+    }
+
+    /// Evaluates to 1 - exp(-y).
+    fn func_grad_from_output(y: f64) -> f64 {
+        -(-y).exp_m1()
+    }
+
+    /// The inverse of the Softplus function.
+    ///
+    /// Evaluates to ln(exp(y) - 1), which is NaN for outputs that are not positive.
+    fn func_inv(y: f64) -> f64 {
+        y + (-(-y).exp_m1()).ln()
+    }
+
+    fn name() -> &'static str {
+        "Softplus"
+    }
+}
+
+/// An activation function with its input scaled by a temperature.
+///
+/// Computes `f(x / T)` for the activation function `f` and temperature `T`.
+/// Temperatures below 1 give sharper outputs and temperatures above 1 give
+/// softer outputs. A temperature of 1 is the same as the activation function.
+///
+/// The temperature is a value rather than part of the type, so this does not
+/// implement `ActivationFunc`. It can still be used as a neural net layer.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::toolkit::activ_fn::{Sigmoid, Temperature};
+///
+/// let sharp = Temperature::new(Sigmoid, 0.5);
+/// assert!(sharp.func(1.0) > 0.85);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Temperature<T: ActivationFunc> {
+    activ_fn: PhantomData<T>,
+    temperature: f64,
+}
+
+impl<T: ActivationFunc> Temperature<T> {
+    /// Constructs a new activation function with the given temperature.
+    ///
+    /// # Panics
+    ///
+    /// - The temperature is not positive.
+    pub fn new(_: T, temperature: f64) -> Temperature<T> {
+        assert!(temperature > 0f64, "The temperature must be positive.");
+
+        Temperature {
+            activ_fn: PhantomData,
+            temperature: temperature,
+        }
+    }
+
+    /// The temperature dividing the inputs.
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    /// The activation function of the scaled input.
+    pub fn func(&self, x: f64) -> f64 {
+        T::func(x / self.temperature)
+    }
+
+    /// The gradient of the activation function of the scaled input.
+    pub fn func_grad(&self, x: f64) -> f64 {
+        T::func_grad(x / self.temperature) / self.temperature
+    }
+
+    /// The gradient of the activation function calculated using its output.
+    pub fn func_grad_from_output(&self, y: f64) -> f64 {
+        T::func_grad_from_output(y) / self.temperature
+    }
+
+    /// The gradient of the activation function given both its input and output.
+    pub fn func_grad_from_input_output(&self, x: f64, y: f64) -> f64 {
+        T::func_grad_from_input_output(x / self.temperature, y) / self.temperature
+    }
+
+    /// The inverse of the activation function.
+    pub fn func_inv(&self, x: f64) -> f64 {
+        T::func_inv(x) * self.temperature
+    }
+}
+
+/// Leaky rectified linear unit activation function.
+///
+/// Computes `x` for positive inputs and `alpha * x` otherwise. The small
+/// slope for negative inputs keeps the gradient from vanishing, so units
+/// cannot get stuck at zero as they can with `ReLU`.
+///
+/// The slope is a value rather than part of the type, so this does not
+/// implement `ActivationFunc`. It can still be used as a neural net layer,
+/// and as the activation of `NeuralNet::mlp`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::toolkit::activ_fn::LeakyReLU;
+///
+/// let leaky = LeakyReLU::new(0.1);
+/// assert_eq!(leaky.func(2.0), 2.0);
+/// assert_eq!(leaky.func(-2.0), -0.2);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LeakyReLU {
+    alpha: f64,
+}
+
+impl LeakyReLU {
+    /// Constructs a leaky ReLU with slope `alpha` for negative inputs.
+    ///
+    /// # Panics
+    ///
+    /// - The slope is not positive.
+    pub fn new(alpha: f64) -> LeakyReLU {
+        assert!(alpha > 0f64, "The slope must be positive.");
+
+        LeakyReLU { alpha: alpha }
+    }
+
+    /// The slope for negative inputs.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Leaky ReLU function.
+    pub fn func(&self, x: f64) -> f64 {
+        if x > 0f64 { x } else { self.alpha * x }
+    }
+
+    /// Gradient of the leaky ReLU function.
+    ///
+    /// Taken to be `alpha` at x = 0.
+    pub fn func_grad(&self, x: f64) -> f64 {
+        if x > 0f64 { 1f64 } else { self.alpha }
+    }
+
+    /// The gradient of the function calculated using its output.
+    ///
+    /// The output has the same sign as the input, so this is exact.
+    pub fn func_grad_from_output(&self, y: f64) -> f64 {
+        self.func_grad(y)
+    }
+
+    /// The gradient of the function given both its input and output.
+    pub fn func_grad_from_input_output(&self, x: f64, _: f64) -> f64 {
+        self.func_grad(x)
+    }
+
+    /// The inverse of the leaky ReLU function.
+    pub fn func_inv(&self, y: f64) -> f64 {
+        if y > 0f64 { y } else { y / self.alpha }
+    }
+}
+
+/// Uses a slope of 0.01.
+impl Default for LeakyReLU {
+    fn default() -> LeakyReLU {
+        LeakyReLU { alpha: 0.01 }
+    }
+}
+
+/// Exponential linear unit activation function.
+///
+/// Computes `x` for positive inputs and `alpha * (exp(x) - 1)` otherwise.
+/// Negative outputs saturate at `-alpha`, which pushes the mean activation
+/// towards zero.
+///
+/// The scale is a value rather than part of the type, so this does not
+/// implement `ActivationFunc`. It can still be used as a neural net layer,
+/// and as the activation of `NeuralNet::mlp`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::toolkit::activ_fn::ELU;
+///
+/// let elu = ELU::new(1.0);
+/// assert_eq!(elu.func(2.0), 2.0);
+/// assert!(elu.func(-20.0) > -1.0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ELU {
+    alpha: f64,
+}
+
+impl ELU {
+    /// Constructs an ELU which saturates at `-alpha` for negative inputs.
+    ///
+    /// # Panics
+    ///
+    /// - The scale is not positive.
+    pub fn new(alpha: f64) -> ELU {
+        assert!(alpha > 0f64, "The scale must be positive.");
+
+        ELU { alpha: alpha }
+    }
+
+    /// The scale of the negative part.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// ELU function.
+    pub fn func(&self, x: f64) -> f64 {
+        if x > 0f64 { x } else { self.alpha * x.exp_m1() }
+    }
+
+    /// Gradient of the ELU function.
+    ///
+    /// Evaluates to 1 for positive inputs and `alpha * exp(x)` otherwise.
+    pub fn func_grad(&self, x: f64) -> f64 {
+        if x > 0f64 { 1f64 } else { self.alpha * x.exp() }
+    }
+
+    /// The gradient of the function calculated using its output.
+    ///
+    /// Evaluates to 1 for positive outputs and `y + alpha` otherwise.
+    pub fn func_grad_from_output(&self, y: f64) -> f64 {
+        if y > 0f64 { 1f64 } else { y + self.alpha }
+    }
+
+    /// The gradient of the function given both its input and output.
+    pub fn func_grad_from_input_output(&self, _: f64, y: f64) -> f64 {
+        self.func_grad_from_output(y)
+    }
+
+    /// The inverse of the ELU function.
+    ///
+    /// Returns NaN for outputs at or below `-alpha`.
+    pub fn func_inv(&self, y: f64) -> f64 {
+        if y > 0f64 {
+            y
+        } else if y > -self.alpha {
+            (y / self.alpha).ln_1p()
+        } else {
+            f64::NAN
+        }
+    }
+}
+
+/// Uses a scale of 1.
+impl Default for ELU {
+    fn default() -> ELU {
+        ELU { alpha: 1f64 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActivationFunc, Sigmoid, Linear, Exp, Tanh, ReLU, Swish, Softplus, Temperature,
+                LeakyReLU, ELU};
+    use std::f64;
+
+    fn check_apply_to_slice<T: ActivationFunc>() {
+        let inputs = vec![-3.0, -0.5, -0.0, 0.0, 0.25, 1.0, 4.0, f64::NAN, f64::INFINITY];
+        let mut outputs = inputs.clone();
+        T::apply_to_slice(&mut outputs);
+
+        for (x, y) in inputs.iter().zip(&outputs) {
+            let expected = T::func(*x);
+            assert!(expected == *y || (expected.is_nan() && y.is_nan()));
+        }
+    }
+
+    #[test]
+    fn apply_to_slice_matches_func() {
+        check_apply_to_slice::<Sigmoid>();
+        check_apply_to_slice::<Linear>();
+        check_apply_to_slice::<Exp>();
+        check_apply_to_slice::<ReLU>();
+        check_apply_to_slice::<Swish>();
+        check_apply_to_slice::<Softplus>();
+    }
+
+    #[test]
+    fn tanh_apply_to_slice() {
+        let inputs = vec![-1e3, -25.0, -3.0, -0.5, -1e-12, -0.0, 0.0, 1e-300, 0.25, 1.0, 19.9,
+                          21.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY];
+        let mut outputs = inputs.clone();
+        Tanh::apply_to_slice(&mut outputs);
+
+        for (x, y) in inputs.iter().zip(&outputs) {
+            let expected = Tanh::func(*x);
+            if expected.is_nan() {
+                assert!(y.is_nan());
+            } else {
+                assert!((expected - y).abs() <= 4.0 * f64::EPSILON * expected.abs(),
+                        "tanh({}) = {}, not {}", x, expected, y);
+            }
+        }
+    }
+
+    #[test]
+    fn swish_gradient() {
+        let h = 1e-6;
+        for &x in &[-6.0, -2.0, -1.0, -0.5, 0.0, 0.3, 1.0, 4.0] {
+            let numeric = (Swish::func(x + h) - Swish::func(x - h)) / (2.0 * h);
+            assert!((numeric - Swish::func_grad(x)).abs() < 1e-8);
+            assert_eq!(Swish::func_grad_from_input_output(x, Swish::func(x)), Swish::func_grad(x));
+        }
+
+        // Swish decreases below its minimum
+        assert!(Swish::func_grad(-2.0) < 0.0);
+        assert!(Swish::func_grad(-1.0) > 0.0);
+    }
+
+    #[test]
+    fn swish_large_inputs() {
+        assert_eq!(Swish::func(-1000.0), 0.0);
+        assert_eq!(Swish::func(1000.0), 1000.0);
+        assert_eq!(Swish::func_grad(-1000.0), 0.0);
+        assert_eq!(Swish::func_grad(1000.0), 1.0);
+    }
+
+    #[test]
+    fn swish_inverse() {
+        for &x in &[-1.0, -0.5, 0.0, 0.7, 3.0, 50.0] {
+            let y = Swish::func(x);
+            assert!((Swish::func_inv(y) - x).abs() < 1e-8);
+            assert!((Swish::func_grad_from_output(y) - Swish::func_grad(x)).abs() < 1e-8);
+        }
+        assert!(Swish::func_inv(-0.5).is_nan());
+    }
+
+    #[test]
+    fn softplus_gradient() {
+        let h = 1e-6;
+        for &x in &[-6.0, -1.0, -0.2, 0.0, 0.5, 2.0, 5.0] {
+            let numeric = (Softplus::func(x + h) - Softplus::func(x - h)) / (2.0 * h);
+            assert!((numeric - Softplus::func_grad(x)).abs() < 1e-8);
+
+            let y = Softplus::func(x);
+            assert!((Softplus::func_grad_from_output(y) - Softplus::func_grad(x)).abs() < 1e-12);
+            assert!((Softplus::func_inv(y) - x).abs() < 1e-8);
+        }
+
+        // No overflow for large inputs
+        assert_eq!(Softplus::func(1000.0), 1000.0);
+        assert_eq!(Softplus::func(-1000.0), 0.0);
+        assert!(Softplus::func_inv(-1.0).is_nan());
+    }
+
+    #[test]
+    fn temperature_one_is_unchanged() {
+        let temp = Temperature::new(Sigmoid, 1.0);
+        for &x in &[-3.0, -0.5, 0.0, 1.0, 4.0] {
+            assert_eq!(temp.func(x), Sigmoid::func(x));
+            assert_eq!(temp.func_grad(x), Sigmoid::func_grad(x));
+        }
+    }
+
+    #[test]
+    fn low_temperature_is_sharper() {
+        let sharp = Temperature::new(Sigmoid, 0.5);
+        let soft = Temperature::new(Sigmoid, 2.0);
+
+        for &x in &[-2.0, -0.5, 0.5, 2.0] {
+            let base = Sigmoid::func(x);
+            // Outputs move away from 0.5 at low temperatures
+            assert!((sharp.func(x) - 0.5).abs() > (base - 0.5).abs());
+            assert!((soft.func(x) - 0.5).abs() < (base - 0.5).abs());
+
+            let y = sharp.func(x);
+            assert!((sharp.func_inv(y) - x).abs() < 1e-10);
+            assert!((sharp.func_grad(x) - sharp.func_grad_from_output(y)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn temperature_not_positive() {
+        let _ = Temperature::new(Sigmoid, 0.0);
+    }
+
+    #[test]
+    fn leaky_relu_and_elu_gradients() {
+        let h = 1e-6;
+        let leaky = LeakyReLU::new(0.2);
+        let elu = ELU::new(1.5);
+        for &x in &[-4.0, -1.0, -0.3, 0.2, 1.0, 3.0] {
+            let numeric = (leaky.func(x + h) - leaky.func(x - h)) / (2.0 * h);
+            assert!((numeric - leaky.func_grad(x)).abs() < 1e-8);
+            assert_eq!(leaky.func_grad_from_output(leaky.func(x)), leaky.func_grad(x));
+
+            let numeric = (elu.func(x + h) - elu.func(x - h)) / (2.0 * h);
+            assert!((numeric - elu.func_grad(x)).abs() < 1e-8);
+            assert!((elu.func_grad_from_output(elu.func(x)) - elu.func_grad(x)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn leaky_relu_and_elu_inverse() {
+        let leaky = LeakyReLU::default();
+        let elu = ELU::default();
+        for &x in &[-3.0, -0.5, 0.0, 0.7, 3.0] {
+            assert!((leaky.func_inv(leaky.func(x)) - x).abs() < 1e-12);
+            assert!((elu.func_inv(elu.func(x)) - x).abs() < 1e-12);
+        }
+
+        // ELU outputs are bounded below by -alpha
+        assert!(elu.func_inv(-1.0).is_nan());
+        assert_eq!(leaky.alpha(), 0.01);
+        assert_eq!(elu.alpha(), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn leaky_relu_not_positive() {
+        let _ = LeakyReLU::new(0.0);
+    }
+}
@@ -16,10 +16,78 @@ pub trait CostFunc<T> {
     /// The cost function.
     fn cost(outputs: &T, targets: &T) -> f64;
 
-    /// The gradient of the cost function.
+    /// The gradient of the cost function summed over the samples.
+    ///
+    /// The costs in this module are means over the rows of the outputs,
+    /// while each row of the gradient is the gradient of the cost of that
+    /// row alone. `Reduction::grad_scale` scales it to the gradient of the
+    /// combined cost.
     fn grad_cost(outputs: &T, targets: &T) -> T;
 }
 
+/// How the costs of the samples in a batch are combined.
+///
+/// The reduction scales the gradient in the same way as the cost, so it
+/// changes the effective learning rate. With `Sum` the gradient of a
+/// batch is `batch_size` times the gradient with `Mean`.
+///
+/// The cost of each sample is given by `sample_costs`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reduction {
+    /// The mean cost over the samples, as computed by the cost function.
+    Mean,
+    /// The total cost over the samples.
+    Sum,
+}
+
+/// Uses the mean cost.
+impl Default for Reduction {
+    fn default() -> Reduction {
+        Reduction::Mean
+    }
+}
+
+impl Reduction {
+    /// The factor by which the mean cost is scaled for a batch of `n` samples.
+    pub fn scale(&self, n: usize) -> f64 {
+        match *self {
+            Reduction::Mean => 1f64,
+            Reduction::Sum => n as f64,
+        }
+    }
+
+    /// The factor by which the summed gradient given by `grad_cost` is
+    /// scaled for a batch of `n` samples, to be the gradient of the cost.
+    pub fn grad_scale(&self, n: usize) -> f64 {
+        match *self {
+            Reduction::Mean => 1f64 / n as f64,
+            Reduction::Sum => 1f64,
+        }
+    }
+}
+
+/// The cost of each sample, one per row of the outputs.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::toolkit::cost_fn::{sample_costs, MeanSqError};
+/// use rusty_machine::linalg::Matrix;
+///
+/// let outputs = Matrix::new(2, 1, vec![1.0, 2.0]);
+/// let targets = Matrix::new(2, 1, vec![1.0, 0.0]);
+///
+/// let costs = sample_costs::<MeanSqError>(&outputs, &targets);
+/// assert_eq!(costs.into_vec(), vec![0.0, 2.0]);
+/// ```
+pub fn sample_costs<C: CostFunc<Matrix<f64>>>(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Vector<f64> {
+    assert_eq!(outputs.rows(), targets.rows());
+    let costs = (0..outputs.rows())
+        .map(|i| C::cost(&outputs.select_rows(&[i]), &targets.select_rows(&[i])))
+        .collect::<Vec<_>>();
+    Vector::new(costs)
+}
+
 /// The mean squared error cost function.
 #[derive(Clone, Copy, Debug)]
 pub struct MeanSqError;
@@ -96,7 +164,442 @@ impl CostFunc<Vector<f64>> for CrossEntropyError {
     }
 }
 
+/// The categorical cross entropy error cost function.
+///
+/// Each row of the outputs holds the predicted probability of each class,
+/// such as the output of a `Softmax` layer, and each row of the targets
+/// holds the true class probabilities, typically a one-hot vector.
+///
+/// Classes with a zero target do not contribute, so outputs of exactly
+/// zero for the other classes do not make the cost `NaN`.
+///
+/// Through a `Softmax` layer the gradient with respect to the layer's
+/// input reduces to `outputs - targets`, as long as each row of the
+/// targets sums to one.
+#[derive(Clone, Copy, Debug)]
+pub struct CategoricalCrossEntropyError;
+
+impl CostFunc<Matrix<f64>> for CategoricalCrossEntropyError {
+    fn cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        let total = outputs.iter()
+            .zip(targets.iter())
+            .filter(|&(_, &t)| t != 0f64)
+            .fold(0f64, |acc, (&o, &t)| acc + t * o.ln());
+
+        -total / (outputs.rows() as f64)
+    }
+
+    fn grad_cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        let grad = outputs.iter()
+            .zip(targets.iter())
+            .map(|(&o, &t)| if t == 0f64 { 0f64 } else { -t / o })
+            .collect::<Vec<_>>();
+        Matrix::new(outputs.rows(), outputs.cols(), grad)
+    }
+}
+
+/// The categorical cross entropy error of the softmax of the outputs.
+///
+/// The outputs are unbounded class scores (logits), such as those of a
+/// Linear layer, and each row of the targets holds the true class
+/// probabilities. This gives the same cost as `CategoricalCrossEntropyError`
+/// after a `Softmax` layer, but the log-probabilities are computed with the
+/// log-sum-exp of each row, so large scores do not overflow and confident
+/// wrong predictions do not make the cost infinite.
+///
+/// The gradient is `softmax(outputs) - targets` for rows of targets which
+/// sum to one.
+#[derive(Clone, Copy, Debug)]
+pub struct SoftmaxCrossEntropyError;
+
+impl SoftmaxCrossEntropyError {
+    /// The log-sum-exp of a row, shifted by its maximum to avoid overflow.
+    fn log_sum_exp(row: &[f64]) -> f64 {
+        let max = row.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        max + row.iter().fold(0f64, |acc, &x| acc + (x - max).exp()).ln()
+    }
+}
+
+impl CostFunc<Matrix<f64>> for SoftmaxCrossEntropyError {
+    fn cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        let mut total = 0f64;
+
+        for (out, tar) in outputs.row_iter().zip(targets.row_iter()) {
+            let (out, tar) = (out.raw_slice(), tar.raw_slice());
+            let lse = SoftmaxCrossEntropyError::log_sum_exp(out);
+            total += out.iter()
+                .zip(tar)
+                .filter(|&(_, &t)| t != 0f64)
+                .fold(0f64, |acc, (&z, &t)| acc + t * (lse - z));
+        }
+
+        total / (outputs.rows() as f64)
+    }
+
+    fn grad_cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        let mut grad = Matrix::zeros(outputs.rows(), outputs.cols());
+
+        for (mut g, (out, tar)) in grad.row_iter_mut().zip(outputs.row_iter().zip(targets.row_iter())) {
+            let (out, tar) = (out.raw_slice(), tar.raw_slice());
+            let lse = SoftmaxCrossEntropyError::log_sum_exp(out);
+            let total = tar.iter().fold(0f64, |acc, t| acc + t);
+            for ((g, &z), &t) in g.raw_slice_mut().iter_mut().zip(out).zip(tar) {
+                *g = total * (z - lse).exp() - t;
+            }
+        }
+
+        grad
+    }
+}
+
+/// The multiclass hinge loss cost function.
+///
+/// Uses the Crammer-Singer formulation with a margin of one. Each row of the
+/// targets is an indicator vector of the true class `y`, and the cost of a
+/// row with scores `s` is `max(0, 1 + max_{j != y} s_j - s_y)`.
+///
+/// The outputs are unbounded class scores, such as those of a Linear layer.
+#[derive(Clone, Copy, Debug)]
+pub struct MulticlassHingeError;
+
+impl MulticlassHingeError {
+    /// Finds the true class and the highest scoring other class of a row.
+    fn classes(outputs: &[f64], targets: &[f64]) -> (usize, usize) {
+        let true_class = targets.iter()
+            .position(|&t| t == 1f64)
+            .expect("Targets must be indicator vectors.");
+
+        let mut other = None;
+        for (idx, &s) in outputs.iter().enumerate() {
+            if idx != true_class && other.map_or(true, |o: usize| s > outputs[o]) {
+                other = Some(idx);
+            }
+        }
+
+        (true_class, other.expect("Hinge loss requires at least two classes."))
+    }
+}
+
+impl CostFunc<Matrix<f64>> for MulticlassHingeError {
+    fn cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        let mut total = 0f64;
+
+        for (out, tar) in outputs.row_iter().zip(targets.row_iter()) {
+            let (out, tar) = (out.raw_slice(), tar.raw_slice());
+            let (y, j) = MulticlassHingeError::classes(out, tar);
+            total += (1f64 + out[j] - out[y]).max(0f64);
+        }
+
+        total / (outputs.rows() as f64)
+    }
+
+    fn grad_cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        let mut grad = Matrix::zeros(outputs.rows(), outputs.cols());
+
+        for (i, (out, tar)) in outputs.row_iter().zip(targets.row_iter()).enumerate() {
+            let (out, tar) = (out.raw_slice(), tar.raw_slice());
+            let (y, j) = MulticlassHingeError::classes(out, tar);
+
+            // Rows without a margin violation have no gradient
+            if 1f64 + out[j] - out[y] > 0f64 {
+                grad[[i, y]] = -1f64;
+                grad[[i, j]] = 1f64;
+            }
+        }
+
+        grad
+    }
+}
+
+/// The mean squared error cost function, ignoring missing targets.
+///
+/// Target entries which are `NaN` are treated as missing. They do not
+/// contribute to the cost and their gradient is zero. The cost is
+/// normalized by the number of rows, as for `MeanSqError`, so the two
+/// are equal when no targets are missing.
+#[derive(Clone, Copy, Debug)]
+pub struct MaskedMeanSqError;
+
+impl CostFunc<Matrix<f64>> for MaskedMeanSqError {
+    fn cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        let total = masked_sum(outputs, targets, |o, t| (o - t) * (o - t));
+        total / (2f64 * outputs.rows() as f64)
+    }
+
+    fn grad_cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        masked_grad(outputs, targets, |o, t| o - t)
+    }
+}
+
+/// The cross entropy error cost function, ignoring missing targets.
+///
+/// Target entries which are `NaN` are treated as missing. They do not
+/// contribute to the cost and their gradient is zero. The cost is
+/// normalized by the number of rows, as for `CrossEntropyError`.
+#[derive(Clone, Copy, Debug)]
+pub struct MaskedCrossEntropyError;
+
+impl CostFunc<Matrix<f64>> for MaskedCrossEntropyError {
+    fn cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        let total = masked_sum(outputs, targets, |o, t| {
+            t * o.ln() + (1f64 - t) * (1f64 - o).ln()
+        });
+        -total / outputs.rows() as f64
+    }
+
+    fn grad_cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        masked_grad(outputs, targets, |o, t| (o - t) / (o * (1f64 - o)))
+    }
+}
+
+/// The Huber loss cost function.
+///
+/// Each entry with residual `r = output - target` costs `r^2 / 2` when
+/// `|r| <= delta` and `delta * (|r| - delta / 2)` otherwise. The cost is
+/// normalized by the number of rows, so for small residuals it matches
+/// `MeanSqError`. Large residuals grow linearly, which makes regression
+/// robust to outliers.
+///
+/// The gradient is the residual clipped to `[-delta, delta]`, scaled in
+/// the same way as the gradient of `MeanSqError`.
+///
+/// The `CostFunc` functions take no value, so they use the default delta
+/// of 1. Other deltas are used through `loss` and `grad_loss`, or with
+/// `HuberCriterion` in a neural network.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::learning::toolkit::cost_fn::HuberLoss;
+/// use rusty_machine::linalg::Matrix;
+///
+/// let huber = HuberLoss::new(0.5);
+/// let outputs = Matrix::new(2, 1, vec![0.2, 3.0]);
+/// let targets = Matrix::new(2, 1, vec![0.0, 0.0]);
+///
+/// // 0.5 * 0.2^2 + 0.5 * (3.0 - 0.25), averaged over the rows
+/// assert!((huber.loss(&outputs, &targets) - 0.6975).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct HuberLoss {
+    delta: f64,
+}
+
+impl HuberLoss {
+    /// Constructs a Huber loss which is quadratic for residuals up to `delta`.
+    ///
+    /// # Panics
+    ///
+    /// - `delta` is not positive.
+    pub fn new(delta: f64) -> HuberLoss {
+        assert!(delta > 0f64, "The Huber loss delta must be positive.");
+
+        HuberLoss { delta: delta }
+    }
+
+    /// The largest residual with a quadratic cost.
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// The Huber loss of the outputs.
+    pub fn loss(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        let total = outputs.iter()
+            .zip(targets.iter())
+            .map(|(o, t)| {
+                let r = (o - t).abs();
+                if r <= self.delta {
+                    0.5 * r * r
+                } else {
+                    self.delta * (r - 0.5 * self.delta)
+                }
+            })
+            .sum::<f64>();
+
+        total / (outputs.rows() as f64)
+    }
+
+    /// The gradient of the Huber loss.
+    pub fn grad_loss(&self, outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        (outputs - targets).apply(&|r| r.max(-self.delta).min(self.delta))
+    }
+}
+
+/// Uses a delta of 1.
+impl Default for HuberLoss {
+    fn default() -> HuberLoss {
+        HuberLoss { delta: 1f64 }
+    }
+}
+
+impl CostFunc<Matrix<f64>> for HuberLoss {
+    fn cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> f64 {
+        HuberLoss::default().loss(outputs, targets)
+    }
+
+    fn grad_cost(outputs: &Matrix<f64>, targets: &Matrix<f64>) -> Matrix<f64> {
+        HuberLoss::default().grad_loss(outputs, targets)
+    }
+}
+
+/// Sums `f` over the entries with a target.
+fn masked_sum<F: Fn(f64, f64) -> f64>(outputs: &Matrix<f64>, targets: &Matrix<f64>, f: F) -> f64 {
+    outputs.iter()
+        .zip(targets.iter())
+        .filter(|&(_, t)| !t.is_nan())
+        .fold(0f64, |total, (&o, &t)| total + f(o, t))
+}
+
+/// Applies `f` to the entries with a target, leaving zeros elsewhere.
+fn masked_grad<F: Fn(f64, f64) -> f64>(outputs: &Matrix<f64>, targets: &Matrix<f64>, f: F) -> Matrix<f64> {
+    let grad = outputs.iter()
+        .zip(targets.iter())
+        .map(|(&o, &t)| if t.is_nan() { 0f64 } else { f(o, t) })
+        .collect::<Vec<_>>();
+    Matrix::new(outputs.rows(), outputs.cols(), grad)
+}
+
 /// Logarithm for applying within cost function.
 fn ln(x: f64) -> f64 {
     x.ln()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CostFunc, MeanSqError, CrossEntropyError, MaskedMeanSqError,
+                MaskedCrossEntropyError, CategoricalCrossEntropyError, SoftmaxCrossEntropyError,
+                HuberLoss, Reduction, sample_costs};
+    use linalg::{Matrix, BaseMatrix, BaseMatrixMut};
+    use std::f64::NAN;
+
+    #[test]
+    fn sample_costs_sum_to_total() {
+        let outputs = Matrix::new(3, 2, vec![0.2, 0.7, 0.9, 0.1, 0.5, 0.5]);
+        let targets = Matrix::new(3, 2, vec![0.0, 1.0, 1.0, 0.0, 1.0, 0.0]);
+
+        let costs = sample_costs::<CrossEntropyError>(&outputs, &targets);
+        assert_eq!(costs.size(), 3);
+
+        let total = CrossEntropyError::cost(&outputs, &targets) * Reduction::Sum.scale(3);
+        assert!((costs.sum() - total).abs() < 1e-12);
+        assert_eq!(Reduction::Mean.scale(3), 1.0);
+        assert_eq!(Reduction::Mean.grad_scale(4), 0.25);
+        assert_eq!(Reduction::Sum.grad_scale(4), 1.0);
+    }
+
+    #[test]
+    fn categorical_cross_entropy() {
+        let outputs = Matrix::new(2, 3, vec![0.7, 0.2, 0.1, 0.0, 0.4, 0.6]);
+        let targets = Matrix::new(2, 3, vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+
+        // The zero output of a wrong class does not contribute
+        let expected = -(0.7f64.ln() + 0.6f64.ln()) / 2.0;
+        assert!((CategoricalCrossEntropyError::cost(&outputs, &targets) - expected).abs() < 1e-12);
+
+        let grad = CategoricalCrossEntropyError::grad_cost(&outputs, &targets);
+        assert!((grad[[0, 0]] + 1.0 / 0.7).abs() < 1e-12);
+        assert!((grad[[1, 2]] + 1.0 / 0.6).abs() < 1e-12);
+        assert_eq!(grad[[1, 0]], 0.0);
+    }
+
+    #[test]
+    fn softmax_cross_entropy() {
+        let logits = Matrix::new(2, 3, vec![2.0, 0.5, -1.0, 0.0, 1.0, 3.0]);
+        let targets = Matrix::new(2, 3, vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+        // The softmax of each row, as computed by a Softmax layer
+        let mut probs = logits.clone().apply(&f64::exp);
+        for mut row in probs.row_iter_mut() {
+            let total = row.raw_slice().iter().fold(0f64, |acc, p| acc + p);
+            for p in row.raw_slice_mut() {
+                *p /= total;
+            }
+        }
+
+        let cost = SoftmaxCrossEntropyError::cost(&logits, &targets);
+        assert!((cost - CategoricalCrossEntropyError::cost(&probs, &targets)).abs() < 1e-12);
+        let grad = SoftmaxCrossEntropyError::grad_cost(&logits, &targets);
+        for (g, e) in grad.iter().zip((&probs - &targets).iter()) {
+            assert!((g - e).abs() < 1e-12);
+        }
+
+        // Large scores neither overflow nor give an infinite cost
+        let logits = Matrix::new(1, 2, vec![1000.0, -1000.0]);
+        let targets = Matrix::new(1, 2, vec![0.0, 1.0]);
+        assert_eq!(SoftmaxCrossEntropyError::cost(&logits, &targets), 2000.0);
+        assert_eq!(SoftmaxCrossEntropyError::grad_cost(&logits, &targets).into_vec(), vec![1.0, -1.0]);
+    }
+
+    #[test]
+    fn masked_mse_ignores_missing_targets() {
+        let outputs = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let targets = Matrix::new(2, 3, vec![0.0, NAN, 1.0, NAN, 5.0, 3.0]);
+
+        // Squared errors of the present entries are 1, 4, 0 and 9
+        assert_eq!(MaskedMeanSqError::cost(&outputs, &targets), 14.0 / 4.0);
+
+        let grad = MaskedMeanSqError::grad_cost(&outputs, &targets);
+        assert_eq!(grad.into_vec(), vec![1.0, 0.0, 2.0, 0.0, 0.0, 3.0]);
+    }
+
+    #[test]
+    fn masked_costs_match_without_missing_targets() {
+        let outputs = Matrix::new(3, 2, vec![0.2, 0.7, 0.9, 0.4, 0.1, 0.6]);
+        let targets = Matrix::new(3, 2, vec![0.0, 1.0, 1.0, 0.0, 0.0, 1.0]);
+
+        assert!((MaskedMeanSqError::cost(&outputs, &targets) -
+                 MeanSqError::cost(&outputs, &targets)).abs() < 1e-12);
+        assert_eq!(MaskedMeanSqError::grad_cost(&outputs, &targets),
+                   MeanSqError::grad_cost(&outputs, &targets));
+
+        assert!((MaskedCrossEntropyError::cost(&outputs, &targets) -
+                 CrossEntropyError::cost(&outputs, &targets)).abs() < 1e-12);
+        let grad = MaskedCrossEntropyError::grad_cost(&outputs, &targets);
+        let expected = CrossEntropyError::grad_cost(&outputs, &targets);
+        for (g, e) in grad.iter().zip(expected.iter()) {
+            assert!((g - e).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn masked_cross_entropy_ignores_missing_targets() {
+        let outputs = Matrix::new(2, 2, vec![0.2, 0.5, 0.9, 0.4]);
+        let targets = Matrix::new(2, 2, vec![0.0, NAN, 1.0, NAN]);
+
+        let expected = -(0.8f64.ln() + 0.9f64.ln()) / 2.0;
+        assert!((MaskedCrossEntropyError::cost(&outputs, &targets) - expected).abs() < 1e-12);
+
+        let grad = MaskedCrossEntropyError::grad_cost(&outputs, &targets);
+        assert_eq!(grad[[0, 1]], 0.0);
+        assert_eq!(grad[[1, 1]], 0.0);
+        assert!(grad.iter().all(|g| g.is_finite()));
+    }
+
+    #[test]
+    fn huber_loss() {
+        let outputs = Matrix::new(2, 2, vec![0.5, -0.3, 4.0, -2.5]);
+        let targets = Matrix::new(2, 2, vec![0.0, 0.0, 1.0, 0.0]);
+
+        // Small residuals match the mean squared error
+        let small = outputs.select_rows(&[0]);
+        let small_targets = targets.select_rows(&[0]);
+        assert!((HuberLoss::new(1.0).loss(&small, &small_targets) -
+                 MeanSqError::cost(&small, &small_targets)).abs() < 1e-12);
+
+        // Residuals of 3 and -2.5 beyond delta grow linearly
+        let huber = HuberLoss::new(1.0);
+        let expected = (0.125 + 0.045 + 2.5 + 2.0) / 2.0;
+        assert!((huber.loss(&outputs, &targets) - expected).abs() < 1e-12);
+        assert_eq!(huber.grad_loss(&outputs, &targets).into_vec(), vec![0.5, -0.3, 1.0, -1.0]);
+
+        // The trait functions use the default delta
+        assert_eq!(HuberLoss::cost(&outputs, &targets), huber.loss(&outputs, &targets));
+        assert_eq!(HuberLoss::grad_cost(&outputs, &targets), huber.grad_loss(&outputs, &targets));
+    }
+
+    #[test]
+    #[should_panic]
+    fn huber_delta_not_positive() {
+        let _ = HuberLoss::new(0.0);
+    }
+}
@@ -0,0 +1,196 @@
+//! Convergence detection for iterative algorithms.
+//!
+//! The `PlateauDetector` tracks an objective which is being minimized
+//! and signals convergence once it stops improving.
+//!
+//! # Examples
+//!
+//! ```
+//! use rusty_machine::learning::toolkit::plateau::PlateauDetector;
+//!
+//! // Stop after 2 iterations which improve by less than 1%
+//! let mut detector = PlateauDetector::new(0.01, 2);
+//!
+//! for cost in &[10.0, 5.0, 4.99, 4.0, 3.999, 3.998] {
+//!     if detector.update(*cost) {
+//!         break;
+//!     }
+//! }
+//!
+//! assert!(detector.has_plateaued());
+//! assert_eq!(detector.best(), Some(3.998));
+//! ```
+
+/// Detects when an objective being minimized has stopped improving.
+///
+/// Each new value is compared against the best value seen so far. When
+/// the relative improvement is no greater than the tolerance for
+/// `patience` consecutive values the objective has plateaued. An absolute
+/// tolerance can also be set, in which case an improvement no greater
+/// than either tolerance is not progress.
+///
+/// Comparing against the best value rather than the previous one means
+/// that noisy objectives, such as the cost of stochastic gradient descent,
+/// do not count a rebound after an increase as improvement. A patience
+/// greater than one avoids stopping early on a single unlucky step.
+///
+/// To maximize an objective, such as a log-likelihood, pass its negation.
+#[derive(Clone, Copy, Debug)]
+pub struct PlateauDetector {
+    /// The smallest relative improvement which counts as progress
+    tolerance: f64,
+    /// The smallest absolute improvement which counts as progress
+    abs_tolerance: f64,
+    /// The number of consecutive values without progress before stopping
+    patience: usize,
+    /// The best value seen so far
+    best: Option<f64>,
+    /// The number of consecutive values without progress
+    stalled: usize,
+}
+
+impl PlateauDetector {
+    /// Constructs a new plateau detector.
+    ///
+    /// # Panics
+    ///
+    /// - The tolerance is negative.
+    /// - The patience is zero.
+    pub fn new(tolerance: f64, patience: usize) -> PlateauDetector {
+        assert!(tolerance >= 0f64, "The tolerance cannot be negative.");
+        assert!(patience > 0, "The patience must be at least 1.");
+
+        PlateauDetector {
+            tolerance: tolerance,
+            abs_tolerance: 0f64,
+            patience: patience,
+            best: None,
+            stalled: 0,
+        }
+    }
+
+    /// Sets the smallest absolute improvement which counts as progress.
+    ///
+    /// This is zero by default, so that only the relative tolerance applies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_machine::learning::toolkit::plateau::PlateauDetector;
+    ///
+    /// // Near zero even tiny changes are large relative improvements
+    /// let mut detector = PlateauDetector::new(0.01, 1).with_abs_tolerance(1e-6);
+    /// assert!(!detector.update(1e-6));
+    /// assert!(detector.update(0.5e-6));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// - The tolerance is negative.
+    pub fn with_abs_tolerance(mut self, abs_tolerance: f64) -> PlateauDetector {
+        assert!(abs_tolerance >= 0f64, "The tolerance cannot be negative.");
+        self.abs_tolerance = abs_tolerance;
+        self
+    }
+
+    /// Records the next value of the objective.
+    ///
+    /// Returns true if the objective has plateaued.
+    pub fn update(&mut self, objective: f64) -> bool {
+        match self.best {
+            None => self.best = Some(objective),
+            Some(best) => {
+                // NaN values count as progress, so they never stop the algorithm
+                let improvement = best - objective;
+                let relative = improvement / best.abs().max(f64::MIN_POSITIVE);
+                if relative <= self.tolerance || improvement <= self.abs_tolerance {
+                    self.stalled += 1;
+                } else {
+                    self.stalled = 0;
+                }
+
+                if objective < best {
+                    self.best = Some(objective);
+                }
+            }
+        }
+
+        self.has_plateaued()
+    }
+
+    /// Whether the objective has plateaued.
+    pub fn has_plateaued(&self) -> bool {
+        self.stalled >= self.patience
+    }
+
+    /// The best value of the objective seen so far.
+    pub fn best(&self) -> Option<f64> {
+        self.best
+    }
+
+    /// Forgets every recorded value.
+    pub fn reset(&mut self) {
+        self.best = None;
+        self.stalled = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlateauDetector;
+
+    #[test]
+    fn fires_after_patience_flat_steps() {
+        let mut detector = PlateauDetector::new(1e-3, 3);
+
+        assert!(!detector.update(10.0));
+        assert!(!detector.update(10.0));
+        assert!(!detector.update(10.0));
+        assert!(detector.update(10.0));
+
+        detector.reset();
+        assert!(!detector.has_plateaued());
+        assert_eq!(detector.best(), None);
+    }
+
+    #[test]
+    fn single_flat_step_does_not_fire() {
+        let mut detector = PlateauDetector::new(1e-3, 2);
+
+        for cost in &[10.0, 9.0, 9.0, 8.0, 7.0, 7.0, 6.0] {
+            assert!(!detector.update(*cost));
+        }
+        assert_eq!(detector.best(), Some(6.0));
+    }
+
+    #[test]
+    fn absolute_tolerance() {
+        // The relative tolerance alone stops on a small relative step
+        let mut detector = PlateauDetector::new(1e-3, 1);
+        assert!(!detector.update(1e6));
+        assert!(detector.update(1e6 - 10.0));
+
+        // The absolute tolerance alone stops on a small absolute step
+        let mut detector = PlateauDetector::new(0.0, 1).with_abs_tolerance(1.0);
+        assert!(!detector.update(1e6));
+        assert!(!detector.update(1e6 - 10.0));
+        assert!(detector.update(1e6 - 10.5));
+    }
+
+    #[test]
+    fn noisy_rebound_is_not_progress() {
+        let mut detector = PlateauDetector::new(1e-3, 3);
+
+        // The objective jumps up and back down without beating the best value
+        assert!(!detector.update(5.0));
+        assert!(!detector.update(6.0));
+        assert!(!detector.update(5.0));
+        assert!(detector.update(5.5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_patience() {
+        let _ = PlateauDetector::new(1e-3, 0);
+    }
+}
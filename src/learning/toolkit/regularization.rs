@@ -76,7 +76,8 @@ impl<T: Float + FromPrimitive> Regularization<T> {
     }
 
     fn l2_reg_cost(mat: &MatrixSlice<T>, x: T) -> T {
-        Euclidean.norm(mat) * x / ((T::one() + T::one()) * FromPrimitive::from_usize(mat.rows()).unwrap())
+        let l2_norm = Euclidean.norm(mat);
+        l2_norm * l2_norm * x / ((T::one() + T::one()) * FromPrimitive::from_usize(mat.rows()).unwrap())
     }
 
     fn l2_reg_grad(mat: &MatrixSlice<T>, x: T) -> Matrix<T> {
@@ -136,7 +137,8 @@ mod tests {
         let a = no_reg.reg_cost(mat_slice);
         let b = no_reg.reg_grad(mat_slice);
 
-        assert!((a - (Euclidean.norm(&input_mat) / 12f64)) < 1e-18);
+        let l2_norm = Euclidean.norm(&input_mat);
+        assert!((a - (l2_norm * l2_norm / 12f64)).abs() < 1e-12);
 
         let true_grad = &input_mat / 6f64;
         for eps in (b - true_grad).into_vec() {
@@ -154,7 +156,8 @@ mod tests {
         let a = no_reg.reg_cost(mat_slice);
         let b = no_reg.reg_grad(mat_slice);
 
-        assert!(a - ((Euclidean.norm(&input_mat) / 24f64) + (42f64 / 12f64)) < 1e-18);
+        let l2_norm = Euclidean.norm(&input_mat);
+        assert!((a - ((l2_norm * l2_norm / 24f64) + (42f64 / 12f64))).abs() < 1e-12);
 
         let l1_true_grad = Matrix::new(3, 4,
             vec![-1., -1., -1., 1., 1., 1., 1., 1., 1., 1., 1., 1.]
@@ -16,6 +16,7 @@
 //! The currently supported techniques are:
 //!
 //! - Linear Regression
+//! - Elastic Net Regression
 //! - Logistic Regression
 //! - Generalized Linear Models
 //! - K-Means Clustering
@@ -26,6 +27,7 @@
 //! - Naive Bayes Classifiers
 //! - DBSCAN
 //! - k-Nearest Neighbor Classifiers
+//! - Random Forests
 //! - Principal Component Analysis
 //!
 //! ### linalg
@@ -111,6 +113,8 @@
 extern crate rulinalg;
 extern crate num as libnum;
 extern crate rand;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 
 pub mod prelude;
 
@@ -121,16 +125,26 @@ pub mod linalg {
     pub use rulinalg::matrix::{Axes, Matrix, MatrixSlice, MatrixSliceMut, BaseMatrix, BaseMatrixMut};
     pub use rulinalg::vector::Vector;
     pub use rulinalg::norm;
+    pub use self::argmax::ArgMax;
+    pub use self::stack::Stack;
+
+    mod argmax;
+    mod stack;
 }
 
 /// Module for data handling
 pub mod data {
+    pub mod csv;
+    pub mod encoding;
+    pub mod feature_types;
     pub mod transforms;
 }
 
 /// Module for machine learning.
 pub mod learning {
     pub mod dbscan;
+    pub mod elastic_net;
+    pub mod ensemble;
     pub mod glm;
     pub mod gmm;
     pub mod lin_reg;
@@ -142,6 +156,8 @@ pub mod learning {
     pub mod naive_bayes;
     pub mod knn;
     pub mod pca;
+    pub mod random_forest;
+    pub mod smoothing;
 
     pub mod error;
 
@@ -168,6 +184,7 @@ pub mod learning {
 
     /// Module for optimization in machine learning setting.
     pub mod optim {
+        use std::ops::Range;
 
         /// Trait for models which can be gradient-optimized.
         pub trait Optimizable {
@@ -182,6 +199,31 @@ pub mod learning {
                             inputs: &Self::Inputs,
                             targets: &Self::Targets)
                             -> (f64, Vec<f64>);
+
+            /// Whether decoupled weight decay applies to each parameter.
+            ///
+            /// By default every parameter except the bias terms
+            /// given by `bias_mask` is decayed.
+            fn weight_decay_mask(&self, params: &[f64]) -> Vec<bool> {
+                self.bias_mask(params).into_iter().map(|is_bias| !is_bias).collect()
+            }
+
+            /// Whether each parameter is a bias term.
+            ///
+            /// Used by optimizers which treat biases differently,
+            /// such as `StochasticGD` with a bias learning rate multiplier.
+            /// By default no parameter is a bias.
+            fn bias_mask(&self, params: &[f64]) -> Vec<bool> {
+                vec![false; params.len()]
+            }
+
+            /// The ranges of the parameters belonging to each group, such as a layer.
+            ///
+            /// Used to record the gradient norm of each group in the
+            /// `TrainingHistory`. By default all parameters form one group.
+            fn param_groups(&self, params: &[f64]) -> Vec<Range<usize>> {
+                vec![0..params.len()]
+            }
         }
 
         /// Trait for optimization algorithms.
@@ -195,10 +237,49 @@ pub mod learning {
                         inputs: &M::Inputs,
                         targets: &M::Targets)
                         -> Vec<f64>;
+
+            /// Return the optimized parameters along with a history of the optimization.
+            ///
+            /// The default implementation records no history.
+            fn optimize_with_history(&self,
+                                     model: &M,
+                                     start: &[f64],
+                                     inputs: &M::Inputs,
+                                     targets: &M::Targets)
+                                     -> (Vec<f64>, TrainingHistory) {
+                (self.optimize(model, start, inputs, targets), TrainingHistory::new())
+            }
+        }
+
+        /// Trait for optimization algorithms which can continue from a previous state.
+        ///
+        /// This allows training on data in batches without resetting
+        /// internal buffers such as momentum between calls.
+        pub trait ResumableOptimAlgorithm<M: Optimizable> : OptimAlgorithm<M> {
+            /// Run the given number of iterations starting from the parameters
+            /// and the state left by a previous call.
+            ///
+            /// An empty state starts the algorithm afresh.
+            fn resume(&self,
+                      model: &M,
+                      start: &[f64],
+                      state: &mut OptimState,
+                      inputs: &M::Inputs,
+                      targets: &M::Targets,
+                      iters: usize)
+                      -> (Vec<f64>, TrainingHistory);
         }
 
+        pub use self::history::TrainingHistory;
+        pub use self::schedule::LrSchedule;
+        pub use self::state::OptimState;
+
+        pub mod grad_check;
         pub mod grad_desc;
         pub mod fmincg;
+        pub mod history;
+        pub mod schedule;
+        pub mod state;
     }
 
     /// Module for learning tools.
@@ -206,6 +287,7 @@ pub mod learning {
         pub mod activ_fn;
         pub mod cost_fn;
         pub mod kernel;
+        pub mod plateau;
         pub mod rand_utils;
         pub mod regularization;
     }
@@ -223,6 +305,7 @@ pub mod stats {
 pub mod analysis {
     pub mod confusion_matrix;
     pub mod cross_validation;
+    pub mod feature_selection;
     pub mod score;
 }
 
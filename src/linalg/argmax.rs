@@ -0,0 +1,115 @@
+//! Argmax reductions of matrices.
+//!
+//! The maximum and sum of each row or column are provided by
+//! `BaseMatrix::max` and `BaseMatrix::sum_cols`/`BaseMatrix::sum_rows`.
+//! This module adds the position of each maximum.
+
+use linalg::BaseMatrix;
+
+/// The position of the largest entry in each row or column of a matrix.
+///
+/// This is implemented for every matrix type, so it can be used
+/// with `Matrix` as well as `MatrixSlice`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::linalg::{Matrix, ArgMax};
+///
+/// let mat = Matrix::new(2, 3, vec![1.0, 5.0, 2.0,
+///                                  4.0, 4.0, -1.0]);
+///
+/// assert_eq!(mat.argmax_rows(), vec![1, 0]);
+/// assert_eq!(mat.argmax_cols(), vec![1, 0, 0]);
+/// ```
+pub trait ArgMax {
+    /// The column index of the largest entry in each row.
+    ///
+    /// Ties are broken toward the lowest index.
+    ///
+    /// # Panics
+    ///
+    /// - The matrix has no columns.
+    fn argmax_rows(&self) -> Vec<usize>;
+
+    /// The row index of the largest entry in each column.
+    ///
+    /// Ties are broken toward the lowest index.
+    ///
+    /// # Panics
+    ///
+    /// - The matrix has no rows.
+    fn argmax_cols(&self) -> Vec<usize>;
+}
+
+impl<M: BaseMatrix<f64>> ArgMax for M {
+    fn argmax_rows(&self) -> Vec<usize> {
+        assert!(self.cols() > 0, "Cannot find the maximum of an empty row.");
+
+        self.row_iter()
+            .map(|row| {
+                let row = row.raw_slice();
+                let mut best = 0;
+                for (idx, &x) in row.iter().enumerate().skip(1) {
+                    // Strictly greater keeps the lowest index of ties
+                    if x > row[best] {
+                        best = idx;
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+
+    fn argmax_cols(&self) -> Vec<usize> {
+        assert!(self.rows() > 0, "Cannot find the maximum of an empty column.");
+
+        let mut best = vec![0; self.cols()];
+        let mut best_vals = self.row_iter().next().unwrap().raw_slice().to_vec();
+        for (idx, row) in self.row_iter().enumerate().skip(1) {
+            for ((b, v), &x) in best.iter_mut().zip(best_vals.iter_mut()).zip(row.raw_slice()) {
+                if x > *v {
+                    *b = idx;
+                    *v = x;
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArgMax;
+    use linalg::{Matrix, BaseMatrix};
+
+    #[test]
+    fn argmax() {
+        let mat = Matrix::new(3, 4, vec![1.0, 3.0, 3.0, -2.0,
+                                         -5.0, -1.0, -4.0, -1.0,
+                                         0.5, 0.0, 3.0, 2.0]);
+
+        assert_eq!(mat.argmax_rows(), vec![1, 1, 2]);
+        assert_eq!(mat.argmax_cols(), vec![0, 0, 0, 2]);
+
+        // Slices are reduced in the same way
+        let slice = mat.sub_slice([1, 2], 2, 2);
+        assert_eq!(slice.argmax_rows(), vec![1, 0]);
+        assert_eq!(slice.argmax_cols(), vec![1, 1]);
+    }
+
+    #[test]
+    fn single_column() {
+        let mat = Matrix::new(3, 1, vec![2.0, -1.0, 2.0]);
+
+        assert_eq!(mat.argmax_rows(), vec![0, 0, 0]);
+        assert_eq!(mat.argmax_cols(), vec![0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn argmax_no_columns() {
+        let mat = Matrix::<f64>::new(2, 0, vec![]);
+        let _ = mat.argmax_rows();
+    }
+}
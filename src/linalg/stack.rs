@@ -0,0 +1,140 @@
+//! Concatenation of matrices.
+//!
+//! `BaseMatrix::hcat` and `BaseMatrix::vcat` panic when the matrices
+//! do not fit together. This module adds checked versions which return
+//! an error instead, for joining matrices whose shapes come from data.
+
+use linalg::{BaseMatrix, Matrix};
+use learning::LearningResult;
+use learning::error::{Error, ErrorKind};
+
+/// Checked concatenation of matrices.
+///
+/// This is implemented for every matrix type, so it can be used
+/// with `Matrix` as well as `MatrixSlice`.
+///
+/// A matrix with no entries joins with any matrix, leaving it unchanged.
+/// This allows an empty matrix to be used as the start of a stack.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_machine::linalg::{Matrix, Stack};
+///
+/// let a = Matrix::new(2, 1, vec![1.0, 2.0]);
+/// let b = Matrix::new(2, 2, vec![3.0, 4.0,
+///                                5.0, 6.0]);
+///
+/// assert_eq!(a.hstack(&b).unwrap(), Matrix::new(2, 3, vec![1.0, 3.0, 4.0,
+///                                                          2.0, 5.0, 6.0]));
+///
+/// // The columns do not match
+/// assert!(a.vstack(&b).is_err());
+/// ```
+pub trait Stack {
+    /// Joins the columns of `other` to the right of this matrix.
+    ///
+    /// Returns an `InvalidData` error if the matrices have a different
+    /// number of rows.
+    fn hstack<M: BaseMatrix<f64>>(&self, other: &M) -> LearningResult<Matrix<f64>>;
+
+    /// Joins the rows of `other` below this matrix.
+    ///
+    /// Returns an `InvalidData` error if the matrices have a different
+    /// number of columns.
+    fn vstack<M: BaseMatrix<f64>>(&self, other: &M) -> LearningResult<Matrix<f64>>;
+}
+
+impl<T: BaseMatrix<f64>> Stack for T {
+    fn hstack<M: BaseMatrix<f64>>(&self, other: &M) -> LearningResult<Matrix<f64>> {
+        if let Some(mat) = join_empty(self, other) {
+            return Ok(mat);
+        }
+
+        if self.rows() != other.rows() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  format!("Cannot join matrices with {} and {} rows side by side.",
+                                          self.rows(),
+                                          other.rows())));
+        }
+        Ok(self.hcat(other))
+    }
+
+    fn vstack<M: BaseMatrix<f64>>(&self, other: &M) -> LearningResult<Matrix<f64>> {
+        if let Some(mat) = join_empty(self, other) {
+            return Ok(mat);
+        }
+
+        if self.cols() != other.cols() {
+            return Err(Error::new(ErrorKind::InvalidData,
+                                  format!("Cannot stack matrices with {} and {} columns.",
+                                          self.cols(),
+                                          other.cols())));
+        }
+        Ok(self.vcat(other))
+    }
+}
+
+/// Copies the other matrix if either of them has no entries.
+fn join_empty<A, B>(a: &A, b: &B) -> Option<Matrix<f64>>
+    where A: BaseMatrix<f64>,
+          B: BaseMatrix<f64>
+{
+    if b.rows() == 0 || b.cols() == 0 {
+        Some(to_matrix(a))
+    } else if a.rows() == 0 || a.cols() == 0 {
+        Some(to_matrix(b))
+    } else {
+        None
+    }
+}
+
+fn to_matrix<M: BaseMatrix<f64>>(mat: &M) -> Matrix<f64> {
+    Matrix::new(mat.rows(), mat.cols(), mat.iter().cloned().collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stack;
+    use linalg::{Matrix, BaseMatrix};
+
+    #[test]
+    fn hstack_vstack() {
+        let a = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let b = Matrix::new(2, 1, vec![5.0, 6.0]);
+        let c = Matrix::new(1, 2, vec![7.0, 8.0]);
+
+        assert_eq!(a.hstack(&b).unwrap(),
+                   Matrix::new(2, 3, vec![1.0, 2.0, 5.0, 3.0, 4.0, 6.0]));
+        assert_eq!(a.vstack(&c).unwrap(),
+                   Matrix::new(3, 2, vec![1.0, 2.0, 3.0, 4.0, 7.0, 8.0]));
+
+        // Slices are joined in the same way
+        let slice = a.sub_slice([1, 0], 1, 2);
+        assert_eq!(slice.vstack(&c).unwrap(), Matrix::new(2, 2, vec![3.0, 4.0, 7.0, 8.0]));
+    }
+
+    #[test]
+    fn empty_is_identity() {
+        let a = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let no_cols = Matrix::<f64>::new(2, 0, vec![]);
+        let no_rows = Matrix::<f64>::new(0, 2, vec![]);
+        let empty = Matrix::<f64>::new(0, 0, vec![]);
+
+        assert_eq!(a.hstack(&no_cols).unwrap(), a);
+        assert_eq!(no_cols.hstack(&a).unwrap(), a);
+        assert_eq!(a.vstack(&no_rows).unwrap(), a);
+        assert_eq!(no_rows.vstack(&a).unwrap(), a);
+        assert_eq!(empty.hstack(&a).unwrap(), a);
+        assert_eq!(a.vstack(&empty).unwrap(), a);
+    }
+
+    #[test]
+    fn mismatched_dimensions() {
+        let a = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let b = Matrix::new(3, 1, vec![5.0, 6.0, 7.0]);
+
+        assert!(a.hstack(&b).is_err());
+        assert!(a.vstack(&b).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use rm::linalg::{BaseMatrix, Matrix, Vector};
+use rm::learning::SupModel;
+use rm::learning::elastic_net::ElasticNet;
+
+fn data() -> (Matrix<f64>, Vector<f64>) {
+    let inputs = Matrix::new(8, 3, vec![1.0, 0.2, -1.0,
+                                        2.0, -0.4, 0.5,
+                                        3.0, 0.9, 0.0,
+                                        4.0, 0.1, 1.5,
+                                        5.0, -0.7, -0.5,
+                                        6.0, 0.3, 2.0,
+                                        7.0, 0.8, -1.5,
+                                        8.0, -0.2, 1.0]);
+    // Strongly depends on the first column, weakly on the third
+    let targets = inputs.row_iter()
+        .map(|row| 1.0 + 3.0 * row[0] + 0.3 * row[2])
+        .collect::<Vec<_>>();
+    (inputs, Vector::new(targets))
+}
+
+#[test]
+fn test_elastic_net_fit() {
+    let (inputs, targets) = data();
+
+    // With almost no penalty the model recovers the coefficients
+    let mut model = ElasticNet::new(1e-8, 0.5);
+    model.train(&inputs, &targets).unwrap();
+    let params = model.parameters().unwrap();
+
+    let expected = [1.0, 3.0, 0.0, 0.3];
+    for (p, e) in params.iter().zip(expected.iter()) {
+        assert!((p - e).abs() < 1e-4);
+    }
+
+    let outputs = model.predict(&inputs).unwrap();
+    for (o, t) in outputs.iter().zip(targets.iter()) {
+        assert!((o - t).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_regularization_path_sparsity() {
+    let (inputs, targets) = data();
+    let lambdas = [100.0, 1.0, 0.1, 0.001];
+
+    let path = ElasticNet::new(1.0, 1.0).regularization_path(&inputs, &targets, &lambdas).unwrap();
+    assert_eq!(path.rows(), 4);
+    assert_eq!(path.cols(), 4);
+
+    let nonzero = (0..lambdas.len())
+        .map(|i| (1..4).filter(|&j| path[[j, i]] != 0.0).count())
+        .collect::<Vec<_>>();
+
+    // The largest penalty removes every coefficient, and coefficients
+    // enter the model as the penalty decreases
+    assert_eq!(nonzero[0], 0);
+    assert!(nonzero.windows(2).all(|w| w[0] <= w[1]));
+    assert!(nonzero[3] > nonzero[1]);
+
+    // With no coefficients the intercept is the mean target
+    assert!((path[[0, 0]] - targets.sum() / 8.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_regularization_path_warm_start() {
+    let (inputs, targets) = data();
+    let lambdas = [5.0, 0.5, 0.05];
+
+    let path = ElasticNet::new(1.0, 0.7).regularization_path(&inputs, &targets, &lambdas).unwrap();
+
+    // Each column matches a model fitted from scratch
+    for (i, &lambda) in lambdas.iter().enumerate() {
+        let mut model = ElasticNet::new(lambda, 0.7);
+        model.train(&inputs, &targets).unwrap();
+
+        for (j, p) in model.parameters().unwrap().iter().enumerate() {
+            assert!((path[[j, i]] - p).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_regularization_path_invalid() {
+    let (inputs, targets) = data();
+    let model = ElasticNet::default();
+
+    assert!(model.regularization_path(&inputs, &targets, &[1.0, -1.0]).is_err());
+    assert!(model.regularization_path(&inputs, &Vector::zeros(3), &[1.0]).is_err());
+    assert!(model.predict(&inputs).is_err());
+}
@@ -1,7 +1,10 @@
 use rm::linalg::Matrix;
 use rm::linalg::Vector;
 use rm::learning::SupModel;
-use rm::learning::gp::GaussianProcess;
+use rm::learning::gp::{BayesianModelAverage, ConstMean, GaussianProcess};
+use rm::learning::toolkit::kernel::SquaredExp;
+
+use std::f64::consts::{E, PI};
 
 #[test]
 fn test_default_gp() {
@@ -17,3 +20,70 @@ fn test_default_gp() {
 
 	let _outputs = gp.predict(&test_inputs).unwrap();
 }
+
+#[test]
+fn test_bayesian_model_average() {
+	let inputs = Matrix::new(20, 1, (0..20).map(|x| x as f64 * 0.3).collect::<Vec<_>>());
+	let targets = inputs.data().iter().map(|x| x.sin()).collect::<Vector<f64>>();
+
+	// The data is smooth, matching the long lengthscale
+	let smooth = GaussianProcess::new(SquaredExp::new(1., 1.), ConstMean::default(), 1e-2);
+	let rough = GaussianProcess::new(SquaredExp::new(0.05, 1.), ConstMean::default(), 1e-2);
+
+	let mut average = BayesianModelAverage::new(vec![Box::new(smooth), Box::new(rough)]);
+	assert!(average.predict(&inputs).is_err());
+	average.train(&inputs, &targets).unwrap();
+
+	let log_liks = average.models().iter()
+		.map(|m| m.log_marginal_likelihood().unwrap())
+		.collect::<Vec<_>>();
+	assert!(log_liks[0] > log_liks[1]);
+
+	let weights = average.weights().unwrap().to_vec();
+	assert!((weights[0] + weights[1] - 1.0).abs() < 1e-12);
+	assert!(weights[0] > 0.99);
+
+	let test_inputs = Matrix::new(3, 1, vec![0.45, 1.65, 3.75]);
+	let outputs = average.predict(&test_inputs).unwrap();
+	let smooth_outputs = average.models()[0].predict(&test_inputs).unwrap();
+	let rough_outputs = average.models()[1].predict(&test_inputs).unwrap();
+
+	for i in 0..3 {
+		let expected = weights[0] * smooth_outputs[i] + weights[1] * rough_outputs[i];
+		assert!((outputs[i] - expected).abs() < 1e-10);
+		assert!((outputs[i] - smooth_outputs[i]).abs() < (outputs[i] - rough_outputs[i]).abs());
+	}
+}
+
+#[test]
+fn test_predictive_entropy() {
+	let mut gp = GaussianProcess::new(SquaredExp::new(1., 1.), ConstMean::default(), 0.01);
+
+	let inputs = Matrix::new(5,1,vec![0.,1.,2.,3.,4.]);
+	let targets = Vector::new(vec![0.,1.,2.,1.,0.]);
+	gp.train(&inputs, &targets).unwrap();
+
+	// Within, just outside and far from the training data
+	let test_inputs = Matrix::new(3,1,vec![2.,5.5,20.]);
+	let entropy = gp.predictive_entropy(&test_inputs).unwrap();
+
+	assert!(entropy[0] < entropy[1]);
+	assert!(entropy[1] < entropy[2]);
+
+	// Far from the data the variance is the prior variance plus the noise
+	let prior = 0.5 * (2. * PI * E * 1.01f64).ln();
+	assert!((entropy[2] - prior).abs() < 1e-10);
+}
+
+#[test]
+fn test_predictive_entropy_without_noise() {
+	let mut gp = GaussianProcess::new(SquaredExp::new(1., 1.), ConstMean::default(), 0.);
+
+	let inputs = Matrix::new(3,1,vec![0.,2.,4.]);
+	let targets = Vector::new(vec![0.,1.,0.]);
+	gp.train(&inputs, &targets).unwrap();
+
+	// The variance at a training point vanishes but the entropy stays finite
+	let entropy = gp.predictive_entropy(&inputs).unwrap();
+	assert!(entropy.iter().all(|e| e.is_finite()));
+}
@@ -2,6 +2,7 @@ use rm::linalg::Matrix;
 use rm::linalg::Vector;
 use rm::learning::SupModel;
 use rm::learning::lin_reg::LinRegressor;
+use rm::learning::error::ErrorKind;
 use libnum::abs;
 
 #[test]
@@ -83,3 +84,53 @@ fn test_train_no_data() {
 
     assert!(res.is_err());
 }
+
+#[test]
+fn test_regression_f32_matches_f64() {
+    let input_data = vec![1.0, 2.5, 3.0, 4.5, 6.0, 1.5, 2.0, 8.0];
+    let target_data = vec![3.1, 6.0, 7.2, 9.8, 13.1, 4.0, 4.9, 17.0];
+
+    let mut lin_mod = LinRegressor::default();
+    lin_mod.train(&Matrix::new(8, 1, input_data.clone()),
+                  &Vector::new(target_data.clone())).unwrap();
+
+    let mut lin_mod_f32 = LinRegressor::<f32>::new(None);
+    lin_mod_f32.train(&Matrix::new(8, 1, input_data.iter().map(|x| *x as f32).collect::<Vec<_>>()),
+                      &Vector::new(target_data.iter().map(|x| *x as f32).collect::<Vec<_>>()))
+               .unwrap();
+
+    let params = lin_mod.parameters().unwrap();
+    let params_f32 = lin_mod_f32.parameters().unwrap();
+
+    for (p, p_f32) in params.iter().zip(params_f32.iter()) {
+        assert!(abs(p - *p_f32 as f64) < 1e-4);
+    }
+
+    let prediction = lin_mod_f32.predict(&Matrix::new(1, 1, vec![5.0f32])).unwrap();
+    assert!(abs(prediction[0] as f64 - (params[0] + 5.0 * params[1])) < 1e-4);
+}
+
+#[test]
+fn test_predict_untrained() {
+    let lin_mod = LinRegressor::default();
+    let inputs = Matrix::new(3, 1, vec![2.0, 3.0, 4.0]);
+
+    let err = lin_mod.predict(&inputs).unwrap_err();
+    match *err.kind() {
+        ErrorKind::UntrainedModel => {}
+        _ => panic!("Expected an untrained model error, got {:?}", err),
+    }
+    assert!(err.to_string().contains("not been trained"));
+}
+
+#[test]
+fn test_predict_wrong_columns() {
+    let mut lin_mod = LinRegressor::default();
+    let inputs = Matrix::new(3, 1, vec![2.0, 3.0, 4.0]);
+    let targets = Vector::new(vec![5.0, 6.0, 7.0]);
+
+    lin_mod.train(&inputs, &targets).unwrap();
+
+    let wide_inputs = Matrix::new(1, 2, vec![2.0, 3.0]);
+    assert!(lin_mod.predict(&wide_inputs).is_err());
+}
@@ -1,7 +1,10 @@
 use rm::learning::optim::Optimizable;
 use rm::learning::optim::fmincg::ConjugateGD;
-use rm::learning::optim::grad_desc::{GradientDesc, StochasticGD, AdaGrad, RMSProp};
+use rm::learning::optim::grad_desc::{GradientDesc, StochasticGD, AdaGrad, RMSProp, Adam};
 use rm::learning::optim::OptimAlgorithm;
+use rm::learning::nnet::{NeuralNet, MSECriterion};
+use rm::learning::toolkit::activ_fn::Sigmoid;
+use rm::learning::SupModel;
 
 use rm::linalg::Matrix;
 
@@ -97,4 +100,106 @@ fn convex_rmsprop_training() {
 
   assert!(params[0] - 20f64 < 1e-10);
   assert!(x_sq.compute_grad(&params, &Matrix::zeros(1, 1), &Matrix::zeros(1, 1)).0 < 1e-10);
-}
\ No newline at end of file
+}
+#[test]
+fn nnet_rmsprop_reduces_cost() {
+    let inputs = Matrix::new(5, 3, vec![1., 1., 1., 2., 2., 2., 3., 3., 3.,
+                                        4., 4., 4., 5., 5., 5.]);
+    let targets = Matrix::new(5, 3, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.,
+                                         0., 0., 1., 0., 0., 1.]);
+
+    let mse = |outputs: &Matrix<f64>| {
+        outputs.data().iter()
+               .zip(targets.data().iter())
+               .fold(0f64, |acc, (o, t)| acc + (o - t) * (o - t)) / 5f64
+    };
+
+    let mut model = NeuralNet::mlp(&[3, 5, 3], MSECriterion::default(), RMSProp::default(), Sigmoid);
+    let start_cost = mse(&model.predict(&inputs).unwrap());
+
+    model.train(&inputs, &targets).unwrap();
+    let end_cost = mse(&model.predict(&inputs).unwrap());
+
+    assert!(end_cost < start_cost);
+}
+
+#[test]
+fn convex_adam_training() {
+    let x_sq = XSqModel { c: 20f64 };
+
+    let adam = Adam::new(1.0, 0.9, 0.999, 1e-8, 10);
+    let test_data = vec![100f64];
+    let params = adam.optimize(&x_sq,
+                               &test_data[..],
+                               &Matrix::zeros(100, 1),
+                               &Matrix::zeros(100, 1));
+
+    assert!((params[0] - 20f64).abs() < 1e-2);
+}
+
+#[test]
+fn nnet_adam_reduces_cost() {
+    let inputs = Matrix::new(5, 3, vec![1., 1., 1., 2., 2., 2., 3., 3., 3.,
+                                        4., 4., 4., 5., 5., 5.]);
+    let targets = Matrix::new(5, 3, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.,
+                                         0., 0., 1., 0., 0., 1.]);
+
+    let mse = |outputs: &Matrix<f64>| {
+        outputs.data().iter()
+               .zip(targets.data().iter())
+               .fold(0f64, |acc, (o, t)| acc + (o - t) * (o - t)) / 5f64
+    };
+
+    let adam = Adam::new(0.01, 0.9, 0.999, 1e-8, 50);
+    let mut model = NeuralNet::mlp(&[3, 5, 3], MSECriterion::default(), adam, Sigmoid);
+    let start_cost = mse(&model.predict(&inputs).unwrap());
+
+    model.train(&inputs, &targets).unwrap();
+    let end_cost = mse(&model.predict(&inputs).unwrap());
+
+    assert!(end_cost < start_cost);
+}
+
+#[test]
+fn gd_update_ratios() {
+    let x_sq = XSqModel { c: 20f64 };
+
+    let gd = GradientDesc::new(0.001, 10);
+    let (_, history) = gd.optimize_with_history(&x_sq,
+                                                &[100f64],
+                                                &Matrix::zeros(1, 1),
+                                                &Matrix::zeros(1, 1));
+    assert_eq!(history.iterations(), 10);
+    assert!(history.update_ratios().iter().all(|r| r.is_finite() && *r < 1e-2));
+
+    // Starting from zero parameters still gives finite ratios
+    let (_, history) = gd.optimize_with_history(&x_sq,
+                                                &[0f64],
+                                                &Matrix::zeros(1, 1),
+                                                &Matrix::zeros(1, 1));
+    assert!(history.update_ratios().iter().all(|r| r.is_finite()));
+
+    // An absurd learning rate gives ratios far above the healthy ~1e-3
+    let gd = GradientDesc::new(100.0, 10);
+    let (_, history) = gd.optimize_with_history(&x_sq,
+                                                &[100f64],
+                                                &Matrix::zeros(1, 1),
+                                                &Matrix::zeros(1, 1));
+    assert!(history.update_ratios().iter().all(|r| r.is_finite() && *r > 1.0));
+}
+
+#[test]
+fn sgd_records_history() {
+    let x_sq = XSqModel { c: 20f64 };
+
+    let sgd = StochasticGD::new(0.5, 1f64, 5);
+    let (params, history) = sgd.optimize_with_history(&x_sq,
+                                                      &[100f64],
+                                                      &Matrix::zeros(10, 1),
+                                                      &Matrix::zeros(10, 1));
+    assert!(history.iterations() > 0 && history.iterations() <= 5);
+    assert_eq!(history.costs().len(), history.update_ratios().len());
+    assert!(history.update_ratios().iter().all(|r| r.is_finite()));
+
+    assert_eq!(params.len(), 1);
+}
@@ -1,4 +1,4 @@
-use rm::linalg::Matrix;
+use rm::linalg::{Matrix, BaseMatrix};
 use rm::learning::UnSupModel;
 use rm::learning::pca::PCA;
 
@@ -124,4 +124,44 @@ fn test_wide() {
 
     let exp = Matrix::new(1, 2, vec![-6.550335224256381, 1.517487926775624]);
     assert_matrix_eq!(outputs, exp, comp=abs, tol=1e-8);
-}
\ No newline at end of file
+}
+#[test]
+fn test_power_iteration_top_component() {
+    // Points spread along (1, 2, 0.5) with a little variation elsewhere
+    let data = (0..20).flat_map(|i| {
+        let t = i as f64 - 10.;
+        let e = (i as f64).sin() * 0.3;
+        vec![t + e, 2. * t - e, 0.5 * t + 0.5 * e]
+    }).collect::<Vec<_>>();
+    let inputs = Matrix::new(20, 3, data);
+
+    let mut exact = PCA::new(1, true);
+    exact.train(&inputs).unwrap();
+
+    let mut power = PCA::new(1, true);
+    power.fit_power_iteration(&inputs, 1, 1000).unwrap();
+
+    let exact_comp = exact.components().unwrap();
+    let power_comp = power.components().unwrap();
+    assert_eq!(power_comp.rows(), 3);
+    assert_eq!(power_comp.cols(), 1);
+
+    // The components should agree up to sign
+    let dot = exact_comp.data().iter()
+                        .zip(power_comp.data().iter())
+                        .fold(0., |acc, (x, y)| acc + x * y);
+    assert!((dot.abs() - 1.).abs() < 1e-8);
+
+    let new_data = Matrix::new(1, 3, vec![1., 2., 3.]);
+    let exact_out = exact.predict(&new_data).unwrap();
+    let power_out = power.predict(&new_data).unwrap();
+    assert!((exact_out[[0, 0]].abs() - power_out[[0, 0]].abs()).abs() < 1e-6);
+}
+
+#[test]
+fn test_power_iteration_too_many_components() {
+    let inputs = Matrix::new(3, 2, vec![1., 2., 3., 4., 5., 7.]);
+
+    let mut model = PCA::default();
+    assert!(model.fit_power_iteration(&inputs, 3, 10).is_err());
+}
@@ -0,0 +1,104 @@
+use rm::learning::smoothing::{MovingAverage, HoltWinters, Seasonality};
+use rm::learning::UnSupModel;
+use rm::linalg::Vector;
+
+#[test]
+fn test_constant_series() {
+	let model = MovingAverage::new(5);
+	let series = Vector::new(vec![0.7; 20]);
+
+	let smoothed = model.predict(&series).unwrap();
+	assert_eq!(smoothed.size(), 20);
+	for x in smoothed.iter() {
+		assert!((x - 0.7).abs() < 1e-12);
+	}
+}
+
+#[test]
+fn test_step_change_lag() {
+	let window = 10;
+	let model = MovingAverage::new(window);
+
+	// The series steps from 0 to 1 at index 50
+	let series = Vector::new((0..100).map(|i| if i < 50 { 0.0 } else { 1.0 }).collect::<Vec<_>>());
+	let smoothed = model.predict(&series).unwrap();
+
+	// The smoothed series passes halfway roughly half a window after the step
+	let crossing = smoothed.iter().position(|&x| x >= 0.5).unwrap();
+	assert!(crossing >= 50 + window / 2 - 1 && crossing <= 50 + window / 2);
+
+	// And reaches the new level once the window has passed the step
+	assert_eq!(smoothed[50 + window - 1], 1.0);
+	assert!(smoothed[50 + window - 2] < 1.0);
+}
+
+#[test]
+fn test_continues_training_series() {
+	let mut model = MovingAverage::new(4);
+	model.train(&Vector::new(vec![1.0, 2.0, 3.0, 4.0, 5.0])).unwrap();
+
+	let smoothed = model.predict(&Vector::new(vec![6.0, 7.0])).unwrap();
+	assert_eq!(smoothed.into_vec(), vec![4.5, 5.5]);
+
+	assert!(model.train(&Vector::new(vec![1.0, ::std::f64::NAN])).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_zero_window() {
+	let _ = MovingAverage::new(0);
+}
+
+/// A trending series with a seasonal pattern of length 4.
+fn seasonal_series(len: usize, seasonality: Seasonality) -> Vec<f64> {
+	let pattern = [3.0, -1.0, -4.0, 2.0];
+	(0..len).map(|t| {
+		let level = 20.0 + 0.5 * t as f64;
+		match seasonality {
+			Seasonality::Additive => level + pattern[t % 4],
+			Seasonality::Multiplicative => level * (1.0 + pattern[t % 4] / 10.0),
+		}
+	}).collect()
+}
+
+#[test]
+fn test_holt_winters_additive() {
+	let series = seasonal_series(48, Seasonality::Additive);
+
+	let mut model = HoltWinters::new(0.5, 0.2, 0.3, 4);
+	assert!(model.forecast(4).is_err());
+	model.fit(&Vector::new(series[..40].to_vec())).unwrap();
+
+	let forecast = model.forecast(8).unwrap();
+	for (f, x) in forecast.iter().zip(&series[40..]) {
+		assert!((f - x).abs() < 0.05);
+	}
+}
+
+#[test]
+fn test_holt_winters_multiplicative() {
+	let series = seasonal_series(48, Seasonality::Multiplicative);
+
+	let mut model = HoltWinters::new(0.5, 0.2, 0.3, 4).with_seasonality(Seasonality::Multiplicative);
+	model.fit(&Vector::new(series[..40].to_vec())).unwrap();
+
+	let forecast = model.forecast(8).unwrap();
+	for (f, x) in forecast.iter().zip(&series[40..]) {
+		assert!((f - x).abs() / x < 0.01);
+	}
+
+	// The series must be positive
+	let mut negative = series.clone();
+	negative[5] = -1.0;
+	assert!(model.fit(&Vector::new(negative)).is_err());
+}
+
+#[test]
+fn test_holt_winters_short_series() {
+	let mut model = HoltWinters::new(0.5, 0.2, 0.3, 4);
+	assert!(model.fit(&Vector::new(vec![1.0, 2.0, 3.0])).is_err());
+
+	// A single season fits with no initial trend
+	model.fit(&Vector::new(vec![1.0, 2.0, 3.0, 4.0])).unwrap();
+	assert_eq!(model.forecast(2).unwrap().size(), 2);
+}
@@ -5,11 +5,13 @@ extern crate num as libnum;
 
 pub mod learning {
     mod dbscan;
+    mod elastic_net;
     mod lin_reg;
     mod k_means;
     mod gp;
     mod knn;
     mod pca;
+    mod smoothing;
 
     pub mod optim {
     	mod grad_desc;